@@ -37,8 +37,8 @@ use hyper::body::HttpBody;
 use hyper::http::HeaderMap;
 use hyper::Body;
 use jsonrpsee_core::client::{
-	generate_batch_id_range, BatchResponse, CertificateStore, ClientT, IdKind, RequestIdManager, Subscription,
-	SubscriptionClientT,
+	generate_batch_id_range, BatchResponse, CertificateStore, ClientT, IdKind, JsonRpcVersion, RequestIdManager,
+	Subscription, SubscriptionClientT,
 };
 use jsonrpsee_core::params::BatchRequestBuilder;
 use jsonrpsee_core::traits::ToRpcParams;
@@ -80,8 +80,10 @@ pub struct HttpClientBuilder<L = Identity> {
 	max_concurrent_requests: usize,
 	certificate_store: CertificateStore,
 	id_kind: IdKind,
+	json_rpc_version: JsonRpcVersion,
 	max_log_length: u32,
 	headers: HeaderMap,
+	trace_context: bool,
 	service_builder: tower::ServiceBuilder<L>,
 }
 
@@ -144,6 +146,12 @@ impl<L> HttpClientBuilder<L> {
 		self
 	}
 
+	/// Configure the JSON-RPC version of the method calls, notifications and batches (default is JSON-RPC 2.0).
+	pub fn json_rpc_version(mut self, version: JsonRpcVersion) -> Self {
+		self.json_rpc_version = version;
+		self
+	}
+
 	/// Max length for logging for requests and responses in number characters.
 	///
 	/// Logs bigger than this limit will be truncated.
@@ -160,16 +168,27 @@ impl<L> HttpClientBuilder<L> {
 		self
 	}
 
+	/// Propagate the W3C trace context to the server (disabled by default).
+	///
+	/// When enabled, each HTTP request carries a `traceparent` header which continues the trace of
+	/// [`TraceParent::current`](jsonrpsee_core::tracing::TraceParent::current) or starts a new trace.
+	pub fn propagate_trace_context(mut self, enabled: bool) -> Self {
+		self.trace_context = enabled;
+		self
+	}
+
 	/// Set custom tower middleware.
 	pub fn set_middleware<T>(self, service_builder: tower::ServiceBuilder<T>) -> HttpClientBuilder<T> {
 		HttpClientBuilder {
 			certificate_store: self.certificate_store,
 			id_kind: self.id_kind,
+			json_rpc_version: self.json_rpc_version,
 			headers: self.headers,
 			max_log_length: self.max_log_length,
 			max_concurrent_requests: self.max_concurrent_requests,
 			max_request_size: self.max_request_size,
 			max_response_size: self.max_response_size,
+			trace_context: self.trace_context,
 			service_builder,
 			request_timeout: self.request_timeout,
 		}
@@ -193,8 +212,10 @@ where
 			request_timeout,
 			certificate_store,
 			id_kind,
+			json_rpc_version,
 			headers,
 			max_log_length,
+			trace_context,
 			service_builder,
 			..
 		} = self;
@@ -208,10 +229,12 @@ where
 			headers,
 			service_builder,
 		)
-		.map_err(|e| Error::Transport(e.into()))?;
+		.map_err(|e| Error::Transport(e.into()))?
+		.with_trace_context(trace_context);
 		Ok(HttpClient {
 			transport,
 			id_manager: Arc::new(RequestIdManager::new(max_concurrent_requests, id_kind)),
+			json_rpc_version,
 			request_timeout,
 		})
 	}
@@ -226,8 +249,10 @@ impl Default for HttpClientBuilder<Identity> {
 			max_concurrent_requests: 256,
 			certificate_store: CertificateStore::Native,
 			id_kind: IdKind::Number,
+			json_rpc_version: JsonRpcVersion::V2,
 			max_log_length: 4096,
 			headers: HeaderMap::new(),
+			trace_context: false,
 			service_builder: tower::ServiceBuilder::new(),
 		}
 	}
@@ -249,6 +274,8 @@ pub struct HttpClient<S = HttpBackend> {
 	request_timeout: Duration,
 	/// Request ID manager.
 	id_manager: Arc<RequestIdManager>,
+	/// JSON-RPC version of the method calls, notifications and batches.
+	json_rpc_version: JsonRpcVersion,
 }
 
 impl<S> HttpClient<S> {
@@ -273,8 +300,10 @@ where
 		Params: ToRpcParams + Send,
 	{
 		let params = params.to_rpc_params()?;
-		let notif =
-			serde_json::to_string(&NotificationSer::borrowed(&method, params.as_deref())).map_err(Error::ParseError)?;
+		let notif = self
+			.json_rpc_version
+			.notification_to_string(NotificationSer::borrowed(&method, params.as_deref()))
+			.map_err(Error::ParseError)?;
 
 		let fut = self.transport.send(notif);
 
@@ -298,7 +327,7 @@ where
 		let params = params.to_rpc_params()?;
 
 		let request = RequestSer::borrowed(&id, &method, params.as_deref());
-		let raw = serde_json::to_string(&request).map_err(Error::ParseError)?;
+		let raw = self.json_rpc_version.request_to_string(request).map_err(Error::ParseError)?;
 
 		let fut = self.transport.send_and_read_body(raw);
 		let body = match tokio::time::timeout(self.request_timeout, fut).await {
@@ -341,10 +370,13 @@ where
 				id,
 				method: method.into(),
 				params: params.map(StdCow::Owned),
+				traceparent: None,
 			});
 		}
 
-		let fut = self.transport.send_and_read_body(serde_json::to_string(&batch_request).map_err(Error::ParseError)?);
+		let fut = self
+			.transport
+			.send_and_read_body(self.json_rpc_version.batch_to_string(batch_request).map_err(Error::ParseError)?);
 
 		let body = match tokio::time::timeout(self.request_timeout, fut).await {
 			Ok(Ok(body)) => body,
@@ -12,7 +12,7 @@ use hyper::http::{HeaderMap, HeaderValue};
 use jsonrpsee_core::client::CertificateStore;
 use jsonrpsee_core::error::GenericTransportError;
 use jsonrpsee_core::http_helpers;
-use jsonrpsee_core::tracing::{rx_log_from_bytes, tx_log_from_str};
+use jsonrpsee_core::tracing::{rx_log_from_bytes, tx_log_from_str, TraceParent, TRACEPARENT};
 use std::error::Error as StdError;
 use std::future::Future;
 use std::pin::Pin;
@@ -31,6 +31,9 @@ pub enum HttpBackend<B = Body> {
 	Https(Client<hyper_rustls::HttpsConnector<HttpConnector>, B>),
 	/// Hyper client with http connector.
 	Http(Client<HttpConnector, B>),
+	/// Hyper client with Unix domain socket connector.
+	#[cfg(all(feature = "unix", unix))]
+	Unix(Client<unix::UnixConnector, B>),
 }
 
 impl Clone for HttpBackend {
@@ -39,6 +42,8 @@ impl Clone for HttpBackend {
 			Self::Http(inner) => Self::Http(inner.clone()),
 			#[cfg(feature = "__tls")]
 			Self::Https(inner) => Self::Https(inner.clone()),
+			#[cfg(all(feature = "unix", unix))]
+			Self::Unix(inner) => Self::Unix(inner.clone()),
 		}
 	}
 }
@@ -58,6 +63,8 @@ where
 			Self::Http(inner) => inner.poll_ready(ctx),
 			#[cfg(feature = "__tls")]
 			Self::Https(inner) => inner.poll_ready(ctx),
+			#[cfg(all(feature = "unix", unix))]
+			Self::Unix(inner) => inner.poll_ready(ctx),
 		}
 		.map_err(Into::into)
 	}
@@ -67,6 +74,8 @@ where
 			Self::Http(inner) => inner.call(req),
 			#[cfg(feature = "__tls")]
 			Self::Https(inner) => inner.call(req),
+			#[cfg(all(feature = "unix", unix))]
+			Self::Unix(inner) => inner.call(req),
 		};
 
 		Box::pin(async move { resp.await.map_err(Into::into) })
@@ -90,6 +99,8 @@ pub struct HttpTransportClient<S> {
 	max_log_length: u32,
 	/// Custom headers to pass with every request.
 	headers: HeaderMap,
+	/// Whether to propagate the trace context in the `traceparent` header.
+	trace_context: bool,
 }
 
 impl<B, S> HttpTransportClient<S>
@@ -110,6 +121,26 @@ where
 		service_builder: tower::ServiceBuilder<L>,
 	) -> Result<Self, Error> {
 		let mut url = Url::parse(target.as_ref()).map_err(|e| Error::Url(format!("Invalid URL: {e}")))?;
+
+		#[cfg(all(feature = "unix", unix))]
+		if url.scheme() == jsonrpsee_client_transport::unix::UNIX_SCHEME {
+			let unix = jsonrpsee_client_transport::unix::UnixTransport::from_url(&url)
+				.ok_or_else(|| Error::Url("Invalid unix domain socket path".into()))?;
+			let client = HttpBackend::Unix(Client::builder().build(unix::UnixConnector(unix)));
+
+			// The socket is addressed by the connector, the request itself is made to `localhost`.
+			url = Url::parse("http://localhost/").expect("valid URL; qed");
+			return Ok(Self::from_parts(
+				url,
+				client,
+				max_request_size,
+				max_response_size,
+				max_log_length,
+				headers,
+				service_builder,
+			));
+		}
+
 		if url.host_str().is_none() {
 			return Err(Error::Url("Invalid host".into()));
 		}
@@ -145,26 +176,50 @@ where
 			}
 		};
 
-		// Cache request headers: 2 default headers, followed by user custom headers.
+		Ok(Self::from_parts(url, client, max_request_size, max_response_size, max_log_length, headers, service_builder))
+	}
+
+	fn from_parts<L: Layer<HttpBackend<Body>, Service = S>>(
+		url: Url,
+		client: HttpBackend,
+		max_request_size: u32,
+		max_response_size: u32,
+		max_log_length: u32,
+		headers: HeaderMap,
+		service_builder: tower::ServiceBuilder<L>,
+	) -> Self {
+		// Cache request headers: 3 default headers, followed by user custom headers.
 		// Maintain order for headers in case of duplicate keys:
 		// https://datatracker.ietf.org/doc/html/rfc7230#section-3.2.2
-		let mut cached_headers = HeaderMap::with_capacity(2 + headers.len());
+		let mut cached_headers = HeaderMap::with_capacity(3 + headers.len());
 		cached_headers.insert(hyper::header::CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE_JSON));
 		cached_headers.insert(hyper::header::ACCEPT, HeaderValue::from_static(CONTENT_TYPE_JSON));
+		// The compressed responses are decoded when the body is read.
+		cached_headers.insert(
+			hyper::header::ACCEPT_ENCODING,
+			HeaderValue::from_static(http_helpers::ContentEncoding::ACCEPT_ALL),
+		);
 		for (key, value) in headers.into_iter() {
 			if let Some(key) = key {
 				cached_headers.insert(key, value);
 			}
 		}
 
-		Ok(Self {
+		Self {
 			target: url.as_str().to_owned(),
 			client: service_builder.service(client),
 			max_request_size,
 			max_response_size,
 			max_log_length,
 			headers: cached_headers,
-		})
+			trace_context: false,
+		}
+	}
+
+	/// Propagate the trace context in the `traceparent` header of every request.
+	pub(crate) fn with_trace_context(mut self, enabled: bool) -> Self {
+		self.trace_context = enabled;
+		self
 	}
 
 	async fn inner_send(&self, body: String) -> Result<hyper::Response<B>, Error> {
@@ -177,6 +232,11 @@ where
 		let mut req = hyper::Request::post(&self.target);
 		if let Some(headers) = req.headers_mut() {
 			*headers = self.headers.clone();
+			if self.trace_context {
+				let traceparent = HeaderValue::try_from(TraceParent::next_call().to_string())
+					.expect("traceparent is a valid header value; qed");
+				headers.insert(TRACEPARENT, traceparent);
+			}
 		}
 		let req = req.body(From::from(body)).expect("URI and request headers are valid; qed");
 		let response = self.client.clone().ready().await?.call(req).await?;
@@ -242,7 +302,7 @@ impl From<GenericTransportError> for Error {
 	fn from(err: GenericTransportError) -> Self {
 		match err {
 			GenericTransportError::TooLarge => Self::RequestTooLarge,
-			GenericTransportError::Malformed => Self::Malformed,
+			GenericTransportError::Malformed | GenericTransportError::UnsupportedEncoding => Self::Malformed,
 			GenericTransportError::Inner(e) => Self::Http(e.into()),
 		}
 	}
@@ -254,6 +314,69 @@ impl From<hyper::Error> for Error {
 	}
 }
 
+#[cfg(all(feature = "unix", unix))]
+mod unix {
+	use std::future::Future;
+	use std::io;
+	use std::pin::Pin;
+	use std::task::{Context, Poll};
+
+	use hyper::client::connect::{Connected, Connection};
+	use hyper::Uri;
+	use jsonrpsee_client_transport::unix::UnixTransport;
+	use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+	use tokio::net::UnixStream;
+
+	/// Hyper connector which connects to a Unix domain socket regardless of the requested URI.
+	#[derive(Debug, Clone)]
+	pub struct UnixConnector(pub(super) UnixTransport);
+
+	impl tower::Service<Uri> for UnixConnector {
+		type Response = UnixConnection;
+		type Error = io::Error;
+		type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+		fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+			Poll::Ready(Ok(()))
+		}
+
+		fn call(&mut self, _: Uri) -> Self::Future {
+			let transport = self.0.clone();
+			Box::pin(async move { transport.connect().await.map(UnixConnection) })
+		}
+	}
+
+	/// Connection to a Unix domain socket.
+	#[derive(Debug)]
+	pub struct UnixConnection(UnixStream);
+
+	impl Connection for UnixConnection {
+		fn connected(&self) -> Connected {
+			Connected::new()
+		}
+	}
+
+	impl AsyncRead for UnixConnection {
+		fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+			Pin::new(&mut self.0).poll_read(cx, buf)
+		}
+	}
+
+	impl AsyncWrite for UnixConnection {
+		fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+			Pin::new(&mut self.0).poll_write(cx, buf)
+		}
+
+		fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+			Pin::new(&mut self.0).poll_flush(cx)
+		}
+
+		fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+			Pin::new(&mut self.0).poll_shutdown(cx)
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
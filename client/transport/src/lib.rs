@@ -35,6 +35,11 @@
 #[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
 pub mod ws;
 
+/// Unix domain socket transport.
+#[cfg(all(feature = "unix", unix))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "unix", unix))))]
+pub mod unix;
+
 /// Websocket transport via web-sys.
 #[cfg(all(feature = "web", target_arch = "wasm32"))]
 #[cfg_attr(docsrs, doc(cfg(feature = "web")))]
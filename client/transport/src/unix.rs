@@ -0,0 +1,96 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Unix domain socket targets are addressed by URLs with the `unix` scheme
+//! and the absolute path of the socket, such as `unix:///run/jsonrpsee.sock`.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tokio::net::UnixStream;
+use url::Url;
+
+/// The URL scheme of Unix domain socket targets.
+pub const UNIX_SCHEME: &str = "unix";
+
+/// Connects to a JSON-RPC server listening on a Unix domain socket.
+///
+/// The HTTP and WebSocket protocols are spoken on top of the socket
+/// as if it was a TCP connection to `localhost`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnixTransport {
+	path: PathBuf,
+}
+
+impl UnixTransport {
+	/// Create a new transport which connects to the socket at `path`.
+	pub fn new(path: impl Into<PathBuf>) -> Self {
+		Self { path: path.into() }
+	}
+
+	/// Create a new transport from a `unix://` URL.
+	///
+	/// Returns `None` if the URL doesn't have the `unix` scheme or doesn't contain an absolute path.
+	pub fn from_url(url: &Url) -> Option<Self> {
+		if url.scheme() != UNIX_SCHEME {
+			return None;
+		}
+
+		url.to_file_path().ok().map(Self::new)
+	}
+
+	/// Get the path of the socket.
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Open a new connection to the socket.
+	pub async fn connect(&self) -> io::Result<UnixStream> {
+		UnixStream::connect(&self.path).await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{UnixTransport, Url};
+
+	fn parse(url: &str) -> Option<UnixTransport> {
+		UnixTransport::from_url(&Url::parse(url).unwrap())
+	}
+
+	#[test]
+	fn unix_url_works() {
+		assert_eq!(parse("unix:///tmp/jsonrpsee.sock"), Some(UnixTransport::new("/tmp/jsonrpsee.sock")));
+		assert_eq!(parse("unix://localhost/tmp/jsonrpsee.sock"), Some(UnixTransport::new("/tmp/jsonrpsee.sock")));
+		assert_eq!(parse("unix:///tmp/my%20socket.sock"), Some(UnixTransport::new("/tmp/my socket.sock")));
+	}
+
+	#[test]
+	fn faulty_unix_url() {
+		assert_eq!(parse("ws:///tmp/jsonrpsee.sock"), None);
+		assert_eq!(parse("unix://example.com/tmp/jsonrpsee.sock"), None);
+	}
+}
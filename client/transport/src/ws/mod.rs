@@ -32,6 +32,8 @@ use std::time::Duration;
 
 use futures_util::io::{BufReader, BufWriter};
 use jsonrpsee_core::client::{CertificateStore, ReceivedMessage, TransportReceiverT, TransportSenderT};
+#[cfg(feature = "ws-deflate")]
+use jsonrpsee_core::ws_deflate::PerMessageDeflate;
 use jsonrpsee_core::TEN_MB_SIZE_BYTES;
 use jsonrpsee_core::{async_trait, Cow};
 use soketto::connection::Error::Utf8;
@@ -43,6 +45,9 @@ use thiserror::Error;
 use tokio::net::TcpStream;
 
 pub use http::{uri::InvalidUri, HeaderMap, HeaderValue, Uri};
+#[cfg(feature = "ws-deflate")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ws-deflate")))]
+pub use jsonrpsee_core::ws_deflate::WsDeflateConfig;
 pub use soketto::handshake::client::Header;
 pub use url::Url;
 
@@ -74,6 +79,9 @@ pub struct WsTransportClientBuilder {
 	pub max_response_size: u32,
 	/// Max number of redirections.
 	pub max_redirections: usize,
+	/// `permessage-deflate` configuration offered to the server, if enabled.
+	#[cfg(feature = "ws-deflate")]
+	pub permessage_deflate: Option<WsDeflateConfig>,
 }
 
 impl Default for WsTransportClientBuilder {
@@ -85,6 +93,8 @@ impl Default for WsTransportClientBuilder {
 			connection_timeout: Duration::from_secs(10),
 			headers: http::HeaderMap::new(),
 			max_redirections: 5,
+			#[cfg(feature = "ws-deflate")]
+			permessage_deflate: None,
 		}
 	}
 }
@@ -150,6 +160,17 @@ impl WsTransportClientBuilder {
 		self.max_redirections = redirect;
 		self
 	}
+
+	/// Offer the `permessage-deflate` extension to the server during the handshake (default is disabled).
+	///
+	/// The [maximum response size](Self::max_response_size) applies to the decompressed messages
+	/// and the connection is terminated if a message exceeds it.
+	#[cfg(feature = "ws-deflate")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "ws-deflate")))]
+	pub fn permessage_deflate(mut self, config: WsDeflateConfig) -> Self {
+		self.permessage_deflate = Some(config);
+		self
+	}
 }
 
 /// Stream mode, either plain TCP or TLS.
@@ -278,9 +299,70 @@ impl WsTransportClientBuilder {
 	/// Try to establish the connection.
 	pub async fn build(self, uri: Url) -> Result<(Sender, Receiver), WsHandshakeError> {
 		let target: Target = uri.try_into()?;
+
+		#[cfg(all(feature = "unix", unix))]
+		if let Some(unix) = target.unix.clone() {
+			return self.try_connect_unix(unix, target).await;
+		}
+
 		self.try_connect(target).await
 	}
 
+	#[cfg(all(feature = "unix", unix))]
+	async fn try_connect_unix(
+		self,
+		unix: crate::unix::UnixTransport,
+		target: Target,
+	) -> Result<(Sender, Receiver), WsHandshakeError> {
+		tracing::debug!("Connecting to target: {:?}", target);
+
+		let socket = match tokio::time::timeout(self.connection_timeout, unix.connect()).await {
+			Ok(socket) => socket?,
+			Err(_) => return Err(WsHandshakeError::Timeout(self.connection_timeout)),
+		};
+
+		let mut client = WsHandshakeClient::new(
+			BufReader::new(BufWriter::new(EitherStream::Unix(socket))),
+			&target.host_header,
+			&target.path_and_query,
+		);
+
+		let headers: Vec<_> =
+			self.headers.iter().map(|(key, value)| Header { name: key.as_str(), value: value.as_bytes() }).collect();
+		client.set_headers(&headers);
+		self.add_extensions(&mut client);
+
+		match client.handshake().await? {
+			ServerResponse::Accepted { .. } => {
+				tracing::debug!("Connection established to target: {:?}", target);
+				Ok(self.finish(client))
+			}
+			ServerResponse::Rejected { status_code } => {
+				tracing::debug!("Connection rejected: {:?}", status_code);
+				Err(WsHandshakeError::Rejected { status_code })
+			}
+			ServerResponse::Redirect { .. } => {
+				Err(WsHandshakeError::Url("Redirections are not supported on unix domain sockets".into()))
+			}
+		}
+	}
+
+	#[cfg_attr(not(feature = "ws-deflate"), allow(unused_variables))]
+	fn add_extensions(&self, client: &mut WsHandshakeClient<'_, BufReader<BufWriter<EitherStream>>>) {
+		#[cfg(feature = "ws-deflate")]
+		if let Some(config) = self.permessage_deflate {
+			let max_size = self.max_response_size as usize;
+			client.add_extension(Box::new(PerMessageDeflate::new(soketto::Mode::Client, config, max_size)));
+		}
+	}
+
+	fn finish(&self, client: WsHandshakeClient<'_, BufReader<BufWriter<EitherStream>>>) -> (Sender, Receiver) {
+		let mut builder = client.into_builder();
+		builder.set_max_message_size(self.max_response_size as usize);
+		let (sender, receiver) = builder.finish();
+		(Sender { inner: sender, max_request_size: self.max_request_size }, Receiver { inner: receiver })
+	}
+
 	async fn try_connect(self, mut target: Target) -> Result<(Sender, Receiver), WsHandshakeError> {
 		let mut err = None;
 
@@ -329,18 +411,13 @@ impl WsTransportClientBuilder {
 					.map(|(key, value)| Header { name: key.as_str(), value: value.as_bytes() })
 					.collect();
 				client.set_headers(&headers);
+				self.add_extensions(&mut client);
 
 				// Perform the initial handshake.
 				match client.handshake().await {
 					Ok(ServerResponse::Accepted { .. }) => {
 						tracing::debug!("Connection established to target: {:?}", target);
-						let mut builder = client.into_builder();
-						builder.set_max_message_size(self.max_response_size as usize);
-						let (sender, receiver) = builder.finish();
-						return Ok((
-							Sender { inner: sender, max_request_size: self.max_request_size },
-							Receiver { inner: receiver },
-						));
+						return Ok(self.finish(client));
 					}
 
 					Ok(ServerResponse::Rejected { status_code }) => {
@@ -481,12 +558,30 @@ pub struct Target {
 	_mode: Mode,
 	/// The path and query parts from an URL.
 	path_and_query: String,
+	/// Unix domain socket to connect to instead of the socket addresses.
+	#[cfg(all(feature = "unix", unix))]
+	unix: Option<crate::unix::UnixTransport>,
 }
 
 impl TryFrom<url::Url> for Target {
 	type Error = WsHandshakeError;
 
 	fn try_from(url: Url) -> Result<Self, Self::Error> {
+		#[cfg(all(feature = "unix", unix))]
+		if url.scheme() == crate::unix::UNIX_SCHEME {
+			let unix = crate::unix::UnixTransport::from_url(&url)
+				.ok_or_else(|| WsHandshakeError::Url("Invalid unix domain socket path".into()))?;
+
+			return Ok(Self {
+				sockaddrs: Vec::new(),
+				host: "localhost".to_owned(),
+				host_header: "localhost".to_owned(),
+				_mode: Mode::Plain,
+				path_and_query: "/".to_owned(),
+				unix: Some(unix),
+			});
+		}
+
 		let _mode = match url.scheme() {
 			"ws" => Mode::Plain,
 			#[cfg(feature = "__tls")]
@@ -514,6 +609,8 @@ impl TryFrom<url::Url> for Target {
 			host_header: url.authority().to_string(),
 			_mode,
 			path_and_query: path_and_query.to_string(),
+			#[cfg(all(feature = "unix", unix))]
+			unix: None,
 		})
 	}
 }
@@ -634,6 +731,14 @@ mod tests {
 		assert_ws_target(target, "127.0.0.1", "127.0.0.1", Mode::Tls, "/");
 	}
 
+	#[cfg(all(feature = "unix", unix))]
+	#[test]
+	fn unix_target_works() {
+		let target = parse_target("unix:///tmp/jsonrpsee.sock").unwrap();
+		assert_ws_target(target.clone(), "localhost", "localhost", Mode::Plain, "/");
+		assert_eq!(target.unix.unwrap().path(), std::path::Path::new("/tmp/jsonrpsee.sock"));
+	}
+
 	#[test]
 	fn ws_default_port_is_omitted() {
 		let target = parse_target("ws://127.0.0.1:80").unwrap();
@@ -24,7 +24,8 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-//! Convenience wrapper for a stream (AsyncRead + AsyncWrite) which can either be plain TCP or TLS.
+//! Convenience wrapper for a stream (AsyncRead + AsyncWrite) which can either be plain TCP, TLS
+//! or a Unix domain socket.
 
 use std::io::Error as IoError;
 use std::pin::Pin;
@@ -47,6 +48,9 @@ pub(crate) enum EitherStream {
 	/// Encrypted socket stream.
 	#[cfg(feature = "__tls")]
 	Tls(#[pin] tokio_rustls::client::TlsStream<TcpStream>),
+	/// Unix domain socket stream.
+	#[cfg(all(feature = "unix", unix))]
+	Unix(#[pin] tokio::net::UnixStream),
 }
 
 impl AsyncRead for EitherStream {
@@ -63,6 +67,12 @@ impl AsyncRead for EitherStream {
 				futures_util::pin_mut!(compat);
 				AsyncRead::poll_read(compat, cx, buf)
 			}
+			#[cfg(all(feature = "unix", unix))]
+			EitherStreamProj::Unix(u) => {
+				let compat = u.compat();
+				futures_util::pin_mut!(compat);
+				AsyncRead::poll_read(compat, cx, buf)
+			}
 		}
 	}
 
@@ -83,6 +93,12 @@ impl AsyncRead for EitherStream {
 				futures_util::pin_mut!(compat);
 				AsyncRead::poll_read_vectored(compat, cx, bufs)
 			}
+			#[cfg(all(feature = "unix", unix))]
+			EitherStreamProj::Unix(u) => {
+				let compat = u.compat();
+				futures_util::pin_mut!(compat);
+				AsyncRead::poll_read_vectored(compat, cx, bufs)
+			}
 		}
 	}
 }
@@ -101,6 +117,12 @@ impl AsyncWrite for EitherStream {
 				futures_util::pin_mut!(compat);
 				AsyncWrite::poll_write(compat, cx, buf)
 			}
+			#[cfg(all(feature = "unix", unix))]
+			EitherStreamProj::Unix(u) => {
+				let compat = u.compat_write();
+				futures_util::pin_mut!(compat);
+				AsyncWrite::poll_write(compat, cx, buf)
+			}
 		}
 	}
 
@@ -117,6 +139,12 @@ impl AsyncWrite for EitherStream {
 				futures_util::pin_mut!(compat);
 				AsyncWrite::poll_write_vectored(compat, cx, bufs)
 			}
+			#[cfg(all(feature = "unix", unix))]
+			EitherStreamProj::Unix(u) => {
+				let compat = u.compat_write();
+				futures_util::pin_mut!(compat);
+				AsyncWrite::poll_write_vectored(compat, cx, bufs)
+			}
 		}
 	}
 
@@ -133,6 +161,12 @@ impl AsyncWrite for EitherStream {
 				futures_util::pin_mut!(compat);
 				AsyncWrite::poll_flush(compat, cx)
 			}
+			#[cfg(all(feature = "unix", unix))]
+			EitherStreamProj::Unix(u) => {
+				let compat = u.compat_write();
+				futures_util::pin_mut!(compat);
+				AsyncWrite::poll_flush(compat, cx)
+			}
 		}
 	}
 
@@ -149,6 +183,12 @@ impl AsyncWrite for EitherStream {
 				futures_util::pin_mut!(compat);
 				AsyncWrite::poll_close(compat, cx)
 			}
+			#[cfg(all(feature = "unix", unix))]
+			EitherStreamProj::Unix(u) => {
+				let compat = u.compat_write();
+				futures_util::pin_mut!(compat);
+				AsyncWrite::poll_close(compat, cx)
+			}
 		}
 	}
 }
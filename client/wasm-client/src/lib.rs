@@ -36,7 +36,7 @@ pub use jsonrpsee_types as types;
 use std::time::Duration;
 
 use jsonrpsee_client_transport::web;
-use jsonrpsee_core::client::{ClientBuilder, IdKind};
+use jsonrpsee_core::client::{ClientBuilder, IdKind, JsonRpcVersion};
 use jsonrpsee_core::Error;
 
 /// Builder for [`Client`].
@@ -62,20 +62,24 @@ use jsonrpsee_core::Error;
 #[derive(Copy, Clone, Debug)]
 pub struct WasmClientBuilder {
 	id_kind: IdKind,
+	json_rpc_version: JsonRpcVersion,
 	max_concurrent_requests: usize,
 	max_buffer_capacity_per_subscription: usize,
 	max_log_length: u32,
 	request_timeout: Duration,
+	trace_context: bool,
 }
 
 impl Default for WasmClientBuilder {
 	fn default() -> Self {
 		Self {
 			id_kind: IdKind::Number,
+			json_rpc_version: JsonRpcVersion::V2,
 			max_log_length: 4096,
 			max_concurrent_requests: 256,
 			max_buffer_capacity_per_subscription: 1024,
 			request_timeout: Duration::from_secs(60),
+			trace_context: false,
 		}
 	}
 }
@@ -110,6 +114,12 @@ impl WasmClientBuilder {
 		self
 	}
 
+	/// See documentation for [`ClientBuilder::json_rpc_version`] (default is JSON-RPC 2.0).
+	pub fn json_rpc_version(mut self, version: JsonRpcVersion) -> Self {
+		self.json_rpc_version = version;
+		self
+	}
+
 	/// Set maximum length for logging calls and responses.
 	///
 	/// Logs bigger than this limit will be truncated.
@@ -118,14 +128,22 @@ impl WasmClientBuilder {
 		self
 	}
 
+	/// See documentation for [`ClientBuilder::propagate_trace_context`] (disabled by default).
+	pub fn propagate_trace_context(mut self, enabled: bool) -> Self {
+		self.trace_context = enabled;
+		self
+	}
+
 	/// Build the client with specified URL to connect to.
 	pub async fn build(self, url: impl AsRef<str>) -> Result<Client, Error> {
 		let Self {
 			max_log_length,
 			id_kind,
+			json_rpc_version,
 			request_timeout,
 			max_concurrent_requests,
 			max_buffer_capacity_per_subscription,
+			trace_context,
 		} = self;
 		let (sender, receiver) = web::connect(url).await.map_err(|e| Error::Transport(e.into()))?;
 
@@ -133,8 +151,10 @@ impl WasmClientBuilder {
 			.set_max_logging_length(max_log_length)
 			.request_timeout(request_timeout)
 			.id_format(id_kind)
+			.json_rpc_version(json_rpc_version)
 			.max_buffer_capacity_per_subscription(max_buffer_capacity_per_subscription)
-			.max_concurrent_requests(max_concurrent_requests);
+			.max_concurrent_requests(max_concurrent_requests)
+			.propagate_trace_context(trace_context);
 
 		Ok(builder.build_with_wasm(sender, receiver))
 	}
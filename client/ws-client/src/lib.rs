@@ -42,11 +42,14 @@ pub use jsonrpsee_core::client::Client as WsClient;
 pub use jsonrpsee_types as types;
 
 pub use http::{HeaderMap, HeaderValue};
+#[cfg(feature = "ws-deflate")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ws-deflate")))]
+pub use jsonrpsee_client_transport::ws::WsDeflateConfig;
 use std::time::Duration;
 use url::Url;
 
 use jsonrpsee_client_transport::ws::WsTransportClientBuilder;
-use jsonrpsee_core::client::{CertificateStore, ClientBuilder, IdKind};
+use jsonrpsee_core::client::{CertificateStore, ClientBuilder, IdKind, JsonRpcVersion};
 use jsonrpsee_core::{Error, TEN_MB_SIZE_BYTES};
 
 /// Builder for [`WsClient`].
@@ -87,7 +90,11 @@ pub struct WsClientBuilder {
 	max_buffer_capacity_per_subscription: usize,
 	max_redirections: usize,
 	id_kind: IdKind,
+	json_rpc_version: JsonRpcVersion,
 	max_log_length: u32,
+	trace_context: bool,
+	#[cfg(feature = "ws-deflate")]
+	permessage_deflate: Option<WsDeflateConfig>,
 }
 
 impl Default for WsClientBuilder {
@@ -104,7 +111,11 @@ impl Default for WsClientBuilder {
 			max_buffer_capacity_per_subscription: 1024,
 			max_redirections: 5,
 			id_kind: IdKind::Number,
+			json_rpc_version: JsonRpcVersion::V2,
 			max_log_length: 4096,
+			trace_context: false,
+			#[cfg(feature = "ws-deflate")]
+			permessage_deflate: None,
 		}
 	}
 }
@@ -199,12 +210,26 @@ impl WsClientBuilder {
 		self
 	}
 
+	/// See documentation [`WsTransportClientBuilder::permessage_deflate`] (default is disabled).
+	#[cfg(feature = "ws-deflate")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "ws-deflate")))]
+	pub fn permessage_deflate(mut self, config: WsDeflateConfig) -> Self {
+		self.permessage_deflate = Some(config);
+		self
+	}
+
 	/// See documentation for [`ClientBuilder::id_format`] (default is Number).
 	pub fn id_format(mut self, kind: IdKind) -> Self {
 		self.id_kind = kind;
 		self
 	}
 
+	/// See documentation for [`ClientBuilder::json_rpc_version`] (default is JSON-RPC 2.0).
+	pub fn json_rpc_version(mut self, version: JsonRpcVersion) -> Self {
+		self.json_rpc_version = version;
+		self
+	}
+
 	/// Set maximum length for logging calls and responses.
 	///
 	/// Logs bigger than this limit will be truncated.
@@ -213,6 +238,15 @@ impl WsClientBuilder {
 		self
 	}
 
+	/// Propagate the W3C trace context to the server (disabled by default).
+	///
+	/// When enabled, each request carries a `traceparent` member which continues the trace of
+	/// [`TraceParent::current`](jsonrpsee_core::tracing::TraceParent::current) or starts a new trace.
+	pub fn propagate_trace_context(mut self, enabled: bool) -> Self {
+		self.trace_context = enabled;
+		self
+	}
+
 	/// Build the client with specified URL to connect to.
 	/// You must provide the port number in the URL.
 	///
@@ -232,7 +266,11 @@ impl WsClientBuilder {
 			max_redirections,
 			max_buffer_capacity_per_subscription,
 			id_kind,
+			json_rpc_version,
 			max_log_length,
+			trace_context,
+			#[cfg(feature = "ws-deflate")]
+			permessage_deflate,
 		} = self;
 
 		let transport_builder = WsTransportClientBuilder {
@@ -242,6 +280,8 @@ impl WsClientBuilder {
 			max_request_size,
 			max_response_size,
 			max_redirections,
+			#[cfg(feature = "ws-deflate")]
+			permessage_deflate,
 		};
 
 		let uri = Url::parse(url.as_ref()).map_err(|e| Error::Transport(e.into()))?;
@@ -252,7 +292,9 @@ impl WsClientBuilder {
 			.request_timeout(request_timeout)
 			.max_concurrent_requests(max_concurrent_requests)
 			.id_format(id_kind)
-			.set_max_logging_length(max_log_length);
+			.json_rpc_version(json_rpc_version)
+			.set_max_logging_length(max_log_length)
+			.propagate_trace_context(trace_context);
 
 		if let Some(interval) = ping_interval {
 			client = client.ping_interval(interval);
@@ -10,7 +10,7 @@ use crate::client::{
 };
 use crate::error::Error;
 use crate::params::BatchRequestBuilder;
-use crate::tracing::{rx_log_from_json, tx_log_from_str};
+use crate::tracing::{rx_log_from_json, tx_log_from_str, TraceParent};
 use crate::traits::ToRpcParams;
 use crate::JsonRawValue;
 use std::borrow::Cow as StdCow;
@@ -38,7 +38,7 @@ use serde::de::DeserializeOwned;
 use tokio::sync::{mpsc, oneshot};
 use tracing::instrument;
 
-use super::{generate_batch_id_range, FrontToBack, IdKind, RequestIdManager};
+use super::{generate_batch_id_range, FrontToBack, IdKind, JsonRpcVersion, RequestIdManager};
 
 #[derive(Debug, Default, Clone)]
 pub(crate) struct ThreadSafeRequestManager(Arc<std::sync::Mutex<RequestManager>>);
@@ -90,8 +90,10 @@ pub struct ClientBuilder {
 	max_concurrent_requests: usize,
 	max_buffer_capacity_per_subscription: usize,
 	id_kind: IdKind,
+	json_rpc_version: JsonRpcVersion,
 	max_log_length: u32,
 	ping_interval: Option<Duration>,
+	trace_context: bool,
 }
 
 impl Default for ClientBuilder {
@@ -101,8 +103,10 @@ impl Default for ClientBuilder {
 			max_concurrent_requests: 256,
 			max_buffer_capacity_per_subscription: 1024,
 			id_kind: IdKind::Number,
+			json_rpc_version: JsonRpcVersion::V2,
 			max_log_length: 4096,
 			ping_interval: None,
+			trace_context: false,
 		}
 	}
 }
@@ -147,6 +151,14 @@ impl ClientBuilder {
 		self
 	}
 
+	/// Configure the JSON-RPC version of the method calls, notifications and batches (default is JSON-RPC 2.0).
+	///
+	/// Subscriptions are always made with JSON-RPC 2.0 calls.
+	pub fn json_rpc_version(mut self, version: JsonRpcVersion) -> Self {
+		self.json_rpc_version = version;
+		self
+	}
+
 	/// Set maximum length for logging calls and responses.
 	///
 	/// Logs bigger than this limit will be truncated.
@@ -172,6 +184,15 @@ impl ClientBuilder {
 		self
 	}
 
+	/// Propagate the W3C trace context to the server (disabled by default).
+	///
+	/// When enabled, each request carries a `traceparent` member which continues the
+	/// trace of [`TraceParent::current`] or starts a new trace.
+	pub fn propagate_trace_context(mut self, enabled: bool) -> Self {
+		self.trace_context = enabled;
+		self
+	}
+
 	/// Build the client with given transport.
 	///
 	/// ## Panics
@@ -216,7 +237,9 @@ impl ClientBuilder {
 			request_timeout: self.request_timeout,
 			error: AsyncMutex::new(ErrorFromBack::Unread(err_from_back)),
 			id_manager: RequestIdManager::new(self.max_concurrent_requests, self.id_kind),
+			json_rpc_version: self.json_rpc_version,
 			max_log_length: self.max_log_length,
+			trace_context: self.trace_context,
 			on_exit: Some(client_dropped_tx),
 		}
 	}
@@ -265,7 +288,9 @@ impl ClientBuilder {
 			request_timeout: self.request_timeout,
 			error: AsyncMutex::new(ErrorFromBack::Unread(err_from_back)),
 			id_manager: RequestIdManager::new(self.max_concurrent_requests, self.id_kind),
+			json_rpc_version: self.json_rpc_version,
 			max_log_length: self.max_log_length,
+			trace_context: self.trace_context,
 			on_exit: Some(client_dropped_tx),
 		}
 	}
@@ -283,10 +308,14 @@ pub struct Client {
 	request_timeout: Duration,
 	/// Request ID manager.
 	id_manager: RequestIdManager,
+	/// JSON-RPC version of the method calls, notifications and batches.
+	json_rpc_version: JsonRpcVersion,
 	/// Max length for logging for requests and responses.
 	///
 	/// Entries bigger than this limit will be truncated.
 	max_log_length: u32,
+	/// Whether to propagate the trace context to the server.
+	trace_context: bool,
 	/// When the client is dropped a message is sent to the background thread.
 	on_exit: Option<oneshot::Sender<()>>,
 }
@@ -297,6 +326,11 @@ impl Client {
 		ClientBuilder::new()
 	}
 
+	/// Get the `traceparent` of an outgoing call if trace context propagation is enabled.
+	fn traceparent(&self) -> Option<String> {
+		self.trace_context.then(|| TraceParent::next_call().to_string())
+	}
+
 	/// Checks if the client is connected to the target.
 	pub fn is_connected(&self) -> bool {
 		!self.to_back.is_closed()
@@ -342,7 +376,7 @@ impl ClientT for Client {
 		let params = params.to_rpc_params()?;
 		let notif = NotificationSer::borrowed(&method, params.as_deref());
 
-		let raw = serde_json::to_string(&notif).map_err(Error::ParseError)?;
+		let raw = self.json_rpc_version.notification_to_string(notif).map_err(Error::ParseError)?;
 		tx_log_from_str(&raw, self.max_log_length);
 
 		let sender = self.to_back.clone();
@@ -368,8 +402,12 @@ impl ClientT for Client {
 		let id = guard.inner();
 
 		let params = params.to_rpc_params()?;
-		let raw =
-			serde_json::to_string(&RequestSer::borrowed(&id, &method, params.as_deref())).map_err(Error::ParseError)?;
+		let raw = self
+			.json_rpc_version
+			.request_to_string(
+				RequestSer::borrowed(&id, &method, params.as_deref()).with_traceparent(self.traceparent()),
+			)
+			.map_err(Error::ParseError)?;
 		tx_log_from_str(&raw, self.max_log_length);
 
 		if self
@@ -402,6 +440,8 @@ impl ClientT for Client {
 		let guard = self.id_manager.next_request_id()?;
 		let id_range = generate_batch_id_range(&guard, batch.len() as u64)?;
 
+		// All entries of the batch are sent as part of the same outgoing call.
+		let traceparent = self.traceparent();
 		let mut batches = Vec::with_capacity(batch.len());
 		for ((method, params), id) in batch.into_iter().zip(id_range.clone()) {
 			let id = self.id_manager.as_id_kind().into_id(id);
@@ -410,12 +450,13 @@ impl ClientT for Client {
 				id,
 				method: method.into(),
 				params: params.map(StdCow::Owned),
+				traceparent: traceparent.clone().map(Into::into),
 			});
 		}
 
 		let (send_back_tx, send_back_rx) = oneshot::channel();
 
-		let raw = serde_json::to_string(&batches).map_err(Error::ParseError)?;
+		let raw = self.json_rpc_version.batch_to_string(batches).map_err(Error::ParseError)?;
 
 		tx_log_from_str(&raw, self.max_log_length);
 
@@ -484,8 +525,10 @@ impl SubscriptionClientT for Client {
 		let (id_sub, id_unsub) = guard.inner();
 		let params = params.to_rpc_params()?;
 
-		let raw = serde_json::to_string(&RequestSer::borrowed(&id_sub, &subscribe_method, params.as_deref()))
-			.map_err(Error::ParseError)?;
+		let raw = serde_json::to_string(
+			&RequestSer::borrowed(&id_sub, &subscribe_method, params.as_deref()).with_traceparent(self.traceparent()),
+		)
+		.map_err(Error::ParseError)?;
 
 		tx_log_from_str(&raw, self.max_log_length);
 
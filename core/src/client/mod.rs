@@ -39,7 +39,7 @@ use crate::traits::ToRpcParams;
 use async_trait::async_trait;
 use core::marker::PhantomData;
 use futures_util::stream::{Stream, StreamExt};
-use jsonrpsee_types::{ErrorObject, Id, SubscriptionId};
+use jsonrpsee_types::{ErrorObject, Id, NotificationSer, RequestSer, RequestV1Ser, SubscriptionId};
 use serde::de::DeserializeOwned;
 use serde_json::Value as JsonValue;
 use tokio::sync::{mpsc, oneshot};
@@ -485,6 +485,44 @@ impl IdKind {
 	}
 }
 
+/// Version of the JSON-RPC protocol a client speaks.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum JsonRpcVersion {
+	/// JSON-RPC 1.0, whose calls have no `jsonrpc` member and where a notification is a call with a null `id`.
+	///
+	/// Subscriptions are always made with JSON-RPC 2.0 calls.
+	V1,
+	/// JSON-RPC 2.0.
+	#[default]
+	V2,
+}
+
+impl JsonRpcVersion {
+	/// Serialize a method call as a call of this version.
+	pub fn request_to_string(self, request: RequestSer) -> Result<String, serde_json::Error> {
+		match self {
+			Self::V1 => serde_json::to_string(&RequestV1Ser::from(request)),
+			Self::V2 => serde_json::to_string(&request),
+		}
+	}
+
+	/// Serialize a notification as a call of this version.
+	pub fn notification_to_string(self, notif: NotificationSer) -> Result<String, serde_json::Error> {
+		match self {
+			Self::V1 => serde_json::to_string(&RequestV1Ser::from(notif)),
+			Self::V2 => serde_json::to_string(&notif),
+		}
+	}
+
+	/// Serialize a batch of method calls as calls of this version.
+	pub fn batch_to_string(self, batch: Vec<RequestSer>) -> Result<String, serde_json::Error> {
+		match self {
+			Self::V1 => serde_json::to_string(&batch.into_iter().map(RequestV1Ser::from).collect::<Vec<_>>()),
+			Self::V2 => serde_json::to_string(&batch),
+		}
+	}
+}
+
 /// Generate a range of IDs to be used in a batch request.
 pub fn generate_batch_id_range(guard: &RequestIdGuard<Id>, len: u64) -> Result<Range<u64>, Error> {
 	let id_start = guard.inner().try_parse_inner_as_number()?;
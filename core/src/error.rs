@@ -117,6 +117,9 @@ pub enum GenericTransportError {
 	/// Malformed request
 	#[error("Malformed request")]
 	Malformed,
+	/// The body was encoded with an unsupported `Content-Encoding`.
+	#[error("Unsupported content encoding")]
+	UnsupportedEncoding,
 	/// Concrete transport error.
 	#[error("Transport error: {0}")]
 	Inner(anyhow::Error),
@@ -33,9 +33,12 @@ use std::error::Error as StdError;
 
 /// Read a data from [`hyper::body::HttpBody`] and return the data if it is valid JSON and within the allowed size range.
 ///
+/// Bodies with a `gzip`, `deflate` or `br` [`Content-Encoding`](ContentEncoding) are decoded and must be within
+/// the allowed size range both before and after decoding.
+///
 /// Returns `Ok((bytes, single))` if the body was in valid size range; and a bool indicating whether the JSON-RPC
 /// request is a single or a batch.
-/// Returns `Err` if the body was too large or the body couldn't be read.
+/// Returns `Err` if the body was too large, used an unsupported encoding or couldn't be read.
 pub async fn read_body<B>(
 	headers: &hyper::HeaderMap,
 	body: B,
@@ -46,6 +49,37 @@ where
 	B::Data: Send,
 	B::Error: Into<Box<dyn StdError + Send + Sync>>,
 {
+	let mut received_data = read_body_bytes(headers, body, max_body_size).await?;
+
+	// trim the whitespaces to determine whether it's valid JSON-RPC call.
+	let first_non_whitespace = received_data.iter().enumerate().take(128).find(|(_, byte)| !byte.is_ascii_whitespace());
+
+	let (skip, is_single) = match first_non_whitespace {
+		Some((idx, b'{')) => (idx, true),
+		Some((idx, b'[')) => (idx, false),
+		_ => return Err(GenericTransportError::Malformed),
+	};
+
+	// ignore whitespace as these doesn't matter just makes the JSON decoding slower.
+	received_data.drain(..skip);
+
+	tracing::trace!("HTTP response body: {}", std::str::from_utf8(&received_data).unwrap_or("Invalid UTF-8 data"));
+	Ok((received_data, is_single))
+}
+
+/// Similar to [`read_body`] but the body is returned as it is, which is used for binary encoded bodies.
+pub async fn read_body_bytes<B>(
+	headers: &hyper::HeaderMap,
+	body: B,
+	max_body_size: u32,
+) -> Result<Vec<u8>, GenericTransportError>
+where
+	B: HttpBody + Send + 'static,
+	B::Data: Send,
+	B::Error: Into<Box<dyn StdError + Send + Sync>>,
+{
+	let encoding = ContentEncoding::from_headers(headers)?;
+
 	// NOTE(niklasad1): Values bigger than `u32::MAX` will be turned into zero here. This is unlikely to occur in
 	// practice and in that case we fallback to allocating in the while-loop below instead of pre-allocating.
 	let body_size = read_header_content_length(headers).unwrap_or(0);
@@ -58,52 +92,159 @@ where
 
 	// only allocate up to 16KB initially
 	let mut received_data = Vec::with_capacity(std::cmp::min(body_size as usize, 16 * 1024));
-	let mut is_single = None;
 
 	while let Some(d) = body.data().await {
 		let data = d.map_err(|e| GenericTransportError::Inner(anyhow!(e.into())))?;
 
-		// if it's the first chunk, trim the whitespaces to determine whether it's valid JSON-RPC call.
-		if received_data.is_empty() {
-			let first_non_whitespace =
-				data.chunk().iter().enumerate().take(128).find(|(_, byte)| !byte.is_ascii_whitespace());
+		if data.chunk().len() + received_data.len() > max_body_size as usize {
+			return Err(GenericTransportError::TooLarge);
+		}
 
-			let skip = match first_non_whitespace {
-				Some((idx, b'{')) => {
-					is_single = Some(true);
-					idx
-				}
-				Some((idx, b'[')) => {
-					is_single = Some(false);
-					idx
-				}
-				_ => return Err(GenericTransportError::Malformed),
-			};
+		received_data.extend_from_slice(data.chunk());
+	}
+
+	if let Some(encoding) = encoding {
+		received_data = encoding.decode(&received_data, max_body_size)?;
+	}
+
+	Ok(received_data)
+}
+
+/// Content coding of an HTTP body, as in the `Content-Encoding` and `Accept-Encoding` headers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ContentEncoding {
+	/// `gzip`.
+	Gzip,
+	/// `deflate`, i.e. the zlib format.
+	Deflate,
+	/// `br`, i.e. brotli.
+	Brotli,
+}
+
+impl ContentEncoding {
+	/// The supported encodings, in order of preference.
+	pub const ALL: [Self; 3] = [Self::Brotli, Self::Gzip, Self::Deflate];
 
-			if data.chunk().len() - skip > max_body_size as usize {
-				return Err(GenericTransportError::TooLarge);
+	/// The value of the `Accept-Encoding` header that accepts all supported encodings.
+	pub const ACCEPT_ALL: &'static str = "br, gzip, deflate";
+
+	/// Returns the name of the encoding in HTTP headers.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::Gzip => "gzip",
+			Self::Deflate => "deflate",
+			Self::Brotli => "br",
+		}
+	}
+
+	/// Parse the name of an encoding, returns `None` if it isn't supported.
+	pub fn parse(name: &str) -> Option<Self> {
+		let name = name.trim();
+		Self::ALL.into_iter().find(|encoding| name.eq_ignore_ascii_case(encoding.as_str())).or_else(|| {
+			// `x-gzip` is an alias of `gzip`.
+			name.eq_ignore_ascii_case("x-gzip").then_some(Self::Gzip)
+		})
+	}
+
+	/// Read the `Content-Encoding` of a body, returns `None` if the body isn't encoded.
+	///
+	/// Fails if the body was encoded with an unsupported encoding or with more than one encoding.
+	pub fn from_headers(headers: &hyper::HeaderMap) -> Result<Option<Self>, GenericTransportError> {
+		let mut values = headers.get_all(hyper::header::CONTENT_ENCODING).iter();
+
+		let Some(value) = values.next() else { return Ok(None) };
+		let value = value.to_str().map_err(|_| GenericTransportError::UnsupportedEncoding)?.trim();
+
+		if values.next().is_some() || value.contains(',') {
+			return Err(GenericTransportError::UnsupportedEncoding);
+		}
+
+		if value.is_empty() || value.eq_ignore_ascii_case("identity") {
+			return Ok(None);
+		}
+
+		Self::parse(value).map(Some).ok_or(GenericTransportError::UnsupportedEncoding)
+	}
+
+	/// Pick the preferred encoding out of those accepted by the `Accept-Encoding` headers, if any.
+	///
+	/// The encoding with the highest quality value wins, ties are broken by the order of [`ContentEncoding::ALL`].
+	pub fn negotiate(headers: &hyper::HeaderMap) -> Option<Self> {
+		let mut qualities = [None; 3];
+
+		for value in headers.get_all(hyper::header::ACCEPT_ENCODING).iter().filter_map(|v| v.to_str().ok()) {
+			for item in value.split(',') {
+				let mut parts = item.split(';');
+				let name = parts.next().unwrap_or_default().trim();
+				let quality = parts
+					.find_map(|param| param.trim().strip_prefix("q=").or_else(|| param.trim().strip_prefix("Q=")))
+					.map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())
+					.unwrap_or(0.0);
+
+				for (idx, encoding) in Self::ALL.into_iter().enumerate() {
+					// Explicitly named encodings take precedence over the wildcard.
+					if Self::parse(name) == Some(encoding) || (name == "*" && qualities[idx].is_none()) {
+						qualities[idx] = Some(quality);
+					}
+				}
 			}
+		}
 
-			// ignore whitespace as these doesn't matter just makes the JSON decoding slower.
-			received_data.extend_from_slice(&data.chunk()[skip..]);
-		} else {
-			if data.chunk().len() + received_data.len() > max_body_size as usize {
-				return Err(GenericTransportError::TooLarge);
+		let mut best: Option<(Self, f32)> = None;
+		for (encoding, quality) in Self::ALL.into_iter().zip(qualities) {
+			match quality {
+				Some(q) if q > 0.0 && best.map_or(true, |(_, best_q)| q > best_q) => best = Some((encoding, q)),
+				_ => (),
 			}
+		}
+
+		best.map(|(encoding, _)| encoding)
+	}
+
+	/// Encode `data`.
+	pub fn encode(&self, data: &[u8]) -> Vec<u8> {
+		use std::io::Write;
+
+		let mut out = Vec::with_capacity(data.len() / 4);
 
-			received_data.extend_from_slice(data.chunk());
+		match self {
+			Self::Gzip => {
+				let mut encoder = flate2::write::GzEncoder::new(&mut out, flate2::Compression::fast());
+				encoder.write_all(data).and_then(|_| encoder.finish().map(|_| ()))
+			}
+			Self::Deflate => {
+				let mut encoder = flate2::write::ZlibEncoder::new(&mut out, flate2::Compression::fast());
+				encoder.write_all(data).and_then(|_| encoder.finish().map(|_| ()))
+			}
+			Self::Brotli => {
+				let mut encoder = brotli::CompressorWriter::new(&mut out, 4096, 4, 22);
+				encoder.write_all(data).and_then(|_| encoder.flush())
+			}
 		}
+		.expect("Writing to a Vec is infallible; qed");
+
+		out
 	}
 
-	match is_single {
-		Some(single) if !received_data.is_empty() => {
-			tracing::trace!(
-				"HTTP response body: {}",
-				std::str::from_utf8(&received_data).unwrap_or("Invalid UTF-8 data")
-			);
-			Ok((received_data, single))
+	/// Decode `data`, fails if the decoded data is bigger than `max_size` or if `data` isn't encoded properly.
+	pub fn decode(&self, data: &[u8], max_size: u32) -> Result<Vec<u8>, GenericTransportError> {
+		use std::io::Read;
+
+		let reader: Box<dyn Read + '_> = match self {
+			Self::Gzip => Box::new(flate2::read::MultiGzDecoder::new(data)),
+			Self::Deflate => Box::new(flate2::read::ZlibDecoder::new(data)),
+			Self::Brotli => Box::new(brotli::Decompressor::new(data, 4096)),
+		};
+
+		let mut out = Vec::with_capacity(std::cmp::min(data.len() * 4, max_size as usize));
+		// Read one more byte than allowed to find out whether the decoded data is too big.
+		reader.take(max_size as u64 + 1).read_to_end(&mut out).map_err(|_| GenericTransportError::Malformed)?;
+
+		if out.len() > max_size as usize {
+			return Err(GenericTransportError::TooLarge);
 		}
-		_ => Err(GenericTransportError::Malformed),
+
+		Ok(out)
 	}
 }
 
@@ -138,7 +279,7 @@ pub fn read_header_values<'a>(
 
 #[cfg(test)]
 mod tests {
-	use super::{read_body, read_header_content_length};
+	use super::{read_body, read_header_content_length, ContentEncoding};
 
 	#[tokio::test]
 	async fn body_to_bytes_size_limit_works() {
@@ -163,4 +304,44 @@ mod tests {
 		headers.insert(hyper::header::CONTENT_LENGTH, "18446744073709551616".parse().unwrap());
 		assert_eq!(read_header_content_length(&headers), None);
 	}
+
+	#[tokio::test]
+	async fn read_encoded_body_works() {
+		let json = br#"  {"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+
+		for encoding in ContentEncoding::ALL {
+			let mut headers = hyper::header::HeaderMap::new();
+			headers.insert(hyper::header::CONTENT_ENCODING, encoding.as_str().parse().unwrap());
+
+			let body = hyper::Body::from(encoding.encode(json));
+			let (body, is_single) = read_body(&headers, body, 1024).await.unwrap();
+			assert_eq!(body, &json[2..]);
+			assert!(is_single);
+
+			// The limit applies to the decoded body.
+			let body = hyper::Body::from(encoding.encode(&[b' '; 4096]));
+			assert!(read_body(&headers, body, 1024).await.is_err());
+		}
+
+		let mut headers = hyper::header::HeaderMap::new();
+		headers.insert(hyper::header::CONTENT_ENCODING, "zstd".parse().unwrap());
+		let err = read_body(&headers, hyper::Body::from(&json[..]), 1024).await.unwrap_err();
+		assert!(matches!(err, crate::GenericTransportError::UnsupportedEncoding));
+	}
+
+	#[test]
+	fn negotiate_encoding_works() {
+		let negotiate = |accept: &str| {
+			let mut headers = hyper::header::HeaderMap::new();
+			headers.insert(hyper::header::ACCEPT_ENCODING, accept.parse().unwrap());
+			ContentEncoding::negotiate(&headers)
+		};
+
+		assert_eq!(negotiate("gzip, deflate, br"), Some(ContentEncoding::Brotli));
+		assert_eq!(negotiate("gzip;q=1.0, br;q=0.5"), Some(ContentEncoding::Gzip));
+		assert_eq!(negotiate("deflate"), Some(ContentEncoding::Deflate));
+		assert_eq!(negotiate("*, br;q=0"), Some(ContentEncoding::Gzip));
+		assert_eq!(negotiate("identity, zstd"), None);
+		assert_eq!(ContentEncoding::negotiate(&hyper::header::HeaderMap::new()), None);
+	}
 }
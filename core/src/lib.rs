@@ -46,6 +46,10 @@ cfg_http_helpers! {
 	pub mod http_helpers;
 }
 
+cfg_ws_deflate! {
+	pub mod ws_deflate;
+}
+
 cfg_server! {
 	pub mod id_providers;
 	pub mod server;
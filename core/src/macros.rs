@@ -26,6 +26,12 @@ macro_rules! cfg_http_helpers {
 	};
 }
 
+macro_rules! cfg_ws_deflate {
+	($($item:item)*) => {
+		cfg_feature!("ws-deflate", $($item)*);
+	};
+}
+
 macro_rules! cfg_async_client {
 	($($item:item)*) => {
 		$(
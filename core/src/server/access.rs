@@ -0,0 +1,148 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::server::{MethodAccess, Methods};
+use jsonrpsee_types::error::{reject_access_denied, ErrorCode};
+use jsonrpsee_types::{ErrorObjectOwned, Extensions};
+
+/// What the server does with calls to methods of a given [`MethodAccess`] class.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Permission {
+	/// The method can be called, this is the default.
+	#[default]
+	Allow,
+	/// Calls to the method are rejected with the
+	/// [`ACCESS_DENIED_CODE`](jsonrpsee_types::error::ACCESS_DENIED_CODE) error.
+	Deny,
+	/// The method is treated as if it wasn't registered and isn't visible to introspection.
+	Hide,
+}
+
+/// The role of an authenticated caller.
+///
+/// The server doesn't authenticate callers itself, instead a HTTP middleware inserts the role into the extensions
+/// of the HTTP request or a RPC middleware inserts it into the extensions of the call.
+/// The [`AccessPolicy`] then uses the role to look up the permissions of the caller.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Role(String);
+
+impl Role {
+	/// Create a new role.
+	pub fn new(role: impl Into<String>) -> Self {
+		Self(role.into())
+	}
+
+	/// Get the name of the role.
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+}
+
+/// Policy that decides whether the methods of each [`MethodAccess`] class are allowed, denied or hidden.
+///
+/// The permissions of a [`Role`] take precedence over the default permissions of the policy and
+/// everything that isn't configured is allowed.
+///
+/// The server inserts the policy into the [`Extensions`] of every call such that introspection methods can
+/// respect it, see [`AccessPolicy::visible_methods`].
+///
+/// ```
+/// use jsonrpsee_core::server::{AccessPolicy, MethodAccess, Permission};
+///
+/// // Hide the unsafe methods from everyone except the callers with the `admin` role.
+/// let policy = AccessPolicy::new()
+///     .set(MethodAccess::Unsafe, Permission::Hide)
+///     .set_for_role("admin", MethodAccess::Unsafe, Permission::Allow);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct AccessPolicy(Arc<Inner>);
+
+#[derive(Debug, Default, Clone)]
+struct Inner {
+	default: HashMap<MethodAccess, Permission>,
+	roles: HashMap<Role, HashMap<MethodAccess, Permission>>,
+}
+
+impl AccessPolicy {
+	/// Create a new policy which allows all methods.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the permission of the methods in the `access` class for all callers.
+	pub fn set(mut self, access: MethodAccess, permission: Permission) -> Self {
+		Arc::make_mut(&mut self.0).default.insert(access, permission);
+		self
+	}
+
+	/// Set the permission of the methods in the `access` class for the callers with the given `role`.
+	pub fn set_for_role(mut self, role: impl Into<String>, access: MethodAccess, permission: Permission) -> Self {
+		Arc::make_mut(&mut self.0).roles.entry(Role::new(role)).or_default().insert(access, permission);
+		self
+	}
+
+	/// Get the permission of the methods in the `access` class for a caller with the given `role`.
+	pub fn permission(&self, access: MethodAccess, role: Option<&Role>) -> Permission {
+		role.and_then(|role| self.0.roles.get(role))
+			.and_then(|permissions| permissions.get(&access))
+			.or_else(|| self.0.default.get(&access))
+			.copied()
+			.unwrap_or_default()
+	}
+
+	/// Get the names of the methods that aren't hidden from the caller of the call with the given `extensions`.
+	///
+	/// Denied methods are still listed because calling them reveals that they exist anyway.
+	pub fn visible_methods<'a>(
+		&'a self,
+		methods: &'a Methods,
+		extensions: &'a Extensions,
+	) -> impl Iterator<Item = &'static str> + 'a {
+		let role = extensions.get::<Role>();
+
+		methods
+			.method_names()
+			.filter(move |name| self.permission(methods.method_access(name), role) != Permission::Hide)
+	}
+
+	/// Check whether the caller of the call with the given `extensions` may call the method.
+	///
+	/// Methods that aren't registered are always permitted such that the server reports them as not found.
+	pub fn check(&self, methods: &Methods, method_name: &str, extensions: &Extensions) -> Result<(), ErrorObjectOwned> {
+		if methods.method(method_name).is_none() {
+			return Ok(());
+		}
+
+		match self.permission(methods.method_access(method_name), extensions.get::<Role>()) {
+			Permission::Allow => Ok(()),
+			Permission::Deny => Err(reject_access_denied(method_name)),
+			Permission::Hide => Err(ErrorCode::MethodNotFound.into()),
+		}
+	}
+}
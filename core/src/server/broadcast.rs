@@ -0,0 +1,231 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Broadcast of notifications to the subscribers of a subscription.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Weak};
+
+use crate::server::subscription::{PendingSubscriptionSink, SubscriptionMessage};
+use crate::SubscriptionResult;
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use tokio::sync::Notify;
+
+/// What happens to a subscriber which lags behind, once its buffer is full.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum LagPolicy {
+	/// Drop the oldest buffered notification to buffer the new one.
+	#[default]
+	DropOldest,
+	/// Drop the new notification.
+	DropNewest,
+	/// Close the subscription with an error notification.
+	Close,
+}
+
+/// Fans out notifications to the subscribers of a subscription.
+///
+/// The items are serialized once and buffered for every subscriber, which receives them as fast as its connection
+/// allows. Each subscriber has a bounded buffer and a [`LagPolicy`] which decides what happens once it's full.
+///
+/// The subscriptions end once the broadcaster has been closed with [`SubscriptionBroadcaster::close`], after their
+/// buffered notifications were sent. The subscription callbacks hold a clone of the broadcaster, so dropping the
+/// clone of the producer doesn't end them.
+///
+/// ```
+/// use jsonrpsee_core::server::{LagPolicy, RpcModule, SubscriptionBroadcaster};
+///
+/// let broadcaster = SubscriptionBroadcaster::new(16);
+/// let mut module = RpcModule::new(broadcaster.clone());
+/// module
+///     .register_subscription("subscribe_blocks", "block", "unsubscribe_blocks", |_, pending, broadcaster, _| {
+///         async move { broadcaster.subscribe(pending, LagPolicy::DropOldest).await }
+///     })
+///     .unwrap();
+///
+/// // Somewhere else, produce the items and close the subscriptions once done.
+/// broadcaster.send(&1337).unwrap();
+/// broadcaster.close();
+/// ```
+#[derive(Debug, Clone)]
+pub struct SubscriptionBroadcaster {
+	inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+	capacity: usize,
+	subscribers: Mutex<Subscribers>,
+}
+
+#[derive(Debug, Default)]
+struct Subscribers {
+	next_id: u64,
+	queues: FxHashMap<u64, Arc<Queue>>,
+	closed: bool,
+}
+
+/// The buffered notifications of a subscriber.
+#[derive(Debug)]
+struct Queue {
+	state: Mutex<QueueState>,
+	notify: Notify,
+	policy: LagPolicy,
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+	msgs: VecDeque<SubscriptionMessage>,
+	lagged: bool,
+	closed: bool,
+}
+
+impl SubscriptionBroadcaster {
+	/// Create a broadcaster which buffers up to `capacity` notifications per subscriber.
+	pub fn new(capacity: usize) -> Self {
+		Self { inner: Arc::new(Inner { capacity: capacity.max(1), subscribers: Default::default() }) }
+	}
+
+	/// Serialize the item as JSON and send it to all subscribers, returns the number of subscribers.
+	///
+	/// Fails if the item couldn't be serialized.
+	pub fn send(&self, item: &impl Serialize) -> Result<usize, serde_json::Error> {
+		SubscriptionMessage::from_json(item).map(|msg| self.send_message(msg))
+	}
+
+	/// Send the message to all subscribers, returns the number of subscribers.
+	pub fn send_message(&self, msg: SubscriptionMessage) -> usize {
+		let subscribers = self.inner.subscribers.lock();
+
+		for queue in subscribers.queues.values() {
+			let mut state = queue.state.lock();
+			if state.msgs.len() >= self.inner.capacity {
+				match queue.policy {
+					LagPolicy::DropOldest => {
+						state.msgs.pop_front();
+					}
+					LagPolicy::DropNewest => continue,
+					LagPolicy::Close => {
+						state.lagged = true;
+						drop(state);
+						queue.notify.notify_one();
+						continue;
+					}
+				}
+			}
+			state.msgs.push_back(msg.clone());
+			drop(state);
+			queue.notify.notify_one();
+		}
+
+		subscribers.queues.len()
+	}
+
+	/// Get the number of subscribers.
+	pub fn subscriber_count(&self) -> usize {
+		self.inner.subscribers.lock().queues.len()
+	}
+
+	/// End the subscriptions once their buffered notifications have been sent.
+	///
+	/// The subscriptions which are made afterwards end right away and the items which are sent afterwards are dropped.
+	pub fn close(&self) {
+		let mut subscribers = self.inner.subscribers.lock();
+		subscribers.closed = true;
+
+		for queue in subscribers.queues.drain().map(|(_, queue)| queue) {
+			queue.state.lock().closed = true;
+			queue.notify.notify_one();
+		}
+	}
+
+	/// Accept the subscription and send it the notifications of the broadcaster until it's closed.
+	///
+	/// The notifications which are sent once the subscription has been accepted are all received, unless they are
+	/// dropped by the lag `policy`. If the subscriber is closed because it lagged behind, this fails with the error
+	/// which is sent in the close notification when returned from the subscription callback.
+	pub async fn subscribe(&self, pending: PendingSubscriptionSink, policy: LagPolicy) -> SubscriptionResult {
+		let queue = Arc::new(Queue { state: Default::default(), notify: Notify::new(), policy });
+		let _guard = self.register(queue.clone());
+
+		let Ok(sink) = pending.accept().await else { return Ok(()) };
+
+		loop {
+			let msg = loop {
+				{
+					let mut state = queue.state.lock();
+					if state.lagged {
+						return Err("The subscription lagged behind the broadcaster".into());
+					}
+					if let Some(msg) = state.msgs.pop_front() {
+						break msg;
+					}
+					if state.closed {
+						return Ok(());
+					}
+				}
+
+				tokio::select! {
+					_ = queue.notify.notified() => (),
+					_ = sink.closed() => return Ok(()),
+				}
+			};
+
+			if sink.send(msg).await.is_err() {
+				return Ok(());
+			}
+		}
+	}
+
+	fn register(&self, queue: Arc<Queue>) -> Registration {
+		let mut subscribers = self.inner.subscribers.lock();
+		let id = subscribers.next_id;
+		subscribers.next_id += 1;
+		if subscribers.closed {
+			queue.state.lock().closed = true;
+		} else {
+			subscribers.queues.insert(id, queue);
+		}
+
+		Registration { inner: Arc::downgrade(&self.inner), id }
+	}
+}
+
+/// Removes the subscriber from the broadcaster once the subscription has ended.
+struct Registration {
+	inner: Weak<Inner>,
+	id: u64,
+}
+
+impl Drop for Registration {
+	fn drop(&mut self) {
+		if let Some(inner) = self.inner.upgrade() {
+			inner.subscribers.lock().queues.remove(&self.id);
+		}
+	}
+}
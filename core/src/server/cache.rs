@@ -0,0 +1,298 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Response cache for idempotent methods.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::server::Role;
+use jsonrpsee_types::{Extensions, Params};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use serde_json::value::RawValue;
+use serde_json::Value;
+
+/// Default maximum number of cached responses per method.
+const DEFAULT_MAX_ENTRIES: usize = 1024;
+
+/// Configuration of the response cache of a method.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ResponseCacheConfig {
+	ttl: Duration,
+	max_entries: usize,
+}
+
+impl ResponseCacheConfig {
+	/// Cache the successful responses of a method for `ttl`.
+	pub fn new(ttl: Duration) -> Self {
+		Self { ttl, max_entries: DEFAULT_MAX_ENTRIES }
+	}
+
+	/// Set the maximum number of cached responses (default is 1024).
+	///
+	/// When the cache is full, expired responses are dropped first
+	/// and otherwise the response that expires soonest.
+	pub fn max_entries(mut self, max: usize) -> Self {
+		self.max_entries = max;
+		self
+	}
+
+	/// Get the time-to-live of the cached responses.
+	pub fn ttl(&self) -> Duration {
+		self.ttl
+	}
+}
+
+impl From<Duration> for ResponseCacheConfig {
+	fn from(ttl: Duration) -> Self {
+		Self::new(ttl)
+	}
+}
+
+/// Computes the cache key of a call from its params and extensions, calls without a key aren't cached.
+pub(crate) type CacheKeyFn = dyn Fn(&Params, &Extensions) -> Option<String> + Send + Sync;
+
+/// Cache of the successful responses of a method, keyed by the [`Role`] of the caller and the canonicalized
+/// params of the call unless the method was cached with a custom key.
+///
+/// The cache is available in the [`Extensions`] of the calls to the cached method such that the method
+/// can invalidate its own entries, and through [`Methods::method_cache`](crate::server::Methods::method_cache).
+#[derive(Clone)]
+pub struct ResponseCache {
+	config: ResponseCacheConfig,
+	key: Arc<CacheKeyFn>,
+	entries: Arc<Mutex<FxHashMap<String, CacheEntry>>>,
+}
+
+impl fmt::Debug for ResponseCache {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ResponseCache").field("config", &self.config).field("entries", &self.entries).finish()
+	}
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+	result: Box<RawValue>,
+	expires_at: Instant,
+}
+
+impl ResponseCache {
+	pub(crate) fn new(config: ResponseCacheConfig, key: Arc<CacheKeyFn>) -> Self {
+		Self { config, key, entries: Default::default() }
+	}
+
+	/// Get the configuration of the cache.
+	pub fn config(&self) -> ResponseCacheConfig {
+		self.config
+	}
+
+	/// Drop the cached response of the call with `params` and `extensions`.
+	pub fn invalidate(&self, params: &Params, extensions: &Extensions) {
+		if let Some(key) = self.key(params, extensions) {
+			self.entries.lock().remove(&key);
+		}
+	}
+
+	/// Drop all cached responses.
+	pub fn clear(&self) {
+		self.entries.lock().clear();
+	}
+
+	/// Number of cached responses, including expired responses which haven't been dropped yet.
+	pub fn len(&self) -> usize {
+		self.entries.lock().len()
+	}
+
+	/// Returns whether there are no cached responses.
+	pub fn is_empty(&self) -> bool {
+		self.entries.lock().is_empty()
+	}
+
+	/// Get the cache key of the call with `params` and `extensions`.
+	pub(crate) fn key(&self, params: &Params, extensions: &Extensions) -> Option<String> {
+		(self.key)(params, extensions)
+	}
+
+	/// Get the cached result for `key` if it hasn't expired.
+	pub(crate) fn get(&self, key: &str) -> Option<Box<RawValue>> {
+		let mut entries = self.entries.lock();
+
+		match entries.get(key) {
+			Some(entry) if entry.expires_at > Instant::now() => Some(entry.result.clone()),
+			Some(_) => {
+				entries.remove(key);
+				None
+			}
+			None => None,
+		}
+	}
+
+	/// Cache `result` for `key`.
+	pub(crate) fn insert(&self, key: String, result: Box<RawValue>) {
+		if self.config.max_entries == 0 {
+			return;
+		}
+
+		let now = Instant::now();
+		let mut entries = self.entries.lock();
+
+		if entries.len() >= self.config.max_entries && !entries.contains_key(&key) {
+			entries.retain(|_, entry| entry.expires_at > now);
+		}
+
+		if entries.len() >= self.config.max_entries && !entries.contains_key(&key) {
+			let soonest = entries.iter().min_by_key(|(_, entry)| entry.expires_at).map(|(key, _)| key.clone());
+			if let Some(soonest) = soonest {
+				entries.remove(&soonest);
+			}
+		}
+
+		entries.insert(key, CacheEntry { result, expires_at: now + self.config.ttl });
+	}
+}
+
+/// Canonical representation of the params of a call, such that params which only differ
+/// in whitespace or in the order of the fields of objects share the same cache entry.
+///
+/// Returns `None` if the params are not valid JSON.
+pub(crate) fn cache_key(params: &Params) -> Option<String> {
+	#[cfg(feature = "cbor")]
+	if params.is_cbor() {
+		return None;
+	}
+
+	let Some(raw) = params.as_str() else {
+		return Some(String::new());
+	};
+
+	let value: Value = serde_json::from_str(raw).ok()?;
+	let mut key = String::with_capacity(raw.len());
+	write_canonical(&value, &mut key);
+
+	Some(key)
+}
+
+/// Key of a call made up of the [`Role`] of the caller and the canonicalized params,
+/// such that the calls of different roles don't share a result.
+pub(crate) fn role_cache_key(params: &Params, extensions: &Extensions) -> Option<String> {
+	let role = serde_json::to_string(&extensions.get::<Role>().map(Role::as_str)).ok()?;
+	cache_key(params).map(|params| format!("[{role},{params}]"))
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+	match value {
+		Value::Array(values) => {
+			out.push('[');
+			for (i, value) in values.iter().enumerate() {
+				if i > 0 {
+					out.push(',');
+				}
+				write_canonical(value, out);
+			}
+			out.push(']');
+		}
+		Value::Object(map) => {
+			let mut fields: Vec<_> = map.iter().collect();
+			fields.sort_unstable_by(|a, b| a.0.cmp(b.0));
+
+			out.push('{');
+			for (i, (key, value)) in fields.into_iter().enumerate() {
+				if i > 0 {
+					out.push(',');
+				}
+				out.push_str(&Value::String(key.clone()).to_string());
+				out.push(':');
+				write_canonical(value, out);
+			}
+			out.push('}');
+		}
+		other => out.push_str(&other.to_string()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{cache_key, role_cache_key, ResponseCache, ResponseCacheConfig};
+	use crate::server::Role;
+	use jsonrpsee_types::{Extensions, Params};
+	use serde_json::value::RawValue;
+	use std::sync::Arc;
+	use std::time::Duration;
+
+	fn raw(s: &str) -> Box<RawValue> {
+		RawValue::from_string(s.to_owned()).unwrap()
+	}
+
+	#[test]
+	fn cache_key_is_canonical() {
+		let a = cache_key(&Params::new(Some(r#"{"b": [1, {"d": 1, "c": 2}], "a": "x"}"#)));
+		let b = cache_key(&Params::new(Some(r#"{"a":"x","b":[1,{"c":2,"d":1}]}"#)));
+
+		assert_eq!(a, b);
+		assert_eq!(a.as_deref(), Some(r#"{"a":"x","b":[1,{"c":2,"d":1}]}"#));
+		assert_ne!(cache_key(&Params::new(Some("[1,2]"))), cache_key(&Params::new(Some("[2,1]"))));
+		assert_eq!(cache_key(&Params::new(None)).as_deref(), Some(""));
+		assert_eq!(cache_key(&Params::new(Some("[1,"))), None);
+	}
+
+	#[test]
+	fn role_cache_key_includes_the_role() {
+		let params = Params::new(Some("[1]"));
+		let mut extensions = Extensions::new();
+		assert_eq!(role_cache_key(&params, &extensions).as_deref(), Some("[null,[1]]"));
+
+		extensions.insert(Role::new("admin"));
+		assert_eq!(role_cache_key(&params, &extensions).as_deref(), Some(r#"["admin",[1]]"#));
+	}
+
+	#[test]
+	fn cache_entries_expire() {
+		let cache = ResponseCache::new(ResponseCacheConfig::new(Duration::ZERO), Arc::new(role_cache_key));
+		cache.insert("[]".into(), raw("1"));
+
+		assert!(cache.get("[]").is_none());
+		assert!(cache.is_empty());
+	}
+
+	#[test]
+	fn cache_is_bounded() {
+		let config = ResponseCacheConfig::new(Duration::from_secs(60)).max_entries(2);
+		let cache = ResponseCache::new(config, Arc::new(role_cache_key));
+		cache.insert("[null,[1]]".into(), raw("1"));
+		cache.insert("[null,[2]]".into(), raw("2"));
+		cache.insert("[null,[3]]".into(), raw("3"));
+
+		assert_eq!(cache.len(), 2);
+		assert_eq!(cache.get("[null,[3]]").unwrap().get(), "3");
+
+		cache.invalidate(&Params::new(Some("[ 3 ]")), &Extensions::new());
+		assert!(cache.get("[null,[3]]").is_none());
+		cache.clear();
+		assert!(cache.is_empty());
+	}
+}
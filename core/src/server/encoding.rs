@@ -0,0 +1,382 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::fmt;
+
+use jsonrpsee_types::Extensions;
+
+use crate::server::StreamedMessage;
+
+/// Content type of JSON encoded HTTP bodies.
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// Content type of CBOR encoded HTTP bodies.
+#[cfg(feature = "cbor")]
+pub const CBOR_CONTENT_TYPE: &str = "application/cbor";
+
+/// WebSocket subprotocol to exchange CBOR encoded messages.
+#[cfg(feature = "cbor")]
+pub const CBOR_SUBPROTOCOL: &str = "jsonrpc.cbor";
+
+/// Encoding of the JSON-RPC messages of a connection or call.
+///
+/// The messages use the JSON-RPC 2.0 envelope, except for [`Encoding::JsonV1`]. The server
+/// inserts the encoding of a call into its [`Extensions`], see [`Encoding::from_extensions`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Encoding {
+	/// JSON text, as demanded by the JSON-RPC specification.
+	#[default]
+	Json,
+	/// JSON text in the [JSON-RPC 1.0](https://www.jsonrpc.org/specification_v1) envelope, used to
+	/// answer JSON-RPC 1.0 requests.
+	///
+	/// Responses have no `jsonrpc` member and carry both `result` and `error`, one of which is `null`.
+	/// Notifications have no `jsonrpc` member either and a `null` id.
+	JsonV1,
+	/// Binary [CBOR](https://www.rfc-editor.org/rfc/rfc8949), exchanged as WebSocket binary frames
+	/// or as `application/cbor` HTTP bodies.
+	#[cfg(feature = "cbor")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+	Cbor,
+}
+
+impl Encoding {
+	/// Get the encoding of a call from its extensions, which defaults to [`Encoding::Json`].
+	pub fn from_extensions(extensions: &Extensions) -> Self {
+		extensions.get::<Encoding>().copied().unwrap_or_default()
+	}
+
+	/// Get the encoding of an HTTP body from the value of its `Content-Type` header.
+	///
+	/// Parameters of the media type such as `charset` are ignored.
+	pub fn from_content_type(content_type: &str) -> Option<Self> {
+		let media_type = content_type.split(';').next().unwrap_or_default().trim();
+
+		if media_type.eq_ignore_ascii_case(JSON_CONTENT_TYPE) {
+			return Some(Self::Json);
+		}
+
+		#[cfg(feature = "cbor")]
+		if media_type.eq_ignore_ascii_case(CBOR_CONTENT_TYPE) {
+			return Some(Self::Cbor);
+		}
+
+		None
+	}
+
+	/// Get the encoding of a WebSocket subprotocol, `None` if it isn't an encoding.
+	pub fn from_subprotocol(protocol: &str) -> Option<Self> {
+		match protocol {
+			#[cfg(feature = "cbor")]
+			CBOR_SUBPROTOCOL => Some(Self::Cbor),
+			_ => None,
+		}
+	}
+
+	/// The content type of HTTP bodies in this encoding.
+	pub const fn content_type(&self) -> &'static str {
+		match self {
+			Self::Json | Self::JsonV1 => JSON_CONTENT_TYPE,
+			#[cfg(feature = "cbor")]
+			Self::Cbor => CBOR_CONTENT_TYPE,
+		}
+	}
+
+	/// The WebSocket subprotocol that selects this encoding, `None` for JSON which needs none.
+	pub const fn subprotocol(&self) -> Option<&'static str> {
+		match self {
+			Self::Json | Self::JsonV1 => None,
+			#[cfg(feature = "cbor")]
+			Self::Cbor => Some(CBOR_SUBPROTOCOL),
+		}
+	}
+}
+
+/// A serialized JSON-RPC message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodedMessage {
+	/// JSON text.
+	Text(String),
+	/// Binary encoded message, such as CBOR.
+	Binary(Vec<u8>),
+	/// JSON text which is produced while it's sent, such as the response of a streaming method.
+	///
+	/// It has no bytes until it's sent, which the transports do in chunks without buffering it.
+	Stream(StreamedMessage),
+}
+
+impl EncodedMessage {
+	/// Returns the message as `&str` if it's text.
+	pub fn as_str(&self) -> Option<&str> {
+		match self {
+			Self::Text(text) => Some(text),
+			Self::Binary(_) | Self::Stream(_) => None,
+		}
+	}
+
+	/// Returns the bytes of the message, which are empty for a [`EncodedMessage::Stream`].
+	pub fn as_bytes(&self) -> &[u8] {
+		match self {
+			Self::Text(text) => text.as_bytes(),
+			Self::Binary(bytes) => bytes,
+			Self::Stream(_) => &[],
+		}
+	}
+
+	/// Consume the message and returns its bytes, which are empty for a [`EncodedMessage::Stream`].
+	pub fn into_bytes(self) -> Vec<u8> {
+		match self {
+			Self::Text(text) => text.into_bytes(),
+			Self::Binary(bytes) => bytes,
+			Self::Stream(_) => Vec::new(),
+		}
+	}
+
+	/// Returns the length of the message in bytes.
+	pub fn len(&self) -> usize {
+		self.as_bytes().len()
+	}
+
+	/// Returns whether the message is empty.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Returns the message as text, or a placeholder if the message is binary or streamed, for logging.
+	pub fn as_log_str(&self) -> &str {
+		match self {
+			Self::Text(text) => text,
+			Self::Binary(_) => "<binary>",
+			Self::Stream(_) => "<stream>",
+		}
+	}
+}
+
+impl fmt::Display for EncodedMessage {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.as_log_str())
+	}
+}
+
+impl From<String> for EncodedMessage {
+	fn from(text: String) -> Self {
+		Self::Text(text)
+	}
+}
+
+impl From<Vec<u8>> for EncodedMessage {
+	fn from(bytes: Vec<u8>) -> Self {
+		Self::Binary(bytes)
+	}
+}
+
+impl PartialEq<str> for EncodedMessage {
+	fn eq(&self, other: &str) -> bool {
+		self.as_str() == Some(other)
+	}
+}
+
+impl PartialEq<&str> for EncodedMessage {
+	fn eq(&self, other: &&str) -> bool {
+		self.as_str() == Some(*other)
+	}
+}
+
+impl PartialEq<String> for EncodedMessage {
+	fn eq(&self, other: &String) -> bool {
+		self.as_str() == Some(other.as_str())
+	}
+}
+
+impl PartialEq<EncodedMessage> for String {
+	fn eq(&self, other: &EncodedMessage) -> bool {
+		other == self
+	}
+}
+
+impl PartialEq<EncodedMessage> for &str {
+	fn eq(&self, other: &EncodedMessage) -> bool {
+		other == self
+	}
+}
+
+pub(crate) mod json_v1 {
+	//! JSON-RPC 1.0 envelope.
+
+	use jsonrpsee_types::{Id, ResponsePayload};
+	use serde::ser::{SerializeStruct, Serializer};
+	use serde::Serialize;
+
+	/// JSON-RPC 1.0 response, which carries both `result` and `error`.
+	pub(crate) struct Response<'a, T: Clone> {
+		pub(crate) payload: &'a ResponsePayload<'a, T>,
+		pub(crate) id: &'a Id<'a>,
+	}
+
+	impl<'a, T: Serialize + Clone> Serialize for Response<'a, T> {
+		fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+			let mut s = serializer.serialize_struct("Response", 3)?;
+
+			match self.payload {
+				ResponsePayload::Error(err) => {
+					s.serialize_field("result", &())?;
+					s.serialize_field("error", err)?;
+				}
+				ResponsePayload::Result(r) => {
+					s.serialize_field("result", &**r)?;
+					s.serialize_field("error", &())?;
+				}
+			};
+
+			s.serialize_field("id", self.id)?;
+			s.end()
+		}
+	}
+}
+
+#[cfg(feature = "cbor")]
+pub(crate) mod cbor {
+	//! CBOR encoding of the JSON-RPC 2.0 envelope.
+	//!
+	//! The envelopes are serialized with their `id` as the last field, just like the JSON
+	//! envelopes, such that the `id` of a response can be replaced without decoding it.
+
+	use std::io;
+
+	use jsonrpsee_types::{ErrorObject, Id, ResponsePayload, SubscriptionId, TwoPointZero};
+	use serde::ser::{SerializeStruct, Serializer};
+	use serde::Serialize;
+
+	/// CBOR encoding of the text string `"id"`.
+	const ID_KEY: [u8; 3] = [0x62, b'i', b'd'];
+	/// CBOR encoding of `null`.
+	const NULL: u8 = 0xf6;
+
+	/// Serialization error, where an I/O error means that the max size was exceeded.
+	pub(crate) type Error = ciborium::ser::Error<io::Error>;
+
+	/// JSON-RPC response which is serialized with the error `data` as structured value instead of raw JSON.
+	pub(crate) struct Response<'a, T: Clone> {
+		pub(crate) payload: &'a ResponsePayload<'a, T>,
+		pub(crate) id: &'a Id<'a>,
+	}
+
+	impl<'a, T: Serialize + Clone> Serialize for Response<'a, T> {
+		fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+			let mut s = serializer.serialize_struct("Response", 3)?;
+			s.serialize_field("jsonrpc", &TwoPointZero)?;
+
+			match self.payload {
+				ResponsePayload::Error(err) => s.serialize_field("error", &ErrorObjectSer(err))?,
+				ResponsePayload::Result(r) => s.serialize_field("result", &**r)?,
+			};
+
+			s.serialize_field("id", self.id)?;
+			s.end()
+		}
+	}
+
+	struct ErrorObjectSer<'a>(&'a ErrorObject<'a>);
+
+	impl<'a> Serialize for ErrorObjectSer<'a> {
+		fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+			let data = self.0.data().map(|raw| serde_json::from_str::<serde_json::Value>(raw.get()));
+			let data = data.transpose().map_err(serde::ser::Error::custom)?;
+
+			let mut s = serializer.serialize_struct("ErrorObject", if data.is_some() { 3 } else { 2 })?;
+			s.serialize_field("code", &self.0.code())?;
+			s.serialize_field("message", self.0.message())?;
+			if let Some(data) = data {
+				s.serialize_field("data", &data)?;
+			}
+			s.end()
+		}
+	}
+
+	/// Serialize a JSON-RPC response.
+	pub(crate) fn write_response<T: Serialize + Clone>(
+		payload: &ResponsePayload<T>,
+		id: &Id,
+		writer: impl io::Write,
+	) -> Result<(), Error> {
+		ciborium::into_writer(&Response { payload, id }, writer)
+	}
+
+	/// Serialize a JSON-RPC response with an infallible writer.
+	pub(crate) fn response<T: Serialize + Clone>(payload: &ResponsePayload<T>, id: &Id) -> Vec<u8> {
+		let mut bytes = Vec::new();
+		write_response(payload, id, &mut bytes).expect("CBOR serialization infallible; qed");
+		bytes
+	}
+
+	/// Copy of a response made with `Id::Null` with `id` instead, `None` if it wasn't made with `Id::Null`.
+	pub(crate) fn with_id(response: &[u8], id: &Id) -> Option<Vec<u8>> {
+		let body = response.strip_suffix(&[ID_KEY[0], ID_KEY[1], ID_KEY[2], NULL])?;
+
+		let mut bytes = Vec::with_capacity(body.len() + 16);
+		bytes.extend_from_slice(body);
+		bytes.extend_from_slice(&ID_KEY);
+		ciborium::into_writer(id, &mut bytes).expect("Id serialization infallible; qed");
+		Some(bytes)
+	}
+
+	/// Serialize a subscription notification, where `result` is the already serialized result or error.
+	pub(crate) fn notification(method: &str, sub_id: &SubscriptionId, result_or_err: &str, result: &[u8]) -> Vec<u8> {
+		let mut bytes = Vec::with_capacity(result.len() + method.len() + 64);
+		// A map of three entries: `jsonrpc`, `method` and `params`.
+		bytes.push(0xa3);
+		write(&"jsonrpc", &mut bytes);
+		write(&TwoPointZero, &mut bytes);
+		write(&"method", &mut bytes);
+		write(&method, &mut bytes);
+		write(&"params", &mut bytes);
+		// A map of two entries: `subscription` and `result` or `error`.
+		bytes.push(0xa2);
+		write(&"subscription", &mut bytes);
+		write(sub_id, &mut bytes);
+		write(&result_or_err, &mut bytes);
+		bytes.extend_from_slice(result);
+		bytes
+	}
+
+	/// Serialize a value which has already been serialized as JSON.
+	pub(crate) fn from_json(json: &str) -> Result<Vec<u8>, serde_json::Error> {
+		let value: serde_json::Value = serde_json::from_str(json)?;
+		let mut bytes = Vec::with_capacity(json.len());
+		write(&value, &mut bytes);
+		Ok(bytes)
+	}
+
+	/// Serialize a CBOR value as JSON.
+	pub(crate) fn to_json(bytes: &[u8]) -> Result<String, serde_json::Error> {
+		let value: ciborium::Value = ciborium::from_reader(bytes).map_err(serde::de::Error::custom)?;
+		serde_json::to_string(&value)
+	}
+
+	fn write(value: &impl Serialize, bytes: &mut Vec<u8>) {
+		ciborium::into_writer(value, bytes).expect("CBOR serialization infallible; qed");
+	}
+}
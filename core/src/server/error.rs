@@ -24,7 +24,7 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use crate::server::SubscriptionMessage;
+use crate::server::{EncodedMessage, SubscriptionMessage};
 use tokio::sync::mpsc;
 
 /// Error that may occur during [`crate::server::MethodSink::try_send`] or [`crate::server::SubscriptionSink::try_send`].
@@ -60,14 +60,14 @@ pub enum SendTimeoutError {
 #[error("The remote peer closed the connection")]
 pub struct PendingSubscriptionAcceptError;
 
-impl From<mpsc::error::SendError<String>> for DisconnectError {
-	fn from(e: mpsc::error::SendError<String>) -> Self {
+impl From<mpsc::error::SendError<EncodedMessage>> for DisconnectError {
+	fn from(e: mpsc::error::SendError<EncodedMessage>) -> Self {
 		DisconnectError(SubscriptionMessage::from_complete_message(e.0))
 	}
 }
 
-impl From<mpsc::error::TrySendError<String>> for TrySendError {
-	fn from(e: mpsc::error::TrySendError<String>) -> Self {
+impl From<mpsc::error::TrySendError<EncodedMessage>> for TrySendError {
+	fn from(e: mpsc::error::TrySendError<EncodedMessage>) -> Self {
 		match e {
 			mpsc::error::TrySendError::Closed(m) => Self::Closed(SubscriptionMessage::from_complete_message(m)),
 			mpsc::error::TrySendError::Full(m) => Self::Full(SubscriptionMessage::from_complete_message(m)),
@@ -75,8 +75,8 @@ impl From<mpsc::error::TrySendError<String>> for TrySendError {
 	}
 }
 
-impl From<mpsc::error::SendTimeoutError<String>> for SendTimeoutError {
-	fn from(e: mpsc::error::SendTimeoutError<String>) -> Self {
+impl From<mpsc::error::SendTimeoutError<EncodedMessage>> for SendTimeoutError {
+	fn from(e: mpsc::error::SendTimeoutError<EncodedMessage>) -> Self {
 		match e {
 			mpsc::error::SendTimeoutError::Closed(m) => Self::Closed(SubscriptionMessage::from_complete_message(m)),
 			mpsc::error::SendTimeoutError::Timeout(m) => Self::Timeout(SubscriptionMessage::from_complete_message(m)),
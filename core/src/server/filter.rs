@@ -0,0 +1,413 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Poll-based filters of subscriptions.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::id_providers::RandomIntegerIdProvider;
+use crate::server::encoding::{EncodedMessage, Encoding};
+use crate::server::helpers::{MethodResponse, MethodSink};
+use crate::server::rpc_module::{ConnectionId, MaxResponseSize, SubscriptionMethod, UnsubscriptionMethod};
+use crate::server::subscription::{BoundedSubscriptions, SubscriptionState};
+use crate::traits::IdProvider;
+use jsonrpsee_types::error::{
+	reject_filter_not_found, reject_too_many_subscriptions, ErrorCode, ErrorObjectOwned, CALL_EXECUTION_FAILED_CODE,
+};
+use jsonrpsee_types::{Extensions, Id, Params, Response, ResponsePayload, SubscriptionId};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+/// Default maximum number of filters.
+const DEFAULT_MAX_FILTERS: u32 = 1024;
+/// Default maximum number of filters per client.
+const DEFAULT_MAX_FILTERS_PER_CLIENT: u32 = 64;
+/// Default maximum number of buffered notifications per filter.
+const DEFAULT_MAX_BUFFERED: usize = 1024;
+
+/// The subscriptions of the filters aren't tied to a connection, they share this connection ID.
+const FILTER_CONN_ID: ConnectionId = ConnectionId::MAX;
+
+/// Message of the error answered once the subscription of a filter was closed with an error.
+const SUBSCRIPTION_CLOSED_WITH_ERROR_MSG: &str = "Subscription closed with an error";
+
+/// Configuration of the filters of a subscription.
+#[derive(Debug, Clone)]
+pub struct FilterConfig {
+	idle_timeout: Duration,
+	max_filters: u32,
+	max_filters_per_client: u32,
+	max_buffered: usize,
+	id_provider: Option<Arc<dyn IdProvider>>,
+}
+
+impl FilterConfig {
+	/// Drop filters whose changes haven't been polled for `idle_timeout`.
+	pub fn new(idle_timeout: Duration) -> Self {
+		Self {
+			idle_timeout,
+			max_filters: DEFAULT_MAX_FILTERS,
+			max_filters_per_client: DEFAULT_MAX_FILTERS_PER_CLIENT,
+			max_buffered: DEFAULT_MAX_BUFFERED,
+			id_provider: None,
+		}
+	}
+
+	/// Set the maximum number of filters in total (default is 1024).
+	pub fn max_filters(mut self, max: u32) -> Self {
+		self.max_filters = max;
+		self
+	}
+
+	/// Set the maximum number of filters per client (default is 64).
+	///
+	/// Clients are told apart by the [`IpAddr`] found in the extensions of the call, which the server inserts
+	/// for TCP connections, and otherwise by their connection.
+	pub fn max_filters_per_client(mut self, max: u32) -> Self {
+		self.max_filters_per_client = max;
+		self
+	}
+
+	/// Set the maximum number of buffered notifications per filter (default is 1024).
+	///
+	/// Once the buffer is full, the subscription can't send notifications until the changes have been polled.
+	pub fn max_buffered(mut self, max: usize) -> Self {
+		self.max_buffered = max;
+		self
+	}
+
+	/// Set the provider of the filter IDs.
+	///
+	/// By default, the provider of the server is used, which is found in the extensions of the call
+	/// as `Arc<dyn IdProvider>`. Without it, [`RandomIntegerIdProvider`] is used.
+	pub fn id_provider<I: IdProvider + 'static>(mut self, id_provider: I) -> Self {
+		self.id_provider = Some(Arc::new(id_provider));
+		self
+	}
+
+	/// Get the idle timeout of the filters.
+	pub fn idle_timeout(&self) -> Duration {
+		self.idle_timeout
+	}
+}
+
+impl From<Duration> for FilterConfig {
+	fn from(idle_timeout: Duration) -> Self {
+		Self::new(idle_timeout)
+	}
+}
+
+/// Buffered subscription which is polled for its notifications.
+struct Filter {
+	rx: mpsc::Receiver<EncodedMessage>,
+	/// The result which didn't fit in the last response.
+	pending: Option<Value>,
+	/// The error the subscription was closed with, answered once the buffered results were polled.
+	error: Option<Value>,
+	last_poll: Instant,
+	_client_permit: ClientPermit,
+}
+
+/// The client which created a filter.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum Client {
+	Ip(IpAddr),
+	Connection(ConnectionId),
+}
+
+/// Counts a filter of a client until it's dropped.
+struct ClientPermit {
+	client: Client,
+	clients: Arc<Mutex<FxHashMap<Client, u32>>>,
+}
+
+impl Drop for ClientPermit {
+	fn drop(&mut self) {
+		let mut clients = self.clients.lock();
+		if let Some(count) = clients.get_mut(&self.client) {
+			*count -= 1;
+			if *count == 0 {
+				clients.remove(&self.client);
+			}
+		}
+	}
+}
+
+/// The filters of a subscription, keyed by the ID of the subscription.
+#[derive(Clone)]
+pub(crate) struct Filters {
+	config: FilterConfig,
+	subscribe: SubscriptionMethod<'static>,
+	unsubscribe: UnsubscriptionMethod,
+	bounded_subscriptions: BoundedSubscriptions,
+	clients: Arc<Mutex<FxHashMap<Client, u32>>>,
+	filters: Arc<Mutex<FxHashMap<SubscriptionId<'static>, Filter>>>,
+}
+
+impl Filters {
+	pub(crate) fn new(
+		config: FilterConfig,
+		subscribe: SubscriptionMethod<'static>,
+		unsubscribe: UnsubscriptionMethod,
+	) -> Self {
+		Self {
+			bounded_subscriptions: BoundedSubscriptions::new(config.max_filters),
+			config,
+			subscribe,
+			unsubscribe,
+			clients: Default::default(),
+			filters: Default::default(),
+		}
+	}
+
+	/// Subscribe with `params` and answer with the ID of the new filter.
+	pub(crate) async fn create(
+		&self,
+		id: Id<'static>,
+		params: Params<'static>,
+		conn_id: ConnectionId,
+		max_response_size: MaxResponseSize,
+		mut extensions: Extensions,
+	) -> MethodResponse {
+		let encoding = Encoding::from_extensions(&extensions);
+
+		let client = match extensions.get::<IpAddr>() {
+			Some(ip) => Client::Ip(*ip),
+			None => Client::Connection(conn_id),
+		};
+		let Some(client_permit) = self.acquire_client(client) else {
+			let err = reject_too_many_subscriptions(self.config.max_filters_per_client);
+			return MethodResponse::encoded_error(id, err, encoding);
+		};
+		let Some(subscription_permit) = self.bounded_subscriptions.acquire() else {
+			let err = reject_too_many_subscriptions(self.config.max_filters);
+			return MethodResponse::encoded_error(id, err, encoding);
+		};
+
+		// The notifications are buffered as JSON, regardless of the encoding of the call.
+		extensions.insert(Encoding::Json);
+		let (tx, mut rx) = mpsc::channel(self.config.max_buffered.max(1));
+		let id_provider = match &self.config.id_provider {
+			Some(id_provider) => id_provider.clone(),
+			None => {
+				extensions.get::<Arc<dyn IdProvider>>().cloned().unwrap_or_else(|| Arc::new(RandomIntegerIdProvider))
+			}
+		};
+		let state = SubscriptionState { conn_id: FILTER_CONN_ID, id_provider: &*id_provider, subscription_permit };
+		let rp = (self.subscribe)(Id::Number(0), params, MethodSink::new(tx), state, extensions).await;
+
+		// The response to the subscription call is sent to the sink too, it's answered below instead.
+		let _ = rx.recv().await;
+
+		let response = match rp {
+			Ok(rp) => rp.result.as_str().and_then(|rp| serde_json::from_str::<SubscribeResponse>(rp).ok()),
+			Err(_) => None,
+		};
+
+		let sub_id = match response {
+			Some(SubscribeResponse { result: Some(result), .. }) => match SubscriptionId::try_from(result) {
+				Ok(sub_id) => sub_id,
+				Err(_) => return MethodResponse::encoded_error(id, ErrorCode::InternalError, encoding),
+			},
+			Some(SubscribeResponse { error: Some(err), .. }) => {
+				return MethodResponse::encoded_error(id, err, encoding)
+			}
+			_ => return MethodResponse::encoded_error(id, ErrorCode::InternalError, encoding),
+		};
+
+		self.filters.lock().insert(
+			sub_id.clone(),
+			Filter { rx, pending: None, error: None, last_poll: Instant::now(), _client_permit: client_permit },
+		);
+		tokio::spawn(self.clone().expire(sub_id.clone()));
+
+		MethodResponse::encoded_response(id, ResponsePayload::result(sub_id), max_response_size, encoding)
+	}
+
+	/// Count a new filter of `client`, unless it has reached the maximum number of filters per client.
+	fn acquire_client(&self, client: Client) -> Option<ClientPermit> {
+		let mut clients = self.clients.lock();
+		let count = clients.entry(client).or_insert(0);
+		if *count >= self.config.max_filters_per_client {
+			return None;
+		}
+		*count += 1;
+
+		Some(ClientPermit { client, clients: self.clients.clone() })
+	}
+
+	/// Answer with the results of the notifications which were buffered since the filter was last polled.
+	///
+	/// The results which don't fit in `max_response_size` are kept for the next poll. If the subscription was
+	/// closed with an error, the error is answered once all of the results were polled.
+	///
+	/// The filter is dropped once the subscription has been closed and all of its notifications were polled.
+	pub(crate) fn changes(
+		&self,
+		id: Id,
+		params: Params,
+		max_response_size: MaxResponseSize,
+		encoding: Encoding,
+	) -> MethodResponse {
+		let Ok(sub_id) = params.one::<SubscriptionId>().map(SubscriptionId::into_owned) else {
+			return MethodResponse::encoded_error(id, ErrorCode::InvalidParams, encoding);
+		};
+
+		let mut filters = self.filters.lock();
+		let Some(filter) = filters.get_mut(&sub_id) else {
+			return MethodResponse::encoded_error(id, reject_filter_not_found(), encoding);
+		};
+
+		filter.last_poll = Instant::now();
+		let mut changes = Vec::new();
+		let mut closed = false;
+		// The JSON size of the response, which bounds the size of the other encodings.
+		let mut size = serde_json::to_string(&Response::new(ResponsePayload::result(&changes), id.clone()))
+			.expect("JSON serialization infallible; qed")
+			.len();
+
+		loop {
+			let result = match filter.pending.take() {
+				Some(result) => result,
+				None if filter.error.is_some() => break,
+				None => match filter.rx.try_recv() {
+					Ok(msg) => {
+						let Some(params) = msg
+							.as_str()
+							.and_then(|msg| serde_json::from_str::<Notification>(msg).ok())
+							.map(|n| n.params)
+						else {
+							continue;
+						};
+						match (params.result, params.error) {
+							(Some(result), _) => result,
+							(None, Some(err)) => {
+								tracing::debug!("Subscription of filter {:?} closed with error: {}", sub_id, err);
+								filter.error = Some(err);
+								break;
+							}
+							(None, None) => continue,
+						}
+					}
+					Err(mpsc::error::TryRecvError::Empty) => break,
+					Err(mpsc::error::TryRecvError::Disconnected) => {
+						closed = true;
+						break;
+					}
+				},
+			};
+
+			// A result which doesn't fit in an empty response is answered with the oversized response error.
+			let len =
+				serde_json::to_string(&result).map_or(0, |result| result.len()) + usize::from(!changes.is_empty());
+			if !changes.is_empty() && size + len > max_response_size {
+				filter.pending = Some(result);
+				break;
+			}
+			size += len;
+			changes.push(result);
+		}
+
+		let error = if changes.is_empty() { filter.error.take() } else { None };
+		if closed || error.is_some() {
+			filters.remove(&sub_id);
+		}
+		drop(filters);
+
+		if let Some(err) = error {
+			let err =
+				ErrorObjectOwned::owned(CALL_EXECUTION_FAILED_CODE, SUBSCRIPTION_CLOSED_WITH_ERROR_MSG, Some(err));
+			return MethodResponse::encoded_error(id, err, encoding);
+		}
+
+		MethodResponse::encoded_response(id, ResponsePayload::result(changes), max_response_size, encoding)
+	}
+
+	/// Drop the filter and answer with whether it existed.
+	pub(crate) fn uninstall(
+		&self,
+		id: Id,
+		params: Params,
+		max_response_size: MaxResponseSize,
+		encoding: Encoding,
+	) -> MethodResponse {
+		let removed = match params.one::<SubscriptionId>() {
+			Ok(sub_id) => self.remove(&sub_id.into_owned()),
+			Err(_) => return MethodResponse::encoded_error(id, ErrorCode::InvalidParams, encoding),
+		};
+
+		MethodResponse::encoded_response(id, ResponsePayload::result(removed), max_response_size, encoding)
+	}
+
+	/// Drop the filter and unsubscribe, returns whether the filter existed.
+	fn remove(&self, sub_id: &SubscriptionId<'static>) -> bool {
+		if self.filters.lock().remove(sub_id).is_none() {
+			return false;
+		}
+
+		let params = serde_json::to_string(&[sub_id]).expect("SubscriptionId serialization infallible; qed");
+		(self.unsubscribe)(Id::Null, Params::new(Some(&params)), FILTER_CONN_ID, usize::MAX, Extensions::new());
+		true
+	}
+
+	/// Drop the filter once it hasn't been polled for the idle timeout.
+	async fn expire(self, sub_id: SubscriptionId<'static>) {
+		loop {
+			let Some(last_poll) = self.filters.lock().get(&sub_id).map(|filter| filter.last_poll) else { return };
+
+			let expires_at = last_poll + self.config.idle_timeout;
+			if expires_at <= Instant::now() {
+				tracing::debug!("Filter {:?} expired", sub_id);
+				self.remove(&sub_id);
+				return;
+			}
+
+			tokio::time::sleep_until(expires_at.into()).await;
+		}
+	}
+}
+
+#[derive(Deserialize)]
+struct SubscribeResponse {
+	result: Option<Value>,
+	error: Option<ErrorObjectOwned>,
+}
+
+#[derive(Deserialize)]
+struct Notification {
+	params: NotificationParams,
+}
+
+#[derive(Deserialize)]
+struct NotificationParams {
+	result: Option<Value>,
+	error: Option<Value>,
+}
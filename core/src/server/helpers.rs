@@ -34,9 +34,15 @@ use jsonrpsee_types::error::{
 use jsonrpsee_types::{Id, InvalidRequest, Response, ResponsePayload};
 use serde::Serialize;
 use serde_json::value::to_raw_value;
-use tokio::sync::mpsc;
-
-use super::{DisconnectError, SendTimeoutError, SubscriptionMessage, TrySendError};
+use tokio::sync::{mpsc, watch};
+
+#[cfg(feature = "cbor")]
+use super::encoding::cbor;
+use super::encoding::json_v1;
+use super::{
+	DisconnectError, EncodedMessage, Encoding, ResultStream, SendTimeoutError, StreamedMessage, SubscriptionMessage,
+	TrySendError,
+};
 
 /// Bounded writer that allows writing at most `max_len` bytes.
 ///
@@ -87,22 +93,81 @@ impl<'a> io::Write for &'a mut BoundedWriter {
 #[derive(Clone, Debug)]
 pub struct MethodSink {
 	/// Channel sender.
-	tx: mpsc::Sender<String>,
+	tx: mpsc::Sender<EncodedMessage>,
 	/// Max response size in bytes for a executed call.
 	max_response_size: u32,
 	/// Max log length.
 	max_log_length: u32,
+	/// Notified when the server starts to shut down.
+	shutdown: Option<watch::Receiver<()>>,
+	/// Encoding of the messages sent to the client.
+	encoding: Encoding,
 }
 
 impl MethodSink {
 	/// Create a new `MethodSink` with unlimited response size.
-	pub fn new(tx: mpsc::Sender<String>) -> Self {
-		MethodSink { tx, max_response_size: u32::MAX, max_log_length: u32::MAX }
+	pub fn new(tx: mpsc::Sender<EncodedMessage>) -> Self {
+		MethodSink {
+			tx,
+			max_response_size: u32::MAX,
+			max_log_length: u32::MAX,
+			shutdown: None,
+			encoding: Encoding::Json,
+		}
 	}
 
 	/// Create a new `MethodSink` with a limited response size.
-	pub fn new_with_limit(tx: mpsc::Sender<String>, max_response_size: u32, max_log_length: u32) -> Self {
-		MethodSink { tx, max_response_size, max_log_length }
+	pub fn new_with_limit(tx: mpsc::Sender<EncodedMessage>, max_response_size: u32, max_log_length: u32) -> Self {
+		MethodSink { tx, max_response_size, max_log_length, shutdown: None, encoding: Encoding::Json }
+	}
+
+	/// Set the encoding of the messages sent to the client, which is used for the responses
+	/// and notifications that are created by the sink.
+	pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+		self.encoding = encoding;
+		self
+	}
+
+	/// Get the encoding of the messages sent to the client.
+	pub const fn encoding(&self) -> Encoding {
+		self.encoding
+	}
+
+	/// Create a sink with the same configuration which sends the messages to `tx`.
+	///
+	/// The new sink outlives the connection, it isn't notified of the shutdown of the server.
+	pub(crate) fn redirect(&self, tx: mpsc::Sender<EncodedMessage>) -> Self {
+		MethodSink {
+			tx,
+			max_response_size: self.max_response_size,
+			max_log_length: self.max_log_length,
+			shutdown: None,
+			encoding: self.encoding,
+		}
+	}
+
+	/// Set a receiver which is notified once the server starts to shut down.
+	///
+	/// The sink can still be used to send messages after that but
+	/// [`MethodSink::shutdown`] completes such that subscriptions can be closed.
+	pub fn with_shutdown(mut self, shutdown: watch::Receiver<()>) -> Self {
+		self.shutdown = Some(shutdown);
+		self
+	}
+
+	/// Completes when the server starts to shut down.
+	///
+	/// Never completes if no shutdown receiver has been set.
+	///
+	/// # Cancel safety
+	/// This method is cancel safe.
+	pub async fn shutdown(&self) {
+		match self.shutdown.clone() {
+			Some(mut rx) => {
+				let _ = rx.changed().await;
+			}
+			None => futures_util::future::pending().await,
+		}
 	}
 
 	/// Returns whether this channel is closed without needing a context.
@@ -128,28 +193,32 @@ impl MethodSink {
 	/// connection has been closed or if the message buffer is full.
 	///
 	/// Returns the message if the send fails such that either can be thrown away or re-sent later.
-	pub fn try_send(&mut self, msg: String) -> Result<(), TrySendError> {
-		tx_log_from_str(&msg, self.max_log_length);
+	pub fn try_send(&mut self, msg: impl Into<EncodedMessage>) -> Result<(), TrySendError> {
+		let msg = msg.into();
+		tx_log_from_str(msg.as_log_str(), self.max_log_length);
 		self.tx.try_send(msg).map_err(Into::into)
 	}
 
 	/// Async send which will wait until there is space in channel buffer or that the subscription is disconnected.
-	pub async fn send(&self, msg: String) -> Result<(), DisconnectError> {
-		tx_log_from_str(&msg, self.max_log_length);
+	pub async fn send(&self, msg: impl Into<EncodedMessage>) -> Result<(), DisconnectError> {
+		let msg = msg.into();
+		tx_log_from_str(msg.as_log_str(), self.max_log_length);
 		self.tx.send(msg).await.map_err(Into::into)
 	}
 
 	/// Send a JSON-RPC error to the client
 	pub async fn send_error<'a>(&self, id: Id<'a>, err: ErrorObject<'a>) -> Result<(), DisconnectError> {
-		let json =
-			serde_json::to_string(&Response::new(ResponsePayload::<()>::Error(err), id)).expect("valid JSON; qed");
-
-		self.send(json).await
+		self.send(MethodResponse::encoded_error(id, err, self.encoding).result).await
 	}
 
 	/// Similar to to `MethodSink::send` but only waits for a limited time.
-	pub async fn send_timeout(&self, msg: String, timeout: Duration) -> Result<(), SendTimeoutError> {
-		tx_log_from_str(&msg, self.max_log_length);
+	pub async fn send_timeout(
+		&self,
+		msg: impl Into<EncodedMessage>,
+		timeout: Duration,
+	) -> Result<(), SendTimeoutError> {
+		let msg = msg.into();
+		tx_log_from_str(msg.as_log_str(), self.max_log_length);
 		self.tx.send_timeout(msg, timeout).await.map_err(Into::into)
 	}
 
@@ -177,9 +246,12 @@ pub fn prepare_error(data: &[u8]) -> (Id<'_>, ErrorCode) {
 #[derive(Debug, Clone)]
 pub struct MethodResponse {
 	/// Serialized JSON-RPC response,
-	pub result: String,
+	pub result: EncodedMessage,
 	/// Indicates whether the call was successful or not.
 	pub success_or_error: MethodResponseResult,
+	/// Indicates whether the call was a subscription call which has already
+	/// been answered by the subscription itself.
+	pub is_subscription: bool,
 }
 
 impl MethodResponse {
@@ -192,6 +264,47 @@ impl MethodResponse {
 	pub fn is_error(&self) -> bool {
 		self.success_or_error.is_success()
 	}
+
+	/// Copy of a response to a call with `Id::Null`, answering the call with `id` instead.
+	///
+	/// The `id` is the last field of a serialized response, which allows to replace it without
+	/// deserializing the response.
+	pub(crate) fn with_id(&self, id: &Id) -> Self {
+		const NULL_ID: &str = r#""id":null}"#;
+
+		let result = match self.result {
+			EncodedMessage::Text(ref text) => {
+				let Some(body) = text.strip_suffix(NULL_ID) else {
+					debug_assert!(false, "Response wasn't made with `Id::Null`: {text}");
+					return self.clone();
+				};
+
+				let id = serde_json::to_string(id).expect("Id serialization infallible; qed");
+				let mut result = String::with_capacity(body.len() + id.len() + 6);
+				result.push_str(body);
+				result.push_str(r#""id":"#);
+				result.push_str(&id);
+				result.push('}');
+				EncodedMessage::Text(result)
+			}
+			#[cfg(feature = "cbor")]
+			EncodedMessage::Binary(ref bytes) => match cbor::with_id(bytes, id) {
+				Some(bytes) => EncodedMessage::Binary(bytes),
+				None => {
+					debug_assert!(false, "Response wasn't made with `Id::Null`");
+					return self.clone();
+				}
+			},
+			#[cfg(not(feature = "cbor"))]
+			EncodedMessage::Binary(_) => return self.clone(),
+			EncodedMessage::Stream(_) => {
+				debug_assert!(false, "Streamed responses can't be shared between calls");
+				return self.clone();
+			}
+		};
+
+		Self { result, success_or_error: self.success_or_error, is_subscription: self.is_subscription }
+	}
 }
 
 /// Represent the outcome of a method call success or failed.
@@ -229,6 +342,14 @@ impl MethodResponse {
 	/// Send a JSON-RPC response to the client. If the serialization of `result` exceeds `max_response_size`,
 	/// an error will be sent instead.
 	pub fn response<T>(id: Id, result: ResponsePayload<T>, max_response_size: usize) -> Self
+	where
+		T: Serialize + Clone,
+	{
+		Self::encoded_response(id, result, max_response_size, Encoding::Json)
+	}
+
+	/// Same as [`MethodResponse::response`] but the response is serialized in the given encoding.
+	pub fn encoded_response<T>(id: Id, result: ResponsePayload<T>, max_response_size: usize, encoding: Encoding) -> Self
 	where
 		T: Serialize + Clone,
 	{
@@ -240,46 +361,111 @@ impl MethodResponse {
 			MethodResponseResult::Success
 		};
 
-		match serde_json::to_writer(&mut writer, &Response::new(result, id.clone())) {
-			Ok(_) => {
-				// Safety - serde_json does not emit invalid UTF-8.
-				let result = unsafe { String::from_utf8_unchecked(writer.into_bytes()) };
+		let is_io_err = match encoding {
+			Encoding::Json | Encoding::JsonV1 => match write_json_response(&mut writer, result, &id, encoding) {
+				Ok(_) => {
+					// Safety - serde_json does not emit invalid UTF-8.
+					let result = unsafe { String::from_utf8_unchecked(writer.into_bytes()) };
 
-				Self { result, success_or_error }
-			}
-			Err(err) => {
-				tracing::error!("Error serializing response: {:?}", err);
-
-				if err.is_io() {
-					let data = to_raw_value(&format!("Exceeded max limit of {max_response_size}")).ok();
-					let err_code = OVERSIZED_RESPONSE_CODE;
-
-					let err = ResponsePayload::error_borrowed(ErrorObject::borrowed(
-						err_code,
-						OVERSIZED_RESPONSE_MSG,
-						data.as_deref(),
-					));
-					let result =
-						serde_json::to_string(&Response::new(err, id)).expect("JSON serialization infallible; qed");
-
-					Self { result, success_or_error: MethodResponseResult::Failed(err_code) }
-				} else {
-					let err_code = ErrorCode::InternalError;
-					let result = serde_json::to_string(&Response::new(err_code.into(), id))
-						.expect("JSON serialization infallible; qed");
-					Self { result, success_or_error: MethodResponseResult::Failed(err_code.code()) }
+					return Self { result: EncodedMessage::Text(result), success_or_error, is_subscription: false };
 				}
-			}
+				Err(err) => {
+					tracing::error!("Error serializing response: {:?}", err);
+					err.is_io()
+				}
+			},
+			#[cfg(feature = "cbor")]
+			Encoding::Cbor => match cbor::write_response(&result, &id, &mut writer) {
+				Ok(_) => {
+					let result = EncodedMessage::Binary(writer.into_bytes());
+
+					return Self { result, success_or_error, is_subscription: false };
+				}
+				Err(err) => {
+					tracing::error!("Error serializing response: {:?}", err);
+					matches!(err, ciborium::ser::Error::Io(_))
+				}
+			},
+		};
+
+		if is_io_err {
+			Self::oversized_error(id, max_response_size, encoding)
+		} else {
+			Self::encoded_error(id, ErrorCode::InternalError, encoding)
 		}
 	}
 
+	/// Create a response whose `result` is sent while it's produced, see [`EncodedMessage::Stream`].
+	///
+	/// The response isn't limited by a max response size unless it's buffered with
+	/// [`MethodResponse::into_buffered`], which the transports do for CBOR and batch responses.
+	pub fn streamed(id: Id, result: ResultStream, encoding: Encoding) -> Self {
+		let result = EncodedMessage::Stream(StreamedMessage::new(id.into_owned(), result, encoding));
+		Self { result, success_or_error: MethodResponseResult::Success, is_subscription: false }
+	}
+
+	/// Collect a streamed response, such that it's serialized as a whole. Other responses are returned as they are.
+	///
+	/// If the `result` exceeds `max_response_size` an error is returned instead, just as by
+	/// [`MethodResponse::encoded_response`].
+	pub async fn into_buffered(self, max_response_size: usize) -> Self {
+		let EncodedMessage::Stream(ref msg) = self.result else { return self };
+		let (id, encoding) = (msg.id.clone(), msg.encoding);
+
+		let result = match msg.collect_result(max_response_size).await {
+			Ok(Some(result)) => result,
+			Ok(None) => return Self::oversized_error(id, max_response_size, encoding),
+			Err(err) => {
+				tracing::error!("Error serializing streamed response: {:?}", err);
+				return Self::encoded_error(id, ErrorCode::InternalError, encoding);
+			}
+		};
+
+		let parsed = match encoding {
+			Encoding::Json | Encoding::JsonV1 => serde_json::value::RawValue::from_string(result).map(|result| {
+				Self::encoded_response(id.clone(), ResponsePayload::result(result), max_response_size, encoding)
+			}),
+			#[cfg(feature = "cbor")]
+			Encoding::Cbor => serde_json::from_str::<serde_json::Value>(&result).map(|result| {
+				Self::encoded_response(id.clone(), ResponsePayload::result(result), max_response_size, encoding)
+			}),
+		};
+
+		parsed.unwrap_or_else(|err| {
+			tracing::error!("Streamed response isn't valid JSON: {:?}", err);
+			Self::encoded_error(id, ErrorCode::InternalError, encoding)
+		})
+	}
+
+	fn oversized_error(id: Id, max_response_size: usize, encoding: Encoding) -> Self {
+		let data = to_raw_value(&format!("Exceeded max limit of {max_response_size}")).ok();
+		let err = ErrorObject::borrowed(OVERSIZED_RESPONSE_CODE, OVERSIZED_RESPONSE_MSG, data.as_deref());
+
+		Self::encoded_error(id, err, encoding)
+	}
+
 	/// Create a `MethodResponse` from an error.
 	pub fn error<'a>(id: Id, err: impl Into<ErrorObject<'a>>) -> Self {
+		Self::encoded_error(id, err, Encoding::Json)
+	}
+
+	/// Same as [`MethodResponse::error`] but the response is serialized in the given encoding.
+	pub fn encoded_error<'a>(id: Id, err: impl Into<ErrorObject<'a>>, encoding: Encoding) -> Self {
 		let err: ErrorObject = err.into();
 		let err_code = err.code();
-		let err = ResponsePayload::error_borrowed(err);
-		let result = serde_json::to_string(&Response::new(err, id)).expect("JSON serialization infallible; qed");
-		Self { result, success_or_error: MethodResponseResult::Failed(err_code) }
+		let err = ResponsePayload::<()>::error_borrowed(err);
+		let result = match encoding {
+			Encoding::Json => EncodedMessage::Text(
+				serde_json::to_string(&Response::new(err, id)).expect("JSON serialization infallible; qed"),
+			),
+			Encoding::JsonV1 => EncodedMessage::Text(
+				serde_json::to_string(&json_v1::Response { payload: &err, id: &id })
+					.expect("JSON serialization infallible; qed"),
+			),
+			#[cfg(feature = "cbor")]
+			Encoding::Cbor => EncodedMessage::Binary(cbor::response(&err, &id)),
+		};
+		Self { result, success_or_error: MethodResponseResult::Failed(err_code), is_subscription: false }
 	}
 }
 
@@ -287,34 +473,50 @@ impl MethodResponse {
 #[derive(Debug, Clone, Default)]
 pub struct BatchResponseBuilder {
 	/// Serialized JSON-RPC response,
-	result: String,
+	result: Vec<u8>,
 	/// Max limit for the batch
 	max_response_size: usize,
+	/// Encoding of the batch.
+	encoding: Encoding,
 }
 
 impl BatchResponseBuilder {
 	/// Create a new batch response builder with limit.
 	pub fn new_with_limit(limit: usize) -> Self {
-		let mut initial = String::with_capacity(2048);
-		initial.push('[');
+		Self::new_with_encoding(limit, Encoding::Json)
+	}
 
-		Self { result: initial, max_response_size: limit }
+	/// Create a new batch response builder with limit whose responses are serialized in the given encoding.
+	///
+	/// A CBOR batch is an array of indefinite length, such that responses can be appended as they are.
+	pub fn new_with_encoding(limit: usize, encoding: Encoding) -> Self {
+		let mut initial = Vec::with_capacity(2048);
+		match encoding {
+			Encoding::Json | Encoding::JsonV1 => initial.push(b'['),
+			#[cfg(feature = "cbor")]
+			Encoding::Cbor => initial.push(0x9f),
+		}
+
+		Self { result: initial, max_response_size: limit, encoding }
 	}
 
 	/// Append a result from an individual method to the batch response.
 	///
 	/// Fails if the max limit is exceeded and returns to error response to
 	/// return early in order to not process method call responses which are thrown away anyway.
-	pub fn append(&mut self, response: &MethodResponse) -> Result<(), String> {
+	pub fn append(&mut self, response: &MethodResponse) -> Result<(), EncodedMessage> {
 		// `,` will occupy one extra byte for each entry
 		// on the last item the `,` is replaced by `]`.
 		let len = response.result.len() + self.result.len() + 1;
 
 		if len > self.max_response_size {
-			Err(batch_response_error(Id::Null, reject_too_big_batch_response(self.max_response_size)))
+			let err = reject_too_big_batch_response(self.max_response_size);
+			Err(MethodResponse::encoded_error(Id::Null, err, self.encoding).result)
 		} else {
-			self.result.push_str(&response.result);
-			self.result.push(',');
+			self.result.extend_from_slice(response.result.as_bytes());
+			if matches!(self.encoding, Encoding::Json | Encoding::JsonV1) {
+				self.result.push(b',');
+			}
 			Ok(())
 		}
 	}
@@ -325,17 +527,41 @@ impl BatchResponseBuilder {
 	}
 
 	/// Finish the batch response
-	pub fn finish(mut self) -> String {
+	pub fn finish(mut self) -> EncodedMessage {
 		if self.result.len() == 1 {
-			batch_response_error(Id::Null, ErrorObject::from(ErrorCode::InvalidRequest))
-		} else {
-			self.result.pop();
-			self.result.push(']');
-			self.result
+			return MethodResponse::encoded_error(Id::Null, ErrorCode::InvalidRequest, self.encoding).result;
+		}
+
+		match self.encoding {
+			Encoding::Json | Encoding::JsonV1 => {
+				self.result.pop();
+				self.result.push(b']');
+				// Safety - the batch consists of JSON responses, which are valid UTF-8.
+				EncodedMessage::Text(unsafe { String::from_utf8_unchecked(self.result) })
+			}
+			#[cfg(feature = "cbor")]
+			Encoding::Cbor => {
+				self.result.push(0xff);
+				EncodedMessage::Binary(self.result)
+			}
 		}
 	}
 }
 
+/// Serialize a JSON-RPC response in the JSON envelope of `encoding`.
+fn write_json_response<T: Serialize + Clone>(
+	writer: &mut BoundedWriter,
+	payload: ResponsePayload<T>,
+	id: &Id,
+	encoding: Encoding,
+) -> Result<(), serde_json::Error> {
+	if encoding == Encoding::JsonV1 {
+		serde_json::to_writer(writer, &json_v1::Response { payload: &payload, id })
+	} else {
+		serde_json::to_writer(writer, &Response::new(payload, id.clone()))
+	}
+}
+
 /// Create a JSON-RPC error response.
 pub fn batch_response_error(id: Id, err: impl Into<ErrorObject<'static>>) -> String {
 	let err = ResponsePayload::error_borrowed(err);
@@ -364,6 +590,17 @@ mod tests {
 		assert!(serde_json::to_writer(&mut writer, &"x".repeat(99)).is_err());
 	}
 
+	#[test]
+	fn response_with_id_works() {
+		let rp = MethodResponse::response(Id::Null, ResponsePayload::result_borrowed(&"a"), usize::MAX);
+		assert_eq!(rp.with_id(&Id::Str("x".into())).result, r#"{"jsonrpc":"2.0","result":"a","id":"x"}"#);
+
+		let rp = MethodResponse::error(Id::Null, jsonrpsee_types::ErrorObject::owned(1, "err", None::<()>));
+		let rp = rp.with_id(&Id::Number(7));
+		assert_eq!(rp.result, r#"{"jsonrpc":"2.0","error":{"code":1,"message":"err"},"id":7}"#);
+		assert!(!rp.is_success());
+	}
+
 	#[test]
 	fn batch_with_single_works() {
 		let method = MethodResponse::response(Id::Number(1), ResponsePayload::result_borrowed(&"a"), usize::MAX);
@@ -411,4 +648,69 @@ mod tests {
 		let exp_err = r#"{"jsonrpc":"2.0","error":{"code":-32011,"message":"The batch response was too large","data":"Exceeded max limit of 63"},"id":null}"#;
 		assert_eq!(batch, exp_err);
 	}
+
+	#[cfg(feature = "cbor")]
+	fn cbor_response(result: &str, id: ciborium::Value) -> ciborium::Value {
+		use ciborium::Value;
+
+		Value::Map(vec![
+			(Value::Text("jsonrpc".into()), Value::Text("2.0".into())),
+			(Value::Text("result".into()), Value::Text(result.into())),
+			(Value::Text("id".into()), id),
+		])
+	}
+
+	#[cfg(feature = "cbor")]
+	#[test]
+	fn cbor_response_with_id_works() {
+		use super::Encoding;
+		use ciborium::Value;
+
+		let rp = MethodResponse::encoded_response(
+			Id::Null,
+			ResponsePayload::result_borrowed(&"a"),
+			usize::MAX,
+			Encoding::Cbor,
+		);
+		let rp = rp.with_id(&Id::Str("x".into()));
+		let value: Value = ciborium::from_reader(rp.result.as_bytes()).unwrap();
+		assert_eq!(value, cbor_response("a", Value::Text("x".into())));
+
+		let rp =
+			MethodResponse::encoded_response(Id::Number(1), ResponsePayload::result_borrowed(&"a"), 8, Encoding::Cbor);
+		assert!(!rp.is_success());
+	}
+
+	#[cfg(feature = "cbor")]
+	#[test]
+	fn cbor_batch_works() {
+		use super::Encoding;
+		use ciborium::Value;
+
+		let m1 = MethodResponse::encoded_response(
+			Id::Number(1),
+			ResponsePayload::result_borrowed(&"a"),
+			usize::MAX,
+			Encoding::Cbor,
+		);
+		let m2 = MethodResponse::encoded_response(
+			Id::Number(2),
+			ResponsePayload::result_borrowed(&"a"),
+			usize::MAX,
+			Encoding::Cbor,
+		);
+		let mut builder = BatchResponseBuilder::new_with_encoding(1024, Encoding::Cbor);
+		builder.append(&m1).unwrap();
+		builder.append(&m2).unwrap();
+		let batch: Value = ciborium::from_reader(builder.finish().as_bytes()).unwrap();
+
+		let expected = Value::Array(vec![
+			cbor_response("a", Value::Integer(1.into())),
+			cbor_response("a", Value::Integer(2.into())),
+		]);
+		assert_eq!(batch, expected);
+
+		let err = BatchResponseBuilder::new_with_encoding(8, Encoding::Cbor).append(&m1).unwrap_err();
+		assert!(err.as_str().is_none());
+	}
 }
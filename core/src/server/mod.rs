@@ -26,20 +26,48 @@
 
 //! Shared modules for the JSON-RPC servers.
 
+/// Access control of methods.
+mod access;
+/// Broadcast of notifications to the subscribers of a subscription.
+mod broadcast;
+/// Response cache of methods.
+mod cache;
+/// Encoding of the JSON-RPC messages.
+mod encoding;
 /// Error types.
 mod error;
+mod filter;
 /// Helpers.
 pub mod helpers;
+/// OpenRPC description of the methods.
+#[cfg(feature = "openrpc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "openrpc")))]
+pub mod openrpc;
+/// Resumable subscriptions.
+mod resume;
 /// JSON-RPC "modules" group sets of methods that belong together and handles method/subscription registration.
 mod rpc_module;
+/// Streamed method results.
+mod stream;
 /// Subscription related types.
 mod subscription;
 
+pub use access::*;
+pub use broadcast::{LagPolicy, SubscriptionBroadcaster};
+pub use cache::{ResponseCache, ResponseCacheConfig};
+pub use encoding::{EncodedMessage, Encoding, JSON_CONTENT_TYPE};
+#[cfg(feature = "cbor")]
+pub use encoding::{CBOR_CONTENT_TYPE, CBOR_SUBPROTOCOL};
 pub use error::*;
+pub use filter::FilterConfig;
 pub use helpers::{BatchResponseBuilder, BoundedWriter, MethodResponse, MethodSink};
+pub use resume::ResumeConfig;
 pub use rpc_module::*;
+pub use stream::{JsonChunks, ResultStream, StreamedMessage};
 pub use subscription::*;
 
+pub use jsonrpsee_types::Extensions;
+
 use jsonrpsee_types::{ErrorObjectOwned, ResponsePayload};
 
 /// Something that can be converted into a JSON-RPC method call response.
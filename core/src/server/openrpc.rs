@@ -0,0 +1,251 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! [OpenRPC](https://spec.open-rpc.org) description of the methods of a server.
+//!
+//! The `rpc` macro generates a [`MethodSchema`] for every method when the trait is annotated with
+//! `#[rpc(server, openrpc)]` and the descriptions are kept in the [`Methods`](crate::server::Methods),
+//! such that they survive merging several modules into one. Use
+//! [`RpcModule::register_discover`](crate::server::RpcModule::register_discover) to serve the
+//! resulting document as the `rpc.discover` method.
+
+use schemars::gen::SchemaSettings;
+use schemars::schema::Schema;
+use schemars::{JsonSchema, Map};
+use serde::{Deserialize, Serialize};
+
+pub use schemars;
+
+/// Version of the OpenRPC specification that the documents conform to.
+pub const OPENRPC_VERSION: &str = "1.2.6";
+
+/// Name of the method that serves the OpenRPC document.
+pub const DISCOVER_METHOD: &str = "rpc.discover";
+
+/// OpenRPC document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenRpc {
+	/// Version of the OpenRPC specification.
+	pub openrpc: String,
+	/// Metadata of the API.
+	pub info: Info,
+	/// Methods of the API.
+	pub methods: Vec<Method>,
+	/// Schemas that the methods refer to.
+	#[serde(default)]
+	pub components: Components,
+}
+
+/// Metadata of the API.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Info {
+	/// Title of the API.
+	pub title: String,
+	/// Version of the API.
+	pub version: String,
+}
+
+/// Reusable schemas of an OpenRPC document.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Components {
+	/// JSON schemas of the types that the methods refer to.
+	#[serde(default)]
+	pub schemas: Map<String, Schema>,
+}
+
+/// Description of a single method.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Method {
+	/// Name of the method.
+	pub name: String,
+	/// Description of the method, taken from the doc comments.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+	/// Parameters of the method.
+	pub params: Vec<ContentDescriptor>,
+	/// Result of the method, notifications don't have a result.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub result: Option<ContentDescriptor>,
+	/// Whether the method is deprecated.
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub deprecated: bool,
+	/// Whether the parameters are passed by position or by name.
+	#[serde(default)]
+	pub param_structure: ParamStructure,
+	/// Aliases of the method.
+	#[serde(rename = "x-aliases", default, skip_serializing_if = "Vec::is_empty")]
+	pub aliases: Vec<String>,
+	/// Notification and unsubscribe method of a subscription.
+	#[serde(rename = "x-subscription", default, skip_serializing_if = "Option::is_none")]
+	pub subscription: Option<Subscription>,
+}
+
+/// Name and schema of a parameter or a result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContentDescriptor {
+	/// Name of the content.
+	pub name: String,
+	/// Whether the content is required.
+	#[serde(default)]
+	pub required: bool,
+	/// JSON schema of the content.
+	pub schema: Schema,
+}
+
+/// How the parameters of a method are passed.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParamStructure {
+	/// The parameters are passed as an array.
+	ByPosition,
+	/// The parameters are passed as an object.
+	ByName,
+	/// The parameters are passed either way.
+	#[default]
+	Either,
+}
+
+/// Description of a subscription, the method itself returns the subscription ID.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subscription {
+	/// Method of the notifications.
+	pub notification: String,
+	/// Method to unsubscribe.
+	pub unsubscribe: String,
+	/// Items that the notifications carry.
+	pub item: ContentDescriptor,
+}
+
+/// Builder for the OpenRPC description of a method together with the schemas that it refers to.
+///
+/// ```
+/// use jsonrpsee_core::server::openrpc::{MethodSchema, ParamStructure};
+///
+/// let schema = MethodSchema::new()
+///     .description("Adds two numbers.")
+///     .param_structure(ParamStructure::ByPosition)
+///     .param::<u64>("a", true)
+///     .param::<Option<u64>>("b", false)
+///     .result::<u64>();
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MethodSchema {
+	pub(crate) method: Method,
+	pub(crate) definitions: Map<String, Schema>,
+}
+
+impl MethodSchema {
+	/// Create an empty description.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the description of the method.
+	pub fn description(mut self, description: impl Into<String>) -> Self {
+		self.method.description = Some(description.into());
+		self
+	}
+
+	/// Mark the method as deprecated.
+	pub fn deprecated(mut self) -> Self {
+		self.method.deprecated = true;
+		self
+	}
+
+	/// Set how the parameters of the method are passed.
+	pub fn param_structure(mut self, param_structure: ParamStructure) -> Self {
+		self.method.param_structure = param_structure;
+		self
+	}
+
+	/// Add a parameter.
+	pub fn param<T: JsonSchema>(mut self, name: impl Into<String>, required: bool) -> Self {
+		let schema = self.schema_for::<T>();
+		self.method.params.push(ContentDescriptor { name: name.into(), required, schema });
+		self
+	}
+
+	/// Set the type of the result.
+	pub fn result<T: JsonSchema>(mut self) -> Self {
+		let schema = self.schema_for::<T>();
+		self.method.result = Some(ContentDescriptor { name: "result".to_owned(), required: true, schema });
+		self
+	}
+
+	/// Describe the method as a subscription which sends notifications with items of type `T`.
+	pub fn subscription<T: JsonSchema>(
+		mut self,
+		notification: impl Into<String>,
+		unsubscribe: impl Into<String>,
+	) -> Self {
+		let schema = self.schema_for::<T>();
+		self.method.subscription = Some(Subscription {
+			notification: notification.into(),
+			unsubscribe: unsubscribe.into(),
+			item: ContentDescriptor { name: "item".to_owned(), required: true, schema },
+		});
+		self
+	}
+
+	/// Add an alias of the method.
+	pub fn alias(mut self, alias: impl Into<String>) -> Self {
+		self.method.aliases.push(alias.into());
+		self
+	}
+
+	/// Get the description of the method.
+	pub fn method(&self) -> &Method {
+		&self.method
+	}
+
+	fn schema_for<T: JsonSchema>(&mut self) -> Schema {
+		let mut gen = SchemaSettings::draft07()
+			.with(|settings| settings.definitions_path = "#/components/schemas/".to_owned())
+			.into_generator();
+		let schema = gen.subschema_for::<T>();
+		self.definitions.extend(gen.take_definitions());
+		schema
+	}
+}
+
+impl OpenRpc {
+	/// Create an empty document.
+	pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+		Self {
+			openrpc: OPENRPC_VERSION.to_owned(),
+			info: Info { title: title.into(), version: version.into() },
+			methods: Vec::new(),
+			components: Components::default(),
+		}
+	}
+
+	/// Add the description of a method.
+	pub fn add_method(&mut self, schema: &MethodSchema) {
+		self.methods.push(schema.method.clone());
+		self.components.schemas.extend(schema.definitions.iter().map(|(name, schema)| (name.clone(), schema.clone())));
+	}
+}
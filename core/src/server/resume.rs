@@ -0,0 +1,445 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Resumable subscriptions.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "cbor")]
+use crate::server::encoding::cbor;
+use crate::server::encoding::{EncodedMessage, Encoding};
+use crate::server::helpers::{MethodResponse, MethodSink};
+use crate::server::rpc_module::{ConnectionId, SubscriptionMethod, UnsubscriptionMethod};
+use crate::server::subscription::{SubscriptionPermit, SubscriptionState};
+use crate::server::Role;
+use crate::traits::IdProvider;
+use futures_util::{future, FutureExt};
+use jsonrpsee_types::error::{reject_subscription_not_resumable, ErrorCode};
+use jsonrpsee_types::{Extensions, Id, Params, ResponsePayload, SubscriptionId};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+/// Default maximum number of buffered notifications per subscription.
+const DEFAULT_MAX_BUFFERED: usize = 1024;
+
+/// Configuration of resumable subscriptions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ResumeConfig {
+	grace_period: Duration,
+	max_buffered: usize,
+}
+
+impl ResumeConfig {
+	/// Keep the subscriptions of closed connections for `grace_period`, such that they can be resumed.
+	pub fn new(grace_period: Duration) -> Self {
+		Self { grace_period, max_buffered: DEFAULT_MAX_BUFFERED }
+	}
+
+	/// Set the maximum number of notifications which are buffered per subscription to be replayed (default is 1024).
+	///
+	/// A subscription can only be resumed if none of the notifications that the subscriber missed
+	/// were dropped from the buffer.
+	pub fn max_buffered(mut self, max: usize) -> Self {
+		self.max_buffered = max;
+		self
+	}
+
+	/// Get the grace period of the subscriptions of closed connections.
+	pub fn grace_period(&self) -> Duration {
+		self.grace_period
+	}
+}
+
+impl From<Duration> for ResumeConfig {
+	fn from(grace_period: Duration) -> Self {
+		Self::new(grace_period)
+	}
+}
+
+/// Request of a subscriber to resume a subscription.
+struct Resume {
+	sink: MethodSink,
+	permit: SubscriptionPermit,
+	last_seq: u64,
+	response: EncodedMessage,
+	result: oneshot::Sender<Result<(), &'static str>>,
+}
+
+/// Handle of a resumable subscription.
+struct Handle {
+	/// The connection on which the subscription was created, with which it's registered.
+	origin_conn_id: ConnectionId,
+	/// The connection on which the subscription was last resumed.
+	conn_id: ConnectionId,
+	encoding: Encoding,
+	/// The role of the caller which created the subscription, only callers with the same role can resume it.
+	role: Option<Role>,
+	resume: mpsc::UnboundedSender<Resume>,
+}
+
+/// The subscriber of a subscription, with the permit of its connection if the subscription was resumed.
+struct Subscriber {
+	sink: MethodSink,
+	_permit: Option<SubscriptionPermit>,
+}
+
+/// The resumable subscriptions of a subscription method, keyed by the ID of the subscription.
+#[derive(Clone)]
+pub(crate) struct Resumable {
+	config: ResumeConfig,
+	subscriptions: Arc<Mutex<FxHashMap<SubscriptionId<'static>, Handle>>>,
+}
+
+impl Resumable {
+	pub(crate) fn new(config: ResumeConfig) -> Self {
+		Self { config, subscriptions: Default::default() }
+	}
+
+	/// Wrap the callback of a subscription method such that its notifications are numbered and relayed
+	/// to the subscriber, which can resume the subscription on another connection.
+	pub(crate) fn subscribe(
+		&self,
+		subscribe: SubscriptionMethod<'static>,
+		unsubscribe: UnsubscriptionMethod,
+	) -> SubscriptionMethod<'static> {
+		let this = self.clone();
+
+		Arc::new(move |id, params, sink, conn, extensions| {
+			let sink = match extensions.get::<Encoding>() {
+				Some(encoding) => sink.with_encoding(*encoding),
+				None => sink,
+			};
+			let (tx, rx) = mpsc::channel(this.config.max_buffered.max(1));
+			let id_provider = RecordIdProvider { inner: conn.id_provider, id: Mutex::new(None) };
+			let conn_id = conn.conn_id;
+			let role = extensions.get::<Role>().cloned();
+			let state =
+				SubscriptionState { conn_id, id_provider: &id_provider, subscription_permit: conn.subscription_permit };
+			let fut = subscribe(id, params, sink.redirect(tx), state, extensions);
+
+			let (resume_tx, resume_rx) = mpsc::unbounded_channel();
+			let sub_id = id_provider.id.into_inner();
+			if let Some(sub_id) = &sub_id {
+				let handle =
+					Handle { origin_conn_id: conn_id, conn_id, encoding: sink.encoding(), role, resume: resume_tx };
+				this.subscriptions.lock().insert(sub_id.clone(), handle);
+			}
+
+			let relay = this.clone().relay(sub_id, rx, sink, resume_rx, conn_id, unsubscribe.clone());
+			tokio::spawn(relay);
+			fut
+		})
+	}
+
+	/// Wrap the callback of the unsubscribe method such that it unsubscribes from resumed subscriptions.
+	pub(crate) fn unsubscribe(&self, unsubscribe: UnsubscriptionMethod) -> UnsubscriptionMethod {
+		let this = self.clone();
+
+		Arc::new(move |id, params, conn_id, max_response_size, extensions| {
+			// Resumed subscriptions are still registered with the connection on which they were created.
+			let origin_conn_id = params.one::<SubscriptionId>().ok().and_then(|sub_id| {
+				let subscriptions = this.subscriptions.lock();
+				let handle = subscriptions.get(&sub_id.into_owned())?;
+				(handle.conn_id == conn_id).then_some(handle.origin_conn_id)
+			});
+
+			unsubscribe(id, params, origin_conn_id.unwrap_or(conn_id), max_response_size, extensions)
+		})
+	}
+
+	/// The callback of the resume method, whose params are the ID of the subscription and
+	/// the sequence number of the last notification which the subscriber received.
+	pub(crate) fn resume_method(&self) -> SubscriptionMethod<'static> {
+		let this = self.clone();
+
+		Arc::new(move |id, params, sink, conn, extensions| {
+			let this = this.clone();
+			let sink = match extensions.get::<Encoding>() {
+				Some(encoding) => sink.with_encoding(*encoding),
+				None => sink,
+			};
+			let (id, params) = (id.into_owned(), params.into_owned());
+			let (conn_id, permit) = (conn.conn_id, conn.subscription_permit);
+			let role = extensions.get::<Role>().cloned();
+
+			async move {
+				let (sub_id, last_seq) = match params.parse::<(SubscriptionId, u64)>() {
+					Ok((sub_id, last_seq)) => (sub_id.into_owned(), last_seq),
+					Err(_) => {
+						let rp = MethodResponse::encoded_error(id, ErrorCode::InvalidParams, sink.encoding());
+						let _ = sink.send(rp.result.clone()).await;
+						return Ok(rp);
+					}
+				};
+
+				// Only a caller with the role of the caller which created the subscription can resume it.
+				if matches!(this.subscriptions.lock().get(&sub_id), Some(handle) if handle.role != role) {
+					let err = reject_subscription_not_resumable("the role differs from the role of the subscription");
+					let rp = MethodResponse::encoded_error(id, err, sink.encoding());
+					let _ = sink.send(rp.result.clone()).await;
+					return Ok(rp);
+				}
+
+				Ok(this.resume(id, sub_id, last_seq, sink, conn_id, permit).await)
+			}
+			.boxed()
+		})
+	}
+
+	/// Resume the subscription with the `sink` of another connection, once its subscriber has been detached.
+	async fn resume(
+		&self,
+		id: Id<'static>,
+		sub_id: SubscriptionId<'static>,
+		last_seq: u64,
+		sink: MethodSink,
+		conn_id: ConnectionId,
+		permit: SubscriptionPermit,
+	) -> MethodResponse {
+		let encoding = sink.encoding();
+		let max_response_size = sink.max_response_size() as usize;
+		let response = MethodResponse::encoded_response(
+			id.clone(),
+			ResponsePayload::result_borrowed(&sub_id),
+			max_response_size,
+			encoding,
+		);
+
+		let (tx, rx) = oneshot::channel();
+		let resume = Resume { sink: sink.clone(), permit, last_seq, response: response.result.clone(), result: tx };
+		let sent = match self.subscriptions.lock().get(&sub_id) {
+			Some(handle) if handle.encoding != encoding => {
+				Err("the encoding differs from the encoding of the subscription")
+			}
+			Some(handle) => handle.resume.send(resume).map_err(|_| "unknown subscription"),
+			None => Err("unknown subscription"),
+		};
+		let result = match sent {
+			Ok(()) => rx.await.unwrap_or(Err("unknown subscription")),
+			Err(reason) => Err(reason),
+		};
+
+		match result {
+			Ok(()) => {
+				if let Some(handle) = self.subscriptions.lock().get_mut(&sub_id) {
+					handle.conn_id = conn_id;
+				}
+				response
+			}
+			Err(reason) => {
+				let rp = MethodResponse::encoded_error(id, reject_subscription_not_resumable(reason), encoding);
+				let _ = sink.send(rp.result.clone()).await;
+				rp
+			}
+		}
+	}
+
+	/// Relay the messages of the subscription to its subscriber and buffer the notifications to be replayed.
+	///
+	/// Once the connection of the subscriber has been closed, the subscription is kept for the grace period,
+	/// after which the subscriber is unsubscribed unless the subscription was resumed.
+	async fn relay(
+		self,
+		sub_id: Option<SubscriptionId<'static>>,
+		mut rx: mpsc::Receiver<EncodedMessage>,
+		sink: MethodSink,
+		mut resume_rx: mpsc::UnboundedReceiver<Resume>,
+		origin_conn_id: ConnectionId,
+		unsubscribe: UnsubscriptionMethod,
+	) {
+		let mut buffer: VecDeque<(u64, EncodedMessage)> = VecDeque::new();
+		let mut subscriber = Some(Subscriber { sink, _permit: None });
+		let mut detached_at = None;
+		let mut next_seq = 1;
+		let mut answered = false;
+		let mut ended = false;
+
+		loop {
+			tokio::select! {
+				msg = rx.recv(), if !ended => match msg {
+					// The first message is the response to the subscription call, which isn't numbered.
+					Some(msg) if !answered => {
+						answered = true;
+						if let Some(Subscriber { sink, .. }) = &subscriber {
+							if sink.send(msg).await.is_err() {
+								subscriber = None;
+								detached_at = Some(Instant::now());
+							}
+						}
+					}
+					Some(msg) => {
+						let msg = with_seq(msg, next_seq);
+						buffer.push_back((next_seq, msg.clone()));
+						if buffer.len() > self.config.max_buffered {
+							buffer.pop_front();
+						}
+						next_seq += 1;
+
+						if let Some(Subscriber { sink, .. }) = &subscriber {
+							if sink.send(msg).await.is_err() {
+								subscriber = None;
+								detached_at = Some(Instant::now());
+							}
+						}
+					}
+					None if subscriber.is_some() => break,
+					// The subscription has ended, the subscriber can still resume it
+					// to receive the notifications it missed.
+					None => ended = true,
+				},
+				_ = async {
+					match &subscriber {
+						Some(Subscriber { sink, .. }) => sink.closed().await,
+						None => future::pending().await,
+					}
+				} => {
+					subscriber = None;
+					detached_at = Some(Instant::now());
+				}
+				Some(resume) = resume_rx.recv() => {
+					match &subscriber {
+						// Only detached subscriptions can be resumed, they can't be taken over from their subscriber.
+						Some(Subscriber { sink, .. }) if !sink.is_closed() => {
+							let _ = resume.result.send(Err("the subscription is attached to a subscriber"));
+							continue;
+						}
+						Some(_) => {
+							subscriber = None;
+							detached_at = Some(Instant::now());
+						}
+						None => (),
+					}
+
+					let first_seq = buffer.front().map_or(next_seq, |(seq, _)| *seq);
+					if resume.last_seq.saturating_add(1) < first_seq {
+						let _ = resume.result.send(Err("the missed notifications are no longer buffered"));
+						continue;
+					}
+					if resume.last_seq >= next_seq {
+						let _ = resume.result.send(Err("the sequence number is ahead of the subscription"));
+						continue;
+					}
+
+					let Resume { sink, permit, last_seq, response, result } = resume;
+					let _ = result.send(Ok(()));
+
+					let mut sent = sink.send(response).await.is_ok();
+					for (_, msg) in buffer.iter().filter(|(seq, _)| *seq > last_seq) {
+						sent = sent && sink.send(msg.clone()).await.is_ok();
+					}
+
+					if sent {
+						subscriber = Some(Subscriber { sink, _permit: Some(permit) });
+						detached_at = None;
+					} else {
+						subscriber = None;
+						detached_at = Some(Instant::now());
+					}
+
+					if ended && subscriber.is_some() {
+						break;
+					}
+				}
+				_ = async {
+					match detached_at {
+						Some(at) => tokio::time::sleep_until(at + self.config.grace_period).await,
+						None => future::pending().await,
+					}
+				} => break,
+			}
+		}
+
+		let Some(sub_id) = sub_id else { return };
+		self.subscriptions.lock().remove(&sub_id);
+
+		if !ended {
+			tracing::debug!("Subscription {:?} wasn't resumed within the grace period", sub_id);
+			drop(rx);
+			let params = serde_json::to_string(&[&sub_id]).expect("SubscriptionId serialization infallible; qed");
+			unsubscribe(Id::Null, Params::new(Some(&params)), origin_conn_id, usize::MAX, Extensions::new());
+		}
+	}
+}
+
+/// Number the notification with `seq`, which is added to its params.
+fn with_seq(msg: EncodedMessage, seq: u64) -> EncodedMessage {
+	match msg {
+		EncodedMessage::Text(json) => match insert_seq(&json, seq) {
+			Some(json) => EncodedMessage::Text(json),
+			None => EncodedMessage::Text(json),
+		},
+		#[cfg(feature = "cbor")]
+		EncodedMessage::Binary(bytes) => {
+			let numbered = cbor::to_json(&bytes)
+				.ok()
+				.and_then(|json| insert_seq(&json, seq))
+				.and_then(|json| cbor::from_json(&json).ok());
+			EncodedMessage::Binary(numbered.unwrap_or(bytes))
+		}
+		msg => msg,
+	}
+}
+
+fn insert_seq(json: &str, seq: u64) -> Option<String> {
+	const PARAMS: &str = r#""params":{"#;
+
+	let at = json.find(PARAMS)? + PARAMS.len();
+	Some(format!(r#"{}"seq":{seq},{}"#, &json[..at], &json[at..]))
+}
+
+/// Records the ID of the subscription which is generated by the provider.
+#[derive(Debug)]
+struct RecordIdProvider<'a> {
+	inner: &'a dyn IdProvider,
+	id: Mutex<Option<SubscriptionId<'static>>>,
+}
+
+impl IdProvider for RecordIdProvider<'_> {
+	fn next_id(&self) -> SubscriptionId<'static> {
+		let id = self.inner.next_id();
+		*self.id.lock() = Some(id.clone());
+		id
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::insert_seq;
+
+	#[test]
+	fn seq_is_inserted_in_params() {
+		let notif = r#"{"jsonrpc":"2.0","method":"n","params":{"subscription":1,"result":{"params":{}}}}"#;
+		assert_eq!(
+			insert_seq(notif, 7).unwrap(),
+			r#"{"jsonrpc":"2.0","method":"n","params":{"seq":7,"subscription":1,"result":{"params":{}}}}"#
+		);
+		assert_eq!(insert_seq(r#"{"jsonrpc":"2.0","result":1,"id":1}"#, 7), None);
+	}
+}
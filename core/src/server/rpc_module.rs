@@ -29,24 +29,39 @@ use std::fmt::{self, Debug};
 use std::future::Future;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::error::Error;
 use crate::id_providers::RandomIntegerIdProvider;
+use crate::server::cache::{role_cache_key, ResponseCache, ResponseCacheConfig};
+use crate::server::encoding::{EncodedMessage, Encoding};
+use crate::server::filter::{FilterConfig, Filters};
 use crate::server::helpers::{MethodResponse, MethodSink};
+#[cfg(feature = "openrpc")]
+use crate::server::openrpc::{MethodSchema, OpenRpc, DISCOVER_METHOD};
+use crate::server::resume::{Resumable, ResumeConfig};
+use crate::server::stream::ResultStream;
 use crate::server::subscription::{
-	sub_message_to_json, BoundedSubscriptions, IntoSubscriptionCloseResponse, PendingSubscriptionSink,
+	encode_sub_message, BoundedSubscriptions, IntoSubscriptionCloseResponse, PendingSubscriptionSink,
 	SubNotifResultOrError, Subscribers, Subscription, SubscriptionCloseResponse, SubscriptionKey, SubscriptionPermit,
 	SubscriptionState,
 };
+#[cfg(feature = "openrpc")]
+use crate::server::AccessPolicy;
+use crate::tracing::TraceParent;
 use crate::traits::ToRpcParams;
-use futures_util::{future::BoxFuture, FutureExt};
-use jsonrpsee_types::error::{ErrorCode, ErrorObject};
+use crate::JsonRawValue;
+use futures_util::future::{BoxFuture, Shared};
+use futures_util::FutureExt;
+use jsonrpsee_types::error::{reject_call_timeout, ErrorCode, ErrorObject, ErrorObjectOwned};
 use jsonrpsee_types::{
-	Id, Params, Request, Response, ResponsePayload, ResponseSuccess, SubscriptionId as RpcSubscriptionId,
+	Extensions, Id, Params, Request, Response, ResponsePayload, ResponseSuccess, SubscriptionId as RpcSubscriptionId,
 };
-use rustc_hash::FxHashMap;
+use parking_lot::Mutex;
+use rustc_hash::{FxHashMap, FxHashSet};
 use serde::de::DeserializeOwned;
 use tokio::sync::{mpsc, oneshot};
+use tracing::Instrument;
 
 use super::IntoResponse;
 
@@ -54,16 +69,24 @@ use super::IntoResponse;
 /// implemented as a function pointer to a `Fn` function taking four arguments:
 /// the `id`, `params`, a channel the function uses to communicate the result (or error)
 /// back to `jsonrpsee`, and the connection ID (useful for the websocket transport).
-pub type SyncMethod = Arc<dyn Send + Sync + Fn(Id, Params, MaxResponseSize) -> MethodResponse>;
+pub type SyncMethod = Arc<dyn Send + Sync + Fn(Id, Params, MaxResponseSize, Extensions) -> MethodResponse>;
 /// Similar to [`SyncMethod`], but represents an asynchronous handler.
-pub type AsyncMethod<'a> =
-	Arc<dyn Send + Sync + Fn(Id<'a>, Params<'a>, ConnectionId, MaxResponseSize) -> BoxFuture<'a, MethodResponse>>;
+pub type AsyncMethod<'a> = Arc<
+	dyn Send
+		+ Sync
+		+ Fn(Id<'a>, Params<'a>, ConnectionId, MaxResponseSize, Extensions) -> BoxFuture<'a, MethodResponse>,
+>;
 /// Method callback for subscriptions.
 pub type SubscriptionMethod<'a> = Arc<
-	dyn Send + Sync + Fn(Id, Params, MethodSink, SubscriptionState) -> BoxFuture<'a, Result<MethodResponse, Id<'a>>>,
+	dyn Send
+		+ Sync
+		+ Fn(Id, Params, MethodSink, SubscriptionState, Extensions) -> BoxFuture<'a, Result<MethodResponse, Id<'a>>>,
 >;
+/// Method callback for notifications, which are never answered.
+pub type NotificationMethod = Arc<dyn Send + Sync + Fn(Params, Extensions) -> BoxFuture<'static, ()>>;
 // Method callback to unsubscribe.
-type UnsubscriptionMethod = Arc<dyn Send + Sync + Fn(Id, Params, ConnectionId, MaxResponseSize) -> MethodResponse>;
+pub(crate) type UnsubscriptionMethod =
+	Arc<dyn Send + Sync + Fn(Id, Params, ConnectionId, MaxResponseSize, Extensions) -> MethodResponse>;
 
 /// Connection ID, used for stateful protocol such as WebSockets.
 /// For stateless protocols such as http it's unused, so feel free to set it some hardcoded value.
@@ -75,8 +98,8 @@ pub type MaxResponseSize = usize;
 /// Raw response from an RPC
 /// A tuple containing:
 ///   - Call result as a `String`,
-///   - a [`mpsc::UnboundedReceiver<String>`] to receive future subscription results
-pub type RawRpcResponse = (MethodResponse, mpsc::Receiver<String>);
+///   - a [`mpsc::UnboundedReceiver<EncodedMessage>`] to receive future subscription results
+pub type RawRpcResponse = (MethodResponse, mpsc::Receiver<EncodedMessage>);
 
 /// This represent a response to a RPC call
 /// and `Subscribe` calls are handled differently
@@ -121,6 +144,8 @@ pub enum MethodCallback {
 	Subscription(SubscriptionMethod<'static>),
 	/// Unsubscription method handler.
 	Unsubscription(UnsubscriptionMethod),
+	/// Notification handler.
+	Notification(NotificationMethod),
 }
 
 /// Result of a method, either direct value or a future of one.
@@ -147,14 +172,40 @@ impl Debug for MethodCallback {
 			Self::Sync(_) => write!(f, "Sync"),
 			Self::Subscription(_) => write!(f, "Subscription"),
 			Self::Unsubscription(_) => write!(f, "Unsubscription"),
+			Self::Notification(_) => write!(f, "Notification"),
 		}
 	}
 }
 
+/// Access class of a method which the server can use to decide who may call it.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MethodAccess {
+	/// The method is safe to expose to anyone, this is the default.
+	#[default]
+	Safe,
+	/// The method should only be exposed to trusted callers.
+	Unsafe,
+}
+
 /// Reference-counted, clone-on-write collection of synchronous and asynchronous methods.
 #[derive(Default, Debug, Clone)]
 pub struct Methods {
 	callbacks: Arc<FxHashMap<&'static str, MethodCallback>>,
+	/// Timeouts of the methods that were registered with one.
+	timeouts: Arc<FxHashMap<&'static str, Duration>>,
+	/// Access classes of the methods that are not [`MethodAccess::Safe`].
+	access: Arc<FxHashMap<&'static str, MethodAccess>>,
+	/// Response caches of the methods that have one.
+	caches: Arc<FxHashMap<&'static str, ResponseCache>>,
+	/// Methods whose concurrent identical calls share one execution.
+	single_flight: Arc<FxHashSet<&'static str>>,
+	/// Methods whose results are streamed.
+	streaming: Arc<FxHashSet<&'static str>>,
+	/// Unsubscribe methods of the subscription methods.
+	unsubscribes: Arc<FxHashMap<&'static str, &'static str>>,
+	/// OpenRPC descriptions of the methods that have one.
+	#[cfg(feature = "openrpc")]
+	schemas: Arc<FxHashMap<&'static str, MethodSchema>>,
 }
 
 impl Methods {
@@ -205,9 +256,87 @@ impl Methods {
 			callbacks.insert(name, callback);
 		}
 
+		if !other.timeouts.is_empty() {
+			Arc::make_mut(&mut self.timeouts).extend(other.timeouts.iter());
+		}
+
+		if !other.access.is_empty() {
+			Arc::make_mut(&mut self.access).extend(other.access.iter());
+		}
+
+		if !other.caches.is_empty() {
+			Arc::make_mut(&mut self.caches).extend(other.caches.iter().map(|(name, cache)| (*name, cache.clone())));
+		}
+
+		if !other.single_flight.is_empty() {
+			Arc::make_mut(&mut self.single_flight).extend(other.single_flight.iter());
+		}
+
+		if !other.streaming.is_empty() {
+			Arc::make_mut(&mut self.streaming).extend(other.streaming.iter());
+		}
+
+		if !other.unsubscribes.is_empty() {
+			Arc::make_mut(&mut self.unsubscribes).extend(other.unsubscribes.iter());
+		}
+
+		#[cfg(feature = "openrpc")]
+		if !other.schemas.is_empty() {
+			Arc::make_mut(&mut self.schemas).extend(other.schemas.iter().map(|(name, schema)| (*name, schema.clone())));
+		}
+
 		Ok(())
 	}
 
+	/// Returns the timeout of the method if it was registered with one.
+	pub fn method_timeout(&self, method_name: &str) -> Option<Duration> {
+		self.timeouts.get(method_name).copied()
+	}
+
+	/// Returns the access class of the method, methods are [`MethodAccess::Safe`] unless configured otherwise.
+	pub fn method_access(&self, method_name: &str) -> MethodAccess {
+		self.access.get(method_name).copied().unwrap_or_default()
+	}
+
+	/// Returns the response cache of the method if it has one.
+	pub fn method_cache(&self, method_name: &str) -> Option<&ResponseCache> {
+		self.caches.get(method_name)
+	}
+
+	/// Returns whether concurrent identical calls to the method share one execution.
+	pub fn is_single_flight(&self, method_name: &str) -> bool {
+		self.single_flight.contains(method_name)
+	}
+
+	/// Returns whether the result of the method is streamed, see [`RpcModule::register_streaming_method`].
+	pub fn is_streaming(&self, method_name: &str) -> bool {
+		self.streaming.contains(method_name)
+	}
+
+	/// Returns the name of the unsubscribe method of a subscription method.
+	pub fn unsubscribe_method(&self, subscribe_method_name: &str) -> Option<&'static str> {
+		self.unsubscribes.get(subscribe_method_name).copied()
+	}
+
+	/// Returns the OpenRPC description of the method if it has one.
+	#[cfg(feature = "openrpc")]
+	pub fn method_schema(&self, method_name: &str) -> Option<&MethodSchema> {
+		self.schemas.get(method_name)
+	}
+
+	/// Build an OpenRPC document of all methods that have a description, ordered by name.
+	#[cfg(feature = "openrpc")]
+	pub fn openrpc(&self, title: impl Into<String>, version: impl Into<String>) -> OpenRpc {
+		let mut schemas: Vec<_> = self.schemas.iter().collect();
+		schemas.sort_by_key(|(name, _)| **name);
+
+		let mut doc = OpenRpc::new(title, version);
+		for (_, schema) in schemas {
+			doc.add_method(schema);
+		}
+		doc
+	}
+
 	/// Returns the method callback.
 	pub fn method(&self, method_name: &str) -> Option<&MethodCallback> {
 		self.callbacks.get(method_name)
@@ -234,7 +363,7 @@ impl Methods {
 	///     use jsonrpsee::core::RpcResult;
 	///
 	///     let mut module = RpcModule::new(());
-	///     module.register_method::<RpcResult<u64>, _>("echo_call", |params, _| {
+	///     module.register_method::<RpcResult<u64>, _>("echo_call", |params, _, _| {
 	///         params.one::<u64>().map_err(Into::into)
 	///     }).unwrap();
 	///
@@ -251,7 +380,7 @@ impl Methods {
 		let req = Request::new(method.into(), params.as_ref().map(|p| p.as_ref()), Id::Number(0));
 		tracing::trace!("[Methods::call] Method: {:?}, params: {:?}", method, params);
 		let (resp, _) = self.inner_call(req, 1, mock_subscription_permit()).await;
-		let rp = serde_json::from_str::<Response<T>>(&resp.result)?;
+		let rp = serde_json::from_str::<Response<T>>(resp.result.as_str().unwrap_or_default())?;
 		ResponseSuccess::try_from(rp).map(|s| s.result).map_err(Error::Call)
 	}
 
@@ -269,7 +398,7 @@ impl Methods {
 	///     use futures_util::StreamExt;
 	///
 	///     let mut module = RpcModule::new(());
-	///     module.register_subscription("hi", "hi", "goodbye", |_, pending, _| async {
+	///     module.register_subscription("hi", "hi", "goodbye", |_, pending, _, _| async {
 	///         let sink = pending.accept().await?;
 	///
 	///         // see comment above.
@@ -278,7 +407,8 @@ impl Methods {
 	///         Ok(())
 	///     }).unwrap();
 	///     let (resp, mut stream) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"hi","id":0}"#, 1).await.unwrap();
-	///     let resp: Success<u64> = serde_json::from_str::<Response<u64>>(&resp.result).unwrap().try_into().unwrap();
+	///     let resp = resp.result.as_str().unwrap();
+	///     let resp: Success<u64> = serde_json::from_str::<Response<u64>>(resp).unwrap().try_into().unwrap();
 	///     let sub_resp = stream.recv().await.unwrap();
 	///     assert_eq!(
 	///         format!(r#"{{"jsonrpc":"2.0","method":"hi","params":{{"subscription":{},"result":"one answer"}}}}"#, resp.result),
@@ -290,7 +420,7 @@ impl Methods {
 		&self,
 		request: &str,
 		buf_size: usize,
-	) -> Result<(MethodResponse, mpsc::Receiver<String>), Error> {
+	) -> Result<(MethodResponse, mpsc::Receiver<EncodedMessage>), Error> {
 		tracing::trace!("[Methods::raw_json_request] Request: {:?}", request);
 		let req: Request = serde_json::from_str(request)?;
 		let (resp, rx) = self.inner_call(req, buf_size, mock_subscription_permit()).await;
@@ -307,18 +437,29 @@ impl Methods {
 	) -> RawRpcResponse {
 		let (tx, mut rx) = mpsc::channel(buf_size);
 		let id = req.id.clone();
-		let params = Params::new(req.params.map(|params| params.get()));
+		let params = match req.decoded_params {
+			Some(params) => params,
+			None => Params::new(req.params.map(|params| params.get())),
+		};
+		let extensions = req.extensions;
+		let encoding = Encoding::from_extensions(&extensions);
 
 		let response = match self.method(&req.method) {
-			None => MethodResponse::error(req.id, ErrorObject::from(ErrorCode::MethodNotFound)),
-			Some(MethodCallback::Sync(cb)) => (cb)(id, params, usize::MAX),
-			Some(MethodCallback::Async(cb)) => (cb)(id.into_owned(), params.into_owned(), 0, usize::MAX).await,
+			None => MethodResponse::encoded_error(req.id, ErrorObject::from(ErrorCode::MethodNotFound), encoding),
+			Some(MethodCallback::Sync(cb)) => (cb)(id, params, usize::MAX, extensions),
+			Some(MethodCallback::Async(cb)) => {
+				(cb)(id.into_owned(), params.into_owned(), 0, usize::MAX, extensions)
+					.await
+					.into_buffered(usize::MAX)
+					.await
+			}
 			Some(MethodCallback::Subscription(cb)) => {
 				let conn_state =
 					SubscriptionState { conn_id: 0, id_provider: &RandomIntegerIdProvider, subscription_permit };
-				let res = match (cb)(id, params, MethodSink::new(tx.clone()), conn_state).await {
+				let sink = MethodSink::new(tx.clone()).with_encoding(encoding);
+				let res = match (cb)(id, params, sink, conn_state, extensions).await {
 					Ok(rp) => rp,
-					Err(id) => MethodResponse::error(id, ErrorObject::from(ErrorCode::InternalError)),
+					Err(id) => MethodResponse::encoded_error(id, ErrorObject::from(ErrorCode::InternalError), encoding),
 				};
 
 				// This message is not used because it's used for metrics so we discard in other to
@@ -329,7 +470,11 @@ impl Methods {
 
 				res
 			}
-			Some(MethodCallback::Unsubscription(cb)) => (cb)(id, params, 0, usize::MAX),
+			Some(MethodCallback::Unsubscription(cb)) => (cb)(id, params, 0, usize::MAX, extensions),
+			// Notification handlers can't be called with a request because they never produce a response.
+			Some(MethodCallback::Notification(_)) => {
+				MethodResponse::encoded_error(req.id, ErrorObject::from(ErrorCode::MethodNotFound), encoding)
+			}
 		};
 
 		tracing::trace!("[Methods::inner_call] Method: {}, response: {:?}", req.method, response);
@@ -352,7 +497,7 @@ impl Methods {
 	///     use jsonrpsee::core::{Error, EmptyServerParams, RpcResult};
 	///
 	///     let mut module = RpcModule::new(());
-	///     module.register_subscription("hi", "hi", "goodbye", |_, pending, _| async move {
+	///     module.register_subscription("hi", "hi", "goodbye", |_, pending, _, _| async move {
 	///         let sink = pending.accept().await?;
 	///         sink.send("one answer".into()).await?;
 	///         Ok(())
@@ -385,7 +530,7 @@ impl Methods {
 
 		// TODO: hack around the lifetime on the `SubscriptionId` by deserialize first to serde_json::Value.
 		let as_success: ResponseSuccess<serde_json::Value> =
-			serde_json::from_str::<Response<_>>(&resp.result)?.try_into()?;
+			serde_json::from_str::<Response<_>>(resp.result.as_str().unwrap_or_default())?.try_into()?;
 
 		let sub_id = as_success.result.try_into().map_err(|_| Error::InvalidSubscriptionId)?;
 
@@ -451,14 +596,14 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 	where
 		Context: Send + Sync + 'static,
 		R: IntoResponse + 'static,
-		F: Fn(Params, &Context) -> R + Send + Sync + 'static,
+		F: Fn(Params, &Context, &Extensions) -> R + Send + Sync + 'static,
 	{
 		let ctx = self.ctx.clone();
 		self.methods.verify_and_insert(
 			method_name,
-			MethodCallback::Sync(Arc::new(move |id, params, max_response_size| {
-				let rp = callback(params, &*ctx).into_response();
-				MethodResponse::response(id, rp, max_response_size)
+			MethodCallback::Sync(Arc::new(move |id, params, max_response_size, extensions| {
+				let rp = callback(params, &*ctx, &extensions).into_response();
+				MethodResponse::encoded_response(id, rp, max_response_size, Encoding::from_extensions(&extensions))
 			})),
 		)
 	}
@@ -472,24 +617,170 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 	where
 		R: IntoResponse + 'static,
 		Fut: Future<Output = R> + Send,
-		Fun: (Fn(Params<'static>, Arc<Context>) -> Fut) + Clone + Send + Sync + 'static,
+		Fun: (Fn(Params<'static>, Arc<Context>, Extensions) -> Fut) + Clone + Send + Sync + 'static,
 	{
 		let ctx = self.ctx.clone();
 		self.methods.verify_and_insert(
 			method_name,
-			MethodCallback::Async(Arc::new(move |id, params, _, max_response_size| {
+			MethodCallback::Async(Arc::new(move |id, params, _, max_response_size, extensions| {
 				let ctx = ctx.clone();
 				let callback = callback.clone();
+				let encoding = Encoding::from_extensions(&extensions);
 
 				let future = async move {
-					let rp = callback(params, ctx).await.into_response();
-					MethodResponse::response(id, rp, max_response_size)
+					let rp = callback(params, ctx, extensions).await.into_response();
+					MethodResponse::encoded_response(id, rp, max_response_size, encoding)
 				};
 				future.boxed()
 			})),
 		)
 	}
 
+	/// Register a new asynchronous RPC method which is cancelled if it doesn't complete within `timeout`.
+	///
+	/// A call that exceeds the timeout is answered with the
+	/// [`CALL_TIMEOUT_CODE`](jsonrpsee_types::error::CALL_TIMEOUT_CODE) error.
+	///
+	/// ```
+	/// use std::time::Duration;
+	/// use jsonrpsee_core::server::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_async_method_with_timeout("slow", Duration::from_secs(5), |_params, _ctx, _| async {
+	///     tokio::time::sleep(Duration::from_secs(1)).await;
+	///     "done"
+	/// }).unwrap();
+	/// ```
+	pub fn register_async_method_with_timeout<R, Fun, Fut>(
+		&mut self,
+		method_name: &'static str,
+		timeout: Duration,
+		callback: Fun,
+	) -> Result<&mut MethodCallback, Error>
+	where
+		R: IntoResponse + 'static,
+		Fut: Future<Output = R> + Send,
+		Fun: (Fn(Params<'static>, Arc<Context>, Extensions) -> Fut) + Clone + Send + Sync + 'static,
+	{
+		self.register_async_method(method_name, callback)?;
+		Ok(self.set_timeout(method_name, timeout))
+	}
+
+	/// Register a new asynchronous RPC method whose result is streamed to the client while it's produced.
+	///
+	/// Streamed results aren't limited by the max response size of the server and are never held in memory
+	/// as a whole: they are sent with chunked transfer encoding over HTTP and as a fragmented message over
+	/// WebSocket. They are buffered within the max response size when they are part of a batch, when the call
+	/// is encoded in CBOR or when the WebSocket connection uses `permessage-deflate`.
+	///
+	/// A streaming method can't be cached or single-flight.
+	///
+	/// ```
+	/// use futures_util::stream;
+	/// use jsonrpsee_core::server::{ResultStream, RpcModule};
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_streaming_method("state_dump", |_params, _ctx, _| async {
+	///     Ok(ResultStream::from_items(stream::iter(0..1_000_000_u64)))
+	/// }).unwrap();
+	/// assert!(module.is_streaming("state_dump"));
+	/// ```
+	pub fn register_streaming_method<Fun, Fut>(
+		&mut self,
+		method_name: &'static str,
+		callback: Fun,
+	) -> Result<&mut MethodCallback, Error>
+	where
+		Fut: Future<Output = Result<ResultStream, ErrorObjectOwned>> + Send,
+		Fun: (Fn(Params<'static>, Arc<Context>, Extensions) -> Fut) + Clone + Send + Sync + 'static,
+	{
+		self.methods.verify_method_name(method_name)?;
+		Arc::make_mut(&mut self.methods.streaming).insert(method_name);
+
+		let ctx = self.ctx.clone();
+		self.methods.verify_and_insert(
+			method_name,
+			MethodCallback::Async(Arc::new(move |id, params, _, max_response_size, extensions| {
+				let ctx = ctx.clone();
+				let callback = callback.clone();
+				let encoding = Encoding::from_extensions(&extensions);
+
+				let future = async move {
+					let result = match callback(params, ctx, extensions).await {
+						Ok(result) => result,
+						Err(err) => return MethodResponse::encoded_error(id, err, encoding),
+					};
+					let rp = MethodResponse::streamed(id, result, encoding);
+
+					#[cfg(feature = "cbor")]
+					if encoding == Encoding::Cbor {
+						return rp.into_buffered(max_response_size).await;
+					}
+
+					#[cfg(not(feature = "cbor"))]
+					let _ = max_response_size;
+
+					rp
+				};
+				future.boxed()
+			})),
+		)
+	}
+
+	/// Register a new synchronous notification handler.
+	///
+	/// The handler is invoked for every JSON-RPC notification, i.e. a request without an `id`, with the
+	/// given method name and, as the specification demands, the server never responds to it.
+	/// Requests with an `id` to the method are answered with a `method not found` error.
+	///
+	/// ```
+	/// use std::sync::atomic::{AtomicUsize, Ordering};
+	/// use jsonrpsee_core::server::RpcModule;
+	///
+	/// let mut module = RpcModule::new(AtomicUsize::new(0));
+	/// module.register_notification("telemetry", |_params, counter, _| {
+	///     counter.fetch_add(1, Ordering::Relaxed);
+	/// }).unwrap();
+	/// ```
+	pub fn register_notification<F>(
+		&mut self,
+		method_name: &'static str,
+		callback: F,
+	) -> Result<&mut MethodCallback, Error>
+	where
+		F: Fn(Params, &Context, &Extensions) + Send + Sync + 'static,
+	{
+		let ctx = self.ctx.clone();
+		self.methods.verify_and_insert(
+			method_name,
+			MethodCallback::Notification(Arc::new(move |params, extensions| {
+				callback(params, &*ctx, &extensions);
+				futures_util::future::ready(()).boxed()
+			})),
+		)
+	}
+
+	/// Register a new asynchronous notification handler.
+	///
+	/// See [`RpcModule::register_notification`] for further documentation.
+	pub fn register_async_notification<Fun, Fut>(
+		&mut self,
+		method_name: &'static str,
+		callback: Fun,
+	) -> Result<&mut MethodCallback, Error>
+	where
+		Fut: Future<Output = ()> + Send + 'static,
+		Fun: (Fn(Params<'static>, Arc<Context>, Extensions) -> Fut) + Clone + Send + Sync + 'static,
+	{
+		let ctx = self.ctx.clone();
+		self.methods.verify_and_insert(
+			method_name,
+			MethodCallback::Notification(Arc::new(move |params, extensions| {
+				callback(params.into_owned(), ctx.clone(), extensions).boxed()
+			})),
+		)
+	}
+
 	/// Register a new **blocking** synchronous RPC method, which computes the response with the given callback.
 	/// Unlike the regular [`register_method`](RpcModule::register_method), this method can block its thread and perform
 	/// expensive computations.
@@ -501,24 +792,25 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 	where
 		Context: Send + Sync + 'static,
 		R: IntoResponse + 'static,
-		F: Fn(Params, Arc<Context>) -> R + Clone + Send + Sync + 'static,
+		F: Fn(Params, Arc<Context>, Extensions) -> R + Clone + Send + Sync + 'static,
 	{
 		let ctx = self.ctx.clone();
 		let callback = self.methods.verify_and_insert(
 			method_name,
-			MethodCallback::Async(Arc::new(move |id, params, _, max_response_size| {
+			MethodCallback::Async(Arc::new(move |id, params, _, max_response_size, extensions| {
 				let ctx = ctx.clone();
 				let callback = callback.clone();
+				let encoding = Encoding::from_extensions(&extensions);
 
 				tokio::task::spawn_blocking(move || {
-					let rp = callback(params, ctx).into_response();
-					MethodResponse::response(id, rp, max_response_size)
+					let rp = callback(params, ctx, extensions).into_response();
+					MethodResponse::encoded_response(id, rp, max_response_size, encoding)
 				})
-				.map(|result| match result {
+				.map(move |result| match result {
 					Ok(r) => r,
 					Err(err) => {
 						tracing::error!("Join error for blocking RPC method: {:?}", err);
-						MethodResponse::error(Id::Null, ErrorObject::from(ErrorCode::InternalError))
+						MethodResponse::encoded_error(Id::Null, ErrorObject::from(ErrorCode::InternalError), encoding)
 					}
 				})
 				.boxed()
@@ -528,6 +820,25 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 		Ok(callback)
 	}
 
+	/// Register a new **blocking** synchronous RPC method which is answered with the
+	/// [`CALL_TIMEOUT_CODE`](jsonrpsee_types::error::CALL_TIMEOUT_CODE) error if it doesn't complete within `timeout`.
+	///
+	/// The blocking thread can't be interrupted and keeps running until the callback returns.
+	pub fn register_blocking_method_with_timeout<R, F>(
+		&mut self,
+		method_name: &'static str,
+		timeout: Duration,
+		callback: F,
+	) -> Result<&mut MethodCallback, Error>
+	where
+		Context: Send + Sync + 'static,
+		R: IntoResponse + 'static,
+		F: Fn(Params, Arc<Context>, Extensions) -> R + Clone + Send + Sync + 'static,
+	{
+		self.register_blocking_method(method_name, callback)?;
+		Ok(self.set_timeout(method_name, timeout))
+	}
+
 	/// Register a new publish/subscribe interface using JSON-RPC notifications.
 	///
 	/// It implements the [ethereum pubsub specification](https://geth.ethereum.org/docs/rpc/pubsub)
@@ -548,11 +859,12 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 	/// * `notif_method_name` - name of method to be used in the subscription payload (technically a JSON-RPC
 	///   notification)
 	/// * `unsubscription_method` - name of the method to call to terminate a subscription
-	/// * `callback` - A callback to invoke on each subscription; it takes three parameters:
+	/// * `callback` - A callback to invoke on each subscription; it takes four parameters:
 	///     - [`Params`]: JSON-RPC parameters in the subscription call.
 	///     - [`PendingSubscriptionSink`]: A pending subscription waiting to be accepted, in order to send out messages
 	///       on the subscription
 	///     - Context: Any type that can be embedded into the [`RpcModule`].
+	///     - [`Extensions`]: The extensions of the subscription call such as the HTTP headers.
 	///
 	/// # Returns
 	///
@@ -590,7 +902,7 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 	/// use jsonrpsee_types::ErrorObjectOwned;
 	///
 	/// let mut ctx = RpcModule::new(99_usize);
-	/// ctx.register_subscription("sub", "notif_name", "unsub", |params, pending, ctx| async move {
+	/// ctx.register_subscription("sub", "notif_name", "unsub", |params, pending, ctx, _| async move {
 	///
 	///     let x = match params.one::<usize>() {
 	///         Ok(x) => x,
@@ -631,7 +943,11 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 	) -> Result<&mut MethodCallback, Error>
 	where
 		Context: Send + Sync + 'static,
-		F: (Fn(Params<'static>, PendingSubscriptionSink, Arc<Context>) -> Fut) + Send + Sync + Clone + 'static,
+		F: (Fn(Params<'static>, PendingSubscriptionSink, Arc<Context>, Extensions) -> Fut)
+			+ Send
+			+ Sync
+			+ Clone
+			+ 'static,
 		Fut: Future<Output = R> + Send + 'static,
 		R: IntoSubscriptionCloseResponse + Send,
 	{
@@ -642,7 +958,8 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 		let callback = {
 			self.methods.verify_and_insert(
 				subscribe_method_name,
-				MethodCallback::Subscription(Arc::new(move |id, params, method_sink, conn| {
+				MethodCallback::Subscription(Arc::new(move |id, params, method_sink, conn, extensions| {
+					let method_sink = sink_with_call_encoding(method_sink, &extensions);
 					let uniq_sub = SubscriptionKey { conn_id: conn.conn_id, sub_id: conn.id_provider.next_id() };
 
 					// response to the subscription call.
@@ -651,6 +968,9 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 
 					let sub_id = uniq_sub.sub_id.clone();
 					let method = notif_method_name;
+					// The permit is held until the close notification has been sent, which
+					// allows the server to wait for it when shutting down.
+					let permit = Arc::new(conn.subscription_permit);
 
 					let sink = PendingSubscriptionSink {
 						inner: method_sink.clone(),
@@ -659,16 +979,18 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 						uniq_sub,
 						id: id.clone().into_owned(),
 						subscribe: tx,
-						permit: conn.subscription_permit,
+						permit: permit.clone(),
 					};
 
 					// The subscription callback is a future from the subscription
 					// definition and not the as same when the subscription call has been completed.
 					//
 					// This runs until the subscription callback has completed.
-					let sub_fut = callback(params.into_owned(), sink, ctx.clone());
+					let sub_fut = callback(params.into_owned(), sink, ctx.clone(), extensions);
 
-					tokio::spawn(async move {
+					// The subscription task inherits the span and the trace context of the subscription call
+					// such that its notifications are attributed to the call.
+					let sub_task = async move {
 						// This will wait for the subscription future to be resolved
 						let response = match futures_util::future::try_join(sub_fut.map(|f| Ok(f)), accepted_rx).await {
 							Ok((r, _)) => r.into_response(),
@@ -678,16 +1000,24 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 
 						match response {
 							SubscriptionCloseResponse::Notif(msg) => {
-								let json = sub_message_to_json(msg, SubNotifResultOrError::Result, &sub_id, method);
-								let _ = method_sink.send(json).await;
+								let encoding = method_sink.encoding();
+								let msg =
+									encode_sub_message(msg, SubNotifResultOrError::Result, &sub_id, method, encoding);
+								let _ = method_sink.send(msg).await;
 							}
 							SubscriptionCloseResponse::NotifErr(msg) => {
-								let json = sub_message_to_json(msg, SubNotifResultOrError::Error, &sub_id, method);
-								let _ = method_sink.send(json).await;
+								let encoding = method_sink.encoding();
+								let msg =
+									encode_sub_message(msg, SubNotifResultOrError::Error, &sub_id, method, encoding);
+								let _ = method_sink.send(msg).await;
 							}
 							SubscriptionCloseResponse::None => (),
 						}
-					});
+
+						drop(permit);
+					};
+
+					tokio::spawn(TraceParent::in_current_scope(sub_task).in_current_span());
 
 					let id = id.clone().into_owned();
 
@@ -726,7 +1056,7 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 	/// use jsonrpsee_types::ErrorObjectOwned;
 	///
 	/// let mut ctx = RpcModule::new(99_usize);
-	/// ctx.register_subscription_raw("sub", "notif_name", "unsub", |params, pending, ctx| {
+	/// ctx.register_subscription_raw("sub", "notif_name", "unsub", |params, pending, ctx, _| {
 	///
 	///     // The params are parsed outside the async block below to avoid cloning the bytes.
 	///     let val = match params.one::<usize>() {
@@ -765,7 +1095,7 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 	) -> Result<&mut MethodCallback, Error>
 	where
 		Context: Send + Sync + 'static,
-		F: (Fn(Params, PendingSubscriptionSink, Arc<Context>) -> R) + Send + Sync + Clone + 'static,
+		F: (Fn(Params, PendingSubscriptionSink, Arc<Context>, &Extensions) -> R) + Send + Sync + Clone + 'static,
 		R: IntoSubscriptionCloseResponse,
 	{
 		let subscribers = self.verify_and_register_unsubscribe(subscribe_method_name, unsubscribe_method_name)?;
@@ -775,7 +1105,8 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 		let callback = {
 			self.methods.verify_and_insert(
 				subscribe_method_name,
-				MethodCallback::Subscription(Arc::new(move |id, params, method_sink, conn| {
+				MethodCallback::Subscription(Arc::new(move |id, params, method_sink, conn, extensions| {
+					let method_sink = sink_with_call_encoding(method_sink, &extensions);
 					let uniq_sub = SubscriptionKey { conn_id: conn.conn_id, sub_id: conn.id_provider.next_id() };
 
 					// response to the subscription call.
@@ -788,10 +1119,10 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 						uniq_sub,
 						id: id.clone().into_owned(),
 						subscribe: tx,
-						permit: conn.subscription_permit,
+						permit: Arc::new(conn.subscription_permit),
 					};
 
-					callback(params, sink, ctx.clone());
+					callback(params, sink, ctx.clone(), &extensions);
 
 					let id = id.clone().into_owned();
 
@@ -829,7 +1160,9 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 			let subscribers = subscribers.clone();
 			self.methods.mut_callbacks().insert(
 				unsubscribe_method_name,
-				MethodCallback::Unsubscription(Arc::new(move |id, params, conn_id, max_response_size| {
+				MethodCallback::Unsubscription(Arc::new(move |id, params, conn_id, max_response_size, extensions| {
+					let encoding = Encoding::from_extensions(&extensions);
+
 					let sub_id = match params.one::<RpcSubscriptionId>() {
 						Ok(sub_id) => sub_id,
 						Err(_) => {
@@ -840,7 +1173,8 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 								id
 							);
 
-							return MethodResponse::response(id, ResponsePayload::result(false), max_response_size);
+							let rp = ResponsePayload::result(false);
+							return MethodResponse::encoded_response(id, rp, max_response_size, encoding);
 						}
 					};
 
@@ -855,11 +1189,13 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 						);
 					}
 
-					MethodResponse::response(id, ResponsePayload::result(result), max_response_size)
+					MethodResponse::encoded_response(id, ResponsePayload::result(result), max_response_size, encoding)
 				})),
 			);
 		}
 
+		Arc::make_mut(&mut self.methods.unsubscribes).insert(subscribe_method_name, unsubscribe_method_name);
+
 		Ok(subscribers)
 	}
 
@@ -874,8 +1210,563 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 
 		self.methods.mut_callbacks().insert(alias, callback);
 
+		if let Some(timeout) = self.methods.method_timeout(existing_method) {
+			Arc::make_mut(&mut self.methods.timeouts).insert(alias, timeout);
+		}
+
+		if let Some(access) = self.methods.access.get(existing_method).copied() {
+			Arc::make_mut(&mut self.methods.access).insert(alias, access);
+		}
+
+		if let Some(cache) = self.methods.method_cache(existing_method).cloned() {
+			Arc::make_mut(&mut self.methods.caches).insert(alias, cache);
+		}
+
+		if self.methods.is_single_flight(existing_method) {
+			Arc::make_mut(&mut self.methods.single_flight).insert(alias);
+		}
+
+		if self.methods.is_streaming(existing_method) {
+			Arc::make_mut(&mut self.methods.streaming).insert(alias);
+		}
+
+		if let Some(unsubscribe) = self.methods.unsubscribe_method(existing_method) {
+			Arc::make_mut(&mut self.methods.unsubscribes).insert(alias, unsubscribe);
+		}
+
+		Ok(())
+	}
+
+	/// Register methods to poll an already registered subscription, for clients that can't receive notifications.
+	///
+	/// A call to `new_filter_method_name` subscribes with its params on behalf of the caller and is answered with
+	/// the ID of the filter, which is the ID of the subscription. The notifications of the subscription are buffered
+	/// until they are polled with a call to `get_filter_changes_method_name`, which is answered with the array of the
+	/// results of the notifications since the last call, and a call to `uninstall_filter_method_name` unsubscribes.
+	/// Filters which aren't polled within their idle timeout are uninstalled.
+	///
+	/// The filters aren't tied to a connection, such that they can be polled with separate HTTP requests.
+	/// Their number is bounded in total and per client, see [`FilterConfig`].
+	/// The filter methods inherit the access class of the subscription method.
+	///
+	/// ```
+	/// use std::time::Duration;
+	/// use jsonrpsee_core::server::{FilterConfig, RpcModule};
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_subscription("subscribe_blocks", "block", "unsubscribe_blocks", |_, pending, _, _| async move {
+	///     let _sink = pending.accept().await?;
+	///     Ok(())
+	/// }).unwrap();
+	/// module.register_subscription_filter(
+	///     "subscribe_blocks",
+	///     "blocks_newFilter",
+	///     "blocks_getFilterChanges",
+	///     "blocks_uninstallFilter",
+	///     FilterConfig::new(Duration::from_secs(300)),
+	/// ).unwrap();
+	/// ```
+	pub fn register_subscription_filter(
+		&mut self,
+		subscribe_method_name: &'static str,
+		new_filter_method_name: &'static str,
+		get_filter_changes_method_name: &'static str,
+		uninstall_filter_method_name: &'static str,
+		config: impl Into<FilterConfig>,
+	) -> Result<(), Error> {
+		let subscribe = match self.methods.method(subscribe_method_name) {
+			Some(MethodCallback::Subscription(subscribe)) => subscribe.clone(),
+			Some(_) => {
+				let msg = format!("Only subscriptions can be filtered, `{subscribe_method_name}` isn't one");
+				return Err(Error::Custom(msg));
+			}
+			None => return Err(Error::MethodNotFound(subscribe_method_name.into())),
+		};
+		let unsubscribe_method_name = self.methods.unsubscribe_method(subscribe_method_name);
+		let unsubscribe = match unsubscribe_method_name.and_then(|name| self.methods.method(name)) {
+			Some(MethodCallback::Unsubscription(unsubscribe)) => unsubscribe.clone(),
+			_ => return Err(Error::MethodNotFound(subscribe_method_name.into())),
+		};
+
+		for name in [new_filter_method_name, get_filter_changes_method_name, uninstall_filter_method_name] {
+			self.methods.verify_method_name(name)?;
+		}
+
+		let filters = Filters::new(config.into(), subscribe, unsubscribe);
+
+		{
+			let filters = filters.clone();
+			self.methods.mut_callbacks().insert(
+				new_filter_method_name,
+				MethodCallback::Async(Arc::new(move |id, params, conn_id, max_response_size, extensions| {
+					let filters = filters.clone();
+					async move { filters.create(id, params, conn_id, max_response_size, extensions).await }.boxed()
+				})),
+			);
+		}
+		{
+			let filters = filters.clone();
+			self.methods.mut_callbacks().insert(
+				get_filter_changes_method_name,
+				MethodCallback::Sync(Arc::new(move |id, params, max_response_size, extensions| {
+					filters.changes(id, params, max_response_size, Encoding::from_extensions(&extensions))
+				})),
+			);
+		}
+		self.methods.mut_callbacks().insert(
+			uninstall_filter_method_name,
+			MethodCallback::Sync(Arc::new(move |id, params, max_response_size, extensions| {
+				filters.uninstall(id, params, max_response_size, Encoding::from_extensions(&extensions))
+			})),
+		);
+
+		if let Some(access) = self.methods.access.get(subscribe_method_name).copied() {
+			let access_map = Arc::make_mut(&mut self.methods.access);
+			for name in [new_filter_method_name, get_filter_changes_method_name, uninstall_filter_method_name] {
+				access_map.insert(name, access);
+			}
+		}
+
 		Ok(())
 	}
+
+	/// Make the subscriptions of an already registered subscription method resumable on another connection.
+	///
+	/// The notifications of the subscriptions are numbered with a `seq` field in their params, starting at `1`, and
+	/// the last notifications are buffered. Once the connection of a subscriber has been closed, its subscriptions are
+	/// kept for the grace period before they are unsubscribed. A call to `resume_method_name` with the params
+	/// `[subscription_id, last_seq]` resumes the subscription on the connection of the caller: it's answered with the
+	/// ID of the subscription, followed by the buffered notifications after `last_seq` and the notifications to come.
+	/// The call is rejected if any of these notifications are no longer buffered, such that no notification is missed.
+	///
+	/// The call is rejected as well while the subscription is still attached to a subscriber, it can only be resumed
+	/// once the connection of its subscriber has been closed.
+	///
+	/// A detached subscription can only be resumed by a caller with the same [`Role`](crate::server::Role) as the
+	/// caller which created it. Callers without a role can resume the subscriptions of any caller without a role
+	/// who knows their ID, which should thus be unguessable such as the IDs of the default [`RandomIntegerIdProvider`].
+	/// The resume method inherits the access class of the subscription method.
+	///
+	/// ```
+	/// use std::time::Duration;
+	/// use jsonrpsee_core::server::{ResumeConfig, RpcModule};
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_subscription("subscribe_blocks", "block", "unsubscribe_blocks", |_, pending, _, _| async move {
+	///     let _sink = pending.accept().await?;
+	///     Ok(())
+	/// }).unwrap();
+	/// module.set_subscription_resumable(
+	///     "subscribe_blocks",
+	///     "resume_blocks",
+	///     ResumeConfig::new(Duration::from_secs(30)).max_buffered(256),
+	/// ).unwrap();
+	/// ```
+	pub fn set_subscription_resumable(
+		&mut self,
+		subscribe_method_name: &'static str,
+		resume_method_name: &'static str,
+		config: impl Into<ResumeConfig>,
+	) -> Result<(), Error> {
+		let subscribe = match self.methods.method(subscribe_method_name) {
+			Some(MethodCallback::Subscription(subscribe)) => subscribe.clone(),
+			Some(_) => {
+				let msg = format!("Only subscriptions can be resumed, `{subscribe_method_name}` isn't one");
+				return Err(Error::Custom(msg));
+			}
+			None => return Err(Error::MethodNotFound(subscribe_method_name.into())),
+		};
+		let Some(unsubscribe_method_name) = self.methods.unsubscribe_method(subscribe_method_name) else {
+			return Err(Error::MethodNotFound(subscribe_method_name.into()));
+		};
+		let unsubscribe = match self.methods.method(unsubscribe_method_name) {
+			Some(MethodCallback::Unsubscription(unsubscribe)) => unsubscribe.clone(),
+			_ => return Err(Error::MethodNotFound(unsubscribe_method_name.into())),
+		};
+
+		self.methods.verify_method_name(resume_method_name)?;
+
+		let resumable = Resumable::new(config.into());
+		let callbacks = self.methods.mut_callbacks();
+		callbacks.insert(
+			subscribe_method_name,
+			MethodCallback::Subscription(resumable.subscribe(subscribe, unsubscribe.clone())),
+		);
+		callbacks.insert(unsubscribe_method_name, MethodCallback::Unsubscription(resumable.unsubscribe(unsubscribe)));
+		callbacks.insert(resume_method_name, MethodCallback::Subscription(resumable.resume_method()));
+		Arc::make_mut(&mut self.methods.unsubscribes).insert(resume_method_name, unsubscribe_method_name);
+
+		if let Some(access) = self.methods.access.get(subscribe_method_name).copied() {
+			Arc::make_mut(&mut self.methods.access).insert(resume_method_name, access);
+		}
+
+		Ok(())
+	}
+
+	/// Set the access class of an already registered method.
+	///
+	/// Aliases registered afterwards inherit the access class of the method.
+	///
+	/// ```
+	/// use jsonrpsee_core::server::{MethodAccess, RpcModule};
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_method("admin_shutdown", |_, _, _| "ok").unwrap();
+	/// module.set_method_access("admin_shutdown", MethodAccess::Unsafe).unwrap();
+	/// assert_eq!(module.method_access("admin_shutdown"), MethodAccess::Unsafe);
+	/// ```
+	pub fn set_method_access(&mut self, method_name: &'static str, access: MethodAccess) -> Result<(), Error> {
+		if self.methods.method(method_name).is_none() {
+			return Err(Error::MethodNotFound(method_name.into()));
+		}
+
+		let access_map = Arc::make_mut(&mut self.methods.access);
+
+		match access {
+			MethodAccess::Safe => access_map.remove(method_name),
+			_ => access_map.insert(method_name, access),
+		};
+
+		Ok(())
+	}
+
+	/// Cache the successful responses of an already registered method.
+	///
+	/// Calls of the same [`Role`](crate::server::Role) with the same params, after canonicalization, are answered
+	/// from the cache until the cached response expires. The [`ResponseCache`] is available in the extensions of
+	/// the calls to the method, such that the method can invalidate its own entries.
+	///
+	/// Only use this for idempotent methods, i.e. methods which always return the same response
+	/// for the same params within the time-to-live of the cache. Use [`RpcModule::set_method_cache_with_key`]
+	/// if the result depends on other extensions of the call, such as its headers or connection.
+	///
+	/// Calls with CBOR params bypass the cache.
+	///
+	/// ```
+	/// use std::time::Duration;
+	/// use jsonrpsee_core::server::{ResponseCacheConfig, RpcModule};
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_method("chain_metadata", |_, _, _| "metadata").unwrap();
+	/// module.set_method_cache("chain_metadata", ResponseCacheConfig::new(Duration::from_secs(10))).unwrap();
+	/// assert!(module.method_cache("chain_metadata").is_some());
+	/// ```
+	pub fn set_method_cache(
+		&mut self,
+		method_name: &'static str,
+		config: impl Into<ResponseCacheConfig>,
+	) -> Result<(), Error> {
+		self.set_method_cache_with_key(method_name, config, role_cache_key)
+	}
+
+	/// Cache the successful responses of an already registered method by a custom key.
+	///
+	/// The `key` of a call is computed from its params and extensions, calls without a key bypass the cache.
+	/// [`ResponseCache::invalidate`] uses the same key. See [`RpcModule::set_method_cache`] for how the
+	/// responses are cached.
+	///
+	/// ```
+	/// use std::time::Duration;
+	/// use jsonrpsee_core::server::{ResponseCacheConfig, RpcModule};
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_method("account_balance", |_, _, _| 1337).unwrap();
+	/// // Cache the responses per tenant, whose name a middleware inserted into the extensions.
+	/// module.set_method_cache_with_key(
+	///     "account_balance",
+	///     ResponseCacheConfig::new(Duration::from_secs(10)),
+	///     |params, extensions| {
+	///         let tenant = extensions.get::<String>()?;
+	///         Some(format!("{tenant}:{}", params.as_str()?))
+	///     },
+	/// ).unwrap();
+	/// ```
+	pub fn set_method_cache_with_key<K>(
+		&mut self,
+		method_name: &'static str,
+		config: impl Into<ResponseCacheConfig>,
+		key: K,
+	) -> Result<(), Error>
+	where
+		K: Fn(&Params, &Extensions) -> Option<String> + Send + Sync + 'static,
+	{
+		if self.methods.is_streaming(method_name) {
+			return Err(Error::Custom(format!("Streamed results can't be cached, `{method_name}` is streaming")));
+		}
+
+		let cache = ResponseCache::new(config.into(), Arc::new(key));
+
+		let callback = match self.methods.mut_callbacks().get_mut(method_name) {
+			Some(callback) => callback,
+			None => return Err(Error::MethodNotFound(method_name.into())),
+		};
+
+		*callback = match callback {
+			MethodCallback::Sync(inner) => {
+				let (inner, cache) = (inner.clone(), cache.clone());
+				MethodCallback::Sync(Arc::new(move |id, params, max_response_size, mut extensions| {
+					let key = cache.key(&params, &extensions);
+					let encoding = Encoding::from_extensions(&extensions);
+					if let Some(rp) =
+						key.as_deref().and_then(|key| cached_response(&cache, key, &id, max_response_size, encoding))
+					{
+						return rp;
+					}
+
+					extensions.insert(cache.clone());
+					let rp = inner(id, params, max_response_size, extensions);
+					cache_response(&cache, key, &rp);
+					rp
+				}))
+			}
+			MethodCallback::Async(inner) => {
+				let (inner, cache) = (inner.clone(), cache.clone());
+				MethodCallback::Async(Arc::new(move |id, params, conn_id, max_response_size, mut extensions| {
+					let key = cache.key(&params, &extensions);
+					let encoding = Encoding::from_extensions(&extensions);
+					if let Some(rp) =
+						key.as_deref().and_then(|key| cached_response(&cache, key, &id, max_response_size, encoding))
+					{
+						return async move { rp }.boxed();
+					}
+
+					extensions.insert(cache.clone());
+					let call = inner(id, params, conn_id, max_response_size, extensions);
+					let cache = cache.clone();
+
+					async move {
+						let rp = call.await;
+						cache_response(&cache, key, &rp);
+						rp
+					}
+					.boxed()
+				}))
+			}
+			_ => return Err(Error::Custom(format!("Only method calls can be cached, `{method_name}` isn't a method"))),
+		};
+
+		Arc::make_mut(&mut self.methods.caches).insert(method_name, cache);
+
+		Ok(())
+	}
+
+	/// Let concurrent identical calls to an already registered async method share one execution.
+	///
+	/// While a call to the method is in flight, calls with the same params, after canonicalization, wait for
+	/// it to complete instead of running the method again. The response is then sent to all of them, each with
+	/// the `id` of its own request. The method runs with the extensions of the call that started it, so only
+	/// calls of the same [`Role`](crate::server::Role) share an execution. Calls only share an execution with calls
+	/// of the same [`Encoding`], and calls with CBOR params are always executed on their own. If the method panics,
+	/// the waiting calls are answered with an internal error and the next call runs the method again.
+	///
+	/// Use [`RpcModule::set_method_single_flight_with_key`] if the result depends on other extensions
+	/// of the call, such as its headers or connection.
+	///
+	/// ```
+	/// use jsonrpsee_core::server::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_async_method("state_expensive", |_, _, _| async { "result" }).unwrap();
+	/// module.set_method_single_flight("state_expensive").unwrap();
+	/// assert!(module.is_single_flight("state_expensive"));
+	/// ```
+	pub fn set_method_single_flight(&mut self, method_name: &'static str) -> Result<(), Error> {
+		self.set_method_single_flight_with_key(method_name, role_cache_key)
+	}
+
+	/// Let concurrent calls to an already registered async method share one execution if their keys are equal.
+	///
+	/// The `key` of a call is computed from its params and extensions, calls without a key are always executed on
+	/// their own. See [`RpcModule::set_method_single_flight`] for how the execution is shared.
+	///
+	/// ```
+	/// use jsonrpsee_core::server::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_async_method("account_balance", |_, _, _| async { 1337 }).unwrap();
+	/// // Share the execution between the calls of a tenant, whose name a middleware inserted into the extensions.
+	/// module.set_method_single_flight_with_key("account_balance", |params, extensions| {
+	///     let tenant = extensions.get::<String>()?;
+	///     Some(format!("{tenant}:{}", params.as_str()?))
+	/// }).unwrap();
+	/// ```
+	pub fn set_method_single_flight_with_key<K>(&mut self, method_name: &'static str, key: K) -> Result<(), Error>
+	where
+		K: Fn(&Params, &Extensions) -> Option<String> + Send + Sync + 'static,
+	{
+		if self.methods.is_streaming(method_name) {
+			return Err(Error::Custom(format!("Streamed results can't be shared, `{method_name}` is streaming")));
+		}
+
+		let callback = match self.methods.mut_callbacks().get_mut(method_name) {
+			Some(callback) => callback,
+			None => return Err(Error::MethodNotFound(method_name.into())),
+		};
+
+		let MethodCallback::Async(inner) = callback else {
+			return Err(Error::Custom(format!("Only async methods can be single-flight, `{method_name}` isn't async")));
+		};
+
+		let inner = inner.clone();
+		let in_flight: InFlightCalls = Default::default();
+
+		*callback = MethodCallback::Async(Arc::new(move |id, params, conn_id, max_response_size, extensions| {
+			let Some(key) = key(&params, &extensions) else {
+				return inner(id, params, conn_id, max_response_size, extensions);
+			};
+			let key = (Encoding::from_extensions(&extensions), key);
+
+			let call = in_flight
+				.lock()
+				.entry(key.clone())
+				.or_insert_with(|| {
+					// The call is made with `Id::Null` and answered with the `id` of each waiter.
+					let call = inner(Id::Null, params, conn_id, max_response_size, extensions);
+					let in_flight = in_flight.clone();
+
+					async move {
+						// A panic would poison the shared call, so it's answered with an error instead
+						// such that the next call with the same key runs again.
+						let rp = std::panic::AssertUnwindSafe(call).catch_unwind().await.unwrap_or_else(|_| {
+							MethodResponse::encoded_error(Id::Null, ErrorCode::InternalError, key.0)
+						});
+						in_flight.lock().remove(&key);
+						rp
+					}
+					.boxed()
+					.shared()
+				})
+				.clone();
+
+			async move { call.await.with_id(&id) }.boxed()
+		}));
+
+		Arc::make_mut(&mut self.methods.single_flight).insert(method_name);
+
+		Ok(())
+	}
+
+	/// Set the OpenRPC description of an already registered method.
+	///
+	/// This is what the `rpc` macro calls for every method of a trait annotated with `#[rpc(server, openrpc)]`.
+	#[cfg(feature = "openrpc")]
+	pub fn set_method_schema(&mut self, method_name: &'static str, mut schema: MethodSchema) -> Result<(), Error> {
+		if self.methods.method(method_name).is_none() {
+			return Err(Error::MethodNotFound(method_name.into()));
+		}
+
+		schema.method.name = method_name.to_owned();
+		Arc::make_mut(&mut self.methods.schemas).insert(method_name, schema);
+
+		Ok(())
+	}
+
+	/// Register the `rpc.discover` method which returns the OpenRPC document of the methods in this module.
+	///
+	/// The document is built from the methods of the server at the time of the call, so it includes
+	/// the methods that were merged into this module later or added with `ServerHandle::merge_methods`
+	/// and `ServerHandle::replace_methods`. Outside of a server, it describes the methods of this module
+	/// when this was called. When the server has an [`AccessPolicy`], the methods that are hidden from
+	/// the caller are left out of the document.
+	///
+	/// ```
+	/// use jsonrpsee_core::server::openrpc::MethodSchema;
+	/// use jsonrpsee_core::server::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_method("say_hello", |_, _, _| "lo").unwrap();
+	/// module.set_method_schema("say_hello", MethodSchema::new().result::<String>()).unwrap();
+	/// module.register_discover("My API", "1.0.0").unwrap();
+	///
+	/// assert_eq!(module.openrpc("My API", "1.0.0").methods.len(), 1);
+	/// ```
+	#[cfg(feature = "openrpc")]
+	pub fn register_discover(
+		&mut self,
+		title: impl Into<String>,
+		version: impl Into<String>,
+	) -> Result<&mut MethodCallback, Error> {
+		let (title, version) = (title.into(), version.into());
+		let module_methods = self.methods.clone();
+
+		self.register_method(DISCOVER_METHOD, move |_, _, extensions| {
+			// The server inserts the methods which it serves at the time of the call.
+			let methods = extensions.get::<Methods>().unwrap_or(&module_methods);
+			let mut doc = methods.openrpc(title.clone(), version.clone());
+
+			if let Some(policy) = extensions.get::<AccessPolicy>() {
+				let visible: FxHashSet<_> = policy.visible_methods(methods, extensions).collect();
+				doc.methods.retain(|method| visible.contains(method.name.as_str()));
+			}
+
+			ResponsePayload::result(doc)
+		})
+	}
+
+	/// Cancel the calls to an already registered async method that don't complete within `timeout`.
+	fn set_timeout(&mut self, method_name: &'static str, timeout: Duration) -> &mut MethodCallback {
+		Arc::make_mut(&mut self.methods.timeouts).insert(method_name, timeout);
+
+		let callback = self.methods.mut_callbacks().get_mut(method_name).expect("Method was just registered; qed");
+
+		if let MethodCallback::Async(inner) = callback {
+			let inner = inner.clone();
+			*callback = MethodCallback::Async(Arc::new(move |id, params, conn_id, max_response_size, extensions| {
+				let encoding = Encoding::from_extensions(&extensions);
+				let call = inner(id.clone(), params, conn_id, max_response_size, extensions);
+
+				async move {
+					match tokio::time::timeout(timeout, call).await {
+						Ok(rp) => rp,
+						Err(_) => MethodResponse::encoded_error(id, reject_call_timeout(timeout), encoding),
+					}
+				}
+				.boxed()
+			}));
+		}
+
+		callback
+	}
+}
+
+/// Calls in flight of a single-flight method, keyed by their canonicalized params.
+type InFlightCalls = Arc<Mutex<FxHashMap<(Encoding, String), Shared<BoxFuture<'static, MethodResponse>>>>>;
+
+/// Answer a call from the cache if there is a cached result for its params.
+fn cached_response(
+	cache: &ResponseCache,
+	key: &str,
+	id: &Id,
+	max_response_size: usize,
+	encoding: Encoding,
+) -> Option<MethodResponse> {
+	let result = cache.get(key)?;
+	Some(MethodResponse::encoded_response(id.clone(), ResponsePayload::result(&*result), max_response_size, encoding))
+}
+
+/// Use the encoding of a call for the messages sent by its subscription, if the server set one.
+fn sink_with_call_encoding(sink: MethodSink, extensions: &Extensions) -> MethodSink {
+	match extensions.get::<Encoding>() {
+		Some(encoding) => sink.with_encoding(*encoding),
+		None => sink,
+	}
+}
+
+/// Cache the result of a successful call.
+fn cache_response(cache: &ResponseCache, key: Option<String>, rp: &MethodResponse) {
+	#[derive(serde::Deserialize)]
+	struct Success<'a> {
+		#[serde(borrow)]
+		result: &'a JsonRawValue,
+	}
+
+	let Some(key) = key.filter(|_| rp.is_success()) else {
+		return;
+	};
+
+	if let Some(Ok(success)) = rp.result.as_str().map(serde_json::from_str::<Success>) {
+		cache.insert(key, success.result.to_owned());
+	}
 }
 
 fn mock_subscription_permit() -> SubscriptionPermit {
@@ -0,0 +1,197 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::fmt;
+use std::sync::Arc;
+
+use futures_util::future;
+use futures_util::stream::{self, BoxStream, Stream, StreamExt};
+use jsonrpsee_types::Id;
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::server::encoding::Encoding;
+
+/// Stream of the chunks of JSON text of a streamed message.
+pub type JsonChunks = BoxStream<'static, Result<String, serde_json::Error>>;
+
+/// The `result` of a method call as a stream of chunks of JSON text.
+///
+/// The result is sent while it's produced, such that it is never held in memory as a whole,
+/// see [`RpcModule::register_streaming_method`](crate::server::RpcModule::register_streaming_method).
+pub struct ResultStream(JsonChunks);
+
+impl ResultStream {
+	/// Stream a result that is already serialized as chunks of JSON text, which are sent as they are.
+	///
+	/// The concatenation of the chunks must be a single JSON value.
+	pub fn from_chunks<S>(chunks: S) -> Self
+	where
+		S: Stream<Item = String> + Send + 'static,
+	{
+		Self(chunks.map(Ok).boxed())
+	}
+
+	/// Stream a result that is a JSON array of the `items`, which are serialized one at a time.
+	///
+	/// The stream is aborted if an item fails to serialize.
+	pub fn from_items<S, T>(items: S) -> Self
+	where
+		S: Stream<Item = T> + Send + 'static,
+		T: Serialize,
+	{
+		let items = items.enumerate().map(|(i, item)| {
+			let json = serde_json::to_string(&item)?;
+			Ok(if i == 0 { json } else { format!(",{json}") })
+		});
+
+		Self(
+			stream::once(future::ready(Ok("[".to_owned())))
+				.chain(items)
+				.chain(stream::once(future::ready(Ok("]".to_owned()))))
+				.boxed(),
+		)
+	}
+}
+
+impl fmt::Debug for ResultStream {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("ResultStream")
+	}
+}
+
+/// A JSON-RPC response whose `result` is streamed,
+/// see [`EncodedMessage::Stream`](crate::server::EncodedMessage::Stream).
+///
+/// The stream can only be taken once, clones of the message share it.
+#[derive(Clone)]
+pub struct StreamedMessage {
+	pub(crate) id: Id<'static>,
+	pub(crate) encoding: Encoding,
+	result: Arc<Mutex<Option<ResultStream>>>,
+}
+
+impl StreamedMessage {
+	pub(crate) fn new(id: Id<'static>, result: ResultStream, encoding: Encoding) -> Self {
+		Self { id, encoding, result: Arc::new(Mutex::new(Some(result))) }
+	}
+
+	/// Take the stream of the `result` chunks, `None` if it was already taken.
+	pub(crate) fn take_result(&self) -> Option<JsonChunks> {
+		self.result.lock().take().map(|result| result.0)
+	}
+
+	/// Take the stream of the complete response in chunks of JSON text, `None` if it was already taken.
+	///
+	/// The first chunk is the envelope up to the `result` and the last chunk the envelope after it.
+	pub fn take(&self) -> Option<JsonChunks> {
+		let result = self.take_result()?;
+		let id = serde_json::to_string(&self.id).expect("Id serialization infallible; qed");
+
+		// CBOR responses are buffered by `MethodResponse::into_buffered` before they are sent.
+		let (prefix, suffix) = match self.encoding {
+			Encoding::JsonV1 => (r#"{"result":"#.to_owned(), format!(r#","error":null,"id":{id}}}"#)),
+			_ => (r#"{"jsonrpc":"2.0","result":"#.to_owned(), format!(r#","id":{id}}}"#)),
+		};
+
+		Some(
+			stream::once(future::ready(Ok(prefix)))
+				.chain(result)
+				.chain(stream::once(future::ready(Ok(suffix))))
+				.boxed(),
+		)
+	}
+
+	/// Collect the `result` as JSON text of at most `max_len` bytes.
+	///
+	/// Returns `Ok(None)` if the result is larger than `max_len` or was already taken.
+	pub(crate) async fn collect_result(&self, max_len: usize) -> Result<Option<String>, serde_json::Error> {
+		let Some(mut result) = self.take_result() else { return Ok(None) };
+		let mut json = String::new();
+
+		while let Some(chunk) = result.next().await {
+			let chunk = chunk?;
+			if json.len() + chunk.len() > max_len {
+				return Ok(None);
+			}
+			json.push_str(&chunk);
+		}
+
+		Ok(Some(json))
+	}
+}
+
+impl fmt::Debug for StreamedMessage {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("StreamedMessage")
+			.field("id", &self.id)
+			.field("encoding", &self.encoding)
+			.finish_non_exhaustive()
+	}
+}
+
+impl PartialEq for StreamedMessage {
+	fn eq(&self, other: &Self) -> bool {
+		Arc::ptr_eq(&self.result, &other.result)
+	}
+}
+
+impl Eq for StreamedMessage {}
+
+#[cfg(test)]
+mod tests {
+	use super::{ResultStream, StreamedMessage};
+	use crate::server::Encoding;
+	use futures_util::{stream, StreamExt};
+	use jsonrpsee_types::Id;
+
+	async fn concat(msg: &StreamedMessage) -> String {
+		msg.take().unwrap().map(|chunk| chunk.unwrap()).collect::<Vec<_>>().await.concat()
+	}
+
+	#[tokio::test]
+	async fn streamed_items_are_an_array() {
+		let items = ResultStream::from_items(stream::iter(vec![1, 2, 3]));
+		let msg = StreamedMessage::new(Id::Number(1), items, Encoding::Json);
+		assert_eq!(concat(&msg).await, r#"{"jsonrpc":"2.0","result":[1,2,3],"id":1}"#);
+		assert!(msg.clone().take().is_none());
+
+		let empty = ResultStream::from_items(stream::iter(Vec::<u8>::new()));
+		let msg = StreamedMessage::new(Id::Str("a".into()), empty, Encoding::JsonV1);
+		assert_eq!(concat(&msg).await, r#"{"result":[],"error":null,"id":"a"}"#);
+	}
+
+	#[tokio::test]
+	async fn collect_result_is_bounded() {
+		let chunks = || ResultStream::from_chunks(stream::iter(vec!["\"ab".to_owned(), "cd\"".to_owned()]));
+
+		let msg = StreamedMessage::new(Id::Number(1), chunks(), Encoding::Json);
+		assert_eq!(msg.collect_result(6).await.unwrap().as_deref(), Some(r#""abcd""#));
+
+		let msg = StreamedMessage::new(Id::Number(1), chunks(), Encoding::Json);
+		assert_eq!(msg.collect_result(5).await.unwrap(), None);
+	}
+}
@@ -26,6 +26,9 @@
 
 //! Subscription related types and traits for server implementations.
 
+#[cfg(feature = "cbor")]
+use super::encoding::cbor;
+use super::encoding::{EncodedMessage, Encoding};
 use super::helpers::{MethodResponse, MethodSink};
 use crate::server::error::{DisconnectError, PendingSubscriptionAcceptError, SendTimeoutError, TrySendError};
 use crate::server::rpc_module::ConnectionId;
@@ -42,8 +45,30 @@ use tokio::sync::{mpsc, oneshot, OwnedSemaphorePermit, Semaphore};
 
 /// Type-alias for subscribers.
 pub type Subscribers = Arc<Mutex<FxHashMap<SubscriptionKey, (MethodSink, mpsc::Receiver<()>)>>>;
+/// Hook invoked when a subscription slot is acquired or released.
+pub type SubscriptionHook = Arc<dyn Fn() + Send + Sync>;
+
 /// Subscription permit.
-pub type SubscriptionPermit = OwnedSemaphorePermit;
+///
+/// Holds one slot of [`BoundedSubscriptions`] until dropped.
+pub struct SubscriptionPermit {
+	_permit: OwnedSemaphorePermit,
+	on_release: Option<SubscriptionHook>,
+}
+
+impl std::fmt::Debug for SubscriptionPermit {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("SubscriptionPermit").finish_non_exhaustive()
+	}
+}
+
+impl Drop for SubscriptionPermit {
+	fn drop(&mut self) {
+		if let Some(on_release) = self.on_release.take() {
+			on_release();
+		}
+	}
+}
 
 /// Convert something into a subscription close notification
 /// before a subscription is terminated.
@@ -119,6 +144,12 @@ pub enum SubscriptionMessageInner {
 	Complete(String),
 	/// Need subscription ID and method name.
 	NeedsData(String),
+	/// Complete binary encoded message.
+	CompleteBinary(Vec<u8>),
+	/// CBOR encoded result, needs subscription ID and method name.
+	#[cfg(feature = "cbor")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+	NeedsCborData(Vec<u8>),
 }
 
 /// Subscription message.
@@ -142,15 +173,46 @@ impl SubscriptionMessage {
 			method.into(),
 			SubscriptionPayload { subscription, result },
 		))?;
-		Ok(Self::from_complete_message(json))
+		Ok(Self::from_complete_message(EncodedMessage::Text(json)))
 	}
 
-	pub(crate) fn from_complete_message(msg: String) -> Self {
-		SubscriptionMessage(SubscriptionMessageInner::Complete(msg))
+	/// Create a new subscription message from CBOR.
+	///
+	/// Fails if the value couldn't be serialized.
+	#[cfg(feature = "cbor")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+	pub fn from_cbor(t: &impl Serialize) -> Result<Self, ciborium::ser::Error<std::io::Error>> {
+		let mut bytes = Vec::new();
+		ciborium::into_writer(t, &mut bytes)?;
+		Ok(SubscriptionMessage(SubscriptionMessageInner::NeedsCborData(bytes)))
+	}
+
+	/// Create a new subscription message in the given encoding, which is usually
+	/// the encoding of the subscription, see [`SubscriptionSink::encoding`].
+	///
+	/// Messages in a different encoding than the subscription are converted when they are sent
+	/// but creating them in the encoding of the subscription avoids that.
+	///
+	/// Fails if the value couldn't be serialized.
+	pub fn from_value(t: &impl Serialize, encoding: Encoding) -> Result<Self, StringError> {
+		match encoding {
+			Encoding::Json | Encoding::JsonV1 => Self::from_json(t).map_err(Into::into),
+			#[cfg(feature = "cbor")]
+			Encoding::Cbor => Self::from_cbor(t).map_err(Into::into),
+		}
+	}
+
+	pub(crate) fn from_complete_message(msg: EncodedMessage) -> Self {
+		match msg {
+			EncodedMessage::Text(text) => SubscriptionMessage(SubscriptionMessageInner::Complete(text)),
+			EncodedMessage::Binary(bytes) => SubscriptionMessage(SubscriptionMessageInner::CompleteBinary(bytes)),
+			// The stream can't be recovered from the message, it's dropped when the message is.
+			EncodedMessage::Stream(_) => SubscriptionMessage(SubscriptionMessageInner::Complete(String::new())),
+		}
 	}
 
 	pub(crate) fn empty() -> Self {
-		Self::from_complete_message(String::new())
+		Self::from_complete_message(EncodedMessage::Text(String::new()))
 	}
 }
 
@@ -245,7 +307,7 @@ pub struct PendingSubscriptionSink {
 	/// Sender to answer the subscribe call.
 	pub(crate) subscribe: oneshot::Sender<MethodResponse>,
 	/// Subscription permit.
-	pub(crate) permit: OwnedSemaphorePermit,
+	pub(crate) permit: Arc<SubscriptionPermit>,
 }
 
 impl PendingSubscriptionSink {
@@ -258,7 +320,7 @@ impl PendingSubscriptionSink {
 	/// the return value is simply ignored because no further notification are propagated
 	/// once reject has been called.
 	pub async fn reject(self, err: impl Into<ErrorObjectOwned>) {
-		let err = MethodResponse::error(self.id, err.into());
+		let err = MethodResponse::encoded_error(self.id, err.into(), self.inner.encoding());
 		_ = self.inner.send(err.result.clone()).await;
 		_ = self.subscribe.send(err);
 	}
@@ -269,10 +331,11 @@ impl PendingSubscriptionSink {
 	///
 	/// Panics if the subscription response exceeded the `max_response_size`.
 	pub async fn accept(self) -> Result<SubscriptionSink, PendingSubscriptionAcceptError> {
-		let response = MethodResponse::response(
+		let response = MethodResponse::encoded_response(
 			self.id,
 			ResponsePayload::result_borrowed(&self.uniq_sub.sub_id),
 			self.inner.max_response_size() as usize,
+			self.inner.encoding(),
 		);
 		let success = response.is_success();
 
@@ -294,7 +357,7 @@ impl PendingSubscriptionSink {
 				subscribers: self.subscribers,
 				uniq_sub: self.uniq_sub,
 				unsubscribe: IsUnsubscribed(tx),
-				_permit: Arc::new(self.permit),
+				_permit: self.permit,
 			})
 		} else {
 			panic!("The subscription response was too big; adjust the `max_response_size` or change Subscription ID generation");
@@ -305,6 +368,11 @@ impl PendingSubscriptionSink {
 	pub fn connection_id(&self) -> ConnectionId {
 		self.uniq_sub.conn_id
 	}
+
+	/// Get the encoding of the messages sent to the subscriber.
+	pub fn encoding(&self) -> Encoding {
+		self.inner.encoding()
+	}
 }
 
 /// Represents a single subscription that hasn't been processed yet.
@@ -340,6 +408,11 @@ impl SubscriptionSink {
 		self.uniq_sub.conn_id
 	}
 
+	/// Get the encoding of the messages sent to the subscriber.
+	pub fn encoding(&self) -> Encoding {
+		self.inner.encoding()
+	}
+
 	/// Send out a response on the subscription and wait until there is capacity.
 	///
 	///
@@ -356,8 +429,9 @@ impl SubscriptionSink {
 			return Err(DisconnectError(msg));
 		}
 
-		let json = sub_message_to_json(msg, SubNotifResultOrError::Result, &self.uniq_sub.sub_id, self.method);
-		self.inner.send(json).await.map_err(Into::into)
+		let msg =
+			encode_sub_message(msg, SubNotifResultOrError::Result, &self.uniq_sub.sub_id, self.method, self.encoding());
+		self.inner.send(msg).await.map_err(Into::into)
 	}
 
 	/// Similar to to `SubscriptionSink::send` but only waits for a limited time.
@@ -367,8 +441,9 @@ impl SubscriptionSink {
 			return Err(SendTimeoutError::Closed(msg));
 		}
 
-		let json = sub_message_to_json(msg, SubNotifResultOrError::Result, &self.uniq_sub.sub_id, self.method);
-		self.inner.send_timeout(json, timeout).await.map_err(Into::into)
+		let msg =
+			encode_sub_message(msg, SubNotifResultOrError::Result, &self.uniq_sub.sub_id, self.method, self.encoding());
+		self.inner.send_timeout(msg, timeout).await.map_err(Into::into)
 	}
 
 	/// Attempts to immediately send out the message as JSON string to the subscribers but fails if the
@@ -383,8 +458,9 @@ impl SubscriptionSink {
 			return Err(TrySendError::Closed(msg));
 		}
 
-		let json = sub_message_to_json(msg, SubNotifResultOrError::Result, &self.uniq_sub.sub_id, self.method);
-		self.inner.try_send(json).map_err(Into::into)
+		let msg =
+			encode_sub_message(msg, SubNotifResultOrError::Result, &self.uniq_sub.sub_id, self.method, self.encoding());
+		self.inner.try_send(msg).map_err(Into::into)
 	}
 
 	/// Returns whether the subscription is closed.
@@ -393,11 +469,15 @@ impl SubscriptionSink {
 	}
 
 	/// Completes when the subscription has been closed.
+	///
+	/// This also completes when the server is shutting down, after which a final
+	/// close notification may still be sent.
 	pub async fn closed(&self) {
-		// Both are cancel-safe thus ok to use select here.
+		// All are cancel-safe thus ok to use select here.
 		tokio::select! {
 			_ = self.inner.closed() => (),
 			_ = self.unsubscribe.unsubscribed() => (),
+			_ = self.inner.shutdown() => (),
 		}
 	}
 
@@ -417,7 +497,7 @@ impl Drop for SubscriptionSink {
 /// Wrapper struct that maintains a subscription "mainly" for testing.
 #[derive(Debug)]
 pub struct Subscription {
-	pub(crate) rx: mpsc::Receiver<String>,
+	pub(crate) rx: mpsc::Receiver<EncodedMessage>,
 	pub(crate) sub_id: SubscriptionId<'static>,
 }
 
@@ -438,9 +518,10 @@ impl Subscription {
 		let raw = self.rx.recv().await?;
 
 		tracing::debug!("[Subscription::next]: rx {}", raw);
-		let res = match serde_json::from_str::<SubscriptionResponse<T>>(&raw) {
+		let raw = raw.as_str().unwrap_or_default();
+		let res = match serde_json::from_str::<SubscriptionResponse<T>>(raw) {
 			Ok(r) => Some(Ok((r.params.result, r.params.subscription.into_owned()))),
-			Err(e) => match serde_json::from_str::<SubscriptionError<serde_json::Value>>(&raw) {
+			Err(e) => match serde_json::from_str::<SubscriptionError<serde_json::Value>>(raw) {
 				Ok(_) => None,
 				Err(_) => Some(Err(e.into())),
 			},
@@ -456,29 +537,61 @@ impl Drop for Subscription {
 }
 
 /// This wraps [`tokio::sync::Semaphore`] and is used to limit the number of subscriptions per connection.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct BoundedSubscriptions {
 	guard: Arc<Semaphore>,
 	max: u32,
+	on_acquire: Option<SubscriptionHook>,
+	on_release: Option<SubscriptionHook>,
+}
+
+impl std::fmt::Debug for BoundedSubscriptions {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("BoundedSubscriptions").field("guard", &self.guard).field("max", &self.max).finish()
+	}
 }
 
 impl BoundedSubscriptions {
 	/// Create a new bounded subscription.
 	pub fn new(max_subscriptions: u32) -> Self {
-		Self { guard: Arc::new(Semaphore::new(max_subscriptions as usize)), max: max_subscriptions }
+		Self {
+			guard: Arc::new(Semaphore::new(max_subscriptions as usize)),
+			max: max_subscriptions,
+			on_acquire: None,
+			on_release: None,
+		}
+	}
+
+	/// Register hooks which are invoked whenever a subscription slot is acquired
+	/// and when the corresponding [`SubscriptionPermit`] is dropped.
+	pub fn with_hooks(mut self, on_acquire: SubscriptionHook, on_release: SubscriptionHook) -> Self {
+		self.on_acquire = Some(on_acquire);
+		self.on_release = Some(on_release);
+		self
 	}
 
 	/// Attempts to acquire a subscription slot.
 	///
 	/// Fails if `max_subscriptions` have been exceeded.
 	pub fn acquire(&self) -> Option<SubscriptionPermit> {
-		Arc::clone(&self.guard).try_acquire_owned().ok()
+		let permit = Arc::clone(&self.guard).try_acquire_owned().ok()?;
+
+		if let Some(on_acquire) = &self.on_acquire {
+			on_acquire();
+		}
+
+		Some(SubscriptionPermit { _permit: permit, on_release: self.on_release.clone() })
 	}
 
 	/// Get the maximum number of permitted subscriptions.
 	pub const fn max(&self) -> u32 {
 		self.max
 	}
+
+	/// Completes once all subscription slots have been released.
+	pub async fn released(&self) {
+		let _ = self.guard.acquire_many(self.max).await;
+	}
 }
 
 #[derive(Debug)]
@@ -492,21 +605,58 @@ pub struct SubscriptionState<'a> {
 	pub subscription_permit: SubscriptionPermit,
 }
 
-pub(crate) fn sub_message_to_json(
+pub(crate) fn encode_sub_message(
 	msg: SubscriptionMessage,
 	result_or_err: SubNotifResultOrError,
 	sub_id: &SubscriptionId,
 	method: &str,
-) -> String {
-	let result_or_err = result_or_err.as_str();
+	encoding: Encoding,
+) -> EncodedMessage {
+	let field = result_or_err.as_str();
 
-	match msg.0 {
-		SubscriptionMessageInner::Complete(msg) => msg,
-		SubscriptionMessageInner::NeedsData(result) => {
+	match (msg.0, encoding) {
+		(SubscriptionMessageInner::Complete(msg), Encoding::Json | Encoding::JsonV1) => EncodedMessage::Text(msg),
+		(SubscriptionMessageInner::NeedsData(result), Encoding::Json) => {
+			let sub_id = serde_json::to_string(&sub_id).expect("valid JSON; qed");
+			EncodedMessage::Text(format!(
+				r#"{{"jsonrpc":"2.0","method":"{method}","params":{{"subscription":{sub_id},"{field}":{result}}}}}"#,
+			))
+		}
+		(SubscriptionMessageInner::NeedsData(result), Encoding::JsonV1) => {
 			let sub_id = serde_json::to_string(&sub_id).expect("valid JSON; qed");
-			format!(
-				r#"{{"jsonrpc":"2.0","method":"{method}","params":{{"subscription":{sub_id},"{result_or_err}":{result}}}}}"#,
-			)
+			EncodedMessage::Text(format!(
+				r#"{{"method":"{method}","params":{{"subscription":{sub_id},"{field}":{result}}},"id":null}}"#,
+			))
+		}
+		(SubscriptionMessageInner::CompleteBinary(msg), _) => EncodedMessage::Binary(msg),
+		#[cfg(feature = "cbor")]
+		(SubscriptionMessageInner::NeedsCborData(result), Encoding::Cbor) => {
+			EncodedMessage::Binary(cbor::notification(method, sub_id, field, &result))
+		}
+		// Messages which weren't created in the encoding of the subscription are converted,
+		// or sent as they are if that isn't possible.
+		#[cfg(feature = "cbor")]
+		(SubscriptionMessageInner::Complete(msg), Encoding::Cbor) => match cbor::from_json(&msg) {
+			Ok(bytes) => EncodedMessage::Binary(bytes),
+			Err(_) => EncodedMessage::Text(msg),
+		},
+		#[cfg(feature = "cbor")]
+		(SubscriptionMessageInner::NeedsData(result), Encoding::Cbor) => match cbor::from_json(&result) {
+			Ok(result) => EncodedMessage::Binary(cbor::notification(method, sub_id, field, &result)),
+			Err(_) => {
+				let msg = SubscriptionMessage(SubscriptionMessageInner::NeedsData(result));
+				encode_sub_message(msg, result_or_err, sub_id, method, Encoding::Json)
+			}
+		},
+		#[cfg(feature = "cbor")]
+		(SubscriptionMessageInner::NeedsCborData(result), Encoding::Json | Encoding::JsonV1) => {
+			match cbor::to_json(&result) {
+				Ok(result) => {
+					let msg = SubscriptionMessage(SubscriptionMessageInner::NeedsData(result));
+					encode_sub_message(msg, result_or_err, sub_id, method, encoding)
+				}
+				Err(_) => EncodedMessage::Binary(cbor::notification(method, sub_id, field, &result)),
+			}
 		}
 	}
 }
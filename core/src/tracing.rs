@@ -1,6 +1,10 @@
 use serde::Serialize;
 use tracing::Level;
 
+mod trace_context;
+
+pub use trace_context::{TraceParent, TraceScope, TRACEPARENT};
+
 /// Helper for writing trace logs from str.
 pub fn tx_log_from_str(s: impl AsRef<str>, max: u32) {
 	if tracing::enabled!(Level::TRACE) {
@@ -0,0 +1,227 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! [W3C trace context](https://www.w3.org/TR/trace-context/) propagation.
+
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+
+/// Name of the HTTP header and of the JSON-RPC request member that carries the trace context.
+pub const TRACEPARENT: &str = "traceparent";
+
+/// Only version of the `traceparent` format that is produced.
+const VERSION: u8 = 0;
+/// Trace flag which indicates that the caller may have recorded the trace.
+const FLAG_SAMPLED: u8 = 0x01;
+
+thread_local! {
+	static CURRENT: Cell<Option<TraceParent>> = const { Cell::new(None) };
+}
+
+/// W3C `traceparent` which identifies the caller of a request in a distributed trace.
+///
+/// The value is formatted as `{version}-{trace-id}-{parent-id}-{trace-flags}`, for example
+/// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TraceParent {
+	trace_id: u128,
+	parent_id: u64,
+	flags: u8,
+}
+
+impl TraceParent {
+	/// Start a new sampled trace with random identifiers.
+	pub fn new_root() -> Self {
+		let trace_id = ((random_id() as u128) << 64) | random_id() as u128;
+		Self { trace_id, parent_id: random_id(), flags: FLAG_SAMPLED }
+	}
+
+	/// Parse a `traceparent` value.
+	///
+	/// Returns `None` if the value is malformed or contains an all-zero trace or parent ID,
+	/// in which case the W3C specification requires the value to be ignored.
+	pub fn parse(s: &str) -> Option<Self> {
+		let mut parts = s.trim().split('-');
+		let version = parse_hex(parts.next()?, 2)?;
+		let trace_id = parse_hex(parts.next()?, 32)?;
+		let parent_id = parse_hex(parts.next()?, 16)?;
+		let flags = parse_hex(parts.next()?, 2)?;
+
+		// Version `ff` is forbidden and only future versions may carry additional fields.
+		if version == 0xff || (version == VERSION as u128 && parts.next().is_some()) {
+			return None;
+		}
+
+		if trace_id == 0 || parent_id == 0 {
+			return None;
+		}
+
+		Some(Self { trace_id, parent_id: parent_id as u64, flags: flags as u8 })
+	}
+
+	/// Create the trace context for an outgoing call, which continues the trace of
+	/// [`TraceParent::current`] if any or otherwise starts a new trace.
+	pub fn next_call() -> Self {
+		Self::current().map_or_else(Self::new_root, |ctx| ctx.child())
+	}
+
+	/// Create the context of a new span within the same trace, i.e. the returned
+	/// value has the same trace ID but a new random parent ID.
+	pub fn child(&self) -> Self {
+		Self { trace_id: self.trace_id, parent_id: random_id(), flags: self.flags }
+	}
+
+	/// Get the trace ID as a lower-case hex string.
+	pub fn trace_id(&self) -> String {
+		format!("{:032x}", self.trace_id)
+	}
+
+	/// Get the ID of the parent span as a lower-case hex string.
+	pub fn parent_id(&self) -> String {
+		format!("{:016x}", self.parent_id)
+	}
+
+	/// Returns whether the caller may have recorded the trace.
+	pub fn is_sampled(&self) -> bool {
+		self.flags & FLAG_SAMPLED == FLAG_SAMPLED
+	}
+
+	/// Get the trace context of the current task, if any.
+	///
+	/// This is set by [`TraceParent::scope`], the server scopes each method call with the trace context
+	/// of the call such that clients used inside a method callback continue the same trace.
+	pub fn current() -> Option<Self> {
+		CURRENT.with(|c| c.get())
+	}
+
+	/// Run `fut` with `self` as the trace context returned by [`TraceParent::current`].
+	pub fn scope<F: Future>(self, fut: F) -> TraceScope<F> {
+		TraceScope { ctx: Some(self), fut: Box::pin(fut) }
+	}
+
+	/// Run `fut` with the trace context of the current task, if any.
+	pub fn in_current_scope<F: Future>(fut: F) -> TraceScope<F> {
+		TraceScope { ctx: Self::current(), fut: Box::pin(fut) }
+	}
+}
+
+impl std::fmt::Display for TraceParent {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{:02x}-{:032x}-{:016x}-{:02x}", VERSION, self.trace_id, self.parent_id, self.flags)
+	}
+}
+
+/// Future returned by [`TraceParent::scope`].
+#[derive(Debug)]
+pub struct TraceScope<F> {
+	ctx: Option<TraceParent>,
+	fut: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for TraceScope<F> {
+	type Output = F::Output;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let ctx = self.ctx;
+		let prev = CURRENT.with(|c| c.replace(ctx));
+		let res = self.fut.as_mut().poll(cx);
+		CURRENT.with(|c| c.set(prev));
+		res
+	}
+}
+
+fn parse_hex(s: &str, len: usize) -> Option<u128> {
+	if s.len() != len || !s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+		return None;
+	}
+	u128::from_str_radix(s, 16).ok()
+}
+
+/// Non-zero random ID which doesn't require a source of randomness other than `std`.
+fn random_id() -> u64 {
+	static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+	let mut hasher = RandomState::new().build_hasher();
+	hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+	hasher.finish().max(1)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::TraceParent;
+
+	#[test]
+	fn traceparent_roundtrip_works() {
+		let s = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+		let tp = TraceParent::parse(s).unwrap();
+
+		assert_eq!(tp.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+		assert_eq!(tp.parent_id(), "00f067aa0ba902b7");
+		assert!(tp.is_sampled());
+		assert_eq!(tp.to_string(), s);
+
+		let child = tp.child();
+		assert_eq!(child.trace_id(), tp.trace_id());
+		assert_ne!(child.parent_id(), tp.parent_id());
+	}
+
+	#[test]
+	fn invalid_traceparent_is_ignored() {
+		for s in [
+			"",
+			"00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+			"00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+			"00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+			"00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+			"ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+			"00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+		] {
+			assert_eq!(TraceParent::parse(s), None, "{s}");
+		}
+
+		assert!(TraceParent::parse("01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra").is_some());
+	}
+
+	#[tokio::test]
+	async fn scope_sets_current_trace_context() {
+		let tp = TraceParent::new_root();
+
+		assert_eq!(TraceParent::current(), None);
+		let current = tp
+			.scope(async {
+				tokio::task::yield_now().await;
+				TraceParent::current()
+			})
+			.await;
+		assert_eq!(current, Some(tp));
+		assert_eq!(TraceParent::current(), None);
+	}
+}
@@ -0,0 +1,559 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! WebSocket `permessage-deflate` extension, see [RFC 7692](https://www.rfc-editor.org/rfc/rfc7692).
+//!
+//! Unlike the extension shipped with `soketto`, this one can keep the compression context across
+//! messages, negotiates the LZ77 window sizes and bounds the size of the decompressed messages.
+
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
+use soketto::base::{Header, OpCode};
+use soketto::extension::{Extension, Param};
+use soketto::{BoxedError, Mode, Storage};
+
+const EXTENSION_NAME: &str = "permessage-deflate";
+const SERVER_NO_CONTEXT_TAKEOVER: &str = "server_no_context_takeover";
+const CLIENT_NO_CONTEXT_TAKEOVER: &str = "client_no_context_takeover";
+const SERVER_MAX_WINDOW_BITS: &str = "server_max_window_bits";
+const CLIENT_MAX_WINDOW_BITS: &str = "client_max_window_bits";
+
+/// Trailer removed from every compressed message, cf. RFC 7692 section 7.2.1.
+const DEFLATE_TRAILER: [u8; 4] = [0, 0, 0xFF, 0xFF];
+const MIN_WINDOW_BITS: u8 = 9;
+const MAX_WINDOW_BITS: u8 = 15;
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Configuration of the WebSocket `permessage-deflate` extension.
+///
+/// On the client these are the parameters offered to the server, on the server these are applied on top of
+/// whatever the client offers. The window bits are the base-two logarithm of the LZ77 sliding window used
+/// to compress the messages sent by the respective peer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WsDeflateConfig {
+	server_max_window_bits: u8,
+	client_max_window_bits: u8,
+	server_no_context_takeover: bool,
+	client_no_context_takeover: bool,
+}
+
+impl Default for WsDeflateConfig {
+	fn default() -> Self {
+		Self {
+			server_max_window_bits: MAX_WINDOW_BITS,
+			client_max_window_bits: MAX_WINDOW_BITS,
+			server_no_context_takeover: false,
+			client_no_context_takeover: false,
+		}
+	}
+}
+
+impl WsDeflateConfig {
+	/// Create a new config with the default window bits and context takeover enabled.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the maximum window bits the server may use to compress messages. Default is 15.
+	///
+	/// # Panics
+	///
+	/// Panics if `bits` is not within `9..=15`.
+	pub fn server_max_window_bits(mut self, bits: u8) -> Self {
+		assert_window_bits(bits);
+		self.server_max_window_bits = bits;
+		self
+	}
+
+	/// Set the maximum window bits the client may use to compress messages. Default is 15.
+	///
+	/// # Panics
+	///
+	/// Panics if `bits` is not within `9..=15`.
+	pub fn client_max_window_bits(mut self, bits: u8) -> Self {
+		assert_window_bits(bits);
+		self.client_max_window_bits = bits;
+		self
+	}
+
+	/// Whether the server must reset its compression context after every message. Default is false.
+	///
+	/// Resetting the context saves the memory of keeping it around for the lifetime of the connection at the
+	/// cost of a worse compression ratio.
+	pub fn server_no_context_takeover(mut self, enabled: bool) -> Self {
+		self.server_no_context_takeover = enabled;
+		self
+	}
+
+	/// Whether the client must reset its compression context after every message. Default is false.
+	pub fn client_no_context_takeover(mut self, enabled: bool) -> Self {
+		self.client_no_context_takeover = enabled;
+		self
+	}
+}
+
+fn assert_window_bits(bits: u8) {
+	assert!(
+		(MIN_WINDOW_BITS..=MAX_WINDOW_BITS).contains(&bits),
+		"window bits must be within {MIN_WINDOW_BITS}..={MAX_WINDOW_BITS}"
+	);
+}
+
+/// Error returned by the [`PerMessageDeflate`] extension.
+#[derive(Debug, thiserror::Error)]
+pub enum WsDeflateError {
+	/// The decompressed message exceeds the maximum message size.
+	#[error("Decompressed message exceeds the maximum size of {0} bytes")]
+	MessageTooLarge(usize),
+	/// The server accepted the extension with parameters the client didn't offer or can't support.
+	#[error("Invalid permessage-deflate response: {0}")]
+	InvalidResponse(String),
+	/// Compressing a message failed.
+	#[error(transparent)]
+	Compress(#[from] flate2::CompressError),
+	/// Decompressing a message failed.
+	#[error(transparent)]
+	Decompress(#[from] flate2::DecompressError),
+}
+
+/// The `permessage-deflate` [`Extension`] to add to the `soketto` handshake.
+#[derive(Debug)]
+pub struct PerMessageDeflate {
+	mode: Mode,
+	config: WsDeflateConfig,
+	max_message_size: usize,
+	enabled: bool,
+	params: Vec<Param<'static>>,
+	/// Window bits used to compress our messages.
+	our_window_bits: u8,
+	/// Whether to reset the compression context after every message.
+	our_no_context_takeover: bool,
+	/// Whether to reset the decompression context after every message.
+	their_no_context_takeover: bool,
+	compress: Option<Compress>,
+	decompress: Decompress,
+	buffer: Vec<u8>,
+	await_last_fragment: bool,
+}
+
+impl PerMessageDeflate {
+	/// Create the extension for the given side of the connection.
+	///
+	/// Messages which exceed `max_message_size` bytes once decompressed are rejected with
+	/// [`WsDeflateError::MessageTooLarge`], which terminates the connection.
+	pub fn new(mode: Mode, config: WsDeflateConfig, max_message_size: usize) -> Self {
+		let mut params = Vec::new();
+
+		if mode == Mode::Client {
+			if config.server_no_context_takeover {
+				params.push(Param::new(SERVER_NO_CONTEXT_TAKEOVER));
+			}
+			if config.client_no_context_takeover {
+				params.push(Param::new(CLIENT_NO_CONTEXT_TAKEOVER));
+			}
+			if config.server_max_window_bits < MAX_WINDOW_BITS {
+				params.push(param_with_value(SERVER_MAX_WINDOW_BITS, config.server_max_window_bits));
+			}
+			// Always announce that we support limiting our window.
+			let mut p = Param::new(CLIENT_MAX_WINDOW_BITS);
+			if config.client_max_window_bits < MAX_WINDOW_BITS {
+				p.set_value(Some(config.client_max_window_bits.to_string()));
+			}
+			params.push(p);
+		}
+
+		let (our_window_bits, our_no_context_takeover, their_no_context_takeover) = match mode {
+			Mode::Server => {
+				(config.server_max_window_bits, config.server_no_context_takeover, config.client_no_context_takeover)
+			}
+			Mode::Client => {
+				(config.client_max_window_bits, config.client_no_context_takeover, config.server_no_context_takeover)
+			}
+		};
+
+		Self {
+			mode,
+			config,
+			max_message_size,
+			enabled: false,
+			params,
+			our_window_bits,
+			our_no_context_takeover,
+			their_no_context_takeover,
+			compress: None,
+			decompress: Decompress::new(false),
+			buffer: Vec::new(),
+			await_last_fragment: false,
+		}
+	}
+
+	/// Accept the first acceptable offer of the client and build the response parameters.
+	///
+	/// Offers which can't be accepted are ignored, such that the connection falls back to uncompressed messages.
+	fn configure_server(&mut self, params: &[Param]) {
+		if self.enabled {
+			return;
+		}
+
+		let mut server_no_context_takeover = self.config.server_no_context_takeover;
+		let mut client_no_context_takeover = self.config.client_no_context_takeover;
+		let mut server_window_bits = self.config.server_max_window_bits;
+		let mut client_window_bits = None;
+
+		for p in params {
+			match p.name() {
+				SERVER_NO_CONTEXT_TAKEOVER => server_no_context_takeover = true,
+				CLIENT_NO_CONTEXT_TAKEOVER => client_no_context_takeover = true,
+				SERVER_MAX_WINDOW_BITS => match p.value().and_then(parse_window_bits) {
+					// zlib can't compress with a window of 8 bits.
+					Some(bits) if bits >= MIN_WINDOW_BITS => server_window_bits = server_window_bits.min(bits),
+					_ => {
+						tracing::debug!("Declining {EXTENSION_NAME} offer: unsupported {}", p);
+						return;
+					}
+				},
+				CLIENT_MAX_WINDOW_BITS => match p.value().map(parse_window_bits) {
+					None => client_window_bits = Some(self.config.client_max_window_bits),
+					Some(Some(bits)) => client_window_bits = Some(self.config.client_max_window_bits.min(bits)),
+					Some(None) => {
+						tracing::debug!("Declining {EXTENSION_NAME} offer: invalid {}", p);
+						return;
+					}
+				},
+				_ => {
+					tracing::debug!("Declining {EXTENSION_NAME} offer: unknown parameter {}", p);
+					return;
+				}
+			}
+		}
+
+		self.params.clear();
+		if server_no_context_takeover {
+			self.params.push(Param::new(SERVER_NO_CONTEXT_TAKEOVER));
+		}
+		if client_no_context_takeover {
+			self.params.push(Param::new(CLIENT_NO_CONTEXT_TAKEOVER));
+		}
+		if server_window_bits < MAX_WINDOW_BITS {
+			self.params.push(param_with_value(SERVER_MAX_WINDOW_BITS, server_window_bits));
+		}
+		if let Some(bits) = client_window_bits.filter(|bits| *bits < MAX_WINDOW_BITS) {
+			self.params.push(param_with_value(CLIENT_MAX_WINDOW_BITS, bits));
+		}
+
+		self.our_window_bits = server_window_bits;
+		self.our_no_context_takeover = server_no_context_takeover;
+		self.their_no_context_takeover = client_no_context_takeover;
+		self.enabled = true;
+	}
+
+	/// Validate the response of the server against our offer.
+	fn configure_client(&mut self, params: &[Param]) -> Result<(), WsDeflateError> {
+		let mut server_no_context_takeover = false;
+
+		for p in params {
+			match p.name() {
+				SERVER_NO_CONTEXT_TAKEOVER => server_no_context_takeover = true,
+				CLIENT_NO_CONTEXT_TAKEOVER => self.our_no_context_takeover = true,
+				SERVER_MAX_WINDOW_BITS => match p.value().and_then(parse_window_bits) {
+					Some(bits) if bits <= self.config.server_max_window_bits => (),
+					_ => return Err(WsDeflateError::InvalidResponse(p.to_string())),
+				},
+				CLIENT_MAX_WINDOW_BITS => match p.value().and_then(parse_window_bits) {
+					// zlib can't compress with a window of 8 bits.
+					Some(bits) if bits >= MIN_WINDOW_BITS => self.our_window_bits = self.our_window_bits.min(bits),
+					_ => return Err(WsDeflateError::InvalidResponse(p.to_string())),
+				},
+				_ => return Err(WsDeflateError::InvalidResponse(p.to_string())),
+			}
+		}
+
+		if self.config.server_no_context_takeover && !server_no_context_takeover {
+			return Err(WsDeflateError::InvalidResponse(format!("missing {SERVER_NO_CONTEXT_TAKEOVER}")));
+		}
+
+		self.their_no_context_takeover = server_no_context_takeover;
+		self.enabled = true;
+		Ok(())
+	}
+
+	fn decompress(&mut self, data: &mut Vec<u8>) -> Result<(), WsDeflateError> {
+		data.extend_from_slice(&DEFLATE_TRAILER);
+
+		self.buffer.clear();
+		let mut chunk = vec![0; CHUNK_SIZE];
+		let mut input = &data[..];
+
+		loop {
+			let (total_in, total_out) = (self.decompress.total_in(), self.decompress.total_out());
+			let status = self.decompress.decompress(input, &mut chunk, FlushDecompress::Sync)?;
+			let read = (self.decompress.total_in() - total_in) as usize;
+			let written = (self.decompress.total_out() - total_out) as usize;
+
+			if self.buffer.len() + written > self.max_message_size {
+				return Err(WsDeflateError::MessageTooLarge(self.max_message_size));
+			}
+
+			self.buffer.extend_from_slice(&chunk[..written]);
+			input = &input[read..];
+
+			let done = input.is_empty() && written < chunk.len();
+			if done || status != Status::Ok || (read == 0 && written == 0) {
+				break;
+			}
+		}
+
+		if self.their_no_context_takeover {
+			self.decompress.reset(false);
+		}
+
+		std::mem::swap(data, &mut self.buffer);
+		Ok(())
+	}
+
+	fn compress(&mut self, data: &[u8]) -> Result<(), WsDeflateError> {
+		let window_bits = self.our_window_bits;
+		let compress = self
+			.compress
+			.get_or_insert_with(|| Compress::new_with_window_bits(Compression::fast(), false, window_bits));
+
+		self.buffer.clear();
+		self.buffer.reserve(data.len() / 2 + DEFLATE_TRAILER.len());
+		let total_in = compress.total_in();
+
+		loop {
+			if self.buffer.len() == self.buffer.capacity() {
+				self.buffer.reserve(CHUNK_SIZE);
+			}
+			let read = (compress.total_in() - total_in) as usize;
+			compress.compress_vec(&data[read..], &mut self.buffer, FlushCompress::Sync)?;
+			let read = (compress.total_in() - total_in) as usize;
+
+			// The flush is complete once all input was consumed and the output wasn't truncated.
+			if read == data.len() && self.buffer.len() < self.buffer.capacity() {
+				break;
+			}
+		}
+
+		if self.our_no_context_takeover {
+			compress.reset();
+		}
+
+		debug_assert!(self.buffer.ends_with(&DEFLATE_TRAILER));
+		self.buffer.truncate(self.buffer.len().saturating_sub(DEFLATE_TRAILER.len()));
+		Ok(())
+	}
+}
+
+impl Extension for PerMessageDeflate {
+	fn is_enabled(&self) -> bool {
+		self.enabled
+	}
+
+	fn name(&self) -> &str {
+		EXTENSION_NAME
+	}
+
+	fn params(&self) -> &[Param<'_>] {
+		&self.params
+	}
+
+	fn configure(&mut self, params: &[Param]) -> Result<(), BoxedError> {
+		match self.mode {
+			Mode::Server => self.configure_server(params),
+			Mode::Client => self.configure_client(params)?,
+		}
+		Ok(())
+	}
+
+	fn encode(&mut self, header: &mut Header, data: &mut Storage) -> Result<(), BoxedError> {
+		if data.as_ref().is_empty() || !matches!(header.opcode(), OpCode::Text | OpCode::Binary) {
+			return Ok(());
+		}
+
+		self.compress(data.as_ref())?;
+
+		match data {
+			Storage::Owned(d) => std::mem::swap(d, &mut self.buffer),
+			_ => *data = Storage::Owned(std::mem::take(&mut self.buffer)),
+		}
+		header.set_rsv1(true);
+		header.set_payload_len(data.as_ref().len());
+		Ok(())
+	}
+
+	fn decode(&mut self, header: &mut Header, data: &mut Vec<u8>) -> Result<(), BoxedError> {
+		if data.is_empty() {
+			return Ok(());
+		}
+
+		match header.opcode() {
+			OpCode::Text | OpCode::Binary if header.is_rsv1() => {
+				// `soketto` hands over fragmented messages once they are complete.
+				if !header.is_fin() {
+					self.await_last_fragment = true;
+					return Ok(());
+				}
+			}
+			OpCode::Continue if header.is_fin() && self.await_last_fragment => self.await_last_fragment = false,
+			_ => return Ok(()),
+		}
+
+		self.decompress(data)?;
+
+		header.set_rsv1(false);
+		header.set_payload_len(data.len());
+		Ok(())
+	}
+
+	fn reserved_bits(&self) -> (bool, bool, bool) {
+		(true, false, false)
+	}
+}
+
+fn param_with_value(name: &'static str, bits: u8) -> Param<'static> {
+	let mut p = Param::new(name);
+	p.set_value(Some(bits.to_string()));
+	p
+}
+
+fn parse_window_bits(value: &str) -> Option<u8> {
+	value.parse().ok().filter(|bits| (8..=MAX_WINDOW_BITS).contains(bits))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn params(ext: &PerMessageDeflate) -> Vec<String> {
+		ext.params().iter().map(ToString::to_string).collect()
+	}
+
+	fn negotiate(client: WsDeflateConfig, server: WsDeflateConfig) -> (PerMessageDeflate, PerMessageDeflate) {
+		let mut client = PerMessageDeflate::new(Mode::Client, client, 1024 * 1024);
+		let mut server = PerMessageDeflate::new(Mode::Server, server, 1024 * 1024);
+		server.configure(client.params()).unwrap();
+		assert!(server.is_enabled());
+		client.configure(server.params()).unwrap();
+		assert!(client.is_enabled());
+		(client, server)
+	}
+
+	fn roundtrip(from: &mut PerMessageDeflate, to: &mut PerMessageDeflate, msg: &[u8]) -> usize {
+		let mut header = Header::new(OpCode::Text);
+		let mut data = Storage::Shared(msg);
+		from.encode(&mut header, &mut data).unwrap();
+		assert!(header.is_rsv1());
+
+		let mut data = data.as_ref().to_vec();
+		let compressed_len = data.len();
+		to.decode(&mut header, &mut data).unwrap();
+		assert!(!header.is_rsv1());
+		assert_eq!(data, msg);
+		compressed_len
+	}
+
+	#[test]
+	fn negotiates_default_config() {
+		let (client, server) = negotiate(WsDeflateConfig::default(), WsDeflateConfig::default());
+		assert_eq!(params(&client), vec![CLIENT_MAX_WINDOW_BITS]);
+		assert!(params(&server).is_empty());
+		assert!(!client.our_no_context_takeover && !client.their_no_context_takeover);
+		assert!(!server.our_no_context_takeover && !server.their_no_context_takeover);
+	}
+
+	#[test]
+	fn negotiates_window_bits_and_context_takeover() {
+		let client_cfg = WsDeflateConfig::new().server_max_window_bits(12).server_no_context_takeover(true);
+		let server_cfg = WsDeflateConfig::new().server_max_window_bits(14).client_max_window_bits(10);
+		let (client, server) = negotiate(client_cfg, server_cfg);
+
+		assert_eq!(
+			params(&server),
+			vec![SERVER_NO_CONTEXT_TAKEOVER, "server_max_window_bits = 12", "client_max_window_bits = 10"]
+		);
+		assert_eq!(server.our_window_bits, 12);
+		assert!(server.our_no_context_takeover);
+		assert_eq!(client.our_window_bits, 10);
+		assert!(client.their_no_context_takeover);
+	}
+
+	#[test]
+	fn server_declines_unsupported_offers() {
+		let mut server = PerMessageDeflate::new(Mode::Server, WsDeflateConfig::default(), 1024);
+		server.configure(&[param_with_value(SERVER_MAX_WINDOW_BITS, 8)]).unwrap();
+		assert!(!server.is_enabled());
+		server.configure(&[Param::new("foo")]).unwrap();
+		assert!(!server.is_enabled());
+	}
+
+	#[test]
+	fn client_rejects_invalid_responses() {
+		let cfg = WsDeflateConfig::new().server_max_window_bits(10).server_no_context_takeover(true);
+
+		let mut client = PerMessageDeflate::new(Mode::Client, cfg, 1024);
+		assert!(client.configure(&[param_with_value(SERVER_MAX_WINDOW_BITS, 10)]).is_err());
+
+		let mut client = PerMessageDeflate::new(Mode::Client, cfg, 1024);
+		let response = [Param::new(SERVER_NO_CONTEXT_TAKEOVER), param_with_value(SERVER_MAX_WINDOW_BITS, 11)];
+		assert!(client.configure(&response).is_err());
+		assert!(!client.is_enabled());
+	}
+
+	#[test]
+	fn context_takeover_improves_compression() {
+		let msg =
+			br#"{"jsonrpc":"2.0","method":"subscribe_hello","params":{"subscription":1,"result":"hello my friend"}}"#;
+
+		let (mut client, mut server) = negotiate(WsDeflateConfig::default(), WsDeflateConfig::default());
+		let first = roundtrip(&mut server, &mut client, msg);
+		let second = roundtrip(&mut server, &mut client, msg);
+		assert!(second < first);
+		roundtrip(&mut client, &mut server, msg);
+
+		let no_takeover = WsDeflateConfig::new().server_no_context_takeover(true);
+		let (mut client, mut server) = negotiate(no_takeover, WsDeflateConfig::default());
+		let first = roundtrip(&mut server, &mut client, msg);
+		let second = roundtrip(&mut server, &mut client, msg);
+		assert_eq!(first, second);
+	}
+
+	#[test]
+	fn decompressed_size_is_limited() {
+		let (mut client, _) = negotiate(WsDeflateConfig::default(), WsDeflateConfig::default());
+		let mut server = PerMessageDeflate::new(Mode::Server, WsDeflateConfig::default(), 1024);
+		server.configure(client.params()).unwrap();
+
+		let msg = vec![b'a'; 1025];
+		let mut header = Header::new(OpCode::Binary);
+		let mut data = Storage::Shared(&msg);
+		client.encode(&mut header, &mut data).unwrap();
+		assert!(data.as_ref().len() < 1024);
+
+		let mut data = data.as_ref().to_vec();
+		let err = server.decode(&mut header, &mut data).unwrap_err();
+		assert!(matches!(err.downcast_ref(), Some(WsDeflateError::MessageTooLarge(1024))));
+	}
+}
@@ -52,7 +52,7 @@ async fn main() -> anyhow::Result<()> {
 async fn run_server() -> anyhow::Result<SocketAddr> {
 	let server = Server::builder().build("127.0.0.1:0").await?;
 	let mut module = RpcModule::new(());
-	module.register_method("say_hello", |_, _| "lo")?;
+	module.register_method("say_hello", |_, _, _| "lo")?;
 	let addr = server.local_addr()?;
 
 	let handle = server.start(module);
@@ -70,7 +70,7 @@ async fn run_server() -> anyhow::Result<SocketAddr> {
 	let addr = server.local_addr()?;
 
 	let mut module = RpcModule::new(());
-	module.register_method("say_hello", |_, _| "lo").unwrap();
+	module.register_method("say_hello", |_, _, _| "lo").unwrap();
 
 	let handle = server.start(module);
 
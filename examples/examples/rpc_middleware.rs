@@ -0,0 +1,106 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! This example shows how to add JSON-RPC middleware to the server which
+//! is invoked for every single JSON-RPC call.
+//!
+//! It works with both `WebSocket` and `HTTP` which is done in the example.
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::http_client::HttpClientBuilder;
+use jsonrpsee::rpc_params;
+use jsonrpsee::server::middleware::rpc::{RpcServiceBuilder, RpcServiceT};
+use jsonrpsee::server::{RpcModule, Server};
+use jsonrpsee::types::Request;
+use jsonrpsee::ws_client::WsClientBuilder;
+
+#[derive(Clone)]
+pub struct CallsPerConn<S> {
+	service: S,
+	count: Arc<AtomicUsize>,
+}
+
+impl<'a, S> RpcServiceT<'a> for CallsPerConn<S>
+where
+	S: RpcServiceT<'a>,
+{
+	type Future = S::Future;
+
+	fn call(&self, req: Request<'a>) -> Self::Future {
+		let count = self.count.fetch_add(1, Ordering::Relaxed) + 1;
+		tracing::info!("call `{}` is the {count}th call on this connection", req.method);
+		self.service.call(req)
+	}
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+	tracing_subscriber::FmtSubscriber::builder()
+		.with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+		.try_init()
+		.expect("setting default subscriber failed");
+
+	let addr = run_server().await?;
+
+	// WebSocket.
+	{
+		let client = WsClientBuilder::default().build(format!("ws://{}", addr)).await?;
+		let _response: String = client.request("say_hello", rpc_params![]).await?;
+		let _response: Result<String, _> = client.request("unknown_method", rpc_params![]).await;
+		let _response: String = client.request("say_hello", rpc_params![]).await?;
+	}
+
+	// HTTP.
+	{
+		let client = HttpClientBuilder::default().build(format!("http://{}", addr))?;
+		let _response: String = client.request("say_hello", rpc_params![]).await?;
+		let _response: Result<String, _> = client.request("unknown_method", rpc_params![]).await;
+	}
+
+	Ok(())
+}
+
+async fn run_server() -> anyhow::Result<SocketAddr> {
+	// The service is created once per WebSocket connection and once per HTTP request.
+	let rpc_middleware =
+		RpcServiceBuilder::new().layer_fn(|service| CallsPerConn { service, count: Arc::new(AtomicUsize::new(0)) });
+
+	let server = Server::builder().set_rpc_middleware(rpc_middleware).build("127.0.0.1:0").await?;
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _, _| "lo")?;
+	let addr = server.local_addr()?;
+	let handle = server.start(module);
+
+	// In this example we don't care about doing shutdown so let's it run forever.
+	// You may use the `ServerHandle` to shut it down or manage it yourself.
+	tokio::spawn(handle.stopped());
+
+	Ok(addr)
+}
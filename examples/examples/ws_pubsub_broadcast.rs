@@ -24,20 +24,16 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-//! Example that shows how to broadcast to all active subscriptions using `tokio::sync::broadcast`.
+//! Example that shows how to broadcast to all active subscriptions using [`SubscriptionBroadcaster`].
 
 use std::net::SocketAddr;
 
-use futures::future::{self, Either};
+use futures::future;
 use futures::StreamExt;
 use jsonrpsee::core::client::{Subscription, SubscriptionClientT};
-use jsonrpsee::core::server::SubscriptionMessage;
 use jsonrpsee::rpc_params;
-use jsonrpsee::server::{RpcModule, Server};
+use jsonrpsee::server::{LagPolicy, RpcModule, Server, SubscriptionBroadcaster};
 use jsonrpsee::ws_client::WsClientBuilder;
-use jsonrpsee::PendingSubscriptionSink;
-use tokio::sync::broadcast;
-use tokio_stream::wrappers::BroadcastStream;
 
 const NUM_SUBSCRIPTION_RESPONSES: usize = 5;
 
@@ -67,19 +63,23 @@ async fn main() -> anyhow::Result<()> {
 async fn run_server() -> anyhow::Result<SocketAddr> {
 	// let's configure the server only hold 5 messages in memory.
 	let server = Server::builder().set_message_buffer_capacity(5).build("127.0.0.1:0").await?;
-	let (tx, _rx) = broadcast::channel::<usize>(16);
+	// Every subscriber buffers up to 16 items on top of that.
+	let broadcaster = SubscriptionBroadcaster::new(16);
 
-	let mut module = RpcModule::new(tx.clone());
+	let mut module = RpcModule::new(broadcaster.clone());
 
-	std::thread::spawn(move || produce_items(tx));
+	std::thread::spawn(move || produce_items(broadcaster));
 
 	module
-		.register_subscription("subscribe_hello", "s_hello", "unsubscribe_hello", |_, pending, tx| async move {
-			let rx = tx.subscribe();
-			let stream = BroadcastStream::new(rx);
-			pipe_from_stream_with_bounded_buffer(pending, stream).await?;
-			Ok(())
-		})
+		.register_subscription(
+			"subscribe_hello",
+			"s_hello",
+			"unsubscribe_hello",
+			|_, pending, broadcaster, _| async move {
+				// Subscribers which can't keep up miss the oldest items instead of slowing down the others.
+				broadcaster.subscribe(pending, LagPolicy::DropOldest).await
+			},
+		)
 		.unwrap();
 	let addr = server.local_addr()?;
 	let handle = server.start(module);
@@ -91,52 +91,14 @@ async fn run_server() -> anyhow::Result<SocketAddr> {
 	Ok(addr)
 }
 
-async fn pipe_from_stream_with_bounded_buffer(
-	pending: PendingSubscriptionSink,
-	stream: BroadcastStream<usize>,
-) -> Result<(), anyhow::Error> {
-	let sink = pending.accept().await?;
-	let closed = sink.closed();
-
-	futures::pin_mut!(closed, stream);
-
-	loop {
-		match future::select(closed, stream.next()).await {
-			// subscription closed.
-			Either::Left((_, _)) => break Ok(()),
-
-			// received new item from the stream.
-			Either::Right((Some(Ok(item)), c)) => {
-				let notif = SubscriptionMessage::from_json(&item)?;
-
-				// NOTE: this will block until there a spot in the queue
-				// and you might want to do something smarter if it's
-				// critical that "the most recent item" must be sent when it is produced.
-				if sink.send(notif).await.is_err() {
-					break Ok(());
-				}
-
-				closed = c;
-			}
-
-			// Send back back the error.
-			Either::Right((Some(Err(e)), _)) => break Err(e.into()),
-
-			// Stream is closed.
-			Either::Right((None, _)) => break Ok(()),
-		}
-	}
-}
-
 // Naive example that broadcasts the produced values to all active subscribers.
-fn produce_items(tx: broadcast::Sender<usize>) {
+fn produce_items(broadcaster: SubscriptionBroadcaster) {
 	for c in 1..=100 {
 		std::thread::sleep(std::time::Duration::from_millis(1));
 
-		// This might fail if no receivers are alive, could occur if no subscriptions are active...
-		// Also be aware that this will succeed when at least one receiver is alive
+		// The item is serialized once and is only sent to the active subscribers.
 		// Thus, clients connecting at different point in time will not receive
 		// the items sent before the subscription got established.
-		let _ = tx.send(c);
+		let _ = broadcaster.send(&c);
 	}
 }
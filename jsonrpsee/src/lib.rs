@@ -48,6 +48,8 @@
 //! - **`client-ws-transport`** - Enables `ws` transport with TLS.
 //! - **`client-ws-transport-no-tls`** - Enables `ws` transport without TLS.
 //! - **`client-web-transport`** - Enables `websys` transport.
+//! - **`ws-deflate`** - Enables the WebSocket `permessage-deflate` extension for the enabled clients and server.
+//! - **`server-cbor`** - Enables CBOR encoded messages in the server, negotiated per connection.
 
 #![warn(missing_docs, missing_debug_implementations, missing_copy_implementations, unreachable_pub)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
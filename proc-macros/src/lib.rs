@@ -33,6 +33,7 @@ use rpc_macro::RpcDescription;
 mod attributes;
 mod helpers;
 mod render_client;
+mod render_openrpc;
 mod render_server;
 mod rpc_macro;
 pub(crate) mod visitor;
@@ -149,13 +150,16 @@ pub(crate) mod visitor;
 ///   implementation.
 /// - `client_bounds`: replace *all* auto-generated trait bounds with the user-defined ones for the client
 ///   implementation.
+/// - `openrpc`: attach an OpenRPC description, generated from the doc comments, parameters and return types,
+///   to every method such that `RpcModule::register_discover` can serve it. All parameter, result and
+///   subscription item types must implement `schemars::JsonSchema`. Requires the `openrpc` feature.
 ///
 /// **Trait requirements:**
 ///
 /// A trait wrapped with the `rpc` attribute **must not**:
 ///
 /// - have associated types or constants;
-/// - have Rust methods not marked with either the `method` or `subscription` attribute;
+/// - have Rust methods not marked with either the `method`, `subscription` or `notification` attribute;
 /// - be empty.
 ///
 /// At least one of the `server` or `client` flags must be provided, otherwise the compilation will err.
@@ -167,11 +171,20 @@ pub(crate) mod visitor;
 /// **Arguments:**
 ///
 /// - `name` (mandatory): name of the RPC method. Does not have to be the same as the Rust method name.
+/// - `access`: access class of the method, either "safe" or "unsafe", defaults to "safe". The server's
+///   `AccessPolicy` decides whether unsafe methods are allowed, denied or hidden for a caller.
 /// - `aliases`: list of name aliases for the RPC method as a comma separated string.
 ///              Aliases are processed ignoring the namespace, so add the complete name, including the
 ///              namespace.
 /// - `blocking`: when set method execution will always spawn on a dedicated thread. Only usable with non-`async` methods.
+/// - `cache`: the server caches successful responses per role and canonicalized params for the given time-to-live,
+///   for instance "500ms", "10s" or "1m". Only use it for idempotent methods. The method can invalidate its
+///   own entries through the `ResponseCache` in the extensions of the call.
 /// - `param_kind`: kind of structure to use for parameter passing. Can be "array" or "map", defaults to "array".
+/// - `timeout`: the server cancels calls that don't complete within the timeout and responds with a timeout error,
+///   for instance "500ms", "5s", "1m" or "1h". Only usable with `async` or `blocking` methods.
+/// - `with_extensions`: when set the server method gets an additional `ext: &Extensions` parameter after `&self`,
+///   which contains the extensions of the call such as the HTTP headers and the connection details.
 ///
 /// **Method requirements:**
 ///
@@ -195,6 +208,8 @@ pub(crate) mod visitor;
 /// - `unsubscribe_aliases` (optional): Similar to `aliases` but for `unsubscribe`.
 /// - `item` (mandatory): type of items yielded by the subscription. Note that it must be the type, not string.
 /// - `param_kind`: kind of structure to use for parameter passing. Can be "array" or "map", defaults to "array".
+/// - `with_extensions`: when set the server method gets an additional `ext: &Extensions` parameter after
+///   the `PendingSubscriptionSink`, which contains the extensions of the subscription call.
 ///
 /// **Method requirements:**
 ///
@@ -207,6 +222,32 @@ pub(crate) mod visitor;
 ///
 /// - have input parameters or not.
 ///
+/// ### `notification` attribute
+///
+/// `notification` attribute is used to define a handler for JSON-RPC notifications, i.e. calls without an `id`
+/// which the server never answers, not even on errors.
+///
+/// **Arguments:**
+///
+/// - `name` (mandatory): name of the RPC notification. Does not have to be the same as the Rust method name.
+/// - `aliases`: list of name aliases for the RPC notification as a comma separated string.
+///   Aliases are processed ignoring the namespace, so add the complete name, including the
+///   namespace.
+/// - `param_kind`: kind of structure to use for parameter passing. Can be "array" or "map", defaults to "array".
+/// - `with_extensions`: when set the server method gets an additional `ext: &Extensions` parameter after `&self`,
+///   which contains the extensions of the notification.
+///
+/// **Method requirements:**
+///
+/// Rust method marked with the `notification` attribute **must not** have a return value.
+///
+/// Rust method marked with `notification` attribute **may**:
+///
+/// - be either `async` or not;
+/// - have input parameters or not.
+///
+/// The client sends it as a notification and returns once it has been sent.
+///
 /// ## Full workflow example
 ///
 /// ```rust
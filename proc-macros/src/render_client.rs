@@ -25,7 +25,7 @@
 // DEALINGS IN THE SOFTWARE.
 use crate::attributes::ParamKind;
 use crate::helpers::generate_where_clause;
-use crate::rpc_macro::{RpcDescription, RpcMethod, RpcSubscription};
+use crate::rpc_macro::{RpcDescription, RpcMethod, RpcNotification, RpcSubscription};
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{quote, quote_spanned};
 use syn::spanned::Spanned;
@@ -50,6 +50,8 @@ impl RpcDescription {
 		let method_impls =
 			self.methods.iter().map(|method| self.render_method(method)).collect::<Result<Vec<_>, _>>()?;
 		let sub_impls = self.subscriptions.iter().map(|sub| self.render_sub(sub)).collect::<Result<Vec<_>, _>>()?;
+		let notif_impls =
+			self.notifications.iter().map(|notif| self.render_notification(notif)).collect::<Result<Vec<_>, _>>()?;
 
 		let async_trait = self.jrps_client_item(quote! { core::__reexports::async_trait });
 
@@ -61,6 +63,7 @@ impl RpcDescription {
 			pub trait #trait_name #impl_generics: #super_trait where #(#where_clause,)* {
 				#(#method_impls)*
 				#(#sub_impls)*
+				#(#notif_impls)*
 			}
 
 			impl<TypeJsonRpseeInteral #(,#type_idents)*> #trait_name #type_generics for TypeJsonRpseeInteral where TypeJsonRpseeInteral: #super_trait #(,#where_clause)* {}
@@ -192,6 +195,34 @@ impl RpcDescription {
 		Ok(method)
 	}
 
+	fn render_notification(&self, notif: &RpcNotification) -> Result<TokenStream2, syn::Error> {
+		// `jsonrpsee::core::Error`
+		let jrps_error = self.jrps_client_item(quote! { core::Error });
+		// Rust method to invoke (e.g. `self.<foo>(...)`).
+		let rust_method_name = &notif.signature.sig.ident;
+		// List of inputs to put into `Params` (e.g. `self.foo(<12, "baz">)`).
+		let rust_method_params = &notif.signature.sig.inputs;
+		// Name of the RPC notification (e.g. `foo_telemetry`).
+		let rpc_notif_name = self.rpc_identifier(&notif.name);
+
+		// Encoded parameters for the notification.
+		let parameter_builder = self.encode_params(&notif.params, &notif.param_kind, &notif.signature);
+		// Doc-comment to be associated with the method.
+		let docs = &notif.docs;
+		// Mark the method as deprecated, if previously declared as so.
+		let deprecated = &notif.deprecated;
+
+		let method = quote! {
+			#docs
+			#deprecated
+			async fn #rust_method_name(#rust_method_params) -> Result<(), #jrps_error> {
+				let params = { #parameter_builder };
+				self.notification(#rpc_notif_name, params).await
+			}
+		};
+		Ok(method)
+	}
+
 	fn encode_params(
 		&self,
 		params: &[(syn::PatIdent, syn::Type)],
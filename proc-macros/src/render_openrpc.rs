@@ -0,0 +1,133 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use super::RpcDescription;
+use crate::helpers::is_option;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::parse::Parser;
+use syn::{Attribute, GenericArgument, PathArguments};
+
+impl RpcDescription {
+	/// Renders the `set_method_schema` calls which attach the OpenRPC description to every method,
+	/// subscription and notification registered by `into_rpc`.
+	pub(super) fn render_openrpc(&self) -> Vec<TokenStream2> {
+		let methods = self.methods.iter().map(|method| {
+			let name = self.rpc_identifier(&method.name);
+			let schema = self.render_schema(&method.docs, &method.deprecated, &method.params, &method.aliases);
+			let result = ok_type(method.returns.as_ref());
+
+			quote! {
+				rpc.set_method_schema(#name, #schema.result::<#result>())
+			}
+		});
+
+		let subscriptions = self.subscriptions.iter().map(|sub| {
+			let name = self.rpc_identifier(&sub.name);
+			let notif_name = sub.notif_name_override.as_ref().map_or_else(|| name.clone(), |n| self.rpc_identifier(n));
+			let unsub_name = self.rpc_identifier(&sub.unsubscribe);
+			let schema = self.render_schema(&sub.docs, &TokenStream2::new(), &sub.params, &sub.aliases);
+			let item = &sub.item;
+
+			quote! {
+				rpc.set_method_schema(#name, #schema.subscription::<#item>(#notif_name, #unsub_name))
+			}
+		});
+
+		let notifications = self.notifications.iter().map(|notif| {
+			let name = self.rpc_identifier(&notif.name);
+			let schema = self.render_schema(&notif.docs, &notif.deprecated, &notif.params, &notif.aliases);
+
+			quote! {
+				rpc.set_method_schema(#name, #schema)
+			}
+		});
+
+		methods.chain(subscriptions).chain(notifications).collect()
+	}
+
+	/// Renders a `MethodSchema` builder with the description, parameters and aliases.
+	fn render_schema(
+		&self,
+		docs: &TokenStream2,
+		deprecated: &TokenStream2,
+		params: &[(syn::PatIdent, syn::Type)],
+		aliases: &[String],
+	) -> TokenStream2 {
+		let openrpc = self.jrps_server_item(quote! { openrpc });
+
+		let description = doc_text(docs).map(|text| quote!(.description(#text)));
+		let deprecated = (!deprecated.is_empty()).then(|| quote!(.deprecated()));
+		let params = params.iter().map(|(name, ty)| {
+			let name = name.ident.to_string();
+			let name = name.trim_start_matches("r#");
+			let required = !is_option(ty);
+			quote!(.param::<#ty>(#name, #required))
+		});
+
+		quote! {
+			#openrpc::MethodSchema::new() #description #deprecated #(#params)* #(.alias(#aliases))*
+		}
+	}
+}
+
+/// Joins the lines of the doc comments, if any.
+fn doc_text(docs: &TokenStream2) -> Option<String> {
+	let attrs = Attribute::parse_outer.parse2(docs.clone()).ok()?;
+	let lines: Vec<_> = attrs
+		.iter()
+		.filter_map(|attr| match attr.parse_meta() {
+			Ok(syn::Meta::NameValue(syn::MetaNameValue { lit: syn::Lit::Str(lit), .. })) => Some(lit.value()),
+			_ => None,
+		})
+		.collect();
+
+	let text = lines.iter().map(|line| line.strip_prefix(' ').unwrap_or(line)).collect::<Vec<_>>().join("\n");
+	let text = text.trim();
+
+	(!text.is_empty()).then(|| text.to_owned())
+}
+
+/// Returns the `T` of a `Result<T, E>` or `RpcResult<T>` return type, otherwise the type itself.
+fn ok_type(ty: Option<&syn::Type>) -> TokenStream2 {
+	let Some(ty) = ty else {
+		return quote!(());
+	};
+
+	if let syn::Type::Path(path) = ty {
+		if let Some(segment) = path.path.segments.last() {
+			if let PathArguments::AngleBracketed(args) = &segment.arguments {
+				if segment.ident == "Result" || segment.ident == "RpcResult" {
+					if let Some(GenericArgument::Type(ok)) = args.args.first() {
+						return quote!(#ok);
+					}
+				}
+			}
+		}
+	}
+
+	quote!(#ty)
+}
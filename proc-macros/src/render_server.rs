@@ -60,9 +60,18 @@ impl RpcDescription {
 	}
 
 	fn render_methods(&self) -> Result<TokenStream2, syn::Error> {
+		let extensions_ty = self.jrps_server_item(quote! { Extensions });
+
 		let methods = self.methods.iter().map(|method| {
 			let docs = &method.docs;
-			let method_sig = &method.signature;
+			let mut method_sig = method.signature.clone();
+
+			if method.with_extensions {
+				// Add `Extensions` as the first input parameter to the signature.
+				let ext_ty: syn::FnArg = syn::parse_quote!(ext: &#extensions_ty);
+				method_sig.sig.inputs.insert(1, ext_ty);
+			}
+
 			quote! {
 				#docs
 				#method_sig
@@ -77,15 +86,38 @@ impl RpcDescription {
 			let mut sub_sig = sub.signature.clone();
 
 			sub_sig.sig.inputs.insert(1, subscription_sink);
+
+			if sub.with_extensions {
+				// Add `Extensions` as the second input parameter to the signature, after the `SubscriptionSink`.
+				let ext_ty: syn::FnArg = syn::parse_quote!(ext: &#extensions_ty);
+				sub_sig.sig.inputs.insert(2, ext_ty);
+			}
 			quote! {
 				#docs
 				#sub_sig
 			}
 		});
 
+		let notifications = self.notifications.iter().map(|notif| {
+			let docs = &notif.docs;
+			let mut notif_sig = notif.signature.clone();
+
+			if notif.with_extensions {
+				// Add `Extensions` as the first input parameter to the signature.
+				let ext_ty: syn::FnArg = syn::parse_quote!(ext: &#extensions_ty);
+				notif_sig.sig.inputs.insert(1, ext_ty);
+			}
+
+			quote! {
+				#docs
+				#notif_sig
+			}
+		});
+
 		Ok(quote! {
 			#(#methods)*
 			#(#subscriptions)*
+			#(#notifications)*
 		})
 	}
 
@@ -127,27 +159,47 @@ impl RpcDescription {
 				// provided `Params` object.
 				// `params_seq` is the comma-delimited sequence of parameters we're passing to the rust function
 				// called..
-				let (parsing, params_seq) = self.render_params_decoding(&method.params, None);
+				let (parsing, params_seq) = self.render_params_decoding(&method.params, ParamsDecodingFailure::Respond);
 
 				let into_response = self.jrps_server_item(quote! { IntoResponse });
 
 				check_name(&rpc_method_name, rust_method_name.span());
 
+				let ext_pat = if method.with_extensions { quote!(ext) } else { quote!(_) };
+
+				let timeout = method.timeout.map(|millis| quote!(std::time::Duration::from_millis(#millis),));
+
 				if method.signature.sig.asyncness.is_some() {
+					let ext_arg = if method.with_extensions { quote!(&ext,) } else { quote!() };
+					let register_kind = match timeout {
+						Some(_) => quote!(register_async_method_with_timeout),
+						None => quote!(register_async_method),
+					};
+
 					handle_register_result(quote! {
-						rpc.register_async_method(#rpc_method_name, |params, context| async move {
+						rpc.#register_kind(#rpc_method_name, #timeout |params, context, #ext_pat| async move {
 							#parsing
-							#into_response::into_response(context.as_ref().#rust_method_name(#params_seq).await)
+							#into_response::into_response(
+								context.as_ref().#rust_method_name(#ext_arg #params_seq).await
+							)
 						})
 					})
 				} else {
-					let register_kind =
-						if method.blocking { quote!(register_blocking_method) } else { quote!(register_method) };
+					let (register_kind, ext_arg) = match (method.blocking, method.with_extensions) {
+						(true, true) => (quote!(register_blocking_method), quote!(&ext,)),
+						(true, false) => (quote!(register_blocking_method), quote!()),
+						(false, true) => (quote!(register_method), quote!(ext,)),
+						(false, false) => (quote!(register_method), quote!()),
+					};
+					let register_kind = match timeout {
+						Some(_) => quote!(register_blocking_method_with_timeout),
+						None => register_kind,
+					};
 
 					handle_register_result(quote! {
-						rpc.#register_kind(#rpc_method_name, |params, context| {
+						rpc.#register_kind(#rpc_method_name, #timeout |params, context, #ext_pat| {
 							#parsing
-							#into_response::into_response(context.#rust_method_name(#params_seq))
+							#into_response::into_response(context.#rust_method_name(#ext_arg #params_seq))
 						})
 					})
 				}
@@ -170,7 +222,8 @@ impl RpcDescription {
 				// provided `Params` object.
 				// `params_seq` is the comma-delimited sequence of parameters.
 				let pending = proc_macro2::Ident::new("pending", rust_method_name.span());
-				let (parsing, params_seq) = self.render_params_decoding(&sub.params, Some(pending));
+				let (parsing, params_seq) =
+					self.render_params_decoding(&sub.params, ParamsDecodingFailure::RejectSubscription(pending));
 				let sub_err = self.jrps_server_item(quote! { SubscriptionCloseResponse });
 				let into_sub_response = self.jrps_server_item(quote! { IntoSubscriptionCloseResponse });
 
@@ -185,19 +238,76 @@ impl RpcDescription {
 					None => rpc_sub_name.clone(),
 				};
 
+				let ext_pat = if sub.with_extensions { quote!(ext) } else { quote!(_) };
+
 				if sub.signature.sig.asyncness.is_some() {
+					let ext_arg = if sub.with_extensions { quote!(&ext,) } else { quote!() };
+
 					handle_register_result(quote! {
-						rpc.register_subscription(#rpc_sub_name, #rpc_notif_name, #rpc_unsub_name, |params, mut pending, context| async move {
+						rpc.register_subscription(
+							#rpc_sub_name,
+							#rpc_notif_name,
+							#rpc_unsub_name,
+							|params, mut pending, context, #ext_pat| async move {
+								#parsing
+								#into_sub_response::into_response(
+									context.as_ref().#rust_method_name(pending, #ext_arg #params_seq).await
+								)
+							}
+						)
+					})
+				} else {
+					let ext_arg = if sub.with_extensions { quote!(ext,) } else { quote!() };
+
+					handle_register_result(quote! {
+						rpc.register_subscription_raw(
+							#rpc_sub_name,
+							#rpc_notif_name,
+							#rpc_unsub_name,
+							|params, mut pending, context, #ext_pat| {
+								#parsing
+								let _ = context.as_ref().#rust_method_name(pending, #ext_arg #params_seq);
+								#sub_err::None
+							}
+						)
+					})
+				}
+			})
+			.collect::<Vec<_>>();
+
+		let notifications = self
+			.notifications
+			.iter()
+			.map(|notif| {
+				// Rust method to invoke (e.g. `self.<foo>(...)`).
+				let rust_method_name = &notif.signature.sig.ident;
+				// Name of the RPC notification (e.g. `foo_telemetry`).
+				let rpc_notif_name = self.rpc_identifier(&notif.name);
+				// `parsing` is the code associated with parsing structure from the
+				// provided `Params` object.
+				// `params_seq` is the comma-delimited sequence of parameters.
+				let (parsing, params_seq) = self.render_params_decoding(&notif.params, ParamsDecodingFailure::Ignore);
+
+				check_name(&rpc_notif_name, rust_method_name.span());
+
+				let ext_pat = if notif.with_extensions { quote!(ext) } else { quote!(_) };
+
+				if notif.signature.sig.asyncness.is_some() {
+					let ext_arg = if notif.with_extensions { quote!(&ext,) } else { quote!() };
+
+					handle_register_result(quote! {
+						rpc.register_async_notification(#rpc_notif_name, |params, context, #ext_pat| async move {
 							#parsing
-							#into_sub_response::into_response(context.as_ref().#rust_method_name(pending, #params_seq).await)
+							context.as_ref().#rust_method_name(#ext_arg #params_seq).await
 						})
 					})
 				} else {
+					let ext_arg = if notif.with_extensions { quote!(ext,) } else { quote!() };
+
 					handle_register_result(quote! {
-						rpc.register_subscription_raw(#rpc_sub_name, #rpc_notif_name, #rpc_unsub_name, |params, mut pending, context| {
+						rpc.register_notification(#rpc_notif_name, |params, context, #ext_pat| {
 							#parsing
-							let _ = context.as_ref().#rust_method_name(pending, #params_seq);
-							#sub_err::None
+							context.#rust_method_name(#ext_arg #params_seq)
 						})
 					})
 				}
@@ -227,6 +337,34 @@ impl RpcDescription {
 			})
 			.collect::<Vec<_>>();
 
+		let method_access = self
+			.methods
+			.iter()
+			.filter(|method| method.unsafe_access)
+			.map(|method| {
+				let rpc_name = self.rpc_identifier(&method.name);
+				let access = self.jrps_server_item(quote! { MethodAccess });
+
+				handle_register_result(quote! {
+					rpc.set_method_access(#rpc_name, #access::Unsafe)
+				})
+			})
+			.collect::<Vec<_>>();
+
+		let method_caches = self
+			.methods
+			.iter()
+			.filter_map(|method| {
+				let rpc_name = self.rpc_identifier(&method.name);
+				let config = self.jrps_server_item(quote! { ResponseCacheConfig });
+				let ttl = method.cache?;
+
+				Some(handle_register_result(quote! {
+					rpc.set_method_cache(#rpc_name, #config::new(std::time::Duration::from_millis(#ttl)))
+				}))
+			})
+			.collect::<Vec<_>>();
+
 		let subscription_aliases = self
 			.subscriptions
 			.iter()
@@ -263,7 +401,35 @@ impl RpcDescription {
 			})
 			.collect::<Vec<_>>();
 
-		let doc_comment = "Collects all the methods and subscriptions defined in the trait \
+		let notification_aliases = self
+			.notifications
+			.iter()
+			.map(|notif| {
+				let rpc_name = self.rpc_identifier(&notif.name);
+				let rust_method_name = &notif.signature.sig.ident;
+
+				let aliases: Vec<TokenStream2> = notif
+					.aliases
+					.iter()
+					.map(|alias| {
+						check_name(alias, rust_method_name.span());
+						handle_register_result(quote! {
+							rpc.register_alias(#alias, #rpc_name)
+						})
+					})
+					.collect();
+
+				quote!( #(#aliases)* )
+			})
+			.collect::<Vec<_>>();
+
+		let method_schemas: Vec<TokenStream2> = if self.openrpc {
+			self.render_openrpc().into_iter().map(handle_register_result).collect()
+		} else {
+			Vec::new()
+		};
+
+		let doc_comment = "Collects all the methods, subscriptions and notifications defined in the trait \
 								and adds them into a single `RpcModule`.";
 
 		let sub_tys: Vec<syn::Type> = self.subscriptions.clone().into_iter().map(|s| s.item).collect();
@@ -278,8 +444,13 @@ impl RpcDescription {
 				#(#errors)*
 				#(#methods)*
 				#(#subscriptions)*
+				#(#notifications)*
+				#(#method_access)*
+				#(#method_caches)*
+				#(#method_schemas)*
 				#(#method_aliases)*
 				#(#subscription_aliases)*
+				#(#notification_aliases)*
 
 				rpc
 			}
@@ -289,7 +460,7 @@ impl RpcDescription {
 	fn render_params_decoding(
 		&self,
 		params: &[(syn::PatIdent, syn::Type)],
-		sub: Option<proc_macro2::Ident>,
+		on_failure: ParamsDecodingFailure,
 	) -> (TokenStream2, TokenStream2) {
 		if params.is_empty() {
 			return (TokenStream2::default(), TokenStream2::default());
@@ -302,51 +473,37 @@ impl RpcDescription {
 		let response_payload = self.jrps_server_item(quote! { types::ResponsePayload });
 		let tokio = self.jrps_server_item(quote! { tokio });
 
+		// Code to bail out with once the decoding failed, the error is bound to `e`.
+		let bail = match on_failure {
+			ParamsDecodingFailure::Respond => quote! { return #response_payload::Error(e); },
+			ParamsDecodingFailure::RejectSubscription(pending) => quote! {
+				#tokio::spawn(#pending.reject(e));
+				return #sub_err::None;
+			},
+			// Notifications are never answered.
+			ParamsDecodingFailure::Ignore => quote! { return; },
+		};
+
 		// Code to decode sequence of parameters from a JSON array.
 		let decode_array = {
-			let decode_fields = params.iter().map(|(name, ty)| match (is_option(ty), sub.as_ref()) {
-				(true, Some(pending)) => {
+			let decode_fields = params.iter().map(|(name, ty)| {
+				if is_option(ty) {
 					quote! {
 						let #name: #ty = match seq.optional_next() {
 							Ok(v) => v,
 							Err(e) => {
 								#tracing::debug!(concat!("Error parsing optional \"", stringify!(#name), "\" as \"", stringify!(#ty), "\": {:?}"), e);
-								#tokio::spawn(#pending.reject(e));
-								return #sub_err::None;
+								#bail
 							}
 						};
 					}
-				}
-				(true, None) => {
-					quote! {
-						let #name: #ty = match seq.optional_next() {
-							Ok(v) => v,
-							Err(e) => {
-								#tracing::debug!(concat!("Error parsing optional \"", stringify!(#name), "\" as \"", stringify!(#ty), "\": {:?}"), e);
-								return #response_payload::Error(e);
-							}
-						};
-					}
-				}
-				(false, Some(pending)) => {
-					quote! {
-						let #name: #ty = match seq.next() {
-							Ok(v) => v,
-							Err(e) => {
-								#tracing::debug!(concat!("Error parsing optional \"", stringify!(#name), "\" as \"", stringify!(#ty), "\": {:?}"), e);
-								#tokio::spawn(#pending.reject(e));
-								return #sub_err::None;
-							}
-						};
-					}
-				}
-				(false, None) => {
+				} else {
 					quote! {
 						let #name: #ty = match seq.next() {
 							Ok(v) => v,
 							Err(e) => {
 								#tracing::debug!(concat!("Error parsing \"", stringify!(#name), "\" as \"", stringify!(#ty), "\": {:?}"), e);
-								return #response_payload::Error(e);
+								#bail
 							}
 						};
 					}
@@ -400,43 +557,22 @@ impl RpcDescription {
 			let destruct = params.iter().map(|(name, _)| quote! { parsed.#name });
 			let types = params.iter().map(|(_, ty)| ty);
 
-			if let Some(pending) = sub {
-				quote! {
-					#[derive(#serde::Deserialize)]
-					#[serde(crate = #serde_crate)]
-					struct ParamsObject<#(#generics,)*> {
-						#(#fields)*
-					}
-
-					let parsed: ParamsObject<#(#types,)*> = match params.parse() {
-						Ok(p) => p,
-						Err(e) => {
-							#tracing::debug!("Failed to parse JSON-RPC params as object: {}", e);
-							#tokio::spawn(#pending.reject(e));
-							return #sub_err::None;
-						}
-					};
-
-					(#(#destruct),*)
+			quote! {
+				#[derive(#serde::Deserialize)]
+				#[serde(crate = #serde_crate)]
+				struct ParamsObject<#(#generics,)*> {
+					#(#fields)*
 				}
-			} else {
-				quote! {
-					#[derive(#serde::Deserialize)]
-					#[serde(crate = #serde_crate)]
-					struct ParamsObject<#(#generics,)*> {
-						#(#fields)*
-					}
 
-					let parsed: ParamsObject<#(#types,)*> = match params.parse() {
-						Ok(p) => p,
-						Err(e) => {
-							#tracing::debug!("Failed to parse JSON-RPC params as object: {}", e);
-							return #response_payload::Error(e);
-						}
-					};
+				let parsed: ParamsObject<#(#types,)*> = match params.parse() {
+					Ok(p) => p,
+					Err(e) => {
+						#tracing::debug!("Failed to parse JSON-RPC params as object: {}", e);
+						#bail
+					}
+				};
 
-					(#(#destruct),*)
-				}
+				(#(#destruct),*)
 			}
 		};
 
@@ -451,3 +587,13 @@ impl RpcDescription {
 		(parsing, params_fields)
 	}
 }
+
+/// What the generated code does when the parameters of a call can't be decoded.
+enum ParamsDecodingFailure {
+	/// Respond to the method call with the error.
+	Respond,
+	/// Reject the pending subscription with the error.
+	RejectSubscription(proc_macro2::Ident),
+	/// Ignore the error, used for notifications which are never answered.
+	Ignore,
+}
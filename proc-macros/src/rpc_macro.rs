@@ -48,17 +48,37 @@ pub struct RpcMethod {
 	pub returns: Option<syn::Type>,
 	pub signature: syn::TraitItemMethod,
 	pub aliases: Vec<String>,
+	pub with_extensions: bool,
+	/// Timeout of the method in milliseconds.
+	pub timeout: Option<u64>,
+	/// Whether the method was declared with `access = "unsafe"`.
+	pub unsafe_access: bool,
+	/// Time-to-live of cached responses in milliseconds.
+	pub cache: Option<u64>,
 }
 
 impl RpcMethod {
 	pub fn from_item(attr: Attribute, mut method: syn::TraitItemMethod) -> syn::Result<Self> {
-		let [aliases, blocking, name, param_kind] =
-			AttributeMeta::parse(attr)?.retain(["aliases", "blocking", "name", "param_kind"])?;
-
+		let [access, aliases, blocking, cache, name, param_kind, timeout, with_extensions] =
+			AttributeMeta::parse(attr)?.retain([
+				"access",
+				"aliases",
+				"blocking",
+				"cache",
+				"name",
+				"param_kind",
+				"timeout",
+				"with_extensions",
+			])?;
+
+		let unsafe_access = parse_unsafe_access(access)?;
 		let aliases = parse_aliases(aliases)?;
 		let blocking = optional(blocking, Argument::flag)?.is_some();
+		let cache = parse_duration(cache, "cache")?;
 		let name = name?.string()?;
 		let param_kind = parse_param_kind(param_kind)?;
+		let timeout = parse_duration(timeout, "timeout")?;
+		let with_extensions = optional(with_extensions, Argument::flag)?.is_some();
 
 		let sig = method.sig.clone();
 		let docs = extract_doc_comments(&method.attrs);
@@ -71,6 +91,10 @@ impl RpcMethod {
 			return Err(syn::Error::new(sig.span(), "Blocking method must be synchronous"));
 		}
 
+		if timeout.is_some() && !blocking && sig.asyncness.is_none() {
+			return Err(syn::Error::new(sig.span(), "Timeout can only be used with async or blocking methods"));
+		}
+
 		let params: Vec<_> = sig
 			.inputs
 			.into_iter()
@@ -94,11 +118,86 @@ impl RpcMethod {
 			syn::ReturnType::Default => None,
 			syn::ReturnType::Type(_, output) => Some(*output),
 		};
-
 		// We've analyzed attributes and don't need them anymore.
 		method.attrs.clear();
 
-		Ok(Self { aliases, blocking, name, params, param_kind, returns, signature: method, docs, deprecated })
+		Ok(Self {
+			aliases,
+			blocking,
+			name,
+			params,
+			param_kind,
+			returns,
+			signature: method,
+			docs,
+			deprecated,
+			with_extensions,
+			timeout,
+			unsafe_access,
+			cache,
+		})
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct RpcNotification {
+	pub name: String,
+	pub docs: TokenStream2,
+	pub deprecated: TokenStream2,
+	pub params: Vec<(syn::PatIdent, syn::Type)>,
+	pub param_kind: ParamKind,
+	pub signature: syn::TraitItemMethod,
+	pub aliases: Vec<String>,
+	pub with_extensions: bool,
+}
+
+impl RpcNotification {
+	pub fn from_item(attr: Attribute, mut notif: syn::TraitItemMethod) -> syn::Result<Self> {
+		let [aliases, name, param_kind, with_extensions] =
+			AttributeMeta::parse(attr)?.retain(["aliases", "name", "param_kind", "with_extensions"])?;
+
+		let aliases = parse_aliases(aliases)?;
+		let name = name?.string()?;
+		let param_kind = parse_param_kind(param_kind)?;
+		let with_extensions = optional(with_extensions, Argument::flag)?.is_some();
+
+		let sig = notif.sig.clone();
+		let docs = extract_doc_comments(&notif.attrs);
+		let deprecated = match find_attr(&notif.attrs, "deprecated") {
+			Some(attr) => quote!(#attr),
+			None => quote!(),
+		};
+
+		if let syn::ReturnType::Type(_, output) = &sig.output {
+			return Err(syn::Error::new(
+				output.span(),
+				"Notifications are never answered and can't have a return type",
+			));
+		}
+
+		let params: Vec<_> = sig
+			.inputs
+			.into_iter()
+			.filter_map(|arg| match arg {
+				syn::FnArg::Receiver(_) => None,
+				syn::FnArg::Typed(arg) => match *arg.pat {
+					syn::Pat::Ident(name) => Some(Ok((name, *arg.ty))),
+					syn::Pat::Wild(wild) => Some(Err(syn::Error::new(
+						wild.underscore_token.span(),
+						"Method argument names must be valid Rust identifiers; got `_` instead",
+					))),
+					_ => Some(Err(syn::Error::new(
+						arg.span(),
+						format!("Unexpected method signature input; got {:?} ", *arg.pat),
+					))),
+				},
+			})
+			.collect::<Result<_, _>>()?;
+
+		// We've analyzed attributes and don't need them anymore.
+		notif.attrs.clear();
+
+		Ok(Self { name, docs, deprecated, params, param_kind, signature: notif, aliases, with_extensions })
 	}
 }
 
@@ -120,12 +219,21 @@ pub struct RpcSubscription {
 	pub signature: syn::TraitItemMethod,
 	pub aliases: Vec<String>,
 	pub unsubscribe_aliases: Vec<String>,
+	pub with_extensions: bool,
 }
 
 impl RpcSubscription {
 	pub fn from_item(attr: syn::Attribute, mut sub: syn::TraitItemMethod) -> syn::Result<Self> {
-		let [aliases, item, name, param_kind, unsubscribe, unsubscribe_aliases] = AttributeMeta::parse(attr)?
-			.retain(["aliases", "item", "name", "param_kind", "unsubscribe", "unsubscribe_aliases"])?;
+		let [aliases, item, name, param_kind, unsubscribe, unsubscribe_aliases, with_extensions] =
+			AttributeMeta::parse(attr)?.retain([
+				"aliases",
+				"item",
+				"name",
+				"param_kind",
+				"unsubscribe",
+				"unsubscribe_aliases",
+				"with_extensions",
+			])?;
 
 		let aliases = parse_aliases(aliases)?;
 		let map = name?.value::<NameMapping>()?;
@@ -134,6 +242,7 @@ impl RpcSubscription {
 		let item = item?.value()?;
 		let param_kind = parse_param_kind(param_kind)?;
 		let unsubscribe_aliases = parse_aliases(unsubscribe_aliases)?;
+		let with_extensions = optional(with_extensions, Argument::flag)?.is_some();
 
 		let sig = sub.sig.clone();
 		let docs = extract_doc_comments(&sub.attrs);
@@ -170,6 +279,7 @@ impl RpcSubscription {
 			signature: sub,
 			aliases,
 			docs,
+			with_extensions,
 		})
 	}
 }
@@ -188,6 +298,8 @@ pub struct RpcDescription {
 	/// Assuming that trait to which attribute is applied is named `Foo`, the generated
 	/// client trait will have `FooClient` name.
 	pub(crate) needs_client: bool,
+	/// Switch denoting that the server must attach OpenRPC descriptions to the methods.
+	pub(crate) openrpc: bool,
 	/// Optional prefix for RPC namespace.
 	pub(crate) namespace: Option<String>,
 	/// Trait definition in which all the attributes were stripped.
@@ -196,6 +308,8 @@ pub struct RpcDescription {
 	pub(crate) methods: Vec<RpcMethod>,
 	/// List of RPC subscriptions defined in the trait.
 	pub(crate) subscriptions: Vec<RpcSubscription>,
+	/// List of RPC notifications defined in the trait.
+	pub(crate) notifications: Vec<RpcNotification>,
 	/// Optional user defined trait bounds for the client implementation.
 	pub(crate) client_bounds: Option<Punctuated<syn::WherePredicate, Token![,]>>,
 	/// Optional user defined trait bounds for the server implementation.
@@ -204,11 +318,12 @@ pub struct RpcDescription {
 
 impl RpcDescription {
 	pub fn from_item(attr: Attribute, mut item: syn::ItemTrait) -> syn::Result<Self> {
-		let [client, server, namespace, client_bounds, server_bounds] =
-			AttributeMeta::parse(attr)?.retain(["client", "server", "namespace", "client_bounds", "server_bounds"])?;
+		let [client, server, namespace, client_bounds, server_bounds, openrpc] = AttributeMeta::parse(attr)?
+			.retain(["client", "server", "namespace", "client_bounds", "server_bounds", "openrpc"])?;
 
 		let needs_server = optional(server, Argument::flag)?.is_some();
 		let needs_client = optional(client, Argument::flag)?.is_some();
+		let openrpc = optional(openrpc, Argument::flag)?.is_some();
 		let namespace = optional(namespace, Argument::string)?;
 		let client_bounds = optional(client_bounds, Argument::group)?;
 		let server_bounds = optional(server_bounds, Argument::group)?;
@@ -231,6 +346,10 @@ impl RpcDescription {
 			));
 		}
 
+		if openrpc && !needs_server {
+			return Err(syn::Error::new_spanned(&item.ident, "Attribute 'server' must be specified with 'openrpc'"));
+		}
+
 		let jsonrpsee_client_path = crate::helpers::find_jsonrpsee_client_crate().ok();
 		let jsonrpsee_server_path = crate::helpers::find_jsonrpsee_server_crate().ok();
 
@@ -245,48 +364,52 @@ impl RpcDescription {
 
 		let mut methods = Vec::new();
 		let mut subscriptions = Vec::new();
+		let mut notifications = Vec::new();
 
-		// Go through all the methods in the trait and collect methods and
-		// subscriptions.
+		// Go through all the methods in the trait and collect methods,
+		// subscriptions and notifications.
 		for entry in item.items.iter() {
 			if let syn::TraitItem::Method(method) = entry {
 				if method.sig.receiver().is_none() {
 					return Err(syn::Error::new_spanned(&method.sig, "First argument of the trait must be '&self'"));
 				}
 
-				let mut is_method = false;
-				let mut is_sub = false;
-				if let Some(attr) = find_attr(&method.attrs, "method") {
-					is_method = true;
+				let method_attr = find_attr(&method.attrs, "method");
+				let sub_attr = find_attr(&method.attrs, "subscription");
+				let notif_attr = find_attr(&method.attrs, "notification");
 
-					let method_data = RpcMethod::from_item(attr.clone(), method.clone())?;
-					methods.push(method_data);
-				}
-				if let Some(attr) = find_attr(&method.attrs, "subscription") {
-					is_sub = true;
-					if is_method {
+				match (method_attr, sub_attr, notif_attr) {
+					(Some(attr), None, None) => {
+						let method_data = RpcMethod::from_item(attr.clone(), method.clone())?;
+						methods.push(method_data);
+					}
+					(None, Some(attr), None) => {
+						let sub_data = RpcSubscription::from_item(attr.clone(), method.clone())?;
+						subscriptions.push(sub_data);
+					}
+					(None, None, Some(attr)) => {
+						let notif_data = RpcNotification::from_item(attr.clone(), method.clone())?;
+						notifications.push(notif_data);
+					}
+					(None, None, None) => {
 						return Err(syn::Error::new_spanned(
 							method,
-							"Element cannot be both subscription and method at the same time",
+							"Methods must have either 'method', 'subscription' or 'notification' attribute",
+						));
+					}
+					_ => {
+						return Err(syn::Error::new_spanned(
+							method,
+							"Element can only be one of method, subscription or notification at the same time",
 						));
 					}
-
-					let sub_data = RpcSubscription::from_item(attr.clone(), method.clone())?;
-					subscriptions.push(sub_data);
-				}
-
-				if !is_method && !is_sub {
-					return Err(syn::Error::new_spanned(
-						method,
-						"Methods must have either 'method' or 'subscription' attribute",
-					));
 				}
 			} else {
 				return Err(syn::Error::new_spanned(entry, "Only methods allowed in RPC traits"));
 			}
 		}
 
-		if methods.is_empty() && subscriptions.is_empty() {
+		if methods.is_empty() && subscriptions.is_empty() && notifications.is_empty() {
 			return Err(syn::Error::new_spanned(&item, "RPC cannot be empty"));
 		}
 
@@ -295,10 +418,12 @@ impl RpcDescription {
 			jsonrpsee_server_path,
 			needs_server,
 			needs_client,
+			openrpc,
 			namespace,
 			trait_def: item,
 			methods,
 			subscriptions,
+			notifications,
 			client_bounds,
 			server_bounds,
 		})
@@ -347,6 +472,45 @@ fn parse_aliases(arg: Result<Argument, MissingArgument>) -> syn::Result<Vec<Stri
 	Ok(aliases.map(|a| a.list.into_iter().map(|lit| lit.value()).collect()).unwrap_or_default())
 }
 
+/// Parses a duration such as `"500ms"`, `"5s"`, `"1m"` or `"1h"` into milliseconds.
+fn parse_duration(arg: Result<Argument, MissingArgument>, what: &str) -> syn::Result<Option<u64>> {
+	let Some(duration) = optional(arg, Argument::value::<syn::LitStr>)? else {
+		return Ok(None);
+	};
+
+	let value = duration.value();
+	let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+	let (amount, unit) = value.split_at(split);
+
+	let multiplier = match unit {
+		"ms" => 1,
+		"s" => 1_000,
+		"m" => 60_000,
+		"h" => 3_600_000,
+		_ => 0,
+	};
+
+	match amount.parse::<u64>().ok().and_then(|amount| amount.checked_mul(multiplier)) {
+		Some(millis) if millis > 0 => Ok(Some(millis)),
+		_ => Err(syn::Error::new(
+			duration.span(),
+			format!("Invalid {what}, expected a positive duration such as \"500ms\", \"5s\", \"1m\" or \"1h\""),
+		)),
+	}
+}
+
+fn parse_unsafe_access(arg: Result<Argument, MissingArgument>) -> syn::Result<bool> {
+	let Some(access) = optional(arg, Argument::value::<syn::LitStr>)? else {
+		return Ok(false);
+	};
+
+	match access.value().as_str() {
+		"safe" => Ok(false),
+		"unsafe" => Ok(true),
+		_ => Err(syn::Error::new(access.span(), "Invalid access, expected \"safe\" or \"unsafe\"")),
+	}
+}
+
 fn parse_subscribe(arg: Result<Argument, MissingArgument>) -> syn::Result<Option<String>> {
 	let unsub = optional(arg, Argument::string)?;
 
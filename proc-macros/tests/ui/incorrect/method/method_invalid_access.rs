@@ -0,0 +1,10 @@
+use jsonrpsee::proc_macros::rpc;
+
+// Access that is neither safe nor unsafe.
+#[rpc(client, server)]
+pub trait InvalidAccess {
+	#[method(name = "foo", access = "admin")]
+	async fn async_method(&self) -> jsonrpsee::core::RpcResult<u8>;
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use jsonrpsee::proc_macros::rpc;
+
+// Cache time-to-live with an unknown unit.
+#[rpc(client, server)]
+pub trait InvalidCache {
+	#[method(name = "foo", cache = "10 seconds")]
+	async fn async_method(&self) -> jsonrpsee::core::RpcResult<u8>;
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use jsonrpsee::proc_macros::rpc;
+
+// Timeout without a unit.
+#[rpc(client, server)]
+pub trait InvalidTimeout {
+	#[method(name = "foo", timeout = "5")]
+	async fn async_method(&self) -> jsonrpsee::core::RpcResult<u8>;
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use jsonrpsee::proc_macros::rpc;
+
+// Timeout on a method that is neither async nor blocking.
+#[rpc(client, server)]
+pub trait SyncTimeout {
+	#[method(name = "foo", timeout = "5s")]
+	fn sync_method(&self) -> jsonrpsee::core::RpcResult<u8>;
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use jsonrpsee::proc_macros::rpc;
+
+#[rpc(client, openrpc)]
+pub trait OpenRpcWithoutServer {
+	#[method(name = "foo")]
+	async fn foo(&self) -> Result<u8, Error>;
+}
+
+fn main() {}
@@ -26,24 +26,126 @@
 
 //! Utilities for handling async code.
 
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::server::SharedMethods;
+use futures_util::future::{self, Either};
+use jsonrpsee_core::server::Methods;
 use jsonrpsee_core::Error;
-use std::sync::Arc;
-use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore, TryAcquireError};
+use tokio::sync::{watch, Notify, OwnedSemaphorePermit, Semaphore, TryAcquireError};
+use tokio_util::sync::CancellationToken;
+
+/// Create a connected pair of [`ServerHandle`] and [`StopHandle`] for a server with the given methods.
+pub(crate) fn stop_channel(methods: SharedMethods) -> (ServerHandle, StopHandle) {
+	let (tx, rx) = watch::channel(());
+	let shutdown = Arc::new(ShutdownState::default());
+
+	(ServerHandle { tx: Arc::new(tx), shutdown: shutdown.clone(), methods }, StopHandle { rx, shutdown })
+}
+
+/// State shared between the [`ServerHandle`] and the server during a graceful shutdown.
+#[derive(Debug, Default)]
+struct ShutdownState {
+	/// Whether the server was stopped by [`ServerHandle::stop_graceful`].
+	graceful: AtomicBool,
+	/// Cancelled once the graceful shutdown deadline has been hit.
+	abort: CancellationToken,
+	/// Number of calls that were aborted.
+	aborted: AtomicUsize,
+	/// Number of calls that are running.
+	in_flight: AtomicUsize,
+	/// Notified once the last running call has finished.
+	idle: Notify,
+}
+
+impl ShutdownState {
+	/// A future that resolves once no calls are running.
+	async fn calls_finished(&self) {
+		loop {
+			let notified = self.idle.notified();
+			tokio::pin!(notified);
+			notified.as_mut().enable();
+
+			if self.in_flight.load(Ordering::SeqCst) == 0 {
+				return;
+			}
+			notified.await;
+		}
+	}
+}
+
+/// Counts a call as running until it's dropped.
+struct InFlightCall(Arc<ShutdownState>);
+
+impl InFlightCall {
+	fn new(shutdown: Arc<ShutdownState>) -> Self {
+		shutdown.in_flight.fetch_add(1, Ordering::SeqCst);
+		Self(shutdown)
+	}
+}
+
+impl Drop for InFlightCall {
+	fn drop(&mut self) {
+		if self.0.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+			self.0.idle.notify_waiters();
+		}
+	}
+}
 
 /// Represent a stop handle which is a wrapper over a `multi-consumer receiver`
 /// and cloning [`StopHandle`] will get a separate instance of the underlying receiver.
 #[derive(Debug, Clone)]
-pub(crate) struct StopHandle(watch::Receiver<()>);
+pub(crate) struct StopHandle {
+	rx: watch::Receiver<()>,
+	shutdown: Arc<ShutdownState>,
+}
 
 impl StopHandle {
-	pub(crate) fn new(rx: watch::Receiver<()>) -> Self {
-		Self(rx)
-	}
-
 	/// A future that resolves when server has been stopped
 	/// it consumes the stop handle.
 	pub(crate) async fn shutdown(mut self) {
-		let _ = self.0.changed().await;
+		let _ = self.rx.changed().await;
+	}
+
+	/// Returns whether the server is shutting down gracefully and
+	/// open subscriptions should be waited for.
+	pub(crate) fn is_graceful(&self) -> bool {
+		self.shutdown.graceful.load(Ordering::SeqCst)
+	}
+
+	/// A future that resolves when the graceful shutdown deadline has been hit.
+	pub(crate) async fn aborted(&self) {
+		self.shutdown.abort.cancelled().await
+	}
+
+	/// Run a call until it completes or the graceful shutdown deadline has been hit.
+	///
+	/// Returns `None` if the call was aborted.
+	///
+	/// The returned future doesn't keep the connection alive, calls which outlive their connection
+	/// are still waited for by [`ServerHandle::stop_graceful`] and counted if they are aborted.
+	pub(crate) fn run_call<F: Future>(&self, call: F) -> impl Future<Output = Option<F::Output>> {
+		let shutdown = self.shutdown.clone();
+		let in_flight = InFlightCall::new(shutdown.clone());
+
+		async move {
+			let _in_flight = in_flight;
+			let aborted = shutdown.abort.cancelled();
+			tokio::pin!(call, aborted);
+
+			match future::select(call, aborted).await {
+				Either::Left((output, _)) => Some(output),
+				Either::Right(_) => {
+					shutdown.aborted.fetch_add(1, Ordering::SeqCst);
+					None
+				}
+			}
+		}
 	}
 }
 
@@ -52,48 +154,147 @@ impl StopHandle {
 /// When all [`StopHandle`]'s have been `dropped` or `stop` has been called
 /// the server will be stopped.
 #[derive(Debug, Clone)]
-pub struct ServerHandle(Arc<watch::Sender<()>>);
+pub struct ServerHandle {
+	tx: Arc<watch::Sender<()>>,
+	shutdown: Arc<ShutdownState>,
+	methods: SharedMethods,
+}
 
 impl ServerHandle {
 	/// Create a new server handle.
+	///
+	/// The methods of a handle created this way are not used by any server.
 	pub fn new(tx: watch::Sender<()>) -> Self {
-		Self(Arc::new(tx))
+		Self { tx: Arc::new(tx), shutdown: Default::default(), methods: Default::default() }
+	}
+
+	/// Get the methods that are currently registered on the server.
+	pub fn methods(&self) -> Methods {
+		self.methods.load()
+	}
+
+	/// Atomically replace the methods of the running server.
+	///
+	/// New connections and calls use the new methods right away, while calls that are already
+	/// running and open subscriptions are not affected. Existing subscriptions can only be unsubscribed
+	/// if the unsubscribe method of the module that created them is part of the new methods.
+	pub fn replace_methods(&self, methods: impl Into<Methods>) {
+		self.methods.replace(methods.into());
+	}
+
+	/// Atomically merge methods into the methods of the running server.
+	///
+	/// Fails and leaves the registered methods untouched if any of the methods is registered already.
+	pub fn merge_methods(&self, methods: impl Into<Methods>) -> Result<(), Error> {
+		self.methods.merge(methods.into())
 	}
 
 	/// Tell the server to stop without waiting for the server to stop.
 	pub fn stop(&self) -> Result<(), Error> {
-		self.0.send(()).map_err(|_| Error::AlreadyStopped)
+		self.tx.send(()).map_err(|_| Error::AlreadyStopped)
+	}
+
+	/// Stop the server gracefully and wait until it has been stopped.
+	///
+	/// The server stops accepting new connections and lets the in-flight calls finish, including the calls
+	/// of connections which were closed in the meantime.
+	/// Open subscriptions are closed, see [`jsonrpsee_core::server::SubscriptionSink::closed`], such that the
+	/// subscriptions may send out a final close notification before their connection is terminated.
+	///
+	/// Calls that are still running when `timeout` elapses are aborted and
+	/// the remaining connections are closed.
+	///
+	/// Returns the number of aborted calls.
+	pub async fn stop_graceful(&self, timeout: Duration) -> Result<usize, Error> {
+		self.shutdown.graceful.store(true, Ordering::SeqCst);
+		self.stop()?;
+
+		let stopped = async {
+			self.tx.closed().await;
+			self.shutdown.calls_finished().await;
+		};
+
+		// The aborted calls finish right away, they are all counted once no calls are running.
+		if tokio::time::timeout(timeout, stopped).await.is_err() {
+			self.shutdown.abort.cancel();
+			self.tx.closed().await;
+			self.shutdown.calls_finished().await;
+		}
+
+		Ok(self.shutdown.aborted.load(Ordering::SeqCst))
 	}
 
 	/// Wait for the server to stop.
 	pub async fn stopped(self) {
-		self.0.closed().await
+		self.tx.closed().await
 	}
 
 	/// Check if the server has been stopped.
 	pub fn is_stopped(&self) -> bool {
-		self.0.is_closed()
+		self.tx.is_closed()
 	}
 }
 
-/// Limits the number of connections.
+/// Limits the number of connections, in total and optionally per IP address.
 #[derive(Debug)]
-pub(crate) struct ConnectionGuard(Arc<Semaphore>);
+pub(crate) struct ConnectionGuard {
+	permits: Arc<Semaphore>,
+	per_ip: Option<(u32, ConnectionsPerIp)>,
+}
+
+type ConnectionsPerIp = Arc<Mutex<HashMap<IpAddr, u32>>>;
 
 impl ConnectionGuard {
-	pub(crate) fn new(limit: usize) -> Self {
-		Self(Arc::new(Semaphore::new(limit)))
+	pub(crate) fn new(limit: usize, max_per_ip: Option<u32>) -> Self {
+		Self { permits: Arc::new(Semaphore::new(limit)), per_ip: max_per_ip.map(|max| (max, Default::default())) }
 	}
 
-	pub(crate) fn try_acquire(&self) -> Option<OwnedSemaphorePermit> {
-		match self.0.clone().try_acquire_owned() {
-			Ok(guard) => Some(guard),
+	/// Acquire a permit for a connection from `ip`, which isn't limited per IP address if it's `None`.
+	pub(crate) fn try_acquire(&self, ip: Option<IpAddr>) -> Option<ConnectionPermit> {
+		let permit = match self.permits.clone().try_acquire_owned() {
+			Ok(guard) => guard,
 			Err(TryAcquireError::Closed) => unreachable!("Semaphore::Close is never called and can't be closed; qed"),
-			Err(TryAcquireError::NoPermits) => None,
-		}
+			Err(TryAcquireError::NoPermits) => return None,
+		};
+
+		let per_ip = match (&self.per_ip, ip) {
+			(Some((max, conns)), Some(ip)) => {
+				let mut conns_guard = conns.lock().expect("lock poisoned; qed");
+				let count = conns_guard.entry(ip).or_insert(0);
+				if *count >= *max {
+					return None;
+				}
+				*count += 1;
+				Some((conns.clone(), ip))
+			}
+			_ => None,
+		};
+
+		Some(ConnectionPermit { _permit: permit, per_ip })
 	}
 
 	pub(crate) fn available_connections(&self) -> usize {
-		self.0.available_permits()
+		self.permits.available_permits()
+	}
+}
+
+/// Permit of an open connection, it's released when dropped.
+#[derive(Debug)]
+pub(crate) struct ConnectionPermit {
+	_permit: OwnedSemaphorePermit,
+	per_ip: Option<(ConnectionsPerIp, IpAddr)>,
+}
+
+impl Drop for ConnectionPermit {
+	fn drop(&mut self) {
+		let Some((conns, ip)) = &self.per_ip else { return };
+		let mut conns = conns.lock().expect("lock poisoned; qed");
+
+		if let Some(count) = conns.get_mut(ip) {
+			*count -= 1;
+			if *count == 0 {
+				conns.remove(ip);
+			}
+		}
 	}
 }
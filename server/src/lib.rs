@@ -32,9 +32,13 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 mod future;
+mod rate_limit;
 mod server;
 mod transport;
 
+#[cfg(feature = "tls")]
+mod tls;
+
 pub mod logger;
 pub mod middleware;
 
@@ -42,8 +46,22 @@ pub mod middleware;
 mod tests;
 
 pub use future::ServerHandle;
+pub use hyper::http::{HeaderMap, HeaderValue, Uri};
 pub use jsonrpsee_core::server::*;
+#[cfg(feature = "ws-deflate")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ws-deflate")))]
+pub use jsonrpsee_core::ws_deflate::WsDeflateConfig;
 pub use jsonrpsee_core::{id_providers::*, traits::IdProvider};
 pub use jsonrpsee_types as types;
-pub use server::{BatchRequestConfig, Builder as ServerBuilder, PingConfig, Server};
+pub use rate_limit::{RateLimit, RateLimits};
+pub use server::{
+	BatchRequestConfig, Builder as ServerBuilder, ConcurrentCallsPolicy, ConnectionDetails, PingConfig, Server,
+};
 pub use tracing;
+
+#[cfg(feature = "tls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+pub use tls::PeerCertificates;
+#[cfg(feature = "tls")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+pub use tokio_rustls::rustls;
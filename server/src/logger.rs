@@ -28,12 +28,20 @@
 
 use std::net::SocketAddr;
 
+#[cfg(feature = "prometheus")]
+mod prometheus;
+#[cfg(feature = "prometheus")]
+#[cfg_attr(docsrs, doc(cfg(feature = "prometheus")))]
+pub use self::prometheus::PrometheusLogger;
+
 /// HTTP request.
 pub type HttpRequest = hyper::Request<Body>;
 pub use hyper::{Body, HeaderMap as Headers};
 pub use jsonrpsee_core::server::helpers::MethodResponseResult as SuccessOrError;
 pub use jsonrpsee_types::Params;
 
+pub use crate::server::ConcurrentCallsPolicy;
+
 /// The type JSON-RPC v2 call, it can be a subscription, method call or unknown.
 #[derive(Debug, Copy, Clone)]
 pub enum MethodKind {
@@ -43,6 +51,8 @@ pub enum MethodKind {
 	Unsubscription,
 	/// Method call.
 	MethodCall,
+	/// Notification.
+	Notification,
 	/// Unknown method.
 	Unknown,
 }
@@ -52,6 +62,7 @@ impl std::fmt::Display for MethodKind {
 		let s = match self {
 			Self::Subscription => "subscription",
 			Self::MethodCall => "method call",
+			Self::Notification => "notification",
 			Self::Unknown => "unknown",
 			Self::Unsubscription => "unsubscription",
 		};
@@ -113,6 +124,15 @@ pub trait Logger: Send + Sync + Clone + 'static {
 
 	/// Called when a client disconnects
 	fn on_disconnect(&self, _remote_addr: SocketAddr, transport: TransportProtocol);
+
+	/// Called when a subscription slot is acquired on a connection.
+	fn on_subscription_open(&self, _transport: TransportProtocol) {}
+
+	/// Called when a subscription slot is released, i.e. the subscription was closed or rejected.
+	fn on_subscription_close(&self, _transport: TransportProtocol) {}
+
+	/// Called when a message arrives on a connection that has reached its maximum number of concurrent calls.
+	fn on_concurrent_calls_limit(&self, _policy: ConcurrentCallsPolicy, _transport: TransportProtocol) {}
 }
 
 impl Logger for () {
@@ -172,4 +192,19 @@ where
 		self.0.on_disconnect(remote_addr, transport);
 		self.1.on_disconnect(remote_addr, transport);
 	}
+
+	fn on_subscription_open(&self, transport: TransportProtocol) {
+		self.0.on_subscription_open(transport);
+		self.1.on_subscription_open(transport);
+	}
+
+	fn on_subscription_close(&self, transport: TransportProtocol) {
+		self.0.on_subscription_close(transport);
+		self.1.on_subscription_close(transport);
+	}
+
+	fn on_concurrent_calls_limit(&self, policy: ConcurrentCallsPolicy, transport: TransportProtocol) {
+		self.0.on_concurrent_calls_limit(policy, transport);
+		self.1.on_concurrent_calls_limit(policy, transport);
+	}
 }
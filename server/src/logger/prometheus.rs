@@ -0,0 +1,155 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Prometheus metrics for `jsonrpsee` servers.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use jsonrpsee_types::error::METHOD_NOT_FOUND_CODE;
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry};
+
+use super::{HttpRequest, Logger, MethodKind, Params, SuccessOrError, TransportProtocol};
+
+/// Label used for calls to methods that are not registered on the server.
+///
+/// The method name of such calls is controlled by the client and is therefore not used
+/// as label to keep the cardinality of the metrics bounded.
+const UNKNOWN_METHOD: &str = "unknown";
+
+/// [`Logger`] that records the server activity as Prometheus metrics.
+///
+/// The following metrics are registered:
+///
+/// - `jsonrpsee_calls_total{transport, method}`: number of completed method calls.
+/// - `jsonrpsee_call_errors_total{transport, method, code}`: number of method calls that failed,
+///   by JSON-RPC error code.
+/// - `jsonrpsee_call_duration_seconds{transport, method}`: histogram of the method call latencies.
+/// - `jsonrpsee_open_connections{transport}`: number of open connections. For HTTP this is
+///   the number of requests currently being processed.
+/// - `jsonrpsee_active_subscriptions{transport}`: number of active subscriptions.
+///
+/// Use [`PrometheusMetricsLayer`](crate::middleware::PrometheusMetricsLayer) to expose the
+/// metrics of the registry on the server itself.
+#[derive(Debug, Clone)]
+pub struct PrometheusLogger(Arc<Metrics>);
+
+#[derive(Debug)]
+struct Metrics {
+	calls: IntCounterVec,
+	call_errors: IntCounterVec,
+	call_duration: HistogramVec,
+	open_connections: IntGaugeVec,
+	active_subscriptions: IntGaugeVec,
+}
+
+impl PrometheusLogger {
+	/// Create a new [`PrometheusLogger`] and register its metrics in `registry`.
+	///
+	/// Fails if the metrics are already registered in `registry`.
+	pub fn new(registry: &Registry) -> Result<Self, prometheus::Error> {
+		let metrics = Metrics {
+			calls: IntCounterVec::new(
+				Opts::new("jsonrpsee_calls_total", "Number of completed JSON-RPC method calls"),
+				&["transport", "method"],
+			)?,
+			call_errors: IntCounterVec::new(
+				Opts::new("jsonrpsee_call_errors_total", "Number of JSON-RPC method calls that returned an error"),
+				&["transport", "method", "code"],
+			)?,
+			call_duration: HistogramVec::new(
+				HistogramOpts::new("jsonrpsee_call_duration_seconds", "Latency of JSON-RPC method calls"),
+				&["transport", "method"],
+			)?,
+			open_connections: IntGaugeVec::new(
+				Opts::new("jsonrpsee_open_connections", "Number of open connections"),
+				&["transport"],
+			)?,
+			active_subscriptions: IntGaugeVec::new(
+				Opts::new("jsonrpsee_active_subscriptions", "Number of active subscriptions"),
+				&["transport"],
+			)?,
+		};
+
+		registry.register(Box::new(metrics.calls.clone()))?;
+		registry.register(Box::new(metrics.call_errors.clone()))?;
+		registry.register(Box::new(metrics.call_duration.clone()))?;
+		registry.register(Box::new(metrics.open_connections.clone()))?;
+		registry.register(Box::new(metrics.active_subscriptions.clone()))?;
+
+		Ok(Self(Arc::new(metrics)))
+	}
+}
+
+impl Logger for PrometheusLogger {
+	type Instant = Instant;
+
+	fn on_connect(&self, _: SocketAddr, _: &HttpRequest, transport: TransportProtocol) {
+		self.0.open_connections.with_label_values(&[&transport.to_string()]).inc();
+	}
+
+	fn on_request(&self, _: TransportProtocol) -> Self::Instant {
+		Instant::now()
+	}
+
+	fn on_call(&self, _: &str, _: Params, _: MethodKind, _: TransportProtocol) {}
+
+	fn on_result(
+		&self,
+		method_name: &str,
+		success_or_error: SuccessOrError,
+		started_at: Self::Instant,
+		transport: TransportProtocol,
+	) {
+		let transport = transport.to_string();
+		let method = match success_or_error {
+			SuccessOrError::Failed(METHOD_NOT_FOUND_CODE) => UNKNOWN_METHOD,
+			_ => method_name,
+		};
+
+		self.0.calls.with_label_values(&[&transport, method]).inc();
+		self.0.call_duration.with_label_values(&[&transport, method]).observe(started_at.elapsed().as_secs_f64());
+
+		if let SuccessOrError::Failed(code) = success_or_error {
+			self.0.call_errors.with_label_values(&[&transport, method, &code.to_string()]).inc();
+		}
+	}
+
+	fn on_response(&self, _: &str, _: Self::Instant, _: TransportProtocol) {}
+
+	fn on_disconnect(&self, _: SocketAddr, transport: TransportProtocol) {
+		self.0.open_connections.with_label_values(&[&transport.to_string()]).dec();
+	}
+
+	fn on_subscription_open(&self, transport: TransportProtocol) {
+		self.0.active_subscriptions.with_label_values(&[&transport.to_string()]).inc();
+	}
+
+	fn on_subscription_close(&self, transport: TransportProtocol) {
+		self.0.active_subscriptions.with_label_values(&[&transport.to_string()]).dec();
+	}
+}
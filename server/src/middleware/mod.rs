@@ -30,9 +30,17 @@
 mod authority;
 /// HTTP Host filtering middleware.
 mod host_filter;
+/// Serve Prometheus metrics at `GET /path`.
+#[cfg(feature = "prometheus")]
+mod prometheus;
 /// Proxy `GET /path` to internal RPC methods.
 mod proxy_get_request;
+/// JSON-RPC specific middleware.
+pub mod rpc;
 
+#[cfg(feature = "prometheus")]
+#[cfg_attr(docsrs, doc(cfg(feature = "prometheus")))]
+pub use self::prometheus::*;
 pub use authority::*;
 pub use host_filter::*;
 pub use proxy_get_request::*;
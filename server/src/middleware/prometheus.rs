@@ -0,0 +1,129 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Middleware that serves the metrics of a Prometheus registry.
+
+use crate::transport::http;
+use hyper::{Body, Method, Request, Response};
+use jsonrpsee_core::error::Error as RpcError;
+use prometheus::{Encoder, Registry, TextEncoder};
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Layer that applies [`PrometheusMetrics`] which serves the metrics of a registry at `GET /path`.
+///
+/// See [`PrometheusMetrics`] for more details.
+#[derive(Debug, Clone)]
+pub struct PrometheusMetricsLayer {
+	path: String,
+	registry: Registry,
+}
+
+impl PrometheusMetricsLayer {
+	/// Creates a new [`PrometheusMetricsLayer`].
+	///
+	/// See [`PrometheusMetrics`] for more details.
+	pub fn new(path: impl Into<String>, registry: Registry) -> Result<Self, RpcError> {
+		let path = path.into();
+		if !path.starts_with('/') {
+			return Err(RpcError::Custom("PrometheusMetricsLayer path must start with `/`".to_string()));
+		}
+
+		Ok(Self { path, registry })
+	}
+}
+
+impl<S> Layer<S> for PrometheusMetricsLayer {
+	type Service = PrometheusMetrics<S>;
+
+	fn layer(&self, inner: S) -> Self::Service {
+		PrometheusMetrics::new(inner, &self.path, self.registry.clone())
+			.expect("Path already validated in PrometheusMetricsLayer; qed")
+	}
+}
+
+/// Serve the metrics of a Prometheus [`Registry`] at `GET /path`.
+///
+/// The metrics are encoded in the Prometheus text exposition format and all other
+/// requests are passed on to the inner service.
+#[derive(Debug, Clone)]
+pub struct PrometheusMetrics<S> {
+	inner: S,
+	path: Arc<str>,
+	registry: Registry,
+}
+
+impl<S> PrometheusMetrics<S> {
+	/// Creates a new [`PrometheusMetrics`].
+	///
+	/// Fails if the path does not start with `/`.
+	pub fn new(inner: S, path: &str, registry: Registry) -> Result<Self, RpcError> {
+		if !path.starts_with('/') {
+			return Err(RpcError::Custom(format!("PrometheusMetrics path must start with `/`, got: {path}")));
+		}
+
+		Ok(Self { inner, path: Arc::from(path), registry })
+	}
+}
+
+impl<S> Service<Request<Body>> for PrometheusMetrics<S>
+where
+	S: Service<Request<Body>, Response = Response<Body>>,
+	S::Response: 'static,
+	S::Error: Into<Box<dyn Error + Send + Sync>> + 'static,
+	S::Future: Send + 'static,
+{
+	type Response = S::Response;
+	type Error = Box<dyn Error + Send + Sync + 'static>;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+	#[inline]
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(cx).map_err(Into::into)
+	}
+
+	fn call(&mut self, req: Request<Body>) -> Self::Future {
+		if self.path.as_ref() != req.uri() || req.method() != Method::GET {
+			let fut = self.inner.call(req);
+			return Box::pin(async move { fut.await.map_err(Into::into) });
+		}
+
+		let mut buf = Vec::new();
+		let response = match TextEncoder::new().encode(&self.registry.gather(), &mut buf) {
+			Ok(()) => http::response::prometheus_metrics(buf),
+			Err(err) => {
+				tracing::warn!("Failed to encode Prometheus metrics: {err}");
+				http::response::internal_error()
+			}
+		};
+
+		Box::pin(async move { Ok(response) })
+	}
+}
@@ -0,0 +1,289 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! JSON-RPC specific middleware.
+//!
+//! In contrast to the HTTP middleware configured by
+//! [`ServerBuilder::set_middleware`](crate::ServerBuilder::set_middleware) which operates on the raw HTTP request,
+//! the RPC middleware is invoked for every single JSON-RPC call regardless of the transport
+//! and for each call in a batch request.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::rate_limit::ConnectionRateLimiter;
+use crate::server::SharedMethods;
+use futures_util::future::{BoxFuture, FutureExt};
+use jsonrpsee_core::server::helpers::{MethodResponse, MethodResponseResult, MethodSink};
+use jsonrpsee_core::server::{
+	AccessPolicy, BoundedSubscriptions, ConnectionId, Encoding, MethodCallback, SubscriptionState,
+};
+use jsonrpsee_core::traits::IdProvider;
+use jsonrpsee_types::error::{reject_call_timeout, reject_rate_limited, reject_too_many_subscriptions, ErrorCode};
+use jsonrpsee_types::{ErrorObject, Params, Request};
+use tower::layer::util::{Identity, Stack};
+use tower::layer::LayerFn;
+
+/// Similar to the [`tower::Service`] but specific for jsonrpsee and
+/// doesn't require `&mut self` for performance reasons.
+pub trait RpcServiceT<'a> {
+	/// The future response value.
+	type Future: Future<Output = MethodResponse> + Send;
+
+	/// Process a single JSON-RPC call, it may be a subscription or regular call.
+	///
+	/// In this interface, both are treated in the same way but it's possible to
+	/// distinguish those based on the [`MethodResponse::is_subscription`].
+	///
+	/// Notifications are processed as calls with a null ID too, their response is discarded.
+	fn call(&self, request: Request<'a>) -> Self::Future;
+}
+
+/// Similar to [`tower::ServiceBuilder`] but doesn't
+/// support any tower middleware implementations.
+#[derive(Debug, Clone)]
+pub struct RpcServiceBuilder<L>(tower::ServiceBuilder<L>);
+
+impl Default for RpcServiceBuilder<Identity> {
+	fn default() -> Self {
+		RpcServiceBuilder(tower::ServiceBuilder::new())
+	}
+}
+
+impl RpcServiceBuilder<Identity> {
+	/// Create a new [`RpcServiceBuilder`].
+	pub fn new() -> Self {
+		Self::default()
+	}
+}
+
+impl<L> RpcServiceBuilder<L> {
+	/// Add a new layer `T` into the [`RpcServiceBuilder`].
+	///
+	/// See [`tower::ServiceBuilder::layer`] for more details.
+	pub fn layer<T>(self, layer: T) -> RpcServiceBuilder<Stack<T, L>> {
+		RpcServiceBuilder(self.0.layer(layer))
+	}
+
+	/// Add a [`tower::Layer`] built from a function that accepts a service and returns another service.
+	///
+	/// See the documentation for [`tower::ServiceBuilder::layer_fn`] for more details.
+	pub fn layer_fn<F>(self, f: F) -> RpcServiceBuilder<Stack<LayerFn<F>, L>> {
+		RpcServiceBuilder(self.0.layer_fn(f))
+	}
+
+	/// Wrap the service `S` with the middleware.
+	pub(crate) fn service<S>(&self, service: S) -> L::Service
+	where
+		L: tower::Layer<S>,
+	{
+		self.0.service(service)
+	}
+}
+
+/// JSON-RPC service middleware.
+#[derive(Clone, Debug)]
+pub struct RpcService {
+	conn_id: ConnectionId,
+	methods: SharedMethods,
+	max_response_body_size: usize,
+	default_call_timeout: Option<Duration>,
+	access_policy: AccessPolicy,
+	rate_limiter: Option<ConnectionRateLimiter>,
+	cfg: RpcServiceCfg,
+}
+
+/// Marks a request as a notification, to which no response is sent.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct IsNotification;
+
+/// Configuration of the RpcService.
+#[derive(Clone, Debug)]
+pub(crate) enum RpcServiceCfg {
+	/// The server supports only calls.
+	OnlyCalls,
+	/// The server supports both method calls and subscriptions.
+	CallsAndSubscriptions {
+		bounded_subscriptions: BoundedSubscriptions,
+		sink: MethodSink,
+		id_provider: Arc<dyn IdProvider>,
+	},
+}
+
+impl RpcService {
+	/// Create a new service.
+	pub(crate) fn new(
+		methods: SharedMethods,
+		max_response_body_size: usize,
+		conn_id: ConnectionId,
+		default_call_timeout: Option<Duration>,
+		access_policy: AccessPolicy,
+		rate_limiter: Option<ConnectionRateLimiter>,
+		cfg: RpcServiceCfg,
+	) -> Self {
+		Self { methods, max_response_body_size, conn_id, default_call_timeout, access_policy, rate_limiter, cfg }
+	}
+}
+
+impl<'a> RpcServiceT<'a> for RpcService {
+	type Future = BoxFuture<'a, MethodResponse>;
+
+	fn call(&self, req: Request<'a>) -> Self::Future {
+		let conn_id = self.conn_id;
+		let max_response_body_size = self.max_response_body_size;
+
+		let params = match req.decoded_params {
+			Some(params) => params,
+			None => Params::new(req.params.map(|params| params.get())),
+		};
+		let name = &req.method;
+		let id = req.id;
+		let mut extensions = req.extensions;
+		let encoding = Encoding::from_extensions(&extensions);
+
+		let methods = self.methods.load();
+		let is_notification = extensions.get::<IsNotification>().is_some();
+
+		if let Err(err) = self.access_policy.check(&methods, name, &extensions) {
+			return futures_util::future::ready(MethodResponse::encoded_error(id, err, encoding)).boxed();
+		}
+		extensions.insert(self.access_policy.clone());
+		extensions.insert(methods.clone());
+
+		if let Some(Err(retry_after)) = self.rate_limiter.as_ref().map(|limiter| limiter.check_call(name)) {
+			return futures_util::future::ready(MethodResponse::encoded_error(
+				id,
+				reject_rate_limited(retry_after),
+				encoding,
+			))
+			.boxed();
+		}
+
+		match methods.method_with_name(name) {
+			// The response to a notification is discarded, only notification handlers are called.
+			Some((_name, MethodCallback::Notification(callback))) if is_notification => {
+				let fut = (callback)(params, extensions);
+				async move {
+					fut.await;
+					notification_response()
+				}
+				.boxed()
+			}
+			_ if is_notification => futures_util::future::ready(notification_response()).boxed(),
+			None => {
+				let rp = MethodResponse::encoded_error(id, ErrorObject::from(ErrorCode::MethodNotFound), encoding);
+				futures_util::future::ready(rp).boxed()
+			}
+			Some((_name, method)) => match method {
+				MethodCallback::Sync(callback) => {
+					let rp = (callback)(id, params, max_response_body_size, extensions);
+					futures_util::future::ready(rp).boxed()
+				}
+				MethodCallback::Async(callback) => {
+					let params = params.into_owned();
+					let id = id.into_owned();
+
+					// Methods that were registered with a timeout enforce it themselves.
+					match self.default_call_timeout.filter(|_| methods.method_timeout(name).is_none()) {
+						Some(timeout) => {
+							let fut = (callback)(id.clone(), params, conn_id, max_response_body_size, extensions);
+
+							async move {
+								match tokio::time::timeout(timeout, fut).await {
+									Ok(rp) => rp,
+									Err(_) => MethodResponse::encoded_error(id, reject_call_timeout(timeout), encoding),
+								}
+							}
+							.boxed()
+						}
+						None => (callback)(id, params, conn_id, max_response_body_size, extensions),
+					}
+				}
+				MethodCallback::Subscription(callback) => {
+					let RpcServiceCfg::CallsAndSubscriptions { bounded_subscriptions, sink, id_provider } = &self.cfg
+					else {
+						tracing::warn!("Subscriptions not supported");
+						let rp =
+							MethodResponse::encoded_error(id, ErrorObject::from(ErrorCode::InternalError), encoding);
+						return futures_util::future::ready(rp).boxed();
+					};
+
+					if let Some(p) = bounded_subscriptions.acquire() {
+						let conn_state =
+							SubscriptionState { conn_id, id_provider: &**id_provider, subscription_permit: p };
+
+						let fut = (callback)(id, params, sink.clone(), conn_state, extensions);
+
+						async move {
+							match fut.await {
+								Ok(mut rp) => {
+									rp.is_subscription = true;
+									rp
+								}
+								Err(id) => MethodResponse::encoded_error(
+									id,
+									ErrorObject::from(ErrorCode::InternalError),
+									encoding,
+								),
+							}
+						}
+						.boxed()
+					} else {
+						let max = bounded_subscriptions.max();
+						let rp = MethodResponse::encoded_error(id, reject_too_many_subscriptions(max), encoding);
+						futures_util::future::ready(rp).boxed()
+					}
+				}
+				MethodCallback::Unsubscription(callback) => {
+					// Don't adhere to any resource or subscription limits; always let unsubscribing happen!
+					let RpcServiceCfg::CallsAndSubscriptions { .. } = self.cfg else {
+						tracing::warn!("Subscriptions not supported");
+						let rp =
+							MethodResponse::encoded_error(id, ErrorObject::from(ErrorCode::InternalError), encoding);
+						return futures_util::future::ready(rp).boxed();
+					};
+
+					let rp = callback(id, params, conn_id, max_response_body_size, extensions);
+					futures_util::future::ready(rp).boxed()
+				}
+				// Notification handlers never produce a response and can't be called with a request.
+				MethodCallback::Notification(_) => {
+					let rp = MethodResponse::encoded_error(id, ErrorObject::from(ErrorCode::MethodNotFound), encoding);
+					futures_util::future::ready(rp).boxed()
+				}
+			},
+		}
+	}
+}
+
+fn notification_response() -> MethodResponse {
+	MethodResponse {
+		result: String::new().into(),
+		success_or_error: MethodResponseResult::Success,
+		is_subscription: false,
+	}
+}
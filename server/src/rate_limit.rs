@@ -0,0 +1,348 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Token bucket rate limits for the calls to the server.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Number of tracked keys before the buckets that are full again are pruned.
+const MIN_PRUNE_AT: usize = 1024;
+
+/// A token bucket rate limit: `num` calls are allowed in a burst and the bucket is refilled at
+/// `num` calls per `period`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RateLimit {
+	num: u32,
+	period: Duration,
+}
+
+impl RateLimit {
+	/// Allow `num` calls per `period`.
+	///
+	/// # Panics
+	///
+	/// Panics if `num` or `period` is zero.
+	pub fn new(num: u32, period: Duration) -> Self {
+		assert!(num > 0, "The rate limit must allow at least one call");
+		assert!(!period.is_zero(), "The rate limit period must not be zero");
+		Self { num, period }
+	}
+
+	/// Allow `num` calls per second.
+	pub fn per_second(num: u32) -> Self {
+		Self::new(num, Duration::from_secs(1))
+	}
+
+	/// Allow `num` calls per minute.
+	pub fn per_minute(num: u32) -> Self {
+		Self::new(num, Duration::from_secs(60))
+	}
+
+	fn capacity(&self) -> f64 {
+		self.num as f64
+	}
+
+	fn tokens_per_sec(&self) -> f64 {
+		self.num as f64 / self.period.as_secs_f64()
+	}
+}
+
+/// Rate limits of the server, configured with
+/// [`ServerBuilder::set_rate_limits`](crate::ServerBuilder::set_rate_limits).
+///
+/// The limits are keyed by the IP address of the peer, by connection and optionally by method:
+///
+/// - The IP and connection limits are applied to every HTTP request and every WebSocket message,
+///   HTTP requests that exceed them are answered with `429 Too Many Requests` and a `Retry-After` header.
+/// - The method limits are applied to every call of the method by the same IP address, including the calls in a batch.
+///
+/// Connections on a Unix domain socket don't have an IP address, so only the connection limit applies to them.
+///
+/// Calls that exceed a limit are answered with the [`RATE_LIMITED_CODE`](crate::types::error::RATE_LIMITED_CODE)
+/// error, whose data holds the number of milliseconds to wait before trying again.
+///
+/// ```
+/// use jsonrpsee_server::{RateLimit, RateLimits};
+///
+/// let limits = RateLimits::new()
+///     .per_ip(RateLimit::per_second(100))
+///     .per_connection(RateLimit::per_second(20))
+///     .per_method("state_expensive", RateLimit::per_minute(10));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RateLimits {
+	per_ip: Option<RateLimit>,
+	per_connection: Option<RateLimit>,
+	per_method: HashMap<String, RateLimit>,
+}
+
+impl RateLimits {
+	/// Create rate limits that don't limit anything.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Limit the calls from each IP address, over all its connections.
+	pub fn per_ip(mut self, limit: RateLimit) -> Self {
+		self.per_ip = Some(limit);
+		self
+	}
+
+	/// Limit the calls on each connection.
+	pub fn per_connection(mut self, limit: RateLimit) -> Self {
+		self.per_connection = Some(limit);
+		self
+	}
+
+	/// Limit the calls from each IP address to the method `method`.
+	pub fn per_method(mut self, method: impl Into<String>, limit: RateLimit) -> Self {
+		self.per_method.insert(method.into(), limit);
+		self
+	}
+
+	fn is_empty(&self) -> bool {
+		self.per_ip.is_none() && self.per_connection.is_none() && self.per_method.is_empty()
+	}
+}
+
+/// Rate limiter shared by all connections of a server.
+#[derive(Debug, Clone)]
+pub(crate) struct RateLimiter(Arc<RateLimiterInner>);
+
+#[derive(Debug)]
+struct RateLimiterInner {
+	per_ip: Option<(RateLimit, Mutex<Buckets<IpAddr>>)>,
+	per_connection: Option<RateLimit>,
+	per_method: HashMap<String, (RateLimit, Mutex<Buckets<IpAddr>>)>,
+}
+
+impl RateLimiter {
+	/// Create a rate limiter, returns `None` if the limits don't limit anything.
+	pub(crate) fn new(limits: RateLimits) -> Option<Self> {
+		if limits.is_empty() {
+			return None;
+		}
+
+		Some(Self(Arc::new(RateLimiterInner {
+			per_ip: limits.per_ip.map(|limit| (limit, Mutex::default())),
+			per_connection: limits.per_connection,
+			per_method: limits.per_method.into_iter().map(|(name, limit)| (name, (limit, Mutex::default()))).collect(),
+		})))
+	}
+
+	/// Create the rate limiter of a new connection from `ip`, the IP and method limits don't apply if it's `None`.
+	pub(crate) fn connection(&self, ip: Option<IpAddr>) -> ConnectionRateLimiter {
+		let conn = self.0.per_connection.map(|limit| Arc::new(Mutex::new(TokenBucket::full(&limit, Instant::now()))));
+		ConnectionRateLimiter { limiter: self.clone(), ip, conn }
+	}
+}
+
+/// Rate limiter of a single connection.
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectionRateLimiter {
+	limiter: RateLimiter,
+	ip: Option<IpAddr>,
+	conn: Option<Arc<Mutex<TokenBucket>>>,
+}
+
+impl ConnectionRateLimiter {
+	/// Take a token for an HTTP request or a WebSocket message, returns how long to wait before
+	/// trying again if a limit was exceeded.
+	///
+	/// The tokens are only taken if neither the connection nor the IP limit is exceeded.
+	pub(crate) fn check_request(&self) -> Result<(), Duration> {
+		let now = Instant::now();
+		let inner = &self.limiter.0;
+
+		let mut conn = match (&inner.per_connection, &self.conn) {
+			(Some(limit), Some(bucket)) => Some((limit, bucket.lock().expect("lock poisoned; qed"))),
+			_ => None,
+		};
+		let mut ip = match (&inner.per_ip, self.ip) {
+			(Some((limit, buckets)), Some(ip)) => Some((limit, ip, buckets.lock().expect("lock poisoned; qed"))),
+			_ => None,
+		};
+
+		let conn_check = conn.as_mut().map_or(Ok(()), |(limit, bucket)| bucket.check(limit, now));
+		let ip_check =
+			ip.as_mut().map_or(Ok(()), |(limit, ip, buckets)| buckets.get(*ip, limit, now).check(limit, now));
+		match (conn_check, ip_check) {
+			(Ok(()), Ok(())) => (),
+			(Err(wait), Ok(())) | (Ok(()), Err(wait)) => return Err(wait),
+			(Err(conn_wait), Err(ip_wait)) => return Err(conn_wait.max(ip_wait)),
+		}
+
+		if let Some((limit, bucket)) = conn.as_mut() {
+			bucket.take(limit, now)?;
+		}
+		if let Some((limit, ip, buckets)) = ip.as_mut() {
+			buckets.get(*ip, limit, now).take(limit, now)?;
+		}
+
+		Ok(())
+	}
+
+	/// Take a token for a call to `method`, returns how long to wait before trying again if its limit was exceeded.
+	pub(crate) fn check_call(&self, method: &str) -> Result<(), Duration> {
+		match (self.limiter.0.per_method.get(method), self.ip) {
+			(Some((limit, buckets)), Some(ip)) => {
+				buckets.lock().expect("lock poisoned; qed").take(ip, limit, Instant::now())
+			}
+			_ => Ok(()),
+		}
+	}
+}
+
+/// Token buckets keyed by `K`, the buckets that are full again are pruned once there are many of them.
+#[derive(Debug)]
+struct Buckets<K> {
+	buckets: HashMap<K, TokenBucket>,
+	prune_at: usize,
+}
+
+impl<K> Default for Buckets<K> {
+	fn default() -> Self {
+		Self { buckets: HashMap::new(), prune_at: MIN_PRUNE_AT }
+	}
+}
+
+impl<K: Hash + Eq> Buckets<K> {
+	fn take(&mut self, key: K, limit: &RateLimit, now: Instant) -> Result<(), Duration> {
+		self.get(key, limit, now).take(limit, now)
+	}
+
+	/// Get the bucket of `key`, which is full if it's new.
+	fn get(&mut self, key: K, limit: &RateLimit, now: Instant) -> &mut TokenBucket {
+		if self.buckets.len() >= self.prune_at {
+			self.buckets.retain(|_, bucket| !bucket.is_full(limit, now));
+			self.prune_at = MIN_PRUNE_AT.max(self.buckets.len() * 2);
+		}
+
+		self.buckets.entry(key).or_insert_with(|| TokenBucket::full(limit, now))
+	}
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+	tokens: f64,
+	updated_at: Instant,
+}
+
+impl TokenBucket {
+	fn full(limit: &RateLimit, now: Instant) -> Self {
+		Self { tokens: limit.capacity(), updated_at: now }
+	}
+
+	fn refill(&mut self, limit: &RateLimit, now: Instant) {
+		let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+		self.tokens = limit.capacity().min(self.tokens + elapsed * limit.tokens_per_sec());
+		self.updated_at = now;
+	}
+
+	fn is_full(&self, limit: &RateLimit, now: Instant) -> bool {
+		let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+		self.tokens + elapsed * limit.tokens_per_sec() >= limit.capacity()
+	}
+
+	/// Check whether a token can be taken without taking it.
+	fn check(&mut self, limit: &RateLimit, now: Instant) -> Result<(), Duration> {
+		self.refill(limit, now);
+
+		if self.tokens >= 1.0 {
+			Ok(())
+		} else {
+			Err(Duration::from_secs_f64((1.0 - self.tokens) / limit.tokens_per_sec()))
+		}
+	}
+
+	fn take(&mut self, limit: &RateLimit, now: Instant) -> Result<(), Duration> {
+		self.check(limit, now)?;
+		self.tokens -= 1.0;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Buckets, RateLimit, RateLimiter, RateLimits, TokenBucket};
+	use std::net::{IpAddr, Ipv4Addr};
+	use std::time::{Duration, Instant};
+
+	#[test]
+	fn token_bucket_works() {
+		let limit = RateLimit::new(2, Duration::from_secs(1));
+		let now = Instant::now();
+		let mut bucket = TokenBucket::full(&limit, now);
+
+		assert!(bucket.take(&limit, now).is_ok());
+		assert!(bucket.take(&limit, now).is_ok());
+		assert_eq!(bucket.take(&limit, now), Err(Duration::from_millis(500)));
+
+		// Refilled at two tokens per second.
+		let later = now + Duration::from_millis(500);
+		assert!(bucket.take(&limit, later).is_ok());
+		assert!(bucket.take(&limit, later).is_err());
+		assert!(bucket.is_full(&limit, later + Duration::from_secs(1)));
+	}
+
+	#[test]
+	fn full_buckets_are_pruned() {
+		let limit = RateLimit::per_second(1);
+		let now = Instant::now();
+		let mut buckets = Buckets::default();
+
+		for key in 0..super::MIN_PRUNE_AT {
+			assert!(buckets.take(key, &limit, now).is_ok());
+		}
+
+		// All buckets are full again, so only the new one is kept.
+		let later = now + Duration::from_secs(1);
+		assert!(buckets.take(usize::MAX, &limit, later).is_ok());
+		assert_eq!(buckets.buckets.len(), 1);
+	}
+
+	#[test]
+	fn request_limits_are_checked_before_taking_tokens() {
+		let limits = RateLimits::new()
+			.per_ip(RateLimit::new(1, Duration::from_secs(60)))
+			.per_connection(RateLimit::new(2, Duration::from_secs(60)));
+		let limiter = RateLimiter::new(limits).unwrap();
+		let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+		let first = limiter.connection(Some(ip));
+		let second = limiter.connection(Some(ip));
+
+		assert!(first.check_request().is_ok());
+		assert!(second.check_request().is_err());
+
+		// The request exceeded the IP limit, so no token was taken from the connection bucket.
+		let conn_tokens = second.conn.as_ref().unwrap().lock().unwrap().tokens;
+		assert_eq!(conn_tokens, 2.0);
+	}
+}
@@ -28,13 +28,16 @@ use std::error::Error as StdError;
 use std::future::Future;
 use std::net::{SocketAddr, TcpListener as StdTcpListener};
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, PoisonError, RwLock};
 use std::task::{Context, Poll};
 use std::time::Duration;
 
-use crate::future::{ConnectionGuard, ServerHandle, StopHandle};
+use crate::future::{stop_channel, ConnectionGuard, ConnectionPermit, ServerHandle, StopHandle};
 use crate::logger::{Logger, TransportProtocol};
-use crate::transport::{http, ws};
+use crate::middleware::rpc::{RpcService, RpcServiceBuilder, RpcServiceT};
+use crate::rate_limit::{ConnectionRateLimiter, RateLimiter, RateLimits};
+use crate::transport::listener::{self, Listener, Socket};
+use crate::transport::{http, sse, ws};
 
 use futures_util::future::{self, Either, FutureExt};
 use futures_util::io::{BufReader, BufWriter};
@@ -42,13 +45,18 @@ use futures_util::io::{BufReader, BufWriter};
 use hyper::body::HttpBody;
 use jsonrpsee_core::id_providers::RandomIntegerIdProvider;
 
-use jsonrpsee_core::server::Methods;
+#[cfg(feature = "cbor")]
+use jsonrpsee_core::server::Encoding;
+use jsonrpsee_core::server::{AccessPolicy, ConnectionId, Extensions, Methods, Role};
 use jsonrpsee_core::traits::IdProvider;
+#[cfg(feature = "ws-deflate")]
+use jsonrpsee_core::ws_deflate::{PerMessageDeflate, WsDeflateConfig};
 use jsonrpsee_core::{Error, TEN_MB_SIZE_BYTES};
 
 use soketto::handshake::http::is_upgrade_request;
-use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
-use tokio::sync::{mpsc, watch, OwnedSemaphorePermit};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio::sync::mpsc;
 use tokio_util::compat::TokioAsyncReadCompatExt;
 use tower::layer::util::Identity;
 use tower::{Layer, Service};
@@ -58,12 +66,13 @@ use tracing::{instrument, Instrument};
 const MAX_CONNECTIONS: u32 = 100;
 
 /// JSON RPC server.
-pub struct Server<B = Identity, L = ()> {
-	listener: TcpListener,
+pub struct Server<B = Identity, L = (), R = Identity> {
+	listener: Listener,
 	cfg: Settings,
 	logger: L,
 	id_provider: Arc<dyn IdProvider>,
 	service_builder: tower::ServiceBuilder<B>,
+	rpc_middleware: RpcServiceBuilder<R>,
 }
 
 impl Server<Identity, ()> {
@@ -73,7 +82,7 @@ impl Server<Identity, ()> {
 	}
 }
 
-impl<L> std::fmt::Debug for Server<L> {
+impl<B, L, R> std::fmt::Debug for Server<B, L, R> {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		f.debug_struct("Server")
 			.field("listener", &self.listener)
@@ -83,24 +92,28 @@ impl<L> std::fmt::Debug for Server<L> {
 	}
 }
 
-impl<B, L> Server<B, L> {
+impl<B, L, R> Server<B, L, R> {
 	/// Returns socket address to which the server is bound.
+	///
+	/// Fails if the server is listening on a Unix domain socket.
 	pub fn local_addr(&self) -> Result<SocketAddr, Error> {
 		self.listener.local_addr().map_err(Into::into)
 	}
 }
 
-impl<S, B, L> Server<S, L>
+impl<S, B, L, R> Server<S, L, R>
 where
 	L: Logger,
-	S: Layer<TowerService<L>> + Send + 'static,
-	<S as Layer<TowerService<L>>>::Service: Send
+	R: Layer<RpcService> + Clone + Send + Sync + 'static,
+	for<'a> <R as Layer<RpcService>>::Service: RpcServiceT<'a> + Send + Sync + 'static,
+	S: Layer<TowerService<L, R>> + Send + 'static,
+	<S as Layer<TowerService<L, R>>>::Service: Send
 		+ Service<
 			hyper::Request<hyper::Body>,
 			Response = hyper::Response<B>,
 			Error = Box<(dyn StdError + Send + Sync + 'static)>,
 		>,
-	<<S as Layer<TowerService<L>>>::Service as Service<hyper::Request<hyper::Body>>>::Future: Send,
+	<<S as Layer<TowerService<L, R>>>::Service as Service<hyper::Request<hyper::Body>>>::Future: Send,
 	B: HttpBody + Send + 'static,
 	<B as HttpBody>::Error: Send + Sync + StdError,
 	<B as HttpBody>::Data: Send,
@@ -108,21 +121,22 @@ where
 	/// Start responding to connections requests.
 	///
 	/// This will run on the tokio runtime until the server is stopped or the `ServerHandle` is dropped.
+	///
+	/// The methods can be replaced at runtime with [`ServerHandle::replace_methods`]
+	/// and [`ServerHandle::merge_methods`].
 	pub fn start(mut self, methods: impl Into<Methods>) -> ServerHandle {
-		let methods = methods.into();
-		let (stop_tx, stop_rx) = watch::channel(());
-
-		let stop_handle = StopHandle::new(stop_rx);
+		let methods = SharedMethods::new(methods.into());
+		let (server_handle, stop_handle) = stop_channel(methods.clone());
 
 		match self.cfg.tokio_runtime.take() {
 			Some(rt) => rt.spawn(self.start_inner(methods, stop_handle)),
 			None => tokio::spawn(self.start_inner(methods, stop_handle)),
 		};
 
-		ServerHandle::new(stop_tx)
+		server_handle
 	}
 
-	async fn start_inner(self, methods: Methods, stop_handle: StopHandle) {
+	async fn start_inner(self, methods: SharedMethods, stop_handle: StopHandle) {
 		let max_request_body_size = self.cfg.max_request_body_size;
 		let max_response_body_size = self.cfg.max_response_body_size;
 		let max_log_length = self.cfg.max_log_length;
@@ -130,9 +144,11 @@ where
 		let logger = self.logger;
 		let batch_requests_config = self.cfg.batch_requests_config;
 		let id_provider = self.id_provider;
+		let rpc_middleware = self.rpc_middleware;
 
 		let mut id: u32 = 0;
-		let connection_guard = ConnectionGuard::new(self.cfg.max_connections as usize);
+		let connection_guard = ConnectionGuard::new(self.cfg.max_connections as usize, self.cfg.max_connections_per_ip);
+		let rate_limiter = RateLimiter::new(self.cfg.rate_limits.clone());
 		let listener = self.listener;
 
 		let stopped = stop_handle.clone().shutdown();
@@ -148,8 +164,12 @@ where
 						methods: methods.clone(),
 						max_request_body_size,
 						max_response_body_size,
+						http_compression_threshold: self.cfg.http_compression_threshold,
 						max_log_length,
 						max_subscriptions_per_connection,
+						max_concurrent_calls_per_connection: self.cfg.max_concurrent_calls_per_connection,
+						#[cfg(feature = "ws-deflate")]
+						ws_deflate: self.cfg.ws_deflate,
 						batch_requests_config,
 						id_provider: id_provider.clone(),
 						ping_config: self.cfg.ping_config,
@@ -160,6 +180,14 @@ where
 						enable_http: self.cfg.enable_http,
 						enable_ws: self.cfg.enable_ws,
 						message_buffer_capacity: self.cfg.message_buffer_capacity,
+						default_call_timeout: self.cfg.default_call_timeout,
+						access_policy: self.cfg.access_policy.clone(),
+						json_rpc_v1: self.cfg.json_rpc_v1,
+						enable_sse: self.cfg.enable_sse,
+						rate_limiter: rate_limiter.clone(),
+						rpc_middleware: rpc_middleware.clone(),
+						#[cfg(feature = "tls")]
+						tls: self.cfg.tls.clone(),
 					};
 
 					process_connection(
@@ -191,6 +219,33 @@ where
 	}
 }
 
+/// [`Methods`] of a running server which can be replaced at runtime.
+///
+/// Every call looks up the methods that are registered at the time of the call.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SharedMethods(Arc<RwLock<Methods>>);
+
+impl SharedMethods {
+	pub(crate) fn new(methods: Methods) -> Self {
+		Self(Arc::new(RwLock::new(methods)))
+	}
+
+	/// Get the currently registered methods.
+	pub(crate) fn load(&self) -> Methods {
+		self.0.read().unwrap_or_else(PoisonError::into_inner).clone()
+	}
+
+	/// Replace all registered methods.
+	pub(crate) fn replace(&self, methods: Methods) {
+		*self.0.write().unwrap_or_else(PoisonError::into_inner) = methods;
+	}
+
+	/// Merge `other` into the registered methods, fails if any of the methods is registered already.
+	pub(crate) fn merge(&self, other: Methods) -> Result<(), Error> {
+		self.0.write().unwrap_or_else(PoisonError::into_inner).merge(other)
+	}
+}
+
 /// JSON-RPC Websocket server settings.
 #[derive(Debug, Clone)]
 struct Settings {
@@ -198,14 +253,22 @@ struct Settings {
 	max_request_body_size: u32,
 	/// Maximum size in bytes of a response.
 	max_response_body_size: u32,
+	/// Minimum size in bytes of the HTTP responses that are compressed, if enabled.
+	http_compression_threshold: Option<u32>,
 	/// Maximum number of incoming connections allowed.
 	max_connections: u32,
+	/// Maximum number of incoming connections allowed from the same IP address.
+	max_connections_per_ip: Option<u32>,
+	/// Rate limits of the calls.
+	rate_limits: RateLimits,
 	/// Max length for logging for requests and responses
 	///
 	/// Logs bigger than this limit will be truncated.
 	max_log_length: u32,
 	/// Maximum number of subscriptions per connection.
 	max_subscriptions_per_connection: u32,
+	/// Maximum number of concurrent calls per WebSocket connection and what to do once it's reached.
+	max_concurrent_calls_per_connection: Option<(u32, ConcurrentCallsPolicy)>,
 	/// Whether batch requests are supported by this server or not.
 	batch_requests_config: BatchRequestConfig,
 	/// Custom tokio runtime to run the server on.
@@ -216,8 +279,22 @@ struct Settings {
 	enable_ws: bool,
 	/// Number of messages that server is allowed to `buffer` until backpressure kicks in.
 	message_buffer_capacity: u32,
+	/// Timeout of async calls to methods which were registered without a timeout.
+	default_call_timeout: Option<Duration>,
+	/// Access policy of the methods.
+	access_policy: AccessPolicy,
+	/// Accept JSON-RPC 1.0 requests.
+	json_rpc_v1: bool,
+	/// Serve subscriptions over HTTP as server-sent events.
+	enable_sse: bool,
 	/// Ping settings.
 	ping_config: PingConfig,
+	/// WebSocket `permessage-deflate` configuration, if enabled.
+	#[cfg(feature = "ws-deflate")]
+	ws_deflate: Option<WsDeflateConfig>,
+	/// TLS configuration.
+	#[cfg(feature = "tls")]
+	tls: Option<crate::tls::TlsConfig>,
 }
 
 /// Configuration for batch request handling.
@@ -231,6 +308,30 @@ pub enum BatchRequestConfig {
 	Unlimited,
 }
 
+/// What the server does with the calls on a WebSocket connection that has reached its
+/// [maximum number of concurrent calls](Builder::max_concurrent_calls_per_connection).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConcurrentCallsPolicy {
+	/// Stop reading the connection until one of the running calls completes.
+	Backpressure,
+	/// Answer the call immediately with the
+	/// [`SERVER_IS_BUSY_CODE`](crate::types::error::SERVER_IS_BUSY_CODE) error.
+	///
+	/// Notifications are dropped without an answer.
+	Reject,
+}
+
+impl std::fmt::Display for ConcurrentCallsPolicy {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let s = match self {
+			Self::Backpressure => "backpressure",
+			Self::Reject => "reject",
+		};
+
+		write!(f, "{s}")
+	}
+}
+
 /// Configuration for WebSocket ping's.
 ///
 /// If the server sends out a ping then remote peer must reply with a corresponding pong message.
@@ -284,26 +385,39 @@ impl Default for Settings {
 		Self {
 			max_request_body_size: TEN_MB_SIZE_BYTES,
 			max_response_body_size: TEN_MB_SIZE_BYTES,
+			http_compression_threshold: None,
 			max_log_length: 4096,
 			max_connections: MAX_CONNECTIONS,
+			max_connections_per_ip: None,
+			rate_limits: RateLimits::default(),
 			max_subscriptions_per_connection: 1024,
+			max_concurrent_calls_per_connection: None,
 			batch_requests_config: BatchRequestConfig::Unlimited,
 			tokio_runtime: None,
 			enable_http: true,
 			enable_ws: true,
 			message_buffer_capacity: 1024,
+			default_call_timeout: None,
+			access_policy: AccessPolicy::default(),
+			json_rpc_v1: false,
+			enable_sse: false,
 			ping_config: PingConfig::WithoutInactivityCheck(Duration::from_secs(60)),
+			#[cfg(feature = "ws-deflate")]
+			ws_deflate: None,
+			#[cfg(feature = "tls")]
+			tls: None,
 		}
 	}
 }
 
 /// Builder to configure and create a JSON-RPC server
 #[derive(Debug)]
-pub struct Builder<B = Identity, L = ()> {
+pub struct Builder<B = Identity, L = (), R = Identity> {
 	settings: Settings,
 	logger: L,
 	id_provider: Arc<dyn IdProvider>,
 	service_builder: tower::ServiceBuilder<B>,
+	rpc_middleware: RpcServiceBuilder<R>,
 }
 
 impl Default for Builder {
@@ -313,6 +427,7 @@ impl Default for Builder {
 			logger: (),
 			id_provider: Arc::new(RandomIntegerIdProvider),
 			service_builder: tower::ServiceBuilder::new(),
+			rpc_middleware: RpcServiceBuilder::new(),
 		}
 	}
 }
@@ -324,7 +439,7 @@ impl Builder {
 	}
 }
 
-impl<B, L> Builder<B, L> {
+impl<B, L, R> Builder<B, L, R> {
 	/// Set the maximum size of a request body in bytes. Default is 10 MiB.
 	pub fn max_request_body_size(mut self, size: u32) -> Self {
 		self.settings.max_request_body_size = size;
@@ -337,12 +452,48 @@ impl<B, L> Builder<B, L> {
 		self
 	}
 
+	/// Compress the HTTP responses of at least `min_size` bytes with `gzip`, `deflate` or `br`,
+	/// if the client accepts one of them in its `Accept-Encoding` header. Default is disabled.
+	///
+	/// HTTP requests with one of these `Content-Encoding`s are decoded regardless of this setting.
+	pub fn http_compression(mut self, min_size: u32) -> Self {
+		self.settings.http_compression_threshold = Some(min_size);
+		self
+	}
+
 	/// Set the maximum number of connections allowed. Default is 100.
 	pub fn max_connections(mut self, max: u32) -> Self {
 		self.settings.max_connections = max;
 		self
 	}
 
+	/// Set the maximum number of connections allowed from the same IP address, so that a single host
+	/// can't exhaust all connections. Default is unlimited.
+	///
+	/// Connections on a Unix domain socket don't have an IP address, they are only bounded by
+	/// [`Builder::max_connections`].
+	///
+	/// # Panics
+	///
+	/// Panics if `max` is 0.
+	pub fn max_connections_per_ip(mut self, max: u32) -> Self {
+		assert!(max > 0, "The maximum number of connections per IP address must be at least 1");
+		self.settings.max_connections_per_ip = Some(max);
+		self
+	}
+
+	/// Configure the [`RateLimits`] of the calls. Default is unlimited.
+	///
+	/// ```
+	/// use jsonrpsee_server::{RateLimit, RateLimits, ServerBuilder};
+	///
+	/// let builder = ServerBuilder::new().set_rate_limits(RateLimits::new().per_ip(RateLimit::per_second(100)));
+	/// ```
+	pub fn set_rate_limits(mut self, limits: RateLimits) -> Self {
+		self.settings.rate_limits = limits;
+		self
+	}
+
 	/// Configure how [batch requests](https://www.jsonrpc.org/specification#batch) shall be handled
 	/// by the server.
 	///
@@ -358,6 +509,35 @@ impl<B, L> Builder<B, L> {
 		self
 	}
 
+	/// Set the maximum number of calls that may run concurrently on a WebSocket connection.
+	///
+	/// Every WebSocket message counts as one call, so a batch request takes a single slot and
+	/// a subscription only takes one until the subscription call itself has been answered.
+	/// Once the limit is reached, `policy` decides what happens to the next message and
+	/// [`Logger::on_concurrent_calls_limit`](crate::logger::Logger::on_concurrent_calls_limit) is called.
+	///
+	/// This doesn't apply to HTTP where each request already occupies a connection. Default is unlimited.
+	///
+	/// # Panics
+	///
+	/// Panics if `max` is 0.
+	pub fn max_concurrent_calls_per_connection(mut self, max: u32, policy: ConcurrentCallsPolicy) -> Self {
+		assert!(max > 0, "The maximum number of concurrent calls must be at least 1");
+		self.settings.max_concurrent_calls_per_connection = Some((max, policy));
+		self
+	}
+
+	/// Negotiate the WebSocket `permessage-deflate` extension with clients that offer it. Default is disabled.
+	///
+	/// The [maximum request body size](Builder::max_request_body_size) applies to the decompressed messages
+	/// and the connection is terminated if a message exceeds it.
+	#[cfg(feature = "ws-deflate")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "ws-deflate")))]
+	pub fn ws_permessage_deflate(mut self, config: WsDeflateConfig) -> Self {
+		self.settings.ws_deflate = Some(config);
+		self
+	}
+
 	/// Add a logger to the builder [`Logger`](../jsonrpsee_core/logger/trait.Logger.html).
 	///
 	/// ```
@@ -399,12 +579,13 @@ impl<B, L> Builder<B, L> {
 	///
 	/// let builder = ServerBuilder::new().set_logger(MyLogger);
 	/// ```
-	pub fn set_logger<T: Logger>(self, logger: T) -> Builder<B, T> {
+	pub fn set_logger<T: Logger>(self, logger: T) -> Builder<B, T, R> {
 		Builder {
 			settings: self.settings,
 			logger,
 			id_provider: self.id_provider,
 			service_builder: self.service_builder,
+			rpc_middleware: self.rpc_middleware,
 		}
 	}
 
@@ -490,8 +671,75 @@ impl<B, L> Builder<B, L> {
 	///         .unwrap();
 	/// }
 	/// ```
-	pub fn set_middleware<T>(self, service_builder: tower::ServiceBuilder<T>) -> Builder<T, L> {
-		Builder { settings: self.settings, logger: self.logger, id_provider: self.id_provider, service_builder }
+	pub fn set_middleware<T>(self, service_builder: tower::ServiceBuilder<T>) -> Builder<T, L, R> {
+		Builder {
+			settings: self.settings,
+			logger: self.logger,
+			id_provider: self.id_provider,
+			service_builder,
+			rpc_middleware: self.rpc_middleware,
+		}
+	}
+
+	/// Configure a custom [`RpcServiceBuilder`] middleware for composing layers to be applied
+	/// to every JSON-RPC call.
+	///
+	/// Unlike [`Builder::set_middleware`] which operates on the HTTP request, these layers
+	/// are invoked once for each JSON-RPC call regardless of whether it arrived over HTTP or WebSocket,
+	/// including every call in a batch request.
+	///
+	/// The middleware service is created once per WebSocket connection and once per HTTP request.
+	///
+	/// Default: No layers are applied to the JSON-RPC calls.
+	///
+	/// # Examples
+	///
+	/// ```rust
+	/// use std::net::SocketAddr;
+	/// use std::sync::atomic::{AtomicUsize, Ordering};
+	/// use std::sync::{Arc, PoisonError, RwLock};
+	///
+	/// use jsonrpsee_server::middleware::rpc::{RpcServiceBuilder, RpcServiceT};
+	/// use jsonrpsee_server::types::Request;
+	///
+	/// #[derive(Clone)]
+	/// struct CallCounter<S> {
+	///     service: S,
+	///     count: Arc<AtomicUsize>,
+	/// }
+	///
+	/// impl<'a, S: RpcServiceT<'a>> RpcServiceT<'a> for CallCounter<S> {
+	///     type Future = S::Future;
+	///
+	///     fn call(&self, req: Request<'a>) -> Self::Future {
+	///         self.count.fetch_add(1, Ordering::Relaxed);
+	///         self.service.call(req)
+	///     }
+	/// }
+	///
+	/// #[tokio::main]
+	/// async fn main() {
+	///     let count = Arc::new(AtomicUsize::new(0));
+	///     let rpc_middleware = RpcServiceBuilder::new()
+	///         .layer_fn(move |service| CallCounter { service, count: count.clone() });
+	///
+	///     let server = jsonrpsee_server::ServerBuilder::new()
+	///         .set_rpc_middleware(rpc_middleware)
+	///         .build("127.0.0.1:0".parse::<SocketAddr>().unwrap())
+	///         .await
+	///         .unwrap();
+	///
+	///     let _handle = server.start(jsonrpsee_server::RpcModule::new(()));
+	/// }
+	/// ```
+	pub fn set_rpc_middleware<T>(self, rpc_middleware: RpcServiceBuilder<T>) -> Builder<B, L, T> {
+		Builder {
+			settings: self.settings,
+			logger: self.logger,
+			id_provider: self.id_provider,
+			service_builder: self.service_builder,
+			rpc_middleware,
+		}
 	}
 
 	/// Configure the server to only serve JSON-RPC HTTP requests.
@@ -537,6 +785,59 @@ impl<B, L> Builder<B, L> {
 		self
 	}
 
+	/// Set a timeout for the async and blocking methods that were registered without a timeout.
+	///
+	/// Calls that don't complete within the timeout are cancelled and answered with the
+	/// [`CALL_TIMEOUT_CODE`](crate::types::error::CALL_TIMEOUT_CODE) error.
+	///
+	/// Methods that were registered with their own timeout, for instance with
+	/// [`RpcModule::register_async_method_with_timeout`](crate::RpcModule::register_async_method_with_timeout),
+	/// use their own timeout instead. By default, there's no timeout.
+	pub fn default_call_timeout(mut self, timeout: Duration) -> Self {
+		self.settings.default_call_timeout = Some(timeout);
+		self
+	}
+
+	/// Configure which [`MethodAccess`](crate::MethodAccess) classes of methods are allowed,
+	/// denied or hidden on this server.
+	///
+	/// The policy applies to everyone that connects to this server, so the same [`Methods`] can be served
+	/// by several servers with different policies. Use [`AccessPolicy::set_for_role`] to grant the
+	/// callers with a given [`Role`](crate::Role) different permissions.
+	///
+	/// Default: all methods are allowed.
+	pub fn set_access_policy(mut self, policy: AccessPolicy) -> Self {
+		self.settings.access_policy = policy;
+		self
+	}
+
+	/// Accept JSON-RPC 1.0 requests in addition to JSON-RPC 2.0 requests.
+	///
+	/// JSON-RPC 1.0 requests have no `jsonrpc` member and are answered with a response that has
+	/// both a `result` and an `error` member, one of which is null. A JSON-RPC 1.0 request whose `id`
+	/// is null is a notification. Subscription notifications to JSON-RPC 1.0 subscribers are
+	/// sent as JSON-RPC 1.0 notifications.
+	///
+	/// Default: disabled.
+	pub fn enable_json_rpc_v1(mut self) -> Self {
+		self.settings.json_rpc_v1 = true;
+		self
+	}
+
+	/// Serve subscriptions over plain HTTP as server-sent events.
+	///
+	/// A `POST` request with a JSON-RPC subscription call, or a `GET` request with the call in the
+	/// `method`, `params` and `id` query parameters, that accepts `text/event-stream` is answered with
+	/// an event stream. The first event is the response to the call and each following event is a
+	/// notification of the subscription. Closing the response unsubscribes from the subscription.
+	/// `GET` requests for methods which aren't subscriptions are answered with `405 Method Not Allowed`.
+	///
+	/// Default: disabled.
+	pub fn enable_sse(mut self) -> Self {
+		self.settings.enable_sse = true;
+		self
+	}
+
 	/// Set maximum length for logging calls and responses.
 	///
 	/// Logs bigger than this limit will be truncated.
@@ -545,6 +846,39 @@ impl<B, L> Builder<B, L> {
 		self
 	}
 
+	/// Terminate TLS on the server with the given [`rustls::ServerConfig`](crate::rustls::ServerConfig),
+	/// which applies to both HTTPS and WSS connections.
+	///
+	/// Client certificates are verified if the config is built with a client certificate verifier
+	/// such as [`AllowAnyAuthenticatedClient`](crate::rustls::server::AllowAnyAuthenticatedClient) and
+	/// the certificates presented by the client are available to the method callbacks
+	/// as [`PeerCertificates`](crate::PeerCertificates) in the extensions of each call.
+	///
+	/// Default: TLS is disabled.
+	///
+	/// ```rust
+	/// use jsonrpsee_server::rustls::{Certificate, PrivateKey, ServerConfig};
+	/// use jsonrpsee_server::ServerBuilder;
+	///
+	/// fn tls_config(cert_chain: Vec<Certificate>, key: PrivateKey) -> ServerConfig {
+	///     ServerConfig::builder()
+	///         .with_safe_defaults()
+	///         .with_no_client_auth()
+	///         .with_single_cert(cert_chain, key)
+	///         .expect("bad certificate/key")
+	/// }
+	///
+	/// async fn start(cert_chain: Vec<Certificate>, key: PrivateKey) {
+	///     let server = ServerBuilder::default().tls(tls_config(cert_chain, key)).build("127.0.0.1:0").await.unwrap();
+	/// }
+	/// ```
+	#[cfg(feature = "tls")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "tls")))]
+	pub fn tls(mut self, config: impl Into<Arc<crate::rustls::ServerConfig>>) -> Self {
+		self.settings.tls = Some(crate::tls::TlsConfig::new(config.into()));
+		self
+	}
+
 	/// Finalize the configuration of the server. Consumes the [`Builder`].
 	///
 	/// ```rust
@@ -561,8 +895,8 @@ impl<B, L> Builder<B, L> {
 	/// }
 	/// ```
 	///
-	pub async fn build(self, addrs: impl ToSocketAddrs) -> Result<Server<B, L>, Error> {
-		let listener = TcpListener::bind(addrs).await?;
+	pub async fn build(self, addrs: impl ToSocketAddrs) -> Result<Server<B, L, R>, Error> {
+		let listener = Listener::Tcp(TcpListener::bind(addrs).await?);
 
 		Ok(Server {
 			listener,
@@ -570,6 +904,7 @@ impl<B, L> Builder<B, L> {
 			logger: self.logger,
 			id_provider: self.id_provider,
 			service_builder: self.service_builder,
+			rpc_middleware: self.rpc_middleware,
 		})
 	}
 
@@ -596,8 +931,8 @@ impl<B, L> Builder<B, L> {
 	///   let server = ServerBuilder::new().build_from_tcp(socket).unwrap();
 	/// }
 	/// ```
-	pub fn build_from_tcp(self, listener: impl Into<StdTcpListener>) -> Result<Server<B, L>, Error> {
-		let listener = TcpListener::from_std(listener.into())?;
+	pub fn build_from_tcp(self, listener: impl Into<StdTcpListener>) -> Result<Server<B, L, R>, Error> {
+		let listener = Listener::Tcp(TcpListener::from_std(listener.into())?);
 
 		Ok(Server {
 			listener,
@@ -605,27 +940,143 @@ impl<B, L> Builder<B, L> {
 			logger: self.logger,
 			id_provider: self.id_provider,
 			service_builder: self.service_builder,
+			rpc_middleware: self.rpc_middleware,
 		})
 	}
+
+	/// Finalizes the configuration of the server and listens on a Unix domain socket at the given path.
+	///
+	/// The server serves the same HTTP and WebSocket protocols as it does over TCP and the
+	/// socket file is not removed when the server is stopped.
+	///
+	/// Because Unix domain sockets don't have a socket address, [`Server::local_addr`] returns
+	/// an error and the remote address reported to the [`Logger`] and in the [`ConnectionDetails`]
+	/// is the unspecified address `0.0.0.0:0`.
+	///
+	/// ```rust
+	/// use jsonrpsee_server::ServerBuilder;
+	///
+	/// #[tokio::main]
+	/// async fn main() {
+	///   let path = std::env::temp_dir().join("jsonrpsee-doc-example.sock");
+	///   let _ = std::fs::remove_file(&path);
+	///
+	///   let server = ServerBuilder::new().build_from_unix(&path).unwrap();
+	///   assert!(server.local_addr().is_err());
+	/// }
+	/// ```
+	#[cfg(unix)]
+	#[cfg_attr(docsrs, doc(cfg(unix)))]
+	pub fn build_from_unix(self, path: impl AsRef<std::path::Path>) -> Result<Server<B, L, R>, Error> {
+		let listener = Listener::Unix(tokio::net::UnixListener::bind(path)?);
+
+		Ok(Server {
+			listener,
+			cfg: self.settings,
+			logger: self.logger,
+			id_provider: self.id_provider,
+			service_builder: self.service_builder,
+			rpc_middleware: self.rpc_middleware,
+		})
+	}
+}
+
+/// Details about the connection a JSON-RPC call was received on.
+///
+/// It's inserted by the server into the [`Extensions`] of every call
+/// together with the [`HeaderMap`](crate::HeaderMap) and the [`Uri`](crate::Uri) of the HTTP request,
+/// which for WebSocket connections is the upgrade request, and the `Arc<dyn IdProvider>` of the server.
+/// The [`IpAddr`](std::net::IpAddr) of the remote is inserted as well, except for connections on a Unix domain socket.
+///
+/// ```
+/// use jsonrpsee_server::{ConnectionDetails, HeaderMap, RpcModule};
+///
+/// let mut module = RpcModule::new(());
+/// module
+///     .register_method("whoami", |_, _, ext| {
+///         let details = ext.get::<ConnectionDetails>().expect("inserted by the server; qed");
+///         let user_agent = ext
+///             .get::<HeaderMap>()
+///             .and_then(|headers| headers.get("user-agent"))
+///             .and_then(|val| val.to_str().ok())
+///             .map(ToOwned::to_owned);
+///
+///         format!("conn_id={} remote_addr={} user_agent={:?}", details.id(), details.remote_addr(), user_agent)
+///     })
+///     .unwrap();
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct ConnectionDetails {
+	id: ConnectionId,
+	remote_addr: SocketAddr,
+}
+
+impl ConnectionDetails {
+	pub(crate) fn new(id: ConnectionId, remote_addr: SocketAddr) -> Self {
+		Self { id, remote_addr }
+	}
+
+	/// Get the connection ID.
+	pub fn id(&self) -> ConnectionId {
+		self.id
+	}
+
+	/// Get the remote address of the connection.
+	///
+	/// For connections on a Unix domain socket this is the unspecified address `0.0.0.0:0`.
+	pub fn remote_addr(&self) -> SocketAddr {
+		self.remote_addr
+	}
+
+	/// Create the extensions for calls made on the connection of the given HTTP request.
+	pub(crate) fn into_extensions<B>(
+		self,
+		request: &hyper::Request<B>,
+		id_provider: &Arc<dyn IdProvider>,
+	) -> Extensions {
+		let mut extensions = Extensions::new();
+		extensions.insert(self);
+		if let Some(ip) = listener::remote_ip(self.remote_addr) {
+			extensions.insert(ip);
+		}
+		extensions.insert(id_provider.clone());
+		extensions.insert(request.headers().clone());
+		extensions.insert(request.uri().clone());
+		if let Some(role) = request.extensions().get::<Role>() {
+			extensions.insert(role.clone());
+		}
+		#[cfg(feature = "tls")]
+		if let Some(peer_certs) = request.extensions().get::<crate::PeerCertificates>() {
+			extensions.insert(peer_certs.clone());
+		}
+		extensions
+	}
 }
 
 /// Data required by the server to handle requests.
 #[derive(Debug, Clone)]
-pub(crate) struct ServiceData<L: Logger> {
+pub(crate) struct ServiceData<L: Logger, R> {
 	/// Remote server address.
 	pub(crate) remote_addr: SocketAddr,
 	/// Registered server methods.
-	pub(crate) methods: Methods,
+	pub(crate) methods: SharedMethods,
 	/// Max request body size.
 	pub(crate) max_request_body_size: u32,
 	/// Max response body size.
 	pub(crate) max_response_body_size: u32,
+	/// Minimum size of the compressed HTTP responses, if enabled.
+	pub(crate) http_compression_threshold: Option<u32>,
 	/// Max length for logging for request and response
 	///
 	/// Logs bigger than this limit will be truncated.
 	pub(crate) max_log_length: u32,
 	/// Maximum number of subscriptions per connection.
 	pub(crate) max_subscriptions_per_connection: u32,
+	/// Maximum number of concurrent calls per WebSocket connection and what to do once it's reached.
+	pub(crate) max_concurrent_calls_per_connection: Option<(u32, ConcurrentCallsPolicy)>,
+	/// WebSocket `permessage-deflate` configuration, if enabled.
+	#[cfg(feature = "ws-deflate")]
+	pub(crate) ws_deflate: Option<WsDeflateConfig>,
 	/// Whether batch requests are supported by this server or not.
 	pub(crate) batch_requests_config: BatchRequestConfig,
 	/// Subscription ID provider.
@@ -639,13 +1090,47 @@ pub(crate) struct ServiceData<L: Logger> {
 	/// Logger.
 	pub(crate) logger: L,
 	/// Handle to hold a `connection permit`.
-	pub(crate) conn: Arc<OwnedSemaphorePermit>,
+	pub(crate) conn: Arc<ConnectionPermit>,
 	/// Enable HTTP.
 	pub(crate) enable_http: bool,
 	/// Enable WS.
 	pub(crate) enable_ws: bool,
 	/// Number of messages that server is allowed `buffer` until backpressure kicks in.
 	pub(crate) message_buffer_capacity: u32,
+	/// Timeout of async calls to methods which were registered without a timeout.
+	pub(crate) default_call_timeout: Option<Duration>,
+	/// Access policy of the methods.
+	pub(crate) access_policy: AccessPolicy,
+	/// Accept JSON-RPC 1.0 requests.
+	pub(crate) json_rpc_v1: bool,
+	/// Serve subscriptions over HTTP as server-sent events.
+	pub(crate) enable_sse: bool,
+	/// Rate limiter of the connection.
+	pub(crate) rate_limiter: Option<ConnectionRateLimiter>,
+	/// JSON-RPC middleware.
+	pub(crate) rpc_middleware: RpcServiceBuilder<R>,
+}
+
+#[cfg(feature = "ws-deflate")]
+fn offers_permessage_deflate<B>(request: &hyper::Request<B>) -> bool {
+	request
+		.headers()
+		.get_all(hyper::header::SEC_WEBSOCKET_EXTENSIONS)
+		.iter()
+		.filter_map(|value| value.to_str().ok())
+		.any(|value| value.split(',').any(|ext| ext.split(';').next().map(str::trim) == Some("permessage-deflate")))
+}
+
+/// Select the first encoding among the WebSocket subprotocols requested by the client.
+#[cfg(feature = "cbor")]
+fn negotiate_subprotocol<B>(request: &hyper::Request<B>) -> Option<Encoding> {
+	request
+		.headers()
+		.get_all(hyper::header::SEC_WEBSOCKET_PROTOCOL)
+		.iter()
+		.filter_map(|value| value.to_str().ok())
+		.flat_map(|value| value.split(','))
+		.find_map(|protocol| Encoding::from_subprotocol(protocol.trim()))
 }
 
 /// JsonRPSee service compatible with `tower`.
@@ -653,11 +1138,16 @@ pub(crate) struct ServiceData<L: Logger> {
 /// # Note
 /// This is similar to [`hyper::service::service_fn`].
 #[derive(Debug, Clone)]
-pub struct TowerService<L: Logger> {
-	inner: ServiceData<L>,
+pub struct TowerService<L: Logger, R = Identity> {
+	inner: ServiceData<L, R>,
 }
 
-impl<L: Logger> hyper::service::Service<hyper::Request<hyper::Body>> for TowerService<L> {
+impl<L, R> hyper::service::Service<hyper::Request<hyper::Body>> for TowerService<L, R>
+where
+	L: Logger,
+	R: Layer<RpcService> + Clone + Send + Sync + 'static,
+	for<'a> <R as Layer<RpcService>>::Service: RpcServiceT<'a> + Send + Sync + 'static,
+{
 	type Response = hyper::Response<hyper::Body>;
 
 	// The following associated type is required by the `impl<B, U, L: Logger> Server<B, L>` bounds.
@@ -679,10 +1169,40 @@ impl<L: Logger> hyper::service::Service<hyper::Request<hyper::Body>> for TowerSe
 		if self.inner.enable_ws && is_upgrade_request {
 			let mut server = soketto::handshake::http::Server::new();
 
+			// `soketto` replies with an empty extensions header if none of the extensions was negotiated.
+			#[cfg(feature = "ws-deflate")]
+			if let Some(config) = self.inner.ws_deflate.filter(|_| offers_permessage_deflate(&request)) {
+				let max_size = self.inner.max_request_body_size as usize;
+				server.add_extension(Box::new(PerMessageDeflate::new(soketto::Mode::Server, config, max_size)));
+			}
+
 			let response = match server.receive_request(&request) {
-				Ok(response) => {
+				#[cfg_attr(not(feature = "cbor"), allow(unused_mut))]
+				Ok(mut response) => {
 					self.inner.logger.on_connect(self.inner.remote_addr, &request, TransportProtocol::WebSocket);
 					let data = self.inner.clone();
+					#[cfg_attr(not(feature = "cbor"), allow(unused_mut))]
+					let mut extensions = ConnectionDetails::new(data.conn_id as ConnectionId, data.remote_addr)
+						.into_extensions(&request, &data.id_provider);
+
+					// `soketto` doesn't negotiate subprotocols, the selected one is echoed back here.
+					#[cfg(feature = "cbor")]
+					if let Some(encoding) = negotiate_subprotocol(&request) {
+						if let Some(protocol) = encoding.subprotocol() {
+							response.headers_mut().insert(
+								hyper::header::SEC_WEBSOCKET_PROTOCOL,
+								hyper::header::HeaderValue::from_static(protocol),
+							);
+						}
+						extensions.insert(encoding);
+					}
+
+					// Streamed responses are sent as fragmented messages, unless they are compressed.
+					let compressed = response
+						.headers()
+						.get(hyper::header::SEC_WEBSOCKET_EXTENSIONS)
+						.map_or(false, |extensions| !extensions.is_empty());
+					let fragmentation = (!compressed).then(ws::Fragmentation::default);
 
 					tokio::spawn(
 						async move {
@@ -697,9 +1217,12 @@ impl<L: Logger> hyper::service::Service<hyper::Request<hyper::Body>> for TowerSe
 							let stream = BufReader::new(BufWriter::new(upgraded.compat()));
 							let mut ws_builder = server.into_builder(stream);
 							ws_builder.set_max_message_size(data.max_request_body_size as usize);
+							if let Some(fragmentation) = fragmentation.clone() {
+								ws_builder.add_extensions([Box::new(fragmentation) as Box<_>]);
+							}
 							let (sender, receiver) = ws_builder.finish();
 
-							ws::background_task::<L>(sender, receiver, data).await;
+							ws::background_task(sender, receiver, data, extensions, fragmentation).await;
 						}
 						.in_current_span(),
 					);
@@ -713,43 +1236,76 @@ impl<L: Logger> hyper::service::Service<hyper::Request<hyper::Body>> for TowerSe
 			};
 
 			async { Ok(response) }.boxed()
+		} else if self.inner.enable_http && self.inner.enable_sse && sse::accepts_event_stream(&request) {
+			self.inner.logger.on_connect(self.inner.remote_addr, &request, TransportProtocol::Http);
+
+			let call = self.inner.stop_handle.run_call(sse::handle_request(request, self.inner.clone()));
+
+			Box::pin(async move {
+				match call.await {
+					Some(response) => Ok(response),
+					None => Ok(http::response::aborted()),
+				}
+			})
 		} else if self.inner.enable_http && !is_upgrade_request {
 			// The request wasn't an upgrade request; let's treat it as a standard HTTP request:
 			let data = http::HandleRequest {
 				methods: self.inner.methods.clone(),
 				max_request_body_size: self.inner.max_request_body_size,
 				max_response_body_size: self.inner.max_response_body_size,
+				http_compression_threshold: self.inner.http_compression_threshold,
 				max_log_length: self.inner.max_log_length,
 				batch_requests_config: self.inner.batch_requests_config,
 				logger: self.inner.logger.clone(),
 				conn: self.inner.conn.clone(),
 				remote_addr: self.inner.remote_addr,
+				conn_id: self.inner.conn_id as ConnectionId,
+				default_call_timeout: self.inner.default_call_timeout,
+				access_policy: self.inner.access_policy.clone(),
+				json_rpc_v1: self.inner.json_rpc_v1,
+				rate_limiter: self.inner.rate_limiter.clone(),
+				rpc_middleware: self.inner.rpc_middleware.clone(),
+				id_provider: self.inner.id_provider.clone(),
 			};
 
 			self.inner.logger.on_connect(self.inner.remote_addr, &request, TransportProtocol::Http);
 
-			Box::pin(http::handle_request(request, data).map(Ok))
+			let call = self.inner.stop_handle.run_call(http::handle_request(request, data));
+
+			Box::pin(async move {
+				match call.await {
+					Some(response) => Ok(response),
+					None => Ok(http::response::aborted()),
+				}
+			})
 		} else {
 			Box::pin(async { http::response::denied() }.map(Ok))
 		}
 	}
 }
 
-struct ProcessConnection<L> {
+struct ProcessConnection<L, R> {
 	/// Remote server address.
 	remote_addr: SocketAddr,
 	/// Registered server methods.
-	methods: Methods,
+	methods: SharedMethods,
 	/// Max request body size.
 	max_request_body_size: u32,
 	/// Max response body size.
 	max_response_body_size: u32,
+	/// Minimum size of the compressed HTTP responses, if enabled.
+	http_compression_threshold: Option<u32>,
 	/// Max length for logging for request and response
 	///
 	/// Logs bigger than this limit will be truncated.
 	max_log_length: u32,
 	/// Maximum number of subscriptions per connection.
 	max_subscriptions_per_connection: u32,
+	/// Maximum number of concurrent calls per WebSocket connection and what to do once it's reached.
+	max_concurrent_calls_per_connection: Option<(u32, ConcurrentCallsPolicy)>,
+	/// WebSocket `permessage-deflate` configuration, if enabled.
+	#[cfg(feature = "ws-deflate")]
+	ws_deflate: Option<WsDeflateConfig>,
 	/// Whether batch requests are supported by this server or not.
 	batch_requests_config: BatchRequestConfig,
 	/// Subscription ID provider.
@@ -770,24 +1326,41 @@ struct ProcessConnection<L> {
 	enable_ws: bool,
 	/// Number of messages that server is allowed `buffer` until backpressure kicks in.
 	message_buffer_capacity: u32,
+	/// Timeout of async calls to methods which were registered without a timeout.
+	default_call_timeout: Option<Duration>,
+	/// Access policy of the methods.
+	access_policy: AccessPolicy,
+	/// Accept JSON-RPC 1.0 requests.
+	json_rpc_v1: bool,
+	/// Serve subscriptions over HTTP as server-sent events.
+	enable_sse: bool,
+	/// Rate limiter shared by all connections.
+	rate_limiter: Option<RateLimiter>,
+	/// JSON-RPC middleware.
+	rpc_middleware: RpcServiceBuilder<R>,
+	/// TLS configuration.
+	#[cfg(feature = "tls")]
+	tls: Option<crate::tls::TlsConfig>,
 }
 
 #[instrument(name = "connection", skip_all, fields(remote_addr = %cfg.remote_addr, conn_id = %cfg.conn_id), level = "INFO")]
-fn process_connection<'a, L: Logger, B, U>(
+fn process_connection<'a, L: Logger, B, U, R>(
 	service_builder: &tower::ServiceBuilder<B>,
 	connection_guard: &ConnectionGuard,
-	cfg: ProcessConnection<L>,
-	socket: TcpStream,
+	cfg: ProcessConnection<L, R>,
+	socket: Socket,
 	drop_on_completion: mpsc::Sender<()>,
 ) where
-	B: Layer<TowerService<L>> + Send + 'static,
-	<B as Layer<TowerService<L>>>::Service: Send
+	R: Layer<RpcService> + Clone + Send + Sync + 'static,
+	for<'b> <R as Layer<RpcService>>::Service: RpcServiceT<'b> + Send + Sync + 'static,
+	B: Layer<TowerService<L, R>> + Send + 'static,
+	<B as Layer<TowerService<L, R>>>::Service: Send
 		+ Service<
 			hyper::Request<hyper::Body>,
 			Response = hyper::Response<U>,
 			Error = Box<(dyn StdError + Send + Sync + 'static)>,
 		>,
-	<<B as Layer<TowerService<L>>>::Service as Service<hyper::Request<hyper::Body>>>::Future: Send,
+	<<B as Layer<TowerService<L, R>>>::Service as Service<hyper::Request<hyper::Body>>>::Future: Send,
 	U: HttpBody + Send + 'static,
 	<U as HttpBody>::Error: Send + Sync + StdError,
 	<U as HttpBody>::Data: Send,
@@ -797,7 +1370,7 @@ fn process_connection<'a, L: Logger, B, U>(
 		return;
 	}
 
-	let conn = match connection_guard.try_acquire() {
+	let conn = match connection_guard.try_acquire(listener::remote_ip(cfg.remote_addr)) {
 		Some(conn) => conn,
 		None => {
 			tracing::debug!("Too many connections. Please try again later.");
@@ -819,8 +1392,12 @@ fn process_connection<'a, L: Logger, B, U>(
 			methods: cfg.methods,
 			max_request_body_size: cfg.max_request_body_size,
 			max_response_body_size: cfg.max_response_body_size,
+			http_compression_threshold: cfg.http_compression_threshold,
 			max_log_length: cfg.max_log_length,
 			max_subscriptions_per_connection: cfg.max_subscriptions_per_connection,
+			max_concurrent_calls_per_connection: cfg.max_concurrent_calls_per_connection,
+			#[cfg(feature = "ws-deflate")]
+			ws_deflate: cfg.ws_deflate,
 			batch_requests_config: cfg.batch_requests_config,
 			id_provider: cfg.id_provider,
 			ping_config: cfg.ping_config,
@@ -831,11 +1408,26 @@ fn process_connection<'a, L: Logger, B, U>(
 			enable_http: cfg.enable_http,
 			enable_ws: cfg.enable_ws,
 			message_buffer_capacity: cfg.message_buffer_capacity,
+			default_call_timeout: cfg.default_call_timeout,
+			access_policy: cfg.access_policy,
+			json_rpc_v1: cfg.json_rpc_v1,
+			enable_sse: cfg.enable_sse,
+			rate_limiter: cfg.rate_limiter.map(|limiter| limiter.connection(listener::remote_ip(cfg.remote_addr))),
+			rpc_middleware: cfg.rpc_middleware,
 		},
 	};
 
 	let service = service_builder.service(tower_service);
 
+	#[cfg(feature = "tls")]
+	if let Some(tls) = cfg.tls {
+		tokio::spawn(async {
+			crate::tls::to_https_service(tls, socket, service, cfg.stop_handle).in_current_span().await;
+			drop(drop_on_completion)
+		});
+		return;
+	}
+
 	tokio::spawn(async {
 		to_http_service(socket, service, cfg.stop_handle).in_current_span().await;
 		drop(drop_on_completion)
@@ -843,8 +1435,9 @@ fn process_connection<'a, L: Logger, B, U>(
 }
 
 // Attempts to create a HTTP connection from a socket.
-async fn to_http_service<S, B>(socket: TcpStream, service: S, stop_handle: StopHandle)
+pub(crate) async fn to_http_service<I, S, B>(socket: I, service: S, stop_handle: StopHandle)
 where
+	I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
 	S: Service<hyper::Request<hyper::Body>, Response = hyper::Response<B>> + Send + 'static,
 	S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
 	S::Future: Send,
@@ -853,6 +1446,7 @@ where
 	<B as HttpBody>::Data: Send,
 {
 	let conn = hyper::server::conn::Http::new().serve_connection(socket, service).with_upgrades();
+	let aborted = stop_handle.clone();
 	let stopped = stop_handle.shutdown();
 
 	tokio::pin!(stopped);
@@ -863,7 +1457,15 @@ where
 			// NOTE: the connection should continue to be polled until shutdown can finish.
 			// Thus, both lines below are needed and not a nit.
 			Pin::new(&mut conn).graceful_shutdown();
-			conn.await
+
+			// The connection is dropped if it didn't shut down before the graceful shutdown deadline.
+			let aborted = aborted.aborted();
+			tokio::pin!(aborted);
+
+			match future::select(conn, aborted).await {
+				Either::Left((conn, _)) => conn,
+				Either::Right(_) => Ok(()),
+			}
 		}
 	};
 
@@ -874,11 +1476,11 @@ where
 
 enum AcceptConnection<S> {
 	Shutdown,
-	Established { socket: TcpStream, remote_addr: SocketAddr, stop: S },
+	Established { socket: Socket, remote_addr: SocketAddr, stop: S },
 	Err((std::io::Error, S)),
 }
 
-async fn try_accept_conn<S>(listener: &TcpListener, stopped: S) -> AcceptConnection<S>
+async fn try_accept_conn<S>(listener: &Listener, stopped: S) -> AcceptConnection<S>
 where
 	S: Future + Unpin,
 {
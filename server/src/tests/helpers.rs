@@ -34,27 +34,27 @@ pub(crate) async fn server_with_handles() -> (SocketAddr, ServerHandle) {
 	let ctx = TestContext;
 	let mut module = RpcModule::new(ctx);
 	module
-		.register_method("say_hello", |_, _| {
+		.register_method("say_hello", |_, _, _| {
 			tracing::debug!("server respond to hello");
 			"hello"
 		})
 		.unwrap();
 	module
-		.register_method::<Result<u64, ErrorObjectOwned>, _>("add", |params, _| {
+		.register_method::<Result<u64, ErrorObjectOwned>, _>("add", |params, _, _| {
 			let params: Vec<u64> = params.parse()?;
 			let sum: u64 = params.into_iter().sum();
 			Ok(sum)
 		})
 		.unwrap();
 	module
-		.register_method::<Result<String, ErrorObjectOwned>, _>("multiparam", |params, _| {
+		.register_method::<Result<String, ErrorObjectOwned>, _>("multiparam", |params, _, _| {
 			let params: (String, String, Vec<u8>) = params.parse()?;
 			let r = format!("string1={}, string2={}, vec={}", params.0.len(), params.1.len(), params.2.len());
 			Ok(r)
 		})
 		.unwrap();
 	module
-		.register_async_method("say_hello_async", |_, _| {
+		.register_async_method("say_hello_async", |_, _, _| {
 			async move {
 				tracing::debug!("server respond to hello");
 				// Call some async function inside.
@@ -64,16 +64,16 @@ pub(crate) async fn server_with_handles() -> (SocketAddr, ServerHandle) {
 		})
 		.unwrap();
 	module
-		.register_async_method::<Result<u64, ErrorObjectOwned>, _, _>("add_async", |params, _| async move {
+		.register_async_method::<Result<u64, ErrorObjectOwned>, _, _>("add_async", |params, _, _| async move {
 			let params: Vec<u64> = params.parse()?;
 			let sum: u64 = params.into_iter().sum();
 			Ok(sum)
 		})
 		.unwrap();
-	module.register_method("invalid_params", |_params, _| Err::<(), _>(invalid_params())).unwrap();
-	module.register_method("call_fail", |_params, _| Err::<(), _>(MyAppError)).unwrap();
+	module.register_method("invalid_params", |_params, _, _| Err::<(), _>(invalid_params())).unwrap();
+	module.register_method("call_fail", |_params, _, _| Err::<(), _>(MyAppError)).unwrap();
 	module
-		.register_method::<Result<&str, ErrorObjectOwned>, _>("sleep_for", |params, _| {
+		.register_method::<Result<&str, ErrorObjectOwned>, _>("sleep_for", |params, _, _| {
 			let sleep: Vec<u64> = params.parse()?;
 			std::thread::sleep(std::time::Duration::from_millis(sleep[0]));
 			Ok("Yawn!")
@@ -84,7 +84,7 @@ pub(crate) async fn server_with_handles() -> (SocketAddr, ServerHandle) {
 			"subscribe_hello",
 			"subscribe_hello",
 			"unsubscribe_hello",
-			|_, pending, _| async move {
+			|_, pending, _, _| async move {
 				let sink = pending.accept().await?;
 
 				loop {
@@ -95,22 +95,22 @@ pub(crate) async fn server_with_handles() -> (SocketAddr, ServerHandle) {
 		)
 		.unwrap();
 
-	module.register_method("notif", |_, _| "").unwrap();
+	module.register_method("notif", |_, _, _| "").unwrap();
 	module
-		.register_method("should_err", |_, ctx| {
+		.register_method("should_err", |_, ctx, _| {
 			ctx.err()?;
 			RpcResult::Ok("err")
 		})
 		.unwrap();
 
 	module
-		.register_method("should_ok", |_, ctx| {
+		.register_method("should_ok", |_, ctx, _| {
 			ctx.ok()?;
 			RpcResult::Ok("ok")
 		})
 		.unwrap();
 	module
-		.register_async_method("should_ok_async", |_p, ctx| async move {
+		.register_async_method("should_ok_async", |_p, ctx, _| async move {
 			ctx.ok()?;
 			Ok::<_, MyAppError>("ok")
 		})
@@ -130,21 +130,21 @@ pub(crate) async fn server_with_context() -> SocketAddr {
 	let mut rpc_module = RpcModule::new(ctx);
 
 	rpc_module
-		.register_method("should_err", |_p, ctx| {
+		.register_method("should_err", |_p, ctx, _| {
 			ctx.err()?;
 			RpcResult::Ok("err")
 		})
 		.unwrap();
 
 	rpc_module
-		.register_method("should_ok", |_p, ctx| {
+		.register_method("should_ok", |_p, ctx, _| {
 			ctx.ok()?;
 			RpcResult::Ok("ok")
 		})
 		.unwrap();
 
 	rpc_module
-		.register_async_method("should_ok_async", |_p, ctx| async move {
+		.register_async_method("should_ok_async", |_p, ctx, _| async move {
 			ctx.ok()?;
 			// Call some async function inside.
 			Result::<_, MyAppError>::Ok(futures_util::future::ready("ok!").await)
@@ -152,7 +152,7 @@ pub(crate) async fn server_with_context() -> SocketAddr {
 		.unwrap();
 
 	rpc_module
-		.register_async_method("err_async", |_p, ctx| async move {
+		.register_async_method("err_async", |_p, ctx, _| async move {
 			ctx.ok()?;
 			// Async work that returns an error
 			futures_util::future::err::<(), _>(MyAppError).await
@@ -27,11 +27,12 @@
 use std::net::SocketAddr;
 
 use crate::server::BatchRequestConfig;
-use crate::{RpcModule, ServerBuilder, ServerHandle};
+use crate::{ResultStream, RpcModule, ServerBuilder, ServerHandle};
 use jsonrpsee_core::{Error, RpcResult};
 use jsonrpsee_test_utils::helpers::*;
 use jsonrpsee_test_utils::mocks::{Id, StatusCode};
 use jsonrpsee_test_utils::TimeoutFutureExt;
+use jsonrpsee_types::error::ErrorCode;
 use jsonrpsee_types::ErrorObjectOwned;
 use serde_json::Value as JsonValue;
 
@@ -48,38 +49,38 @@ async fn server() -> (SocketAddr, ServerHandle) {
 	let ctx = TestContext;
 	let mut module = RpcModule::new(ctx);
 	let addr = server.local_addr().unwrap();
-	module.register_method("say_hello", |_, _| "lo").unwrap();
-	module.register_async_method("say_hello_async", |_, _| async move { RpcResult::Ok("lo") }).unwrap();
+	module.register_method("say_hello", |_, _, _| "lo").unwrap();
+	module.register_async_method("say_hello_async", |_, _, _| async move { RpcResult::Ok("lo") }).unwrap();
 	module
-		.register_method("add", |params, _| {
+		.register_method("add", |params, _, _| {
 			let params: Vec<u64> = params.parse()?;
 			let sum: u64 = params.into_iter().sum();
 			RpcResult::Ok(sum)
 		})
 		.unwrap();
 	module
-		.register_method::<Result<String, ErrorObjectOwned>, _>("multiparam", |params, _| {
+		.register_method::<Result<String, ErrorObjectOwned>, _>("multiparam", |params, _, _| {
 			let params: (String, String, Vec<u8>) = params.parse()?;
 			let r = format!("string1={}, string2={}, vec={}", params.0.len(), params.1.len(), params.2.len());
 			Ok(r)
 		})
 		.unwrap();
-	module.register_method("notif", |_, _| "").unwrap();
+	module.register_method("notif", |_, _, _| "").unwrap();
 	module
-		.register_method("should_err", |_, ctx| {
+		.register_method("should_err", |_, ctx, _| {
 			ctx.err()?;
 			Ok::<_, MyAppError>("err")
 		})
 		.unwrap();
 
 	module
-		.register_method("should_ok", |_, ctx| {
+		.register_method("should_ok", |_, ctx, _| {
 			ctx.ok()?;
 			Ok::<_, MyAppError>("ok")
 		})
 		.unwrap();
 	module
-		.register_async_method("should_ok_async", |_p, ctx| async move {
+		.register_async_method("should_ok_async", |_p, ctx, _| async move {
 			ctx.ok()?;
 			Ok::<_, MyAppError>("ok")
 		})
@@ -436,12 +437,12 @@ async fn can_register_modules() {
 	let mut mod2 = RpcModule::new(cx2);
 
 	assert_eq!(mod1.method_names().count(), 0);
-	mod1.register_method("bla", |_, cx| format!("Gave me {cx}")).unwrap();
-	mod1.register_method("bla2", |_, cx| format!("Gave me {cx}")).unwrap();
-	mod2.register_method("yada", |_, cx| format!("Gave me {cx:?}")).unwrap();
+	mod1.register_method("bla", |_, cx, _| format!("Gave me {cx}")).unwrap();
+	mod1.register_method("bla2", |_, cx, _| format!("Gave me {cx}")).unwrap();
+	mod2.register_method("yada", |_, cx, _| format!("Gave me {cx:?}")).unwrap();
 
 	// Won't register, name clashes
-	mod2.register_method("bla", |_, cx| format!("Gave me {cx:?}")).unwrap();
+	mod2.register_method("bla", |_, cx, _| format!("Gave me {cx:?}")).unwrap();
 
 	assert_eq!(mod1.method_names().count(), 2);
 
@@ -458,7 +459,7 @@ async fn can_set_the_max_request_body_size() {
 	// Rejects all requests larger than 100 bytes
 	let server = ServerBuilder::default().max_request_body_size(100).build(addr).await.unwrap();
 	let mut module = RpcModule::new(());
-	module.register_method("anything", |_p, _cx| "a".repeat(100)).unwrap();
+	module.register_method("anything", |_p, _cx, _| "a".repeat(100)).unwrap();
 	let addr = server.local_addr().unwrap();
 	let uri = to_http_uri(addr);
 	let handle = server.start(module);
@@ -483,7 +484,7 @@ async fn can_set_the_max_response_size() {
 	// Set the max response size to 100 bytes
 	let server = ServerBuilder::default().max_response_body_size(100).build(addr).await.unwrap();
 	let mut module = RpcModule::new(());
-	module.register_method("anything", |_p, _cx| "a".repeat(101)).unwrap();
+	module.register_method("anything", |_p, _cx, _| "a".repeat(101)).unwrap();
 	let addr = server.local_addr().unwrap();
 	let uri = to_http_uri(addr);
 	let handle = server.start(module);
@@ -503,7 +504,7 @@ async fn can_set_the_max_response_size_to_batch() {
 	// Set the max response size to 100 bytes
 	let server = ServerBuilder::default().max_response_body_size(100).build(addr).await.unwrap();
 	let mut module = RpcModule::new(());
-	module.register_method("anything", |_p, _cx| "a".repeat(51)).unwrap();
+	module.register_method("anything", |_p, _cx, _| "a".repeat(51)).unwrap();
 	let addr = server.local_addr().unwrap();
 	let uri = to_http_uri(addr);
 	let handle = server.start(module);
@@ -524,7 +525,7 @@ async fn disabled_batches() {
 	let server =
 		ServerBuilder::default().set_batch_request_config(BatchRequestConfig::Disabled).build(addr).await.unwrap();
 	let mut module = RpcModule::new(());
-	module.register_method("should_ok", |_, _ctx| "ok").unwrap();
+	module.register_method("should_ok", |_, _ctx, _| "ok").unwrap();
 	let addr = server.local_addr().unwrap();
 	let uri = to_http_uri(addr);
 	let handle = server.start(module);
@@ -548,7 +549,7 @@ async fn batch_limit_works() {
 	let server =
 		ServerBuilder::default().set_batch_request_config(BatchRequestConfig::Limit(1)).build(addr).await.unwrap();
 	let mut module = RpcModule::new(());
-	module.register_method("should_ok", |_, _ctx| "ok").unwrap();
+	module.register_method("should_ok", |_, _ctx, _| "ok").unwrap();
 	let addr = server.local_addr().unwrap();
 	let uri = to_http_uri(addr);
 	let handle = server.start(module);
@@ -577,3 +578,304 @@ async fn http2_method_call_works() {
 	assert_eq!(response.status, StatusCode::OK);
 	assert_eq!(response.body, ok_response(JsonValue::Number(3.into()), Id::Num(1)));
 }
+
+#[tokio::test]
+async fn rate_limits_work() {
+	use crate::{RateLimit, RateLimits};
+	use std::time::Duration;
+
+	init_logger();
+
+	let limits = RateLimits::new()
+		.per_ip(RateLimit::new(3, Duration::from_secs(60)))
+		.per_method("say_hello", RateLimit::new(1, Duration::from_secs(60)));
+	let server = ServerBuilder::default().set_rate_limits(limits).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _, _| "lo").unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+	let uri = to_http_uri(addr);
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+
+	// The method limit is answered with a JSON-RPC error.
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	let err: JsonValue = serde_json::from_str(&response.body).unwrap();
+	assert_eq!(err["error"]["code"], jsonrpsee_types::error::RATE_LIMITED_CODE);
+	assert!(err["error"]["data"]["retry_after_ms"].as_u64().unwrap() > 59_000);
+
+	// The IP limit is answered with `429 Too Many Requests`.
+	let req = r#"{"jsonrpc":"2.0","method":"unknown","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+	assert_eq!(response.header.get("retry-after").unwrap(), "20");
+}
+
+#[tokio::test]
+async fn max_connections_per_ip_works() {
+	use tokio::net::TcpStream;
+
+	init_logger();
+
+	let server = ServerBuilder::default().max_connections_per_ip(1).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(RpcModule::new(()));
+	let uri = to_http_uri(addr);
+
+	let _conn = TcpStream::connect(addr).await.unwrap();
+	tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+
+	// The slot is free again once the connection was closed.
+	drop(_conn);
+	tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, method_not_found(Id::Num(1)));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn http_compression_works() {
+	use jsonrpsee_core::http_helpers::ContentEncoding;
+
+	init_logger();
+
+	let server = ServerBuilder::default().http_compression(64).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("echo", |params, _, _| params.one::<String>()).unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+	let uri = to_http_uri(addr);
+
+	let call = |req: String, accept: &'static str, encoding: Option<ContentEncoding>| {
+		let mut builder = hyper::Request::post(uri.clone())
+			.header(hyper::header::CONTENT_TYPE, "application/json")
+			.header(hyper::header::ACCEPT_ENCODING, accept);
+		let body = match encoding {
+			Some(encoding) => {
+				builder = builder.header(hyper::header::CONTENT_ENCODING, encoding.as_str());
+				encoding.encode(req.as_bytes())
+			}
+			None => req.into_bytes(),
+		};
+		let request = builder.body(hyper::Body::from(body)).unwrap();
+
+		async move {
+			let response = hyper::Client::new().request(request).await.unwrap();
+			let (parts, body) = response.into_parts();
+			(parts, hyper::body::to_bytes(body).await.unwrap())
+		}
+	};
+
+	let big = "a".repeat(1024);
+	let req = format!(r#"{{"jsonrpc":"2.0","method":"echo","params":["{big}"],"id":1}}"#);
+	let expected = ok_response(JsonValue::String(big), Id::Num(1));
+
+	for encoding in ContentEncoding::ALL {
+		// Compressed requests are decoded and the response is compressed with the accepted encoding.
+		let (parts, body) = call(req.clone(), encoding.as_str(), Some(encoding)).await;
+		assert_eq!(parts.status, StatusCode::OK);
+		assert_eq!(parts.headers[hyper::header::CONTENT_ENCODING], encoding.as_str());
+		assert!(body.len() < expected.len());
+		assert_eq!(encoding.decode(&body, u32::MAX).unwrap(), expected.as_bytes());
+	}
+
+	// Small responses aren't compressed.
+	let small = r#"{"jsonrpc":"2.0","method":"echo","params":["a"],"id":1}"#.to_owned();
+	let (parts, body) = call(small, "gzip", None).await;
+	assert!(parts.headers.get(hyper::header::CONTENT_ENCODING).is_none());
+	assert_eq!(body, ok_response(JsonValue::String("a".into()), Id::Num(1)));
+
+	// Unknown encodings are rejected.
+	let request = hyper::Request::post(uri.clone())
+		.header(hyper::header::CONTENT_TYPE, "application/json")
+		.header(hyper::header::CONTENT_ENCODING, "zstd")
+		.body(hyper::Body::from(req.clone()))
+		.unwrap();
+	let response = hyper::Client::new().request(request).await.unwrap();
+	assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn json_rpc_v1_works() {
+	init_logger();
+
+	let v1_server = ServerBuilder::default().enable_json_rpc_v1().build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("echo", |params, _, _| params.one::<String>()).unwrap();
+	module.register_method("fail", |_, _, _| Err::<(), _>(ErrorObjectOwned::owned(1, "oops", None::<()>))).unwrap();
+	let addr = v1_server.local_addr().unwrap();
+	let _handle = v1_server.start(module);
+	let uri = to_http_uri(addr);
+
+	let cases = [
+		(r#"{"method":"echo","params":["hi"],"id":1}"#, r#"{"result":"hi","error":null,"id":1}"#),
+		(r#"{"method":"fail","params":[],"id":2}"#, r#"{"result":null,"error":{"code":1,"message":"oops"},"id":2}"#),
+		(
+			r#"{"method":"unknown","params":[],"id":3}"#,
+			r#"{"result":null,"error":{"code":-32601,"message":"Method not found"},"id":3}"#,
+		),
+		// A call with a null id is a notification.
+		(r#"{"method":"echo","params":["hi"],"id":null}"#, ""),
+		// JSON-RPC 2.0 calls are still answered with JSON-RPC 2.0 responses.
+		(r#"{"jsonrpc":"2.0","method":"echo","params":["hi"],"id":4}"#, r#"{"jsonrpc":"2.0","result":"hi","id":4}"#),
+		(
+			r#"[{"method":"echo","params":["a"],"id":5},{"jsonrpc":"2.0","method":"echo","params":["b"],"id":6}]"#,
+			r#"[{"result":"a","error":null,"id":5},{"jsonrpc":"2.0","result":"b","id":6}]"#,
+		),
+	];
+
+	for (req, expected) in cases {
+		let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response.status, StatusCode::OK);
+		assert_eq!(response.body, expected);
+	}
+
+	// JSON-RPC 1.0 calls are rejected unless enabled.
+	let (addr, _handle) = server().with_default_timeout().await.unwrap();
+	let req = r#"{"method":"say_hello","params":[],"id":1}"#;
+	let response = http_request(req.into(), to_http_uri(addr)).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, invalid_request(Id::Num(1)));
+}
+
+#[tokio::test]
+async fn streaming_method_works() {
+	init_logger();
+
+	let server = ServerBuilder::default().max_response_body_size(1000).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_streaming_method("state_dump", |params, _, _| async move {
+			let len: u32 = params.one().map_err(|_| ErrorObjectOwned::from(ErrorCode::InvalidParams))?;
+			Ok(ResultStream::from_items(futures_util::stream::iter(0..len)))
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+	let uri = to_http_uri(addr);
+
+	// The result isn't limited by the max response size and is sent with chunked transfer encoding.
+	let req = r#"{"jsonrpc":"2.0","method":"state_dump","params":[10000],"id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.header.get(hyper::header::TRANSFER_ENCODING).unwrap(), "chunked");
+	assert_eq!(response.body, ok_response((0..10000).collect::<Vec<u32>>().into(), Id::Num(1)));
+
+	// Errors of the method are answered as usual.
+	let req = r#"{"jsonrpc":"2.0","method":"state_dump","params":["x"],"id":2}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, invalid_params(Id::Num(2)));
+
+	// Batches are buffered within the max response size.
+	let req = r#"[{"jsonrpc":"2.0","method":"state_dump","params":[3],"id":3},
+		{"jsonrpc":"2.0","method":"state_dump","params":[10000],"id":4}]"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(
+		response.body,
+		format!("[{},{}]", ok_response(vec![0, 1, 2].into(), Id::Num(3)), oversized_response(Id::Num(4), 1000))
+	);
+}
+
+#[tokio::test]
+async fn subscription_over_sse_works() {
+	use hyper::body::HttpBody;
+	use jsonrpsee_core::server::SubscriptionMessage;
+	use jsonrpsee_core::SubscriptionResult;
+
+	init_logger();
+
+	let server = ServerBuilder::default().enable_sse().build("127.0.0.1:0").await.unwrap();
+	let (closed_tx, mut closed_rx) = tokio::sync::mpsc::unbounded_channel();
+	let mut module = RpcModule::new(closed_tx);
+	module
+		.register_subscription("subscribe_ticks", "tick", "unsubscribe_ticks", |_, pending, closed_tx, _| async move {
+			let sink = pending.accept().await?;
+
+			for i in 0.. {
+				tokio::select! {
+					_ = sink.closed() => break,
+					_ = tokio::time::sleep(std::time::Duration::from_millis(10)) => {
+						sink.send(SubscriptionMessage::from_json(&i)?).await?;
+					}
+				}
+			}
+
+			closed_tx.send(()).unwrap();
+			SubscriptionResult::Ok(())
+		})
+		.unwrap();
+	module.register_method("say_hello", |_, _, _| "lo").unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+	let uri = to_http_uri(addr).to_string();
+	let client = hyper::Client::new();
+
+	let request = |method: hyper::Method, uri: String, body: &'static str| {
+		hyper::Request::builder()
+			.method(method)
+			.uri(uri)
+			.header(hyper::header::CONTENT_TYPE, "application/json")
+			.header(hyper::header::ACCEPT, "text/event-stream")
+			.body(hyper::Body::from(body))
+			.unwrap()
+	};
+
+	// The first event is the response to the call, followed by the notifications.
+	let req = r#"{"jsonrpc":"2.0","method":"subscribe_ticks","id":1}"#;
+	let response = client.request(request(hyper::Method::POST, uri.clone(), req)).await.unwrap();
+	assert_eq!(response.headers().get(hyper::header::CONTENT_TYPE).unwrap(), "text/event-stream");
+	let mut body = response.into_body();
+	let mut events = String::new();
+	while events.matches("\n\n").count() < 3 {
+		let chunk = body.data().with_default_timeout().await.unwrap().unwrap().unwrap();
+		events.push_str(std::str::from_utf8(&chunk).unwrap());
+	}
+	let events: Vec<JsonValue> = events
+		.split_terminator("\n\n")
+		.take(3)
+		.map(|event| serde_json::from_str(event.strip_prefix("data: ").unwrap()).unwrap())
+		.collect();
+	let sub_id = events[0]["result"].clone();
+	assert_eq!(events[0]["id"], 1);
+	assert_eq!(events[1]["method"], "tick");
+	assert_eq!(events[1]["params"]["subscription"], sub_id);
+	assert_eq!(events[1]["params"]["result"], 0);
+	assert_eq!(events[2]["params"]["result"], 1);
+
+	// Closing the response unsubscribes.
+	drop(body);
+	closed_rx.recv().with_default_timeout().await.unwrap().unwrap();
+
+	// The call can be passed in the query of a `GET` request.
+	let query = format!("{uri}?method=subscribe_ticks&id=%22a%22");
+	let response = client.request(request(hyper::Method::GET, query, "")).await.unwrap();
+	let mut body = response.into_body();
+	let chunk = body.data().with_default_timeout().await.unwrap().unwrap().unwrap();
+	let event: JsonValue = serde_json::from_slice(chunk.strip_prefix(b"data: ").unwrap()).unwrap();
+	assert_eq!(event["id"], "a");
+	drop(body);
+	closed_rx.recv().with_default_timeout().await.unwrap().unwrap();
+
+	// Only subscriptions can be called with a `GET` request.
+	let query = format!("{uri}?method=say_hello");
+	let response = client.request(request(hyper::Method::GET, query, "")).await.unwrap();
+	assert_eq!(response.status(), hyper::StatusCode::METHOD_NOT_ALLOWED);
+	let query = format!("{uri}?method=unknown");
+	let response = client.request(request(hyper::Method::GET, query, "")).await.unwrap();
+	assert_eq!(response.status(), hyper::StatusCode::METHOD_NOT_ALLOWED);
+
+	// Method calls are answered as usual.
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":2}"#;
+	let response = client.request(request(hyper::Method::POST, uri, req)).await.unwrap();
+	let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+	assert_eq!(std::str::from_utf8(&body).unwrap(), ok_response("lo".into(), Id::Num(2)));
+}
@@ -2,3 +2,6 @@ mod helpers;
 mod http;
 mod shared;
 mod ws;
+
+#[cfg(feature = "tls")]
+mod tls;
@@ -1,9 +1,19 @@
+use crate::middleware::rpc::{RpcServiceBuilder, RpcServiceT};
 use crate::tests::helpers::{init_logger, server_with_handles};
+use crate::{
+	AccessPolicy, ConnectionDetails, HeaderMap, MethodAccess, MethodResponse, Methods, Permission, Role, RpcModule,
+	ServerBuilder, ServerHandle, SubscriptionCloseResponse, SubscriptionMessage,
+};
+use futures_util::future::{BoxFuture, FutureExt};
 use hyper::StatusCode;
-use jsonrpsee_core::Error;
+use jsonrpsee_core::{Error, SubscriptionResult};
 use jsonrpsee_test_utils::helpers::{http_request, ok_response, to_http_uri};
 use jsonrpsee_test_utils::mocks::{Id, WebSocketTestClient, WebSocketTestError};
 use jsonrpsee_test_utils::TimeoutFutureExt;
+use jsonrpsee_types::{ErrorObject, Request};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
 #[tokio::test]
@@ -47,7 +57,7 @@ async fn http_only_works() {
 		ServerBuilder::default().http_only().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
 	let mut module = RpcModule::new(());
 	module
-		.register_method("say_hello", |_, _| {
+		.register_method("say_hello", |_, _, _| {
 			tracing::debug!("server respond to hello");
 			"hello"
 		})
@@ -72,7 +82,7 @@ async fn ws_only_works() {
 	let server = ServerBuilder::default().ws_only().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
 	let mut module = RpcModule::new(());
 	module
-		.register_method("say_hello", |_, _| {
+		.register_method("say_hello", |_, _, _| {
 			tracing::debug!("server respond to hello");
 			"hello"
 		})
@@ -89,3 +99,599 @@ async fn ws_only_works() {
 	let response = client.send_request_text(req.to_string()).await.unwrap();
 	assert_eq!(response, ok_response("hello".to_string().into(), Id::Num(1)));
 }
+
+#[derive(Clone)]
+struct CountAndDeny<S> {
+	service: S,
+	calls: Arc<AtomicUsize>,
+}
+
+impl<'a, S> RpcServiceT<'a> for CountAndDeny<S>
+where
+	S: RpcServiceT<'a> + Send + Sync,
+	S::Future: 'a,
+{
+	type Future = BoxFuture<'a, MethodResponse>;
+
+	fn call(&self, req: Request<'a>) -> Self::Future {
+		self.calls.fetch_add(1, Ordering::SeqCst);
+
+		if req.method.starts_with("admin_") {
+			let rp = MethodResponse::error(req.id, ErrorObject::owned(-32001, "Denied", None::<()>));
+			async move { rp }.boxed()
+		} else {
+			self.service.call(req).boxed()
+		}
+	}
+}
+
+async fn server_with_rpc_middleware() -> (SocketAddr, ServerHandle, Arc<AtomicUsize>) {
+	let calls = Arc::new(AtomicUsize::new(0));
+	let c = calls.clone();
+	let rpc_middleware = RpcServiceBuilder::new().layer_fn(move |service| CountAndDeny { service, calls: c.clone() });
+
+	let server = ServerBuilder::default().set_rpc_middleware(rpc_middleware).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
+	module.register_method("admin_hello", |_, _, _| "admin").unwrap();
+	module
+		.register_subscription(
+			"subscribe_hello",
+			"subscribe_hello",
+			"unsubscribe_hello",
+			|_, pending, _, _| async move {
+				let _sink = pending.accept().await?;
+				SubscriptionResult::Ok(())
+			},
+		)
+		.unwrap();
+
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module);
+
+	(addr, handle, calls)
+}
+
+#[tokio::test]
+async fn rpc_middleware_works_http() {
+	init_logger();
+	let (addr, _handle, calls) = server_with_rpc_middleware().with_default_timeout().await.unwrap();
+	let uri = to_http_uri(addr);
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("hello".into(), Id::Num(1)));
+	assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+	let req = r#"{"jsonrpc":"2.0","method":"admin_hello","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, r#"{"jsonrpc":"2.0","error":{"code":-32001,"message":"Denied"},"id":1}"#);
+	assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+	// The middleware is invoked for every call in the batch.
+	let req = r#"[
+		{"jsonrpc":"2.0","method":"say_hello","id":1},
+		{"jsonrpc":"2.0","method":"admin_hello","id":2},
+		{"jsonrpc":"2.0","method":"unknown","id":3}
+	]"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(
+		response.body,
+		r#"[{"jsonrpc":"2.0","result":"hello","id":1},{"jsonrpc":"2.0","error":{"code":-32001,"message":"Denied"},"id":2},{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":3}]"#
+	);
+	assert_eq!(calls.load(Ordering::SeqCst), 5);
+}
+
+#[tokio::test]
+async fn rpc_middleware_works_ws() {
+	init_logger();
+	let (addr, _handle, calls) = server_with_rpc_middleware().with_default_timeout().await.unwrap();
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("hello".into(), Id::Num(1)));
+
+	let req = r#"[{"jsonrpc":"2.0","method":"say_hello","id":1},{"jsonrpc":"2.0","method":"admin_hello","id":2}]"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(
+		response,
+		r#"[{"jsonrpc":"2.0","result":"hello","id":1},{"jsonrpc":"2.0","error":{"code":-32001,"message":"Denied"},"id":2}]"#
+	);
+
+	// Subscriptions are answered only once even if they pass through the middleware.
+	let req = r#"{"jsonrpc":"2.0","method":"subscribe_hello","id":3}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert!(response.contains(r#""id":3"#));
+
+	assert_eq!(calls.load(Ordering::SeqCst), 4);
+}
+
+#[derive(Clone)]
+struct AuthLayer<S> {
+	service: S,
+}
+
+struct AuthenticatedUser(String);
+
+impl<'a, S> RpcServiceT<'a> for AuthLayer<S>
+where
+	S: RpcServiceT<'a> + Send + Sync,
+{
+	type Future = S::Future;
+
+	fn call(&self, mut req: Request<'a>) -> Self::Future {
+		let user = req
+			.extensions()
+			.get::<HeaderMap>()
+			.and_then(|headers| headers.get("authorization"))
+			.and_then(|val| val.to_str().ok())
+			.map(|user| user.to_owned());
+
+		if let Some(user) = user {
+			req.extensions_mut().insert(AuthenticatedUser(user));
+		}
+
+		self.service.call(req)
+	}
+}
+
+#[tokio::test]
+async fn extensions_are_passed_to_methods() {
+	init_logger();
+
+	let rpc_middleware = RpcServiceBuilder::new().layer_fn(|service| AuthLayer { service });
+	let server = ServerBuilder::default().set_rpc_middleware(rpc_middleware).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_method("whoami", |_, _, ext| {
+			ext.get::<AuthenticatedUser>().map(|user| user.0.clone()).unwrap_or_else(|| "anonymous".to_owned())
+		})
+		.unwrap();
+	module
+		.register_async_method("remote_addr", |_, _, ext| async move {
+			ext.get::<ConnectionDetails>().unwrap().remote_addr().ip().to_string()
+		})
+		.unwrap();
+
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let req = r#"{"jsonrpc":"2.0","method":"whoami","id":1}"#;
+	let response = http_request(req.into(), to_http_uri(addr)).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("anonymous".into(), Id::Num(1)));
+
+	let request = hyper::Request::post(to_http_uri(addr))
+		.header(hyper::header::CONTENT_TYPE, "application/json")
+		.header(hyper::header::AUTHORIZATION, "alice")
+		.body(hyper::Body::from(req))
+		.unwrap();
+	let response = hyper::Client::new().request(request).with_default_timeout().await.unwrap().unwrap();
+	let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+	assert_eq!(std::str::from_utf8(&body).unwrap(), ok_response("alice".into(), Id::Num(1)));
+
+	let req = r#"{"jsonrpc":"2.0","method":"remote_addr","id":1}"#;
+	let response = http_request(req.into(), to_http_uri(addr)).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("127.0.0.1".into(), Id::Num(1)));
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("127.0.0.1".into(), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn notifications_are_dispatched() {
+	init_logger();
+
+	let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+
+	let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(tx);
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
+	module
+		.register_notification("telemetry", |params, tx, _| {
+			tx.send(params.one::<String>().unwrap()).unwrap();
+		})
+		.unwrap();
+	module
+		.register_async_notification("telemetry_async", |params, tx, ext| async move {
+			assert!(ext.get::<ConnectionDetails>().is_some());
+			tx.send(params.one::<String>().unwrap()).unwrap();
+		})
+		.unwrap();
+
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	// Single notifications are never answered.
+	let req = r#"{"jsonrpc":"2.0","method":"telemetry","params":["http"]}"#;
+	let response = http_request(req.into(), to_http_uri(addr)).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.body, "");
+	assert_eq!(rx.recv().await.unwrap(), "http");
+
+	// Notifications in mixed batches are dispatched but only the calls are answered.
+	let req = r#"[
+		{"jsonrpc":"2.0","method":"telemetry","params":["http_batch"]},
+		{"jsonrpc":"2.0","method":"say_hello","id":1},
+		{"jsonrpc":"2.0","method":"telemetry_async","params":["http_batch_async"]}
+	]"#;
+	let response = http_request(req.into(), to_http_uri(addr)).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, r#"[{"jsonrpc":"2.0","result":"hello","id":1}]"#);
+	assert_eq!(rx.recv().await.unwrap(), "http_batch");
+	assert_eq!(rx.recv().await.unwrap(), "http_batch_async");
+
+	// Calls to notification handlers are rejected.
+	let req = r#"{"jsonrpc":"2.0","method":"telemetry","params":["call"],"id":1}"#;
+	let response = http_request(req.into(), to_http_uri(addr)).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, jsonrpsee_test_utils::helpers::method_not_found(Id::Num(1)));
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	client.send(r#"{"jsonrpc":"2.0","method":"telemetry_async","params":["ws"]}"#).await.unwrap();
+	assert_eq!(rx.recv().with_default_timeout().await.unwrap().unwrap(), "ws");
+
+	let req = r#"[
+		{"jsonrpc":"2.0","method":"say_hello","id":1},
+		{"jsonrpc":"2.0","method":"telemetry","params":["ws_batch"]}
+	]"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, r#"[{"jsonrpc":"2.0","result":"hello","id":1}]"#);
+	assert_eq!(rx.recv().await.unwrap(), "ws_batch");
+
+	// Batches with only notifications aren't answered.
+	let req = r#"[{"jsonrpc":"2.0","method":"telemetry","params":["ws_batch_only"]}]"#;
+	client.send(req).await.unwrap();
+	assert_eq!(rx.recv().with_default_timeout().await.unwrap().unwrap(), "ws_batch_only");
+	let response = client.send_request_text(r#"{"jsonrpc":"2.0","method":"say_hello","id":2}"#).await.unwrap();
+	assert_eq!(response, ok_response("hello".into(), Id::Num(2)));
+}
+
+fn graceful_shutdown_module() -> RpcModule<()> {
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("sleep", |_, _, _| async {
+			tokio::time::sleep(Duration::from_millis(100)).await;
+			"done"
+		})
+		.unwrap();
+	module
+		.register_async_method("hang", |_, _, _| async {
+			futures_util::future::pending::<()>().await;
+			"never"
+		})
+		.unwrap();
+	module
+		.register_subscription("subscribe_close", "close", "unsubscribe_close", |_, pending, _, _| async move {
+			let sink = pending.accept().await.unwrap();
+			sink.closed().await;
+			SubscriptionCloseResponse::Notif(SubscriptionMessage::from("bye"))
+		})
+		.unwrap();
+	module
+}
+
+#[tokio::test]
+async fn notifications_go_through_rpc_middleware() {
+	use crate::{RateLimit, RateLimits};
+
+	init_logger();
+
+	let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+	let calls = Arc::new(AtomicUsize::new(0));
+	let c = calls.clone();
+	let rpc_middleware = RpcServiceBuilder::new().layer_fn(move |service| CountAndDeny { service, calls: c.clone() });
+
+	let server = ServerBuilder::default()
+		.set_rpc_middleware(rpc_middleware)
+		.set_rate_limits(RateLimits::new().per_method("telemetry", RateLimit::new(1, Duration::from_secs(60))))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(tx);
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
+	for name in ["telemetry", "admin_telemetry"] {
+		module.register_notification(name, |params, tx, _| tx.send(params.one::<String>().unwrap()).unwrap()).unwrap();
+	}
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	for n in 0..3 {
+		client.send(format!(r#"{{"jsonrpc":"2.0","method":"telemetry","params":["{n}"]}}"#)).await.unwrap();
+	}
+	client.send(r#"{"jsonrpc":"2.0","method":"admin_telemetry","params":["admin"]}"#).await.unwrap();
+	let response = client.send_request_text(r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#).await.unwrap();
+	assert_eq!(response, ok_response("hello".into(), Id::Num(1)));
+	tokio::time::sleep(Duration::from_millis(50)).await;
+
+	// The notifications are rate limited and denied by the middleware like calls.
+	let received = rx.recv().with_default_timeout().await.unwrap().unwrap();
+	assert_ne!(received, "admin");
+	assert!(rx.try_recv().is_err());
+	assert_eq!(calls.load(Ordering::SeqCst), 5);
+}
+
+#[tokio::test]
+async fn stop_graceful_drains_in_flight_calls() {
+	init_logger();
+
+	let server = ServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(graceful_shutdown_module());
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let sub = client
+		.send_request_text(r#"{"jsonrpc":"2.0","method":"subscribe_close","id":1}"#)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	assert!(sub.contains(r#""id":1"#));
+
+	client.send(r#"{"jsonrpc":"2.0","method":"sleep","id":2}"#).with_default_timeout().await.unwrap().unwrap();
+	let http_call =
+		tokio::spawn(http_request(r#"{"jsonrpc":"2.0","method":"sleep","id":3}"#.into(), to_http_uri(addr)));
+	tokio::time::sleep(Duration::from_millis(20)).await;
+
+	let aborted = handle.stop_graceful(Duration::from_secs(5)).with_default_timeout().await.unwrap();
+	assert!(matches!(aborted, Ok(0)));
+
+	let response = http_call.await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.body, ok_response("done".into(), Id::Num(3)));
+
+	let mut messages = Vec::new();
+	for _ in 0..2 {
+		messages.push(client.receive().with_default_timeout().await.unwrap().unwrap());
+	}
+	assert!(messages.contains(&ok_response("done".into(), Id::Num(2))));
+	assert!(messages.iter().any(|m| m.contains(r#""method":"close""#) && m.contains(r#""result":"bye""#)));
+
+	assert!(matches!(handle.stop_graceful(Duration::from_secs(5)).await, Err(Error::AlreadyStopped)));
+}
+
+#[tokio::test]
+async fn stop_graceful_aborts_calls_after_deadline() {
+	init_logger();
+
+	let server = ServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(graceful_shutdown_module());
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	client.send(r#"{"jsonrpc":"2.0","method":"hang","id":1}"#).with_default_timeout().await.unwrap().unwrap();
+	let http_call = tokio::spawn(http_request(r#"{"jsonrpc":"2.0","method":"hang","id":2}"#.into(), to_http_uri(addr)));
+	tokio::time::sleep(Duration::from_millis(20)).await;
+
+	let aborted = handle.stop_graceful(Duration::from_millis(100)).with_default_timeout().await.unwrap();
+	assert!(matches!(aborted, Ok(2)));
+
+	let response = http_call.await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stop_graceful_counts_aborted_ws_calls() {
+	init_logger();
+
+	let server = ServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(graceful_shutdown_module());
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	for id in 1..=3 {
+		let call = format!(r#"{{"jsonrpc":"2.0","method":"hang","id":{id}}}"#);
+		client.send(&call).with_default_timeout().await.unwrap().unwrap();
+	}
+	tokio::time::sleep(Duration::from_millis(50)).await;
+
+	// The calls outlive their connection, they are still waited for and aborted at the deadline.
+	client.close().with_default_timeout().await.unwrap().unwrap();
+	tokio::time::sleep(Duration::from_millis(50)).await;
+
+	let aborted = handle.stop_graceful(Duration::from_millis(100)).with_default_timeout().await.unwrap();
+	assert!(matches!(aborted, Ok(3)));
+}
+
+#[tokio::test]
+async fn replace_and_merge_methods_works() {
+	init_logger();
+
+	let notify = Arc::new(tokio::sync::Notify::new());
+	let mut module = RpcModule::new(notify.clone());
+	module.register_method("hello", |_, _, _| "v1").unwrap();
+	module
+		.register_subscription("subscribe_tick", "tick", "unsubscribe_tick", |_, pending, ctx, _| async move {
+			let sink = pending.accept().await?;
+			ctx.notified().await;
+			sink.send("tick".into()).await?;
+			SubscriptionResult::Ok(())
+		})
+		.unwrap();
+
+	let server = ServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module);
+
+	let hello = r#"{"jsonrpc":"2.0","method":"hello","id":1}"#;
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let response = client.send_request_text(hello).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("v1".into(), Id::Num(1)));
+	let sub = r#"{"jsonrpc":"2.0","method":"subscribe_tick","id":2}"#;
+	let response = client.send_request_text(sub).with_default_timeout().await.unwrap().unwrap();
+	assert!(response.contains(r#""id":2"#));
+
+	let mut module = RpcModule::new(());
+	module.register_method("hello", |_, _, _| "v2").unwrap();
+	handle.replace_methods(module);
+	assert!(handle.methods().method("subscribe_tick").is_none());
+
+	// Calls on existing connections use the new methods.
+	let response = client.send_request_text(hello).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("v2".into(), Id::Num(1)));
+	let response = http_request(hello.into(), to_http_uri(addr)).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("v2".into(), Id::Num(1)));
+
+	// Existing subscriptions keep running.
+	notify.notify_one();
+	let notif = client.receive().with_default_timeout().await.unwrap().unwrap();
+	assert!(notif.contains(r#""method":"tick""#) && notif.contains(r#""result":"tick""#));
+
+	let mut plugin = RpcModule::new(());
+	plugin.register_method("plugin_hello", |_, _, _| "plugin").unwrap();
+	handle.merge_methods(plugin).unwrap();
+
+	let mut conflicting = RpcModule::new(());
+	conflicting.register_method("hello", |_, _, _| "v3").unwrap();
+	conflicting.register_method("other", |_, _, _| "other").unwrap();
+	assert!(matches!(handle.merge_methods(conflicting), Err(Error::MethodAlreadyRegistered(_))));
+
+	let plugin_hello = r#"{"jsonrpc":"2.0","method":"plugin_hello","id":3}"#;
+	let response = client.send_request_text(plugin_hello).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("plugin".into(), Id::Num(3)));
+	let methods = handle.methods();
+	let mut names: Vec<_> = methods.method_names().collect();
+	names.sort();
+	assert_eq!(names, vec!["hello", "plugin_hello"]);
+}
+
+#[tokio::test]
+async fn default_call_timeout_works() {
+	use jsonrpsee_types::error::CALL_TIMEOUT_CODE;
+
+	init_logger();
+
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("sleep", |_, _, _| async {
+			tokio::time::sleep(Duration::from_secs(60)).await;
+			"done"
+		})
+		.unwrap();
+	module
+		.register_async_method_with_timeout("own_timeout", Duration::from_secs(60), |_, _, _| async {
+			tokio::time::sleep(Duration::from_millis(200)).await;
+			"done"
+		})
+		.unwrap();
+	module.register_method("sync", |_, _, _| "done").unwrap();
+
+	let server = ServerBuilder::default()
+		.default_call_timeout(Duration::from_millis(50))
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let req = r#"{"jsonrpc":"2.0","method":"sleep","id":1}"#;
+	let response = http_request(req.into(), to_http_uri(addr)).with_default_timeout().await.unwrap().unwrap();
+	assert!(response.body.contains(&format!(r#""code":{CALL_TIMEOUT_CODE}"#)));
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert!(response.contains(&format!(r#""code":{CALL_TIMEOUT_CODE}"#)));
+
+	// Methods that were registered with their own timeout aren't affected by the default.
+	let req = r#"{"jsonrpc":"2.0","method":"own_timeout","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("done".into(), Id::Num(1)));
+
+	let req = r#"{"jsonrpc":"2.0","method":"sync","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("done".into(), Id::Num(1)));
+}
+
+#[derive(Clone)]
+struct AdminLayer<S> {
+	service: S,
+}
+
+impl<'a, S> RpcServiceT<'a> for AdminLayer<S>
+where
+	S: RpcServiceT<'a> + Send + Sync,
+{
+	type Future = S::Future;
+
+	fn call(&self, mut req: Request<'a>) -> Self::Future {
+		req.extensions_mut().insert(Role::new("admin"));
+		self.service.call(req)
+	}
+}
+
+#[tokio::test]
+async fn access_policy_works() {
+	use jsonrpsee_types::error::{ACCESS_DENIED_CODE, METHOD_NOT_FOUND_CODE};
+
+	init_logger();
+
+	let mut module = RpcModule::new(());
+	module.register_method("hello", |_, _, _| "hello").unwrap();
+	module.register_method("admin_reset", |_, _, _| "reset").unwrap();
+	module.set_method_access("admin_reset", MethodAccess::Unsafe).unwrap();
+	module.register_alias("reset", "admin_reset").unwrap();
+	let methods: Methods = module.clone().into();
+	module
+		.register_method("rpc_methods", move |_, _, ext| {
+			let policy = ext.get::<AccessPolicy>().unwrap();
+			let mut names: Vec<_> = policy.visible_methods(&methods, ext).collect();
+			names.sort();
+			names
+		})
+		.unwrap();
+
+	let deny = AccessPolicy::new().set(MethodAccess::Unsafe, Permission::Deny);
+	let hide = AccessPolicy::new().set(MethodAccess::Unsafe, Permission::Hide).set_for_role(
+		"admin",
+		MethodAccess::Unsafe,
+		Permission::Allow,
+	);
+
+	let server = ServerBuilder::default().set_access_policy(deny).build("127.0.0.1:0").await.unwrap();
+	let deny_addr = server.local_addr().unwrap();
+	let _deny_handle = server.start(module.clone());
+
+	let server = ServerBuilder::default().set_access_policy(hide.clone()).build("127.0.0.1:0").await.unwrap();
+	let hide_addr = server.local_addr().unwrap();
+	let _hide_handle = server.start(module.clone());
+
+	let server = ServerBuilder::default()
+		.set_access_policy(hide)
+		.set_rpc_middleware(RpcServiceBuilder::new().layer_fn(|service| AdminLayer { service }))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let admin_addr = server.local_addr().unwrap();
+	let _admin_handle = server.start(module);
+
+	let hello = r#"{"jsonrpc":"2.0","method":"hello","id":1}"#;
+	let reset = r#"{"jsonrpc":"2.0","method":"admin_reset","id":1}"#;
+	let alias = r#"{"jsonrpc":"2.0","method":"reset","id":1}"#;
+	let list = r#"{"jsonrpc":"2.0","method":"rpc_methods","id":1}"#;
+
+	// Denied methods are rejected but still listed.
+	let response = http_request(hello.into(), to_http_uri(deny_addr)).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("hello".into(), Id::Num(1)));
+	let response = http_request(reset.into(), to_http_uri(deny_addr)).with_default_timeout().await.unwrap().unwrap();
+	assert!(response.body.contains(&format!(r#""code":{ACCESS_DENIED_CODE}"#)));
+	let response = http_request(alias.into(), to_http_uri(deny_addr)).with_default_timeout().await.unwrap().unwrap();
+	assert!(response.body.contains(&format!(r#""code":{ACCESS_DENIED_CODE}"#)));
+	let response = http_request(list.into(), to_http_uri(deny_addr)).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(vec!["admin_reset", "hello", "reset"].into(), Id::Num(1)));
+
+	// Hidden methods look like they aren't registered.
+	let mut client = WebSocketTestClient::new(hide_addr).with_default_timeout().await.unwrap().unwrap();
+	let response = client.send_request_text(reset).with_default_timeout().await.unwrap().unwrap();
+	assert!(response.contains(&format!(r#""code":{METHOD_NOT_FOUND_CODE}"#)));
+	let response = client.send_request_text(list).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(vec!["hello"].into(), Id::Num(1)));
+
+	// The permissions of the role take precedence.
+	let mut client = WebSocketTestClient::new(admin_addr).with_default_timeout().await.unwrap().unwrap();
+	let response = client.send_request_text(reset).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("reset".into(), Id::Num(1)));
+	let response = client.send_request_text(list).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(vec!["admin_reset", "hello", "reset"].into(), Id::Num(1)));
+}
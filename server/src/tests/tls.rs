@@ -0,0 +1,166 @@
+//! The certificates in `certs` are self-signed test certificates:
+//! `ca.pem` is the root certificate which signed `server.pem` (valid for `localhost` and `127.0.0.1`)
+//! and `client.pem`.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use crate::rustls::server::AllowAnyAuthenticatedClient;
+use crate::rustls::{Certificate, ClientConfig, PrivateKey, RootCertStore, ServerConfig, ServerName};
+use crate::tests::helpers::init_logger;
+use crate::{PeerCertificates, RpcModule, ServerBuilder, ServerHandle};
+use futures_util::io::{BufReader, BufWriter};
+use jsonrpsee_test_utils::helpers::ok_response;
+use jsonrpsee_test_utils::mocks::Id;
+use jsonrpsee_test_utils::TimeoutFutureExt;
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::TlsConnector;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+const CA_CERT: &str = include_str!("certs/ca.pem");
+const SERVER_CERT: &str = include_str!("certs/server.pem");
+const SERVER_KEY: &str = include_str!("certs/server.key");
+const CLIENT_CERT: &str = include_str!("certs/client.pem");
+const CLIENT_KEY: &str = include_str!("certs/client.key");
+
+fn certs(pem: &str) -> Vec<Certificate> {
+	rustls_pemfile::certs(&mut pem.as_bytes()).unwrap().into_iter().map(Certificate).collect()
+}
+
+fn key(pem: &str) -> PrivateKey {
+	PrivateKey(rustls_pemfile::pkcs8_private_keys(&mut pem.as_bytes()).unwrap().remove(0))
+}
+
+fn roots() -> RootCertStore {
+	let mut roots = RootCertStore::empty();
+	for cert in certs(CA_CERT) {
+		roots.add(&cert).unwrap();
+	}
+	roots
+}
+
+fn server_config(mtls: bool) -> ServerConfig {
+	let builder = ServerConfig::builder().with_safe_defaults();
+	let builder = if mtls {
+		builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots()).boxed())
+	} else {
+		builder.with_no_client_auth()
+	};
+
+	builder.with_single_cert(certs(SERVER_CERT), key(SERVER_KEY)).unwrap()
+}
+
+async fn tls_connect(addr: SocketAddr, client_cert: bool) -> std::io::Result<TlsStream<TcpStream>> {
+	let builder = ClientConfig::builder().with_safe_defaults().with_root_certificates(roots());
+	let config = if client_cert {
+		builder.with_client_auth_cert(certs(CLIENT_CERT), key(CLIENT_KEY)).unwrap()
+	} else {
+		builder.with_no_client_auth()
+	};
+
+	let socket = TcpStream::connect(addr).await?;
+	TlsConnector::from(Arc::new(config)).connect(ServerName::try_from("localhost").unwrap(), socket).await
+}
+
+async fn https_request(addr: SocketAddr, client_cert: bool, body: &'static str) -> Result<String, String> {
+	let stream = tls_connect(addr, client_cert).await.map_err(|e| e.to_string())?;
+	let (mut sender, conn) = hyper::client::conn::handshake(stream).await.map_err(|e| e.to_string())?;
+	tokio::spawn(conn);
+
+	let req = hyper::Request::post("/")
+		.header(hyper::header::HOST, "localhost")
+		.header(hyper::header::CONTENT_TYPE, "application/json")
+		.body(hyper::Body::from(body))
+		.unwrap();
+	let response = sender.send_request(req).await.map_err(|e| e.to_string())?;
+	let body = hyper::body::to_bytes(response.into_body()).await.map_err(|e| e.to_string())?;
+
+	Ok(String::from_utf8(body.to_vec()).unwrap())
+}
+
+async fn wss_request(addr: SocketAddr, body: &'static str) -> String {
+	let stream = tls_connect(addr, false).await.unwrap();
+	let mut client = soketto::handshake::Client::new(BufReader::new(BufWriter::new(stream.compat())), "localhost", "/");
+
+	match client.handshake().await.unwrap() {
+		soketto::handshake::ServerResponse::Accepted { .. } => (),
+		other => panic!("WebSocket handshake failed: {other:?}"),
+	}
+
+	let (mut tx, mut rx) = client.into_builder().finish();
+	tx.send_text(body).await.unwrap();
+	tx.flush().await.unwrap();
+
+	let mut data = Vec::new();
+	rx.receive_data(&mut data).await.unwrap();
+	String::from_utf8(data).unwrap()
+}
+
+async fn server(mtls: bool) -> (SocketAddr, ServerHandle) {
+	let server = ServerBuilder::default().tls(server_config(mtls)).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
+	module
+		.register_method("peer_cert", |_, _, ext| {
+			ext.get::<PeerCertificates>().and_then(|certs| certs.end_entity()).map(|cert| cert.0.clone())
+		})
+		.unwrap();
+
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module);
+
+	(addr, handle)
+}
+
+#[tokio::test]
+async fn tls_http_works() {
+	init_logger();
+	let (addr, _handle) = server(false).with_default_timeout().await.unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = https_request(addr, false, req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("hello".into(), Id::Num(1)));
+
+	let req = r#"{"jsonrpc":"2.0","method":"peer_cert","id":1}"#;
+	let response = https_request(addr, false, req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(serde_json::Value::Null, Id::Num(1)));
+}
+
+#[tokio::test]
+async fn tls_ws_works() {
+	init_logger();
+	let (addr, _handle) = server(false).with_default_timeout().await.unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = wss_request(addr, req).with_default_timeout().await.unwrap();
+	assert_eq!(response, ok_response("hello".into(), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn plaintext_request_to_tls_server_fails() {
+	init_logger();
+	let (addr, _handle) = server(false).with_default_timeout().await.unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response =
+		jsonrpsee_test_utils::helpers::http_request(req.into(), jsonrpsee_test_utils::helpers::to_http_uri(addr))
+			.with_default_timeout()
+			.await
+			.unwrap();
+	assert!(response.is_err());
+}
+
+#[tokio::test]
+async fn mtls_exposes_peer_certificate() {
+	init_logger();
+	let (addr, _handle) = server(true).with_default_timeout().await.unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"peer_cert","id":1}"#;
+	let response = https_request(addr, true, req).with_default_timeout().await.unwrap().unwrap();
+	let client_cert = certs(CLIENT_CERT).remove(0);
+	assert_eq!(response, ok_response(serde_json::json!(client_cert.0), Id::Num(1)));
+
+	// Clients without a certificate are rejected.
+	assert!(https_request(addr, false, req).with_default_timeout().await.unwrap().is_err());
+}
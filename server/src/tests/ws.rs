@@ -29,13 +29,14 @@ use std::time::Duration;
 use crate::server::BatchRequestConfig;
 use crate::tests::helpers::{deser_call, init_logger, server_with_context};
 use crate::types::SubscriptionId;
-use crate::{RpcModule, ServerBuilder};
+use crate::{ResultStream, RpcModule, ServerBuilder};
 use jsonrpsee_core::server::{SendTimeoutError, SubscriptionMessage};
 use jsonrpsee_core::{traits::IdProvider, Error};
 use jsonrpsee_test_utils::helpers::*;
 use jsonrpsee_test_utils::mocks::{Id, WebSocketTestClient, WebSocketTestError};
 use jsonrpsee_test_utils::TimeoutFutureExt;
-use jsonrpsee_types::SubscriptionResponse;
+use jsonrpsee_types::error::ErrorCode;
+use jsonrpsee_types::{ErrorObjectOwned, SubscriptionResponse};
 use serde_json::Value as JsonValue;
 
 use super::helpers::server;
@@ -48,7 +49,7 @@ async fn can_set_the_max_request_body_size() {
 	// Rejects all requests larger than 100 bytes
 	let server = ServerBuilder::default().max_request_body_size(100).build(addr).await.unwrap();
 	let mut module = RpcModule::new(());
-	module.register_method("anything", |_p, _cx| "a".repeat(100)).unwrap();
+	module.register_method("anything", |_p, _cx, _| "a".repeat(100)).unwrap();
 	let addr = server.local_addr().unwrap();
 	let handle = server.start(module);
 
@@ -76,7 +77,7 @@ async fn can_set_the_max_response_body_size() {
 	// Set the max response body size to 100 bytes
 	let server = ServerBuilder::default().max_response_body_size(100).build(addr).await.unwrap();
 	let mut module = RpcModule::new(());
-	module.register_method("anything", |_p, _cx| "a".repeat(101)).unwrap();
+	module.register_method("anything", |_p, _cx, _| "a".repeat(101)).unwrap();
 	let addr = server.local_addr().unwrap();
 	let server_handle = server.start(module);
 
@@ -99,7 +100,7 @@ async fn can_set_the_max_response_size_to_batch() {
 	// Set the max response body size to 100 bytes
 	let server = ServerBuilder::default().max_response_body_size(100).build(addr).await.unwrap();
 	let mut module = RpcModule::new(());
-	module.register_method("anything", |_p, _cx| "a".repeat(51)).unwrap();
+	module.register_method("anything", |_p, _cx, _| "a".repeat(51)).unwrap();
 	let addr = server.local_addr().unwrap();
 	let server_handle = server.start(module);
 
@@ -122,7 +123,7 @@ async fn can_set_max_connections() {
 	// Server that accepts max 2 connections
 	let server = ServerBuilder::default().max_connections(2).build(addr).await.unwrap();
 	let mut module = RpcModule::new(());
-	module.register_method("anything", |_p, _cx| ()).unwrap();
+	module.register_method("anything", |_p, _cx, _| ()).unwrap();
 	let addr = server.local_addr().unwrap();
 
 	let server_handle = server.start(module);
@@ -416,18 +417,21 @@ async fn unknown_field_is_ok() {
 #[tokio::test]
 async fn register_methods_works() {
 	let mut module = RpcModule::new(());
-	assert!(module.register_method("say_hello", |_, _| "lo").is_ok());
-	assert!(module.register_method("say_hello", |_, _| "lo").is_err());
+	assert!(module.register_method("say_hello", |_, _, _| "lo").is_ok());
+	assert!(module.register_method("say_hello", |_, _, _| "lo").is_err());
 	assert!(module
-		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, _, _| async { Ok(()) })
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, _, _, _| async { Ok(()) })
 		.is_ok());
 	assert!(module
-		.register_subscription("subscribe_hello_again", "subscribe_hello_again", "unsubscribe_hello", |_, _, _| async {
-			Ok(())
-		})
+		.register_subscription(
+			"subscribe_hello_again",
+			"subscribe_hello_again",
+			"unsubscribe_hello",
+			|_, _, _, _| async { Ok(()) }
+		)
 		.is_err());
 	assert!(
-		module.register_method("subscribe_hello_again", |_, _| "lo").is_ok(),
+		module.register_method("subscribe_hello_again", |_, _, _| "lo").is_ok(),
 		"Failed register_subscription should not have side-effects"
 	);
 }
@@ -436,8 +440,9 @@ async fn register_methods_works() {
 async fn register_same_subscribe_unsubscribe_is_err() {
 	let mut module = RpcModule::new(());
 	assert!(matches!(
-		module
-			.register_subscription("subscribe_hello", "subscribe_hello", "subscribe_hello", |_, _, _| async { Ok(()) }),
+		module.register_subscription("subscribe_hello", "subscribe_hello", "subscribe_hello", |_, _, _, _| async {
+			Ok(())
+		}),
 		Err(Error::SubscriptionNameConflict(_))
 	));
 }
@@ -499,12 +504,12 @@ async fn can_register_modules() {
 
 	assert_eq!(mod1.method_names().count(), 0);
 	assert_eq!(mod2.method_names().count(), 0);
-	mod1.register_method("bla", |_, cx| format!("Gave me {cx}")).unwrap();
-	mod1.register_method("bla2", |_, cx| format!("Gave me {cx}")).unwrap();
-	mod2.register_method("yada", |_, cx| format!("Gave me {cx:?}")).unwrap();
+	mod1.register_method("bla", |_, cx, _| format!("Gave me {cx}")).unwrap();
+	mod1.register_method("bla2", |_, cx, _| format!("Gave me {cx}")).unwrap();
+	mod2.register_method("yada", |_, cx, _| format!("Gave me {cx:?}")).unwrap();
 
 	// Won't register, name clashes
-	mod2.register_method("bla", |_, cx| format!("Gave me {cx:?}")).unwrap();
+	mod2.register_method("bla", |_, cx, _| format!("Gave me {cx:?}")).unwrap();
 
 	assert_eq!(mod1.method_names().count(), 2);
 	let err = mod1.merge(mod2).unwrap_err();
@@ -564,7 +569,7 @@ async fn custom_subscription_id_works() {
 	let addr = server.local_addr().unwrap();
 	let mut module = RpcModule::new(());
 	module
-		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, sink, _| async {
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, sink, _, _| async {
 			let sink = sink.accept().await.unwrap();
 
 			assert!(matches!(sink.subscription_id(), SubscriptionId::Str(id) if id == "0xdeadbeef"));
@@ -597,7 +602,7 @@ async fn disabled_batches() {
 		.unwrap();
 
 	let mut module = RpcModule::new(());
-	module.register_method("should_ok", |_, _ctx| "ok").unwrap();
+	module.register_method("should_ok", |_, _ctx, _| "ok").unwrap();
 	let addr = server.local_addr().unwrap();
 
 	let server_handle = server.start(module);
@@ -627,7 +632,7 @@ async fn batch_limit_works() {
 		.unwrap();
 
 	let mut module = RpcModule::new(());
-	module.register_method("should_ok", |_, _ctx| "ok").unwrap();
+	module.register_method("should_ok", |_, _ctx, _| "ok").unwrap();
 	let addr = server.local_addr().unwrap();
 
 	let server_handle = server.start(module);
@@ -731,7 +736,7 @@ async fn ws_server_backpressure_works() {
 			"subscribe_with_backpressure_aggregation",
 			"n",
 			"unsubscribe_with_backpressure_aggregation",
-			move |_, pending, mut backpressure_tx| async move {
+			move |_, pending, mut backpressure_tx, _| async move {
 				let sink = pending.accept().await?;
 				let n = SubscriptionMessage::from_json(&1)?;
 				let bp = SubscriptionMessage::from_json(&2)?;
@@ -891,7 +896,7 @@ async fn server_with_infinite_call(
 	let mut module = RpcModule::new(tx);
 
 	module
-		.register_async_method("infinite_call", |_, mut ctx| async move {
+		.register_async_method("infinite_call", |_, mut ctx, _| async move {
 			let tx = std::sync::Arc::make_mut(&mut ctx);
 			tx.send(()).unwrap();
 			futures_util::future::pending::<()>().await;
@@ -902,3 +907,207 @@ async fn server_with_infinite_call(
 
 	(server.start(module), addr)
 }
+
+#[tokio::test]
+async fn ws_max_concurrent_calls_reject_works() {
+	use crate::logger::{HttpRequest, Logger, MethodKind, Params, SuccessOrError, TransportProtocol};
+	use crate::ConcurrentCallsPolicy;
+	use std::net::SocketAddr;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+
+	#[derive(Clone, Default)]
+	struct LimitCounter(Arc<AtomicUsize>);
+
+	impl Logger for LimitCounter {
+		type Instant = ();
+
+		fn on_connect(&self, _: SocketAddr, _: &HttpRequest, _: TransportProtocol) {}
+		fn on_request(&self, _: TransportProtocol) -> Self::Instant {}
+		fn on_call(&self, _: &str, _: Params, _: MethodKind, _: TransportProtocol) {}
+		fn on_result(&self, _: &str, _: SuccessOrError, _: Self::Instant, _: TransportProtocol) {}
+		fn on_response(&self, _: &str, _: Self::Instant, _: TransportProtocol) {}
+		fn on_disconnect(&self, _: SocketAddr, _: TransportProtocol) {}
+
+		fn on_concurrent_calls_limit(&self, policy: ConcurrentCallsPolicy, _: TransportProtocol) {
+			assert_eq!(policy, ConcurrentCallsPolicy::Reject);
+			self.0.fetch_add(1, Ordering::SeqCst);
+		}
+	}
+
+	init_logger();
+
+	let logger = LimitCounter::default();
+	let server = ServerBuilder::default()
+		.max_concurrent_calls_per_connection(1, ConcurrentCallsPolicy::Reject)
+		.set_logger(logger.clone())
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("sleep_ms", |params, _, _| async move {
+			let ms: u64 = params.one().unwrap();
+			tokio::time::sleep(Duration::from_millis(ms)).await;
+			ms
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	client.send(r#"{"jsonrpc":"2.0","method":"sleep_ms","params":[500],"id":1}"#).await.unwrap();
+	let busy = client.send_request_text(r#"{"jsonrpc":"2.0","method":"sleep_ms","params":[0],"id":2}"#).await.unwrap();
+	assert_eq!(busy, server_is_busy(Id::Num(2)));
+
+	// Notifications are dropped silently.
+	client.send(r#"{"jsonrpc":"2.0","method":"sleep_ms","params":[0]}"#).await.unwrap();
+
+	let response = client.receive().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(500.into(), Id::Num(1)));
+
+	// The slot is free again once the call completed.
+	let response =
+		client.send_request_text(r#"{"jsonrpc":"2.0","method":"sleep_ms","params":[0],"id":3}"#).await.unwrap();
+	assert_eq!(response, ok_response(0.into(), Id::Num(3)));
+	assert_eq!(logger.0.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn ws_max_concurrent_calls_backpressure_works() {
+	init_logger();
+
+	let server = ServerBuilder::default()
+		.max_concurrent_calls_per_connection(1, crate::ConcurrentCallsPolicy::Backpressure)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("sleep_ms", |params, _, _| async move {
+			let ms: u64 = params.one().unwrap();
+			tokio::time::sleep(Duration::from_millis(ms)).await;
+			ms
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	client.send(r#"{"jsonrpc":"2.0","method":"sleep_ms","params":[200],"id":1}"#).await.unwrap();
+	client.send(r#"{"jsonrpc":"2.0","method":"sleep_ms","params":[0],"id":2}"#).await.unwrap();
+
+	// The second call is only read once the first one completed, so it can't overtake it.
+	let first = client.receive().with_default_timeout().await.unwrap().unwrap();
+	let second = client.receive().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(first, ok_response(200.into(), Id::Num(1)));
+	assert_eq!(second, ok_response(0.into(), Id::Num(2)));
+}
+
+#[tokio::test]
+async fn ws_rate_limits_work() {
+	use crate::{RateLimit, RateLimits};
+
+	init_logger();
+
+	let server = ServerBuilder::default()
+		.set_rate_limits(RateLimits::new().per_connection(RateLimit::new(2, Duration::from_secs(60))))
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _, _| "lo").unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	for id in 0..2 {
+		let req = format!(r#"{{"jsonrpc":"2.0","method":"say_hello","id":{id}}}"#);
+		let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response, ok_response("lo".into(), Id::Num(id)));
+	}
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":2}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	let err: JsonValue = serde_json::from_str(&response).unwrap();
+	assert_eq!(err["id"], 2);
+	assert_eq!(err["error"]["code"], jsonrpsee_types::error::RATE_LIMITED_CODE);
+	assert!(err["error"]["data"]["retry_after_ms"].as_u64().unwrap() > 29_000);
+
+	// Each connection has its own limit.
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response("lo".into(), Id::Num(2)));
+}
+
+#[tokio::test]
+async fn json_rpc_v1_subscription_works() {
+	init_logger();
+
+	let server = ServerBuilder::default().enable_json_rpc_v1().build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_hello", "hello", "unsubscribe_hello", |_, pending, _, _| async {
+			let sink = pending.accept().await?;
+			sink.send(SubscriptionMessage::from_json(&"hello")?).await?;
+			Ok(())
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let sub = client.send_request_text(r#"{"method":"subscribe_hello","params":[],"id":1}"#).await.unwrap();
+	let sub: JsonValue = serde_json::from_str(&sub).unwrap();
+	assert_eq!(sub["error"], JsonValue::Null);
+	assert_eq!(sub["id"], 1);
+
+	// The notifications of a JSON-RPC 1.0 subscription use JSON-RPC 1.0 framing.
+	let notif = client.receive().with_default_timeout().await.unwrap().unwrap();
+	let expected =
+		format!(r#"{{"method":"hello","params":{{"subscription":{},"result":"hello"}},"id":null}}"#, sub["result"]);
+	assert_eq!(notif, expected);
+}
+
+#[tokio::test]
+async fn streaming_method_works() {
+	init_logger();
+
+	let server = ServerBuilder::default().max_response_body_size(1000).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_streaming_method("state_dump", |params, _, _| async move {
+			let len: u32 = params.one().map_err(|_| ErrorObjectOwned::from(ErrorCode::InvalidParams))?;
+			Ok(ResultStream::from_items(futures_util::stream::iter(0..len)))
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	// The result isn't limited by the max response size and is sent as a fragmented message.
+	let req = r#"{"jsonrpc":"2.0","method":"state_dump","params":[100000],"id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response((0..100000).collect::<Vec<u32>>().into(), Id::Num(1)));
+
+	// The connection is still usable afterwards.
+	let req = r#"{"jsonrpc":"2.0","method":"state_dump","params":[3],"id":2}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(vec![0, 1, 2].into(), Id::Num(2)));
+
+	// Batches are buffered within the max response size.
+	let req = r#"[{"jsonrpc":"2.0","method":"state_dump","params":[3],"id":3},
+		{"jsonrpc":"2.0","method":"state_dump","params":[100000],"id":4}]"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(
+		response,
+		format!("[{},{}]", ok_response(vec![0, 1, 2].into(), Id::Num(3)), oversized_response(Id::Num(4), 1000))
+	);
+}
@@ -0,0 +1,135 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! TLS termination for the server, based on [`rustls`](tokio_rustls::rustls).
+
+use std::error::Error as StdError;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use crate::future::StopHandle;
+use crate::server::to_http_service;
+use crate::transport::listener::Socket;
+
+use futures_util::future::{self, Either};
+use hyper::body::HttpBody;
+use tokio_rustls::rustls::{Certificate, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+use tower::Service;
+
+/// The certificate chain presented by the client during the TLS handshake.
+///
+/// It's only available if the [`ServerConfig`] passed to [`ServerBuilder::tls`](crate::ServerBuilder::tls)
+/// was configured to verify client certificates, i.e, mutual TLS.
+///
+/// The server inserts it into the [`Extensions`](crate::Extensions) of every call and into the
+/// extensions of the HTTP request such that it's available both to the HTTP middleware and to the method callbacks.
+#[derive(Debug, Clone)]
+pub struct PeerCertificates(Arc<Vec<Certificate>>);
+
+impl PeerCertificates {
+	/// Get the end-entity certificate of the client.
+	pub fn end_entity(&self) -> Option<&Certificate> {
+		self.0.first()
+	}
+
+	/// Get the whole certificate chain where the first certificate is the end-entity certificate.
+	pub fn chain(&self) -> &[Certificate] {
+		&self.0
+	}
+}
+
+/// TLS configuration of the server.
+#[derive(Clone)]
+pub(crate) struct TlsConfig(TlsAcceptor);
+
+impl TlsConfig {
+	pub(crate) fn new(config: Arc<ServerConfig>) -> Self {
+		Self(TlsAcceptor::from(config))
+	}
+}
+
+impl std::fmt::Debug for TlsConfig {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("TlsConfig").finish_non_exhaustive()
+	}
+}
+
+/// Performs the TLS handshake and then serves the HTTP connection on the encrypted stream.
+pub(crate) async fn to_https_service<S, B>(tls: TlsConfig, socket: Socket, service: S, stop_handle: StopHandle)
+where
+	S: Service<hyper::Request<hyper::Body>, Response = hyper::Response<B>> + Send + 'static,
+	S::Error: Into<Box<dyn StdError + Send + Sync>>,
+	S::Future: Send,
+	B: HttpBody + Send + 'static,
+	<B as HttpBody>::Error: Send + Sync + StdError,
+	<B as HttpBody>::Data: Send,
+{
+	let accept = tls.0.accept(socket);
+	let stopped = stop_handle.clone().shutdown();
+	tokio::pin!(accept, stopped);
+
+	let stream = match future::select(accept, stopped).await {
+		Either::Left((Ok(stream), _)) => stream,
+		Either::Left((Err(e), _)) => {
+			tracing::debug!("TLS handshake failed: {:?}", e);
+			return;
+		}
+		Either::Right(_) => return,
+	};
+
+	let peer_certs = stream.get_ref().1.peer_certificates().map(|certs| PeerCertificates(Arc::new(certs.to_vec())));
+
+	match peer_certs {
+		Some(peer_certs) => to_http_service(stream, WithPeerCertificates { service, peer_certs }, stop_handle).await,
+		None => to_http_service(stream, service, stop_handle).await,
+	}
+}
+
+/// Service that inserts the [`PeerCertificates`] of the connection into
+/// the extensions of every HTTP request.
+struct WithPeerCertificates<S> {
+	service: S,
+	peer_certs: PeerCertificates,
+}
+
+impl<S> Service<hyper::Request<hyper::Body>> for WithPeerCertificates<S>
+where
+	S: Service<hyper::Request<hyper::Body>>,
+{
+	type Response = S::Response;
+	type Error = S::Error;
+	type Future = S::Future;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.service.poll_ready(cx)
+	}
+
+	fn call(&mut self, mut request: hyper::Request<hyper::Body>) -> Self::Future {
+		request.extensions_mut().insert(self.peer_certs.clone());
+		self.service.call(request)
+	}
+}
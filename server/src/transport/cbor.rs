@@ -0,0 +1,109 @@
+//! Decoding of CBOR encoded JSON-RPC messages.
+//!
+//! The messages are decoded once into a [`Value`] from which the members of the calls are taken,
+//! the params are handed over to the method as they are, see [`Params::from_cbor`].
+
+use jsonrpsee_types::{Id, Params, Request};
+
+use ciborium::Value;
+
+/// A decoded CBOR message.
+pub(crate) enum Message {
+	/// A single call.
+	Single(Value),
+	/// A batch of calls.
+	Batch(Vec<Value>),
+}
+
+impl Message {
+	/// Decode a message, `None` if it isn't valid CBOR.
+	pub(crate) fn decode(data: &[u8]) -> Option<Self> {
+		match ciborium::from_reader(data).ok()? {
+			Value::Array(calls) => Some(Self::Batch(calls)),
+			call => Some(Self::Single(call)),
+		}
+	}
+}
+
+/// A call of a CBOR message.
+pub(crate) enum Call {
+	/// A method call, whose params are in [`Request::decoded_params`].
+	Request(Request<'static>),
+	/// A notification.
+	Notification(String, Params<'static>),
+	/// A call which isn't a valid request object.
+	Invalid(Id<'static>),
+}
+
+impl Call {
+	/// Take the members of a call from a decoded request object.
+	pub(crate) fn parse(call: Value) -> Self {
+		let Value::Map(members) = call else {
+			return Self::Invalid(Id::Null);
+		};
+
+		let mut version = None;
+		let mut id = None;
+		let mut method = None;
+		let mut params = None;
+		let mut traceparent = None;
+
+		for (key, value) in members {
+			match key.as_text() {
+				Some("jsonrpc") => version = Some(value),
+				Some("id") => id = Some(value),
+				Some("method") => method = Some(value),
+				Some("params") => params = Some(value),
+				Some("traceparent") => traceparent = Some(value),
+				_ => (),
+			}
+		}
+
+		let id = match id.map(parse_id) {
+			Some(Some(id)) => Some(id),
+			Some(None) => return Self::Invalid(Id::Null),
+			None => None,
+		};
+
+		let method = match (version, method) {
+			(Some(Value::Text(version)), Some(Value::Text(method))) if version == "2.0" => method,
+			_ => return Self::Invalid(id.unwrap_or(Id::Null)),
+		};
+
+		let params = Params::from_cbor(params);
+
+		match id {
+			Some(id) => {
+				let mut req = Request::new(method.into(), None, id);
+				req.decoded_params = Some(params);
+				req.traceparent = match traceparent {
+					Some(Value::Text(traceparent)) => Some(traceparent.into()),
+					_ => None,
+				};
+				Self::Request(req)
+			}
+			None => Self::Notification(method, params),
+		}
+	}
+}
+
+/// Get the `id` of a message, which is `None` for notifications and `Id::Null` for anything but a request.
+pub(crate) fn message_id(data: &[u8]) -> Option<Id<'static>> {
+	match Message::decode(data) {
+		Some(Message::Single(call)) => match Call::parse(call) {
+			Call::Request(req) => Some(req.id),
+			Call::Notification(..) => None,
+			Call::Invalid(id) => Some(id),
+		},
+		_ => Some(Id::Null),
+	}
+}
+
+fn parse_id(id: Value) -> Option<Id<'static>> {
+	match id {
+		Value::Null => Some(Id::Null),
+		Value::Integer(n) => u64::try_from(n).ok().map(Id::Number),
+		Value::Text(s) => Some(Id::Str(s.into())),
+		_ => None,
+	}
+}
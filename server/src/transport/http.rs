@@ -1,33 +1,51 @@
 use std::convert::Infallible;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
+use crate::future::ConnectionPermit;
 use crate::logger::{self, Logger, TransportProtocol};
-use crate::server::BatchRequestConfig;
+use crate::middleware::rpc::{IsNotification, RpcService, RpcServiceBuilder, RpcServiceCfg, RpcServiceT};
+use crate::rate_limit::ConnectionRateLimiter;
+use crate::server::{BatchRequestConfig, ConnectionDetails, SharedMethods};
+#[cfg(feature = "cbor")]
+use crate::transport::cbor;
+use crate::transport::{call_trace_context, JsonCall};
 
 use futures_util::future::Either;
 use futures_util::stream::{FuturesOrdered, StreamExt};
 use hyper::Method;
 use jsonrpsee_core::error::GenericTransportError;
-use jsonrpsee_core::http_helpers::read_body;
+#[cfg(feature = "cbor")]
+use jsonrpsee_core::http_helpers::read_body_bytes;
+use jsonrpsee_core::http_helpers::{read_body, ContentEncoding};
 use jsonrpsee_core::server::helpers::{
 	batch_response_error, prepare_error, BatchResponseBuilder, MethodResponse, MethodResponseResult,
 };
-use jsonrpsee_core::server::{MethodCallback, Methods};
-use jsonrpsee_core::tracing::{rx_log_from_json, tx_log_from_str};
+use jsonrpsee_core::server::{AccessPolicy, ConnectionId, EncodedMessage, Encoding, MethodCallback, Methods};
+use jsonrpsee_core::tracing::{rx_log_from_json, tx_log_from_str, TraceParent};
+use jsonrpsee_core::traits::IdProvider;
 use jsonrpsee_core::JsonRawValue;
 use jsonrpsee_types::error::{
 	reject_too_big_batch_request, ErrorCode, BATCHES_NOT_SUPPORTED_CODE, BATCHES_NOT_SUPPORTED_MSG,
 };
-use jsonrpsee_types::{ErrorObject, Id, InvalidRequest, Notification, Params, Request};
-use tokio::sync::OwnedSemaphorePermit;
+use jsonrpsee_types::{ErrorObject, Extensions, Id, Params, Request};
+use tracing::field::Empty;
 use tracing::instrument;
 
-type Notif<'a> = Notification<'a, Option<&'a JsonRawValue>>;
+/// Returns the encoding of the request body if its content type is valid for JSON-RPC.
+pub(crate) fn body_encoding(request: &hyper::Request<hyper::Body>) -> Option<Encoding> {
+	let content_type = request.headers().get(hyper::header::CONTENT_TYPE);
 
-/// Checks that content type of received request is valid for JSON-RPC.
-pub(crate) fn content_type_is_json(request: &hyper::Request<hyper::Body>) -> bool {
-	is_json(request.headers().get(hyper::header::CONTENT_TYPE))
+	if is_json(content_type) {
+		return Some(Encoding::Json);
+	}
+
+	// JSON bodies are only accepted with the content types checked by `is_json`.
+	content_type
+		.and_then(|val| val.to_str().ok())
+		.and_then(Encoding::from_content_type)
+		.filter(|encoding| *encoding != Encoding::Json)
 }
 
 /// Returns true if the `content_type` header indicates a valid JSON message.
@@ -39,9 +57,9 @@ pub(crate) fn is_json(content_type: Option<&hyper::header::HeaderValue>) -> bool
 	})
 }
 
-pub(crate) async fn reject_connection(socket: tokio::net::TcpStream) {
+pub(crate) async fn reject_connection(socket: crate::transport::listener::Socket) {
 	async fn reject(_req: hyper::Request<hyper::Body>) -> Result<hyper::Response<hyper::Body>, Infallible> {
-		Ok(response::too_many_requests())
+		Ok(response::too_many_requests(None))
 	}
 
 	if let Err(e) = hyper::server::conn::Http::new().serve_connection(socket, hyper::service::service_fn(reject)).await
@@ -51,51 +69,89 @@ pub(crate) async fn reject_connection(socket: tokio::net::TcpStream) {
 }
 
 #[derive(Debug)]
-pub(crate) struct ProcessValidatedRequest<'a, L: Logger> {
+pub(crate) struct ProcessValidatedRequest<'a, L: Logger, S> {
 	pub(crate) request: hyper::Request<hyper::Body>,
 	pub(crate) logger: &'a L,
 	pub(crate) methods: Methods,
+	pub(crate) rpc_service: &'a S,
 	pub(crate) max_request_body_size: u32,
 	pub(crate) max_response_body_size: u32,
 	pub(crate) max_log_length: u32,
 	pub(crate) batch_requests_config: BatchRequestConfig,
 	pub(crate) request_start: L::Instant,
+	pub(crate) extensions: Extensions,
+	pub(crate) access_policy: &'a AccessPolicy,
+	pub(crate) json_rpc_v1: bool,
 }
 
-/// Process a verified request, it implies a POST request with content type JSON.
-pub(crate) async fn process_validated_request<L: Logger>(
-	input: ProcessValidatedRequest<'_, L>,
-) -> hyper::Response<hyper::Body> {
+/// Process a verified request, it implies a POST request with a supported content type.
+pub(crate) async fn process_validated_request<L, S>(
+	input: ProcessValidatedRequest<'_, L, S>,
+) -> hyper::Response<hyper::Body>
+where
+	L: Logger,
+	for<'a> S: RpcServiceT<'a> + Send + Sync,
+{
 	let ProcessValidatedRequest {
 		request,
 		logger,
 		methods,
+		rpc_service,
 		max_request_body_size,
 		max_response_body_size,
 		max_log_length,
 		batch_requests_config,
 		request_start,
+		extensions,
+		access_policy,
+		json_rpc_v1,
 	} = input;
 
 	let (parts, body) = request.into_parts();
 
+	#[cfg(feature = "cbor")]
+	if Encoding::from_extensions(&extensions) == Encoding::Cbor {
+		let body = match read_body_bytes(&parts.headers, body, max_request_body_size).await {
+			Ok(body) => body,
+			Err(e) => return body_error(e, max_request_body_size),
+		};
+		let call = CallData {
+			logger,
+			methods: &methods,
+			rpc_service,
+			max_response_body_size,
+			max_log_length,
+			request_start,
+			extensions: &extensions,
+			access_policy,
+			json_rpc_v1,
+		};
+		let response = process_cbor_request(body, call, batch_requests_config).await;
+		logger.on_response(response.as_log_str(), request_start, TransportProtocol::Http);
+		return response::ok_encoded_response(response, Encoding::Cbor);
+	}
+
 	let (body, is_single) = match read_body(&parts.headers, body, max_request_body_size).await {
 		Ok(r) => r,
-		Err(GenericTransportError::TooLarge) => return response::too_large(max_request_body_size),
-		Err(GenericTransportError::Malformed) => return response::malformed(),
-		Err(GenericTransportError::Inner(e)) => {
-			tracing::warn!("Internal error reading request body: {}", e);
-			return response::internal_error();
-		}
+		Err(e) => return body_error(e, max_request_body_size),
 	};
 
 	// Single request or notification
 	if is_single {
-		let call =
-			CallData { conn_id: 0, logger, methods: &methods, max_response_body_size, max_log_length, request_start };
+		let call = CallData {
+			logger,
+			methods: &methods,
+			rpc_service,
+			max_response_body_size,
+			max_log_length,
+			request_start,
+			extensions: &extensions,
+			access_policy,
+			json_rpc_v1,
+		};
 		let response = process_single_request(body, call).await;
-		logger.on_response(&response.result, request_start, TransportProtocol::Http);
-		response::ok_response(response.result)
+		logger.on_response(response.result.as_log_str(), request_start, TransportProtocol::Http);
+		response::ok_encoded_response(response.result, Encoding::Json)
 	}
 	// Batch of requests.
 	else {
@@ -105,8 +161,8 @@ pub(crate) async fn process_validated_request<L: Logger>(
 					Id::Null,
 					ErrorObject::borrowed(BATCHES_NOT_SUPPORTED_CODE, BATCHES_NOT_SUPPORTED_MSG, None),
 				);
-				logger.on_response(&response.result, request_start, TransportProtocol::Http);
-				return response::ok_response(response.result);
+				logger.on_response(response.result.as_log_str(), request_start, TransportProtocol::Http);
+				return response::ok_encoded_response(response.result, Encoding::Json);
 			}
 			BatchRequestConfig::Limit(limit) => limit as usize,
 			BatchRequestConfig::Unlimited => usize::MAX,
@@ -115,51 +171,88 @@ pub(crate) async fn process_validated_request<L: Logger>(
 		let response = process_batch_request(Batch {
 			data: body,
 			call: CallData {
-				conn_id: 0,
 				logger,
 				methods: &methods,
+				rpc_service,
 				max_response_body_size,
 				max_log_length,
 				request_start,
+				extensions: &extensions,
+				access_policy,
+				json_rpc_v1,
 			},
 			max_len: limit,
 		})
 		.await;
-		logger.on_response(&response, request_start, TransportProtocol::Http);
-		response::ok_response(response)
+		logger.on_response(response.as_log_str(), request_start, TransportProtocol::Http);
+		response::ok_encoded_response(response, Encoding::Json)
+	}
+}
+
+/// Create the response for a request body that couldn't be read.
+pub(crate) fn body_error(err: GenericTransportError, max_request_body_size: u32) -> hyper::Response<hyper::Body> {
+	match err {
+		GenericTransportError::TooLarge => response::too_large(max_request_body_size),
+		GenericTransportError::Malformed => response::malformed(),
+		GenericTransportError::UnsupportedEncoding => response::unsupported_content_encoding(),
+		GenericTransportError::Inner(e) => {
+			tracing::warn!("Internal error reading request body: {}", e);
+			response::internal_error()
+		}
 	}
 }
 
 #[derive(Debug, Clone)]
-pub(crate) struct Batch<'a, L: Logger> {
+pub(crate) struct Batch<'a, L: Logger, S> {
 	data: Vec<u8>,
-	call: CallData<'a, L>,
+	call: CallData<'a, L, S>,
 	max_len: usize,
 }
 
-#[derive(Debug, Clone)]
-pub(crate) struct CallData<'a, L: Logger> {
-	conn_id: usize,
-	logger: &'a L,
-	methods: &'a Methods,
-	max_response_body_size: u32,
-	max_log_length: u32,
-	request_start: L::Instant,
+#[derive(Debug)]
+pub(crate) struct CallData<'a, L: Logger, S> {
+	pub(crate) logger: &'a L,
+	pub(crate) methods: &'a Methods,
+	pub(crate) rpc_service: &'a S,
+	pub(crate) max_response_body_size: u32,
+	pub(crate) max_log_length: u32,
+	pub(crate) request_start: L::Instant,
+	pub(crate) extensions: &'a Extensions,
+	pub(crate) access_policy: &'a AccessPolicy,
+	pub(crate) json_rpc_v1: bool,
+}
+
+// NOTE: a manual `Clone` impl is needed because `#[derive(Clone)]` would require `S: Clone`.
+impl<'a, L: Logger, S> Clone for CallData<'a, L, S> {
+	fn clone(&self) -> Self {
+		Self {
+			logger: self.logger,
+			methods: self.methods,
+			rpc_service: self.rpc_service,
+			max_response_body_size: self.max_response_body_size,
+			max_log_length: self.max_log_length,
+			request_start: self.request_start,
+			extensions: self.extensions,
+			access_policy: self.access_policy,
+			json_rpc_v1: self.json_rpc_v1,
+		}
+	}
 }
 
 // Batch responses must be sent back as a single message so we read the results from each
 // request in the batch and read the results off of a new channel, `rx_batch`, and then send the
 // complete batch response back to the client over `tx`.
 #[instrument(name = "batch", skip(b), level = "TRACE")]
-pub(crate) async fn process_batch_request<L>(b: Batch<'_, L>) -> String
+pub(crate) async fn process_batch_request<L, S>(b: Batch<'_, L, S>) -> EncodedMessage
 where
 	L: Logger,
+	for<'a> S: RpcServiceT<'a> + Send + Sync,
 {
 	let Batch { data, call, max_len } = b;
 
 	if let Ok(batch) = serde_json::from_slice::<Vec<&JsonRawValue>>(&data) {
 		if batch.len() > max_len {
-			return batch_response_error(Id::Null, reject_too_big_batch_request(max_len));
+			return batch_response_error(Id::Null, reject_too_big_batch_request(max_len)).into();
 		}
 
 		let mut got_notif = false;
@@ -167,153 +260,323 @@ where
 
 		let mut pending_calls: FuturesOrdered<_> = batch
 			.into_iter()
-			.filter_map(|v| {
-				if let Ok(req) = serde_json::from_str::<Request>(v.get()) {
-					Some(Either::Right(execute_call(req, call.clone())))
-				} else if let Ok(_notif) = serde_json::from_str::<Notif>(v.get()) {
+			.map(|v| {
+				let call = call.clone();
+
+				match JsonCall::parse(v.get().as_bytes(), call.json_rpc_v1, call.max_log_length) {
+					JsonCall::Request(req) => {
+						Either::Right(Either::Left(async move { Some(execute_call_with_tracing(req, call).await) }))
+					}
 					// notifications should not be answered.
-					got_notif = true;
-					None
-				} else {
-					// valid JSON but could be not parsable as `InvalidRequest`
-					let id = match serde_json::from_str::<InvalidRequest>(v.get()) {
-						Ok(err) => err.id,
-						Err(_) => Id::Null,
-					};
-
-					Some(Either::Left(async {
-						MethodResponse::error(id, ErrorObject::from(ErrorCode::InvalidRequest))
-					}))
+					JsonCall::Notification(method, params) => {
+						got_notif = true;
+						Either::Right(Either::Right(async move {
+							execute_notification(&method, params, call).await;
+							None
+						}))
+					}
+					JsonCall::Invalid(id) => Either::Left(async move {
+						Some(MethodResponse::error(id, ErrorObject::from(ErrorCode::InvalidRequest)))
+					}),
 				}
 			})
 			.collect();
 
 		while let Some(response) = pending_calls.next().await {
+			let Some(response) = response else { continue };
+			let response = response.into_buffered(call.max_response_body_size as usize).await;
+
 			if let Err(too_large) = batch_response.append(&response) {
 				return too_large;
 			}
 		}
 
 		if got_notif && batch_response.is_empty() {
-			String::new()
+			String::new().into()
 		} else {
 			batch_response.finish()
 		}
 	} else {
-		batch_response_error(Id::Null, ErrorObject::from(ErrorCode::ParseError))
+		batch_response_error(Id::Null, ErrorObject::from(ErrorCode::ParseError)).into()
 	}
 }
 
-pub(crate) async fn process_single_request<L: Logger>(data: Vec<u8>, call: CallData<'_, L>) -> MethodResponse {
-	if let Ok(req) = serde_json::from_slice::<Request>(&data) {
-		execute_call_with_tracing(req, call).await
-	} else if let Ok(notif) = serde_json::from_slice::<Notif>(&data) {
-		execute_notification(notif, call.max_log_length)
-	} else {
-		let (id, code) = prepare_error(&data);
-		MethodResponse::error(id, ErrorObject::from(code))
+/// Process a CBOR encoded single request, notification or batch and encode the response in CBOR.
+#[cfg(feature = "cbor")]
+async fn process_cbor_request<L, S>(
+	data: Vec<u8>,
+	call: CallData<'_, L, S>,
+	batch_requests_config: BatchRequestConfig,
+) -> EncodedMessage
+where
+	L: Logger,
+	for<'a> S: RpcServiceT<'a> + Send + Sync,
+{
+	let batch = match cbor::Message::decode(&data) {
+		Some(cbor::Message::Single(call_data)) => {
+			return match cbor::Call::parse(call_data) {
+				cbor::Call::Request(req) => execute_call_with_tracing(req, call).await.result,
+				cbor::Call::Notification(method, params) => execute_notification(&method, params, call).await.result,
+				cbor::Call::Invalid(id) => {
+					MethodResponse::encoded_error(id, ErrorCode::InvalidRequest, Encoding::Cbor).result
+				}
+			};
+		}
+		Some(cbor::Message::Batch(batch)) => batch,
+		None => return MethodResponse::encoded_error(Id::Null, ErrorCode::ParseError, Encoding::Cbor).result,
+	};
+
+	let max_len = match batch_requests_config {
+		BatchRequestConfig::Disabled => {
+			let err = ErrorObject::borrowed(BATCHES_NOT_SUPPORTED_CODE, BATCHES_NOT_SUPPORTED_MSG, None);
+			return MethodResponse::encoded_error(Id::Null, err, Encoding::Cbor).result;
+		}
+		BatchRequestConfig::Limit(limit) => limit as usize,
+		BatchRequestConfig::Unlimited => usize::MAX,
+	};
+
+	if batch.len() > max_len {
+		return MethodResponse::encoded_error(Id::Null, reject_too_big_batch_request(max_len), Encoding::Cbor).result;
 	}
-}
 
-#[instrument(name = "method_call", fields(method = req.method.as_ref()), skip(call, req), level = "TRACE")]
-pub(crate) async fn execute_call_with_tracing<'a, L: Logger>(
-	req: Request<'a>,
-	call: CallData<'_, L>,
-) -> MethodResponse {
-	execute_call(req, call).await
-}
+	let mut got_notif = false;
+	let mut batch_response =
+		BatchResponseBuilder::new_with_encoding(call.max_response_body_size as usize, Encoding::Cbor);
 
-pub(crate) async fn execute_call<L: Logger>(req: Request<'_>, call: CallData<'_, L>) -> MethodResponse {
-	let CallData { methods, logger, max_response_body_size, max_log_length, conn_id, request_start } = call;
+	let mut pending_calls: FuturesOrdered<_> = batch
+		.into_iter()
+		.map(|v| {
+			let call = call.clone();
 
-	rx_log_from_json(&req, call.max_log_length);
+			match cbor::Call::parse(v) {
+				cbor::Call::Request(req) => {
+					Either::Right(Either::Left(async move { Some(execute_call_with_tracing(req, call).await) }))
+				}
+				// notifications should not be answered.
+				cbor::Call::Notification(method, params) => {
+					got_notif = true;
+					Either::Right(Either::Right(async move {
+						execute_notification(&method, params, call).await;
+						None
+					}))
+				}
+				cbor::Call::Invalid(id) => Either::Left(async move {
+					Some(MethodResponse::encoded_error(id, ErrorCode::InvalidRequest, Encoding::Cbor))
+				}),
+			}
+		})
+		.collect();
 
-	let params = Params::new(req.params.map(|params| params.get()));
-	let name = &req.method;
-	let id = req.id;
+	while let Some(response) = pending_calls.next().await {
+		let Some(response) = response else { continue };
+		let response = response.into_buffered(call.max_response_body_size as usize).await;
 
-	let response = match methods.method_with_name(name) {
-		None => {
-			logger.on_call(name, params.clone(), logger::MethodKind::Unknown, TransportProtocol::Http);
-			MethodResponse::error(id, ErrorObject::from(ErrorCode::MethodNotFound))
+		if let Err(too_large) = batch_response.append(&response) {
+			return too_large;
 		}
-		Some((name, method)) => match method {
-			MethodCallback::Sync(callback) => {
-				logger.on_call(name, params.clone(), logger::MethodKind::MethodCall, TransportProtocol::Http);
-				(callback)(id, params, max_response_body_size as usize)
-			}
-			MethodCallback::Async(callback) => {
-				logger.on_call(name, params.clone(), logger::MethodKind::MethodCall, TransportProtocol::Http);
+	}
 
-				let id = id.into_owned();
-				let params = params.into_owned();
+	if got_notif && batch_response.is_empty() {
+		Vec::new().into()
+	} else {
+		batch_response.finish()
+	}
+}
 
-				(callback)(id, params, conn_id, max_response_body_size as usize).await
-			}
-			MethodCallback::Subscription(_) | MethodCallback::Unsubscription(_) => {
-				logger.on_call(name, params.clone(), logger::MethodKind::Unknown, TransportProtocol::Http);
-				tracing::warn!("Subscriptions not supported on HTTP");
-				MethodResponse::error(id, ErrorObject::from(ErrorCode::InternalError))
-			}
-		},
+pub(crate) async fn process_single_request<L, S>(data: Vec<u8>, call: CallData<'_, L, S>) -> MethodResponse
+where
+	L: Logger,
+	for<'a> S: RpcServiceT<'a> + Send + Sync,
+{
+	match JsonCall::parse(&data, call.json_rpc_v1, call.max_log_length) {
+		JsonCall::Request(req) => execute_call_with_tracing(req, call).await,
+		JsonCall::Notification(method, params) => execute_notification(&method, params, call).await,
+		JsonCall::Invalid(_) => {
+			let (id, code) = prepare_error(&data);
+			MethodResponse::error(id, ErrorObject::from(code))
+		}
+	}
+}
+
+#[instrument(
+	name = "method_call",
+	fields(method = req.method.as_ref(), trace_id = Empty, span_id = Empty, parent_id = Empty),
+	skip(call, req),
+	level = "TRACE"
+)]
+pub(crate) async fn execute_call_with_tracing<'a, L, S>(req: Request<'a>, call: CallData<'_, L, S>) -> MethodResponse
+where
+	L: Logger,
+	for<'b> S: RpcServiceT<'b> + Send + Sync,
+{
+	match call_trace_context(&req, call.extensions) {
+		Some(ctx) => ctx.scope(execute_call(req, call)).await,
+		None => execute_call(req, call).await,
+	}
+}
+
+pub(crate) async fn execute_call<L, S>(mut req: Request<'_>, call: CallData<'_, L, S>) -> MethodResponse
+where
+	L: Logger,
+	for<'a> S: RpcServiceT<'a> + Send + Sync,
+{
+	let CallData { methods, logger, rpc_service, max_log_length, request_start, extensions, .. } = call;
+
+	// The request may carry extensions of its own, such as the encoding of a JSON-RPC 1.0 call.
+	let own_extensions = std::mem::take(&mut req.extensions);
+	req.extensions = extensions.clone();
+	req.extensions.extend(own_extensions);
+	if let Some(ctx) = TraceParent::current() {
+		req.extensions.insert(ctx);
+	}
+
+	rx_log_from_json(&req, call.max_log_length);
+
+	let params = match req.decoded_params {
+		Some(ref params) => params.clone(),
+		None => Params::new(req.params.as_ref().map(|params| params.get())),
+	};
+	let name = req.method.clone();
+
+	let kind = match methods.method(&name) {
+		None => logger::MethodKind::Unknown,
+		Some(MethodCallback::Sync(_) | MethodCallback::Async(_)) => logger::MethodKind::MethodCall,
+		// Subscriptions are only supported over server-sent events.
+		Some(MethodCallback::Subscription(_)) => logger::MethodKind::Subscription,
+		Some(MethodCallback::Unsubscription(_)) => logger::MethodKind::Unsubscription,
+		Some(MethodCallback::Notification(_)) => logger::MethodKind::Unknown,
 	};
+	logger.on_call(&name, params, kind, TransportProtocol::Http);
 
-	tx_log_from_str(&response.result, max_log_length);
-	logger.on_result(name, response.success_or_error, request_start, TransportProtocol::Http);
+	let response = rpc_service.call(req).await;
+
+	tx_log_from_str(response.result.as_log_str(), max_log_length);
+	logger.on_result(&name, response.success_or_error, request_start, TransportProtocol::Http);
 	response
 }
 
-#[instrument(name = "notification", fields(method = notif.method.as_ref()), skip(notif, max_log_length), level = "TRACE")]
-fn execute_notification(notif: Notif, max_log_length: u32) -> MethodResponse {
-	rx_log_from_json(&notif, max_log_length);
-	let response = MethodResponse { result: String::new(), success_or_error: MethodResponseResult::Success };
-	tx_log_from_str(&response.result, max_log_length);
+#[instrument(name = "notification", skip(params, call), level = "TRACE")]
+async fn execute_notification<L, S>(method: &str, params: Params<'_>, call: CallData<'_, L, S>) -> MethodResponse
+where
+	L: Logger,
+	for<'a> S: RpcServiceT<'a> + Send + Sync,
+{
+	let CallData { methods, logger, rpc_service, max_log_length, extensions, .. } = call;
+
+	if let Some(MethodCallback::Notification(_)) = methods.method(method) {
+		logger.on_call(method, params.clone(), logger::MethodKind::Notification, TransportProtocol::Http);
+	} else {
+		tracing::debug!("No notification handler registered for method `{}`; ignoring it", method);
+	}
+
+	// Notifications go through the RPC middleware too, the response is discarded.
+	let mut req = Request::new(method.into(), None, Id::Null);
+	req.decoded_params = Some(params);
+	req.extensions = extensions.clone();
+	req.extensions.insert(IsNotification);
+	if let Some(code) = rpc_service.call(req).await.success_or_error.as_error_code() {
+		tracing::debug!("Notification `{}` was rejected with error code {}; ignoring it", method, code);
+	}
+
+	let response = MethodResponse {
+		result: String::new().into(),
+		success_or_error: MethodResponseResult::Success,
+		is_subscription: false,
+	};
+	tx_log_from_str(response.result.as_log_str(), max_log_length);
 	response
 }
 
-pub(crate) struct HandleRequest<L: Logger> {
-	pub(crate) methods: Methods,
+pub(crate) struct HandleRequest<L: Logger, R> {
+	pub(crate) methods: SharedMethods,
 	pub(crate) max_request_body_size: u32,
 	pub(crate) max_response_body_size: u32,
+	pub(crate) http_compression_threshold: Option<u32>,
 	pub(crate) max_log_length: u32,
 	pub(crate) batch_requests_config: BatchRequestConfig,
 	pub(crate) logger: L,
-	pub(crate) conn: Arc<OwnedSemaphorePermit>,
+	pub(crate) conn: Arc<ConnectionPermit>,
 	pub(crate) remote_addr: SocketAddr,
+	pub(crate) conn_id: ConnectionId,
+	pub(crate) default_call_timeout: Option<Duration>,
+	pub(crate) access_policy: AccessPolicy,
+	pub(crate) json_rpc_v1: bool,
+	pub(crate) rate_limiter: Option<ConnectionRateLimiter>,
+	pub(crate) rpc_middleware: RpcServiceBuilder<R>,
+	pub(crate) id_provider: Arc<dyn IdProvider>,
 }
 
-pub(crate) async fn handle_request<L: Logger>(
+pub(crate) async fn handle_request<L, R>(
 	request: hyper::Request<hyper::Body>,
-	input: HandleRequest<L>,
-) -> hyper::Response<hyper::Body> {
+	input: HandleRequest<L, R>,
+) -> hyper::Response<hyper::Body>
+where
+	L: Logger,
+	R: tower::Layer<RpcService>,
+	for<'a> <R as tower::Layer<RpcService>>::Service: RpcServiceT<'a> + Send + Sync,
+{
 	let HandleRequest {
 		methods,
 		max_request_body_size,
 		max_response_body_size,
+		http_compression_threshold,
 		max_log_length,
 		batch_requests_config,
 		logger,
 		conn,
 		remote_addr,
+		conn_id,
+		default_call_timeout,
+		access_policy,
+		json_rpc_v1,
+		rate_limiter,
+		rpc_middleware,
+		id_provider,
 	} = input;
 
 	let request_start = logger.on_request(TransportProtocol::Http);
+	let rpc_service = rpc_middleware.service(RpcService::new(
+		methods.clone(),
+		max_response_body_size as usize,
+		conn_id,
+		default_call_timeout,
+		access_policy.clone(),
+		rate_limiter.clone(),
+		RpcServiceCfg::OnlyCalls,
+	));
+	let mut extensions = ConnectionDetails::new(conn_id, remote_addr).into_extensions(&request, &id_provider);
+	let encoding = body_encoding(&request);
+	if let Some(encoding) = encoding {
+		extensions.insert(encoding);
+	}
+	let compression = http_compression_threshold
+		.and_then(|threshold| ContentEncoding::negotiate(request.headers()).map(|encoding| (encoding, threshold)));
 
 	// Only the `POST` method is allowed.
 	let res = match *request.method() {
-		Method::POST if content_type_is_json(&request) => {
-			process_validated_request(ProcessValidatedRequest {
-				request,
-				methods,
-				max_request_body_size,
-				max_response_body_size,
-				max_log_length,
-				batch_requests_config,
-				logger: &logger,
-				request_start,
-			})
-			.await
-		}
+		Method::POST if encoding.is_some() => match rate_limiter.as_ref().map(|l| l.check_request()) {
+			Some(Err(retry_after)) => {
+				tracing::debug!("Rate limit exceeded by {}, retry after {:?}", remote_addr, retry_after);
+				response::too_many_requests(Some(retry_after))
+			}
+			_ => {
+				process_validated_request(ProcessValidatedRequest {
+					request,
+					methods: methods.load(),
+					rpc_service: &rpc_service,
+					max_request_body_size,
+					max_response_body_size,
+					max_log_length,
+					batch_requests_config,
+					logger: &logger,
+					request_start,
+					extensions,
+					access_policy: &access_policy,
+					json_rpc_v1,
+				})
+				.await
+			}
+		},
 		// Error scenarios:
 		Method::POST => response::unsupported_content_type(),
 		_ => response::method_not_allowed(),
@@ -322,15 +585,29 @@ pub(crate) async fn handle_request<L: Logger>(
 	drop(conn);
 	logger.on_disconnect(remote_addr, TransportProtocol::Http);
 
-	res
+	match compression {
+		Some((encoding, threshold)) => response::compress(res, encoding, threshold).await,
+		None => res,
+	}
 }
 
 pub(crate) mod response {
+	use std::time::Duration;
+
+	use hyper::body::HttpBody;
+	use jsonrpsee_core::http_helpers::ContentEncoding;
+	use jsonrpsee_core::server::{EncodedMessage, Encoding};
 	use jsonrpsee_types::error::{reject_too_big_request, ErrorCode};
 	use jsonrpsee_types::{ErrorObjectOwned, Id, Response, ResponsePayload};
 
 	const JSON: &str = "application/json; charset=utf-8";
 	const TEXT: &str = "text/plain";
+	#[cfg(not(feature = "cbor"))]
+	const UNSUPPORTED_CONTENT_TYPE: &str =
+		"Supplied content type is not allowed. Content-Type: application/json is required\n";
+	#[cfg(feature = "cbor")]
+	const UNSUPPORTED_CONTENT_TYPE: &str =
+		"Supplied content type is not allowed. Content-Type: application/json or application/cbor is required\n";
 
 	/// Create a response for json internal error.
 	pub(crate) fn internal_error() -> hyper::Response<hyper::Body> {
@@ -392,24 +669,99 @@ pub(crate) mod response {
 		from_template(hyper::StatusCode::OK, body, JSON)
 	}
 
+	/// Create a valid response whose body is encoded with `encoding`.
+	///
+	/// A streamed body is sent with chunked transfer encoding and aborted if the stream fails.
+	pub(crate) fn ok_encoded_response(body: EncodedMessage, encoding: Encoding) -> hyper::Response<hyper::Body> {
+		let content_type = if encoding == Encoding::Json { JSON } else { encoding.content_type() };
+
+		match body {
+			EncodedMessage::Stream(msg) => match msg.take() {
+				Some(chunks) => from_template(hyper::StatusCode::OK, hyper::Body::wrap_stream(chunks), content_type),
+				None => internal_error(),
+			},
+			body => from_template(hyper::StatusCode::OK, body.into_bytes(), content_type),
+		}
+	}
+
+	/// Create a response with metrics in the Prometheus text exposition format.
+	#[cfg(feature = "prometheus")]
+	pub(crate) fn prometheus_metrics(body: Vec<u8>) -> hyper::Response<hyper::Body> {
+		from_template(hyper::StatusCode::OK, body, prometheus::TEXT_FORMAT)
+	}
+
 	/// Create a response for unsupported content type.
 	pub(crate) fn unsupported_content_type() -> hyper::Response<hyper::Body> {
-		from_template(
-			hyper::StatusCode::UNSUPPORTED_MEDIA_TYPE,
-			"Supplied content type is not allowed. Content-Type: application/json is required\n".to_owned(),
+		from_template(hyper::StatusCode::UNSUPPORTED_MEDIA_TYPE, UNSUPPORTED_CONTENT_TYPE.to_owned(), TEXT)
+	}
+
+	/// Create a response for when the server is busy and can't accept more requests (429).
+	///
+	/// If the client exceeded a rate limit, `retry_after` is sent in the `Retry-After` header, rounded up to seconds.
+	pub(crate) fn too_many_requests(retry_after: Option<Duration>) -> hyper::Response<hyper::Body> {
+		let Some(retry_after) = retry_after else {
+			return from_template(
+				hyper::StatusCode::TOO_MANY_REQUESTS,
+				"Too many connections. Please try again later.".to_owned(),
+				TEXT,
+			);
+		};
+
+		let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+		let mut response = from_template(
+			hyper::StatusCode::TOO_MANY_REQUESTS,
+			"Rate limit exceeded. Please try again later.".to_owned(),
 			TEXT,
-		)
+		);
+		response.headers_mut().insert(hyper::header::RETRY_AFTER, secs.into());
+		response
 	}
 
-	/// Create a response for when the server is busy and can't accept more requests.
-	pub(crate) fn too_many_requests() -> hyper::Response<hyper::Body> {
+	/// Create a response for requests with an unsupported content encoding.
+	pub(crate) fn unsupported_content_encoding() -> hyper::Response<hyper::Body> {
 		from_template(
-			hyper::StatusCode::TOO_MANY_REQUESTS,
-			"Too many connections. Please try again later.".to_owned(),
+			hyper::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+			"Supplied content encoding is not supported. Content-Encoding: gzip, deflate or br is required\n"
+				.to_owned(),
 			TEXT,
 		)
 	}
 
+	/// Compress the body of `response` with `encoding` if it's at least `threshold` bytes.
+	pub(crate) async fn compress(
+		response: hyper::Response<hyper::Body>,
+		encoding: ContentEncoding,
+		threshold: u32,
+	) -> hyper::Response<hyper::Body> {
+		let (mut parts, body) = response.into_parts();
+		parts.headers.append(hyper::header::VARY, hyper::header::HeaderValue::from_static("accept-encoding"));
+
+		// Streamed bodies have no known size and are sent as they are, to not buffer them.
+		match body.size_hint().exact() {
+			Some(size) if size >= threshold as u64 => (),
+			_ => return hyper::Response::from_parts(parts, body),
+		}
+
+		let body = match hyper::body::to_bytes(body).await {
+			Ok(body) => body,
+			Err(e) => {
+				tracing::warn!("Internal error reading response body: {}", e);
+				return internal_error();
+			}
+		};
+
+		parts
+			.headers
+			.insert(hyper::header::CONTENT_ENCODING, hyper::header::HeaderValue::from_static(encoding.as_str()));
+		parts.headers.remove(hyper::header::CONTENT_LENGTH);
+		hyper::Response::from_parts(parts, encoding.encode(&body).into())
+	}
+
+	/// Create a response for calls that were aborted because the server was shut down.
+	pub(crate) fn aborted() -> hyper::Response<hyper::Body> {
+		from_template(hyper::StatusCode::SERVICE_UNAVAILABLE, "Server is shutting down.".to_owned(), TEXT)
+	}
+
 	/// Create a response for when the server denied the request.
 	pub(crate) fn denied() -> hyper::Response<hyper::Body> {
 		from_template(hyper::StatusCode::FORBIDDEN, "".to_owned(), TEXT)
@@ -0,0 +1,165 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Listeners and sockets the server accepts connections on, either TCP or Unix domain sockets.
+
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Remote address reported for connections accepted on a Unix domain socket
+/// which doesn't have a [`SocketAddr`].
+#[cfg(unix)]
+fn unix_remote_addr() -> SocketAddr {
+	SocketAddr::from(([0, 0, 0, 0], 0))
+}
+
+/// The IP address of the remote of a connection, `None` for connections on a Unix domain socket
+/// whose remote address is unspecified.
+pub(crate) fn remote_ip(remote_addr: SocketAddr) -> Option<IpAddr> {
+	let ip = remote_addr.ip();
+	(!ip.is_unspecified()).then_some(ip)
+}
+
+/// Listener that the server accepts connections on.
+#[derive(Debug)]
+pub(crate) enum Listener {
+	/// TCP listener.
+	Tcp(TcpListener),
+	/// Unix domain socket listener.
+	#[cfg(unix)]
+	Unix(tokio::net::UnixListener),
+}
+
+impl Listener {
+	/// Returns the local address of a TCP listener.
+	pub(crate) fn local_addr(&self) -> io::Result<SocketAddr> {
+		match self {
+			Self::Tcp(listener) => listener.local_addr(),
+			#[cfg(unix)]
+			Self::Unix(_) => {
+				let msg = "The server is listening on a unix domain socket";
+				Err(io::Error::new(io::ErrorKind::Unsupported, msg))
+			}
+		}
+	}
+
+	/// Accept a new connection.
+	pub(crate) async fn accept(&self) -> io::Result<(Socket, SocketAddr)> {
+		match self {
+			Self::Tcp(listener) => {
+				let (socket, remote_addr) = listener.accept().await?;
+				Ok((Socket::Tcp(socket), remote_addr))
+			}
+			#[cfg(unix)]
+			Self::Unix(listener) => {
+				let (socket, _) = listener.accept().await?;
+				Ok((Socket::Unix(socket), unix_remote_addr()))
+			}
+		}
+	}
+}
+
+/// Socket of an accepted connection.
+#[derive(Debug)]
+pub(crate) enum Socket {
+	/// TCP socket.
+	Tcp(TcpStream),
+	/// Unix domain socket.
+	#[cfg(unix)]
+	Unix(tokio::net::UnixStream),
+}
+
+impl Socket {
+	/// Set `TCP_NODELAY` on TCP sockets, this is a no-op for other sockets.
+	pub(crate) fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+		match self {
+			Self::Tcp(socket) => socket.set_nodelay(nodelay),
+			#[cfg(unix)]
+			Self::Unix(_) => Ok(()),
+		}
+	}
+}
+
+impl AsyncRead for Socket {
+	fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Tcp(s) => Pin::new(s).poll_read(cx, buf),
+			#[cfg(unix)]
+			Self::Unix(s) => Pin::new(s).poll_read(cx, buf),
+		}
+	}
+}
+
+impl AsyncWrite for Socket {
+	fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+		match self.get_mut() {
+			Self::Tcp(s) => Pin::new(s).poll_write(cx, buf),
+			#[cfg(unix)]
+			Self::Unix(s) => Pin::new(s).poll_write(cx, buf),
+		}
+	}
+
+	fn poll_write_vectored(
+		self: Pin<&mut Self>,
+		cx: &mut Context<'_>,
+		bufs: &[io::IoSlice<'_>],
+	) -> Poll<io::Result<usize>> {
+		match self.get_mut() {
+			Self::Tcp(s) => Pin::new(s).poll_write_vectored(cx, bufs),
+			#[cfg(unix)]
+			Self::Unix(s) => Pin::new(s).poll_write_vectored(cx, bufs),
+		}
+	}
+
+	fn is_write_vectored(&self) -> bool {
+		match self {
+			Self::Tcp(s) => s.is_write_vectored(),
+			#[cfg(unix)]
+			Self::Unix(s) => s.is_write_vectored(),
+		}
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Tcp(s) => Pin::new(s).poll_flush(cx),
+			#[cfg(unix)]
+			Self::Unix(s) => Pin::new(s).poll_flush(cx),
+		}
+	}
+
+	fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		match self.get_mut() {
+			Self::Tcp(s) => Pin::new(s).poll_shutdown(cx),
+			#[cfg(unix)]
+			Self::Unix(s) => Pin::new(s).poll_shutdown(cx),
+		}
+	}
+}
@@ -1,2 +1,79 @@
+#[cfg(feature = "cbor")]
+pub(crate) mod cbor;
 pub(crate) mod http;
+pub(crate) mod listener;
+pub(crate) mod sse;
 pub(crate) mod ws;
+
+use jsonrpsee_core::server::Encoding;
+use jsonrpsee_core::tracing::{rx_log_from_json, TraceParent, TRACEPARENT};
+use jsonrpsee_core::{Cow, JsonRawValue};
+use jsonrpsee_types::{Extensions, Id, InvalidRequest, Notification, Params, Request, RequestV1};
+
+type Notif<'a> = Notification<'a, Option<&'a JsonRawValue>>;
+
+/// A JSON encoded call.
+pub(crate) enum JsonCall<'a> {
+	/// A method call, which is answered with JSON-RPC 1.0 framing if it was a JSON-RPC 1.0 call.
+	Request(Request<'a>),
+	/// A notification.
+	Notification(Cow<'a, str>, Params<'a>),
+	/// A call which isn't a valid request object.
+	Invalid(Id<'a>),
+}
+
+impl<'a> JsonCall<'a> {
+	/// Parse a JSON-RPC 2.0 call, or a JSON-RPC 1.0 call if `json_rpc_v1` is set,
+	/// see [`Builder::enable_json_rpc_v1`](crate::Builder::enable_json_rpc_v1).
+	pub(crate) fn parse(data: &'a [u8], json_rpc_v1: bool, max_log_length: u32) -> Self {
+		if let Ok(req) = serde_json::from_slice::<Request>(data) {
+			return Self::Request(req);
+		}
+
+		if let Ok(notif) = serde_json::from_slice::<Notif>(data) {
+			rx_log_from_json(&notif, max_log_length);
+			return Self::Notification(notif.method, Params::new(notif.params.map(|params| params.get())));
+		}
+
+		if let Some(call) = json_rpc_v1.then(|| serde_json::from_slice::<RequestV1>(data).ok()).flatten() {
+			let params = Params::new(call.params.map(|params| params.get()));
+			return match call.id {
+				Id::Null => Self::Notification(call.method, params),
+				id => {
+					let mut req = Request::new(call.method, call.params, id);
+					req.extensions.insert(Encoding::JsonV1);
+					Self::Request(req)
+				}
+			};
+		}
+
+		// valid JSON but could be not parsable as `InvalidRequest`
+		match serde_json::from_slice::<InvalidRequest>(data) {
+			Ok(err) => Self::Invalid(err.id),
+			Err(_) => Self::Invalid(Id::Null),
+		}
+	}
+}
+
+/// Get the trace context of a method call and record it in the current `method_call` span.
+///
+/// The `traceparent` member of the JSON-RPC request takes precedence over the `traceparent`
+/// HTTP header, the returned context identifies the span of the call.
+pub(crate) fn call_trace_context(req: &Request, extensions: &Extensions) -> Option<TraceParent> {
+	let parent = match req.traceparent.as_deref() {
+		Some(tp) => TraceParent::parse(tp),
+		None => extensions
+			.get::<hyper::HeaderMap>()
+			.and_then(|headers| headers.get(TRACEPARENT))
+			.and_then(|tp| tp.to_str().ok())
+			.and_then(TraceParent::parse),
+	}?;
+	let ctx = parent.child();
+
+	let span = tracing::Span::current();
+	span.record("trace_id", ctx.trace_id().as_str());
+	span.record("span_id", ctx.parent_id().as_str());
+	span.record("parent_id", parent.parent_id().as_str());
+
+	Some(ctx)
+}
@@ -0,0 +1,270 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use crate::future::ConnectionPermit;
+use crate::logger::{Logger, TransportProtocol};
+use crate::middleware::rpc::{RpcService, RpcServiceCfg, RpcServiceT};
+use crate::server::{ConnectionDetails, ServiceData};
+use crate::transport::http::{self, response, CallData};
+use crate::transport::JsonCall;
+
+use futures_util::future;
+use futures_util::StreamExt;
+use hyper::Method;
+use jsonrpsee_core::http_helpers::read_body;
+use jsonrpsee_core::server::helpers::{prepare_error, MethodResponse, MethodSink};
+use jsonrpsee_core::server::{BoundedSubscriptions, ConnectionId, EncodedMessage, Encoding, MethodCallback, Methods};
+use jsonrpsee_core::JsonRawValue;
+use jsonrpsee_types::error::ErrorCode;
+use jsonrpsee_types::{ErrorObject, Extensions, Id, Params};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tower::Layer;
+
+const EVENT_STREAM: &str = "text/event-stream";
+
+/// Returns true if the request accepts a `text/event-stream` response.
+pub(crate) fn accepts_event_stream<B>(request: &hyper::Request<B>) -> bool {
+	request
+		.headers()
+		.get_all(hyper::header::ACCEPT)
+		.iter()
+		.filter_map(|value| value.to_str().ok())
+		.flat_map(|value| value.split(','))
+		.any(|media| media.split(';').next().map_or(false, |media| media.trim().eq_ignore_ascii_case(EVENT_STREAM)))
+}
+
+/// Answer a call with an event stream of the notifications of the subscription that it opens.
+///
+/// Calls that don't open a subscription are answered with a plain JSON response.
+pub(crate) async fn handle_request<L, R>(
+	request: hyper::Request<hyper::Body>,
+	svc: ServiceData<L, R>,
+) -> hyper::Response<hyper::Body>
+where
+	L: Logger,
+	R: Layer<RpcService>,
+	for<'a> <R as Layer<RpcService>>::Service: RpcServiceT<'a> + Send + Sync,
+{
+	let ServiceData {
+		methods,
+		max_request_body_size,
+		max_response_body_size,
+		max_log_length,
+		max_subscriptions_per_connection,
+		id_provider,
+		stop_handle,
+		conn_id,
+		logger,
+		conn,
+		remote_addr,
+		message_buffer_capacity,
+		default_call_timeout,
+		access_policy,
+		json_rpc_v1,
+		rate_limiter,
+		rpc_middleware,
+		..
+	} = svc;
+
+	let request_start = logger.on_request(TransportProtocol::Http);
+	let conn_id = conn_id as ConnectionId;
+	let mut extensions = ConnectionDetails::new(conn_id, remote_addr).into_extensions(&request, &id_provider);
+	extensions.insert(Encoding::Json);
+
+	if let Some(Err(retry_after)) = rate_limiter.as_ref().map(|l| l.check_request()) {
+		tracing::debug!("Rate limit exceeded by {}, retry after {:?}", remote_addr, retry_after);
+		logger.on_disconnect(remote_addr, TransportProtocol::Http);
+		return response::too_many_requests(Some(retry_after));
+	}
+
+	let data = match read_call(request, max_request_body_size, &methods.load()).await {
+		Ok(data) => data,
+		Err(res) => {
+			logger.on_disconnect(remote_addr, TransportProtocol::Http);
+			return res;
+		}
+	};
+
+	let (tx, rx) = mpsc::channel::<EncodedMessage>(message_buffer_capacity as usize);
+	let sink = MethodSink::new_with_limit(tx, max_response_body_size, max_log_length);
+	let bounded_subscriptions = {
+		let (open, close) = (logger.clone(), logger.clone());
+		BoundedSubscriptions::new(max_subscriptions_per_connection).with_hooks(
+			Arc::new(move || open.on_subscription_open(TransportProtocol::Http)),
+			Arc::new(move || close.on_subscription_close(TransportProtocol::Http)),
+		)
+	};
+	let rpc_service = rpc_middleware.service(RpcService::new(
+		methods.clone(),
+		max_response_body_size as usize,
+		conn_id,
+		default_call_timeout,
+		access_policy.clone(),
+		rate_limiter,
+		RpcServiceCfg::CallsAndSubscriptions { bounded_subscriptions, sink, id_provider },
+	));
+	let methods = methods.load();
+
+	let (name, response) = match JsonCall::parse(&data, json_rpc_v1, max_log_length) {
+		JsonCall::Request(req) => {
+			let name = req.method.to_string();
+			let call = CallData {
+				logger: &logger,
+				methods: &methods,
+				rpc_service: &rpc_service,
+				max_response_body_size,
+				max_log_length,
+				request_start,
+				extensions: &extensions,
+				access_policy: &access_policy,
+				json_rpc_v1,
+			};
+			(name, http::execute_call_with_tracing(req, call).await)
+		}
+		JsonCall::Notification(..) => {
+			(String::new(), MethodResponse::error(Id::Null, ErrorObject::from(ErrorCode::InvalidRequest)))
+		}
+		JsonCall::Invalid(_) => {
+			let (id, code) = prepare_error(&data);
+			(String::new(), MethodResponse::error(id, ErrorObject::from(code)))
+		}
+	};
+	// The notifications end once the subscription has been closed and the sink is dropped.
+	drop(rpc_service);
+	logger.on_response(response.result.as_log_str(), request_start, TransportProtocol::Http);
+
+	if !response.is_subscription || !response.is_success() {
+		logger.on_disconnect(remote_addr, TransportProtocol::Http);
+		return response::ok_encoded_response(response.result, Encoding::Json);
+	}
+
+	let guard = SubscriptionGuard {
+		unsubscribe: methods.unsubscribe_method(&name),
+		subscription_id: response.result.as_str().and_then(subscription_id),
+		methods,
+		conn_id,
+		extensions,
+		logger,
+		remote_addr,
+		_conn: conn,
+	};
+
+	// The response to the subscription call has already been sent to the sink, before the notifications.
+	let events = ReceiverStream::new(rx)
+		.filter_map(|msg| future::ready(msg.as_str().map(event)))
+		.take_until(stop_handle.shutdown())
+		.map(move |event| {
+			let _guard = &guard;
+			Ok::<_, Infallible>(event)
+		});
+
+	hyper::Response::builder()
+		.header(hyper::header::CONTENT_TYPE, EVENT_STREAM)
+		.header(hyper::header::CACHE_CONTROL, "no-cache")
+		.body(hyper::Body::wrap_stream(events))
+		.expect("Unable to parse response body for type conversion")
+}
+
+/// Read the JSON-RPC call of a `POST` request from its body or of a `GET` request from its query.
+///
+/// A `GET` request can only call a subscription method, since it can be sent cross-site without a CORS preflight.
+async fn read_call(
+	request: hyper::Request<hyper::Body>,
+	max_request_body_size: u32,
+	methods: &Methods,
+) -> Result<Vec<u8>, hyper::Response<hyper::Body>> {
+	match *request.method() {
+		Method::POST if http::is_json(request.headers().get(hyper::header::CONTENT_TYPE)) => {
+			let (parts, body) = request.into_parts();
+			match read_body(&parts.headers, body, max_request_body_size).await {
+				Ok((body, _)) => Ok(body),
+				Err(e) => Err(http::body_error(e, max_request_body_size)),
+			}
+		}
+		Method::GET => {
+			let (method, call) =
+				query_call(request.uri().query().unwrap_or_default()).ok_or_else(response::malformed)?;
+			match methods.method(&method) {
+				Some(MethodCallback::Subscription(_)) => Ok(call),
+				_ => Err(response::method_not_allowed()),
+			}
+		}
+		Method::POST => Err(response::unsupported_content_type()),
+		_ => Err(response::method_not_allowed()),
+	}
+}
+
+/// Build a JSON-RPC call from the `method`, `params` and `id` query parameters, returns the method name and the call.
+///
+/// The `params` must be JSON, the `id` is taken as a string if it isn't JSON and defaults to `0`.
+fn query_call(query: &str) -> Option<(String, Vec<u8>)> {
+	let (mut method, mut params, mut id) = (None, None, None);
+
+	for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+		match &*key {
+			"method" => method = Some(value.into_owned()),
+			"params" => params = Some(serde_json::from_str::<serde_json::Value>(&value).ok()?),
+			"id" => id = Some(serde_json::from_str(&value).unwrap_or(serde_json::Value::String(value.into_owned()))),
+			_ => {}
+		}
+	}
+
+	let method = method?;
+	let mut call = serde_json::json!({ "jsonrpc": "2.0", "method": method, "id": id.unwrap_or_else(|| 0.into()) });
+	if let Some(params) = params {
+		call["params"] = params;
+	}
+	serde_json::to_vec(&call).ok().map(|call| (method, call))
+}
+
+/// Extract the raw subscription ID from the response to a subscription call.
+fn subscription_id(response: &str) -> Option<String> {
+	#[derive(Deserialize)]
+	struct SubscriptionResponse<'a> {
+		#[serde(borrow)]
+		result: &'a JsonRawValue,
+	}
+
+	serde_json::from_str::<SubscriptionResponse>(response).ok().map(|rp| rp.result.get().to_owned())
+}
+
+/// Format a message as an event, each of its lines is sent in a `data` field.
+fn event(msg: &str) -> String {
+	let mut event = String::with_capacity(msg.len() + 8);
+	for line in msg.lines() {
+		event.push_str("data: ");
+		event.push_str(line);
+		event.push('\n');
+	}
+	event.push('\n');
+	event
+}
+
+/// Unsubscribes from the subscription once its event stream has been closed.
+struct SubscriptionGuard<L: Logger> {
+	unsubscribe: Option<&'static str>,
+	subscription_id: Option<String>,
+	methods: Methods,
+	conn_id: ConnectionId,
+	extensions: Extensions,
+	logger: L,
+	remote_addr: SocketAddr,
+	_conn: Arc<ConnectionPermit>,
+}
+
+impl<L: Logger> Drop for SubscriptionGuard<L> {
+	fn drop(&mut self) {
+		let unsubscribe = self.unsubscribe.and_then(|name| self.methods.method(name));
+
+		// Does nothing if the subscription was already closed by the server.
+		if let (Some(MethodCallback::Unsubscription(unsubscribe)), Some(id)) = (unsubscribe, &self.subscription_id) {
+			let params = format!("[{id}]");
+			(unsubscribe)(Id::Null, Params::new(Some(&params)), self.conn_id, usize::MAX, self.extensions.clone());
+		}
+
+		self.logger.on_disconnect(self.remote_addr, TransportProtocol::Http);
+	}
+}
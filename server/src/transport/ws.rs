@@ -1,8 +1,13 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use crate::future::StopHandle;
 use crate::logger::{self, Logger, TransportProtocol};
-use crate::server::{BatchRequestConfig, ServiceData};
+use crate::middleware::rpc::{IsNotification, RpcService, RpcServiceCfg, RpcServiceT};
+use crate::server::{BatchRequestConfig, ConcurrentCallsPolicy, ServiceData, SharedMethods};
+#[cfg(feature = "cbor")]
+use crate::transport::cbor;
+use crate::transport::{call_trace_context, JsonCall};
 use crate::PingConfig;
 
 use futures_util::future::{self, Either, Fuse};
@@ -13,21 +18,28 @@ use hyper::upgrade::Upgraded;
 use jsonrpsee_core::server::helpers::{
 	batch_response_error, prepare_error, BatchResponseBuilder, MethodResponse, MethodSink,
 };
-use jsonrpsee_core::server::{BoundedSubscriptions, CallOrSubscription, MethodCallback, Methods, SubscriptionState};
-use jsonrpsee_core::tracing::{rx_log_from_json, tx_log_from_str};
-use jsonrpsee_core::traits::IdProvider;
+use jsonrpsee_core::server::{
+	AccessPolicy, BoundedSubscriptions, CallOrSubscription, DisconnectError, EncodedMessage, Encoding, MethodCallback,
+	Methods, StreamedMessage,
+};
+use jsonrpsee_core::tracing::{rx_log_from_json, tx_log_from_str, TraceParent};
 use jsonrpsee_core::{Error, JsonRawValue};
 use jsonrpsee_types::error::{
-	reject_too_big_batch_request, reject_too_big_request, reject_too_many_subscriptions, ErrorCode,
-	BATCHES_NOT_SUPPORTED_CODE, BATCHES_NOT_SUPPORTED_MSG,
+	reject_rate_limited, reject_too_big_batch_request, reject_too_big_request, ErrorCode, BATCHES_NOT_SUPPORTED_CODE,
+	BATCHES_NOT_SUPPORTED_MSG,
 };
-use jsonrpsee_types::{ErrorObject, Id, InvalidRequest, Notification, Params, Request};
+use jsonrpsee_types::{ErrorObject, ErrorObjectOwned, Extensions, Id, InvalidRequest, Notification, Params, Request};
+use soketto::base::{Header, OpCode};
 use soketto::connection::Error as SokettoError;
 use soketto::data::ByteSlice125;
+use soketto::extension::{Extension, Param};
+use soketto::{BoxedError, Storage};
 
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, watch, OwnedSemaphorePermit, Semaphore};
 use tokio_stream::wrappers::{IntervalStream, ReceiverStream};
 use tokio_util::compat::Compat;
+use tower::Layer;
+use tracing::field::Empty;
 use tracing::instrument;
 
 pub(crate) type Sender = soketto::Sender<BufReader<BufWriter<Compat<Upgraded>>>>;
@@ -35,11 +47,132 @@ pub(crate) type Receiver = soketto::Receiver<BufReader<BufWriter<Compat<Upgraded
 
 type Notif<'a> = Notification<'a, Option<&'a JsonRawValue>>;
 
-pub(crate) async fn send_message(sender: &mut Sender, response: String) -> Result<(), Error> {
-	sender.send_text_owned(response).await?;
+/// Max size of the frames of a streamed message, the chunks of the message are coalesced up to it.
+const MAX_FRAGMENT_SIZE: usize = 16 * 1024;
+
+pub(crate) async fn send_message(
+	sender: &mut Sender,
+	response: EncodedMessage,
+	fragmentation: Option<&Fragmentation>,
+) -> Result<(), Error> {
+	match response {
+		EncodedMessage::Text(text) => sender.send_text_owned(text).await?,
+		EncodedMessage::Binary(bytes) => sender.send_binary_mut(bytes).await?,
+		EncodedMessage::Stream(msg) => send_streamed_message(sender, msg, fragmentation).await?,
+	}
 	sender.flush().await.map_err(Into::into)
 }
 
+/// Send a streamed message as a fragmented message, or as a whole if the connection can't fragment messages.
+///
+/// A message that fails while it's sent can't be completed, the connection must be closed then.
+async fn send_streamed_message(
+	sender: &mut Sender,
+	msg: StreamedMessage,
+	fragmentation: Option<&Fragmentation>,
+) -> Result<(), Error> {
+	let Some(mut chunks) = msg.take() else {
+		tracing::debug!("WS transport: streamed message was already sent; skipping it");
+		return Ok(());
+	};
+
+	let Some(fragmentation) = fragmentation else {
+		let mut text = String::new();
+		while let Some(chunk) = chunks.next().await {
+			text.push_str(&chunk?);
+		}
+		return sender.send_text_owned(text).await.map_err(Into::into);
+	};
+
+	let mut frame = String::with_capacity(MAX_FRAGMENT_SIZE);
+	let mut is_first = true;
+
+	while let Some(chunk) = chunks.next().await {
+		frame.push_str(&chunk?);
+
+		if frame.len() >= MAX_FRAGMENT_SIZE {
+			fragmentation.set(if is_first { Fragment::First } else { Fragment::Continuation });
+			let sent = sender.send_text_owned(std::mem::replace(&mut frame, String::with_capacity(MAX_FRAGMENT_SIZE)));
+			let sent = sent.await;
+			fragmentation.set(Fragment::Whole);
+			sent?;
+			is_first = false;
+		}
+	}
+
+	fragmentation.set(if is_first { Fragment::Whole } else { Fragment::Last });
+	let sent = sender.send_text_owned(frame).await;
+	fragmentation.set(Fragment::Whole);
+	sent.map_err(Into::into)
+}
+
+/// The part of a message that a data frame carries.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Fragment {
+	/// The frame is the whole message.
+	#[default]
+	Whole,
+	/// The first frame of a fragmented message.
+	First,
+	/// A frame in the middle of a fragmented message.
+	Continuation,
+	/// The last frame of a fragmented message.
+	Last,
+}
+
+/// `soketto` only sends whole messages, this extension turns the data frames it sends into
+/// the fragments of a message as set by the sender.
+///
+/// It must be the last extension, the frames of a message which is compressed with
+/// `permessage-deflate` can't be fragmented.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Fragmentation(Arc<Mutex<Fragment>>);
+
+impl Fragmentation {
+	/// Set the part of a message that the next data frame carries.
+	fn set(&self, fragment: Fragment) {
+		*self.0.lock().expect("Fragmentation lock poisoned; qed") = fragment;
+	}
+}
+
+impl Extension for Fragmentation {
+	fn is_enabled(&self) -> bool {
+		true
+	}
+
+	fn name(&self) -> &str {
+		"fragmentation"
+	}
+
+	fn params(&self) -> &[Param<'_>] {
+		&[]
+	}
+
+	fn configure(&mut self, _: &[Param]) -> Result<(), BoxedError> {
+		Ok(())
+	}
+
+	fn encode(&mut self, header: &mut Header, _: &mut Storage) -> Result<(), BoxedError> {
+		match *self.0.lock().expect("Fragmentation lock poisoned; qed") {
+			Fragment::Whole => (),
+			Fragment::First => {
+				header.set_fin(false);
+			}
+			Fragment::Continuation => {
+				header.set_opcode(OpCode::Continue).set_fin(false);
+			}
+			Fragment::Last => {
+				header.set_opcode(OpCode::Continue);
+			}
+		}
+		Ok(())
+	}
+
+	fn decode(&mut self, _: &mut Header, _: &mut Vec<u8>) -> Result<(), BoxedError> {
+		Ok(())
+	}
+}
+
 pub(crate) async fn send_ping(sender: &mut Sender) -> Result<(), Error> {
 	tracing::debug!("Send ping");
 	// Submit empty slice as "optional" parameter.
@@ -51,35 +184,56 @@ pub(crate) async fn send_ping(sender: &mut Sender) -> Result<(), Error> {
 }
 
 #[derive(Debug, Clone)]
-pub(crate) struct Batch<'a, L: Logger> {
+pub(crate) struct Batch<'a, L: Logger, S> {
 	pub(crate) data: &'a [u8],
-	pub(crate) call: CallData<'a, L>,
+	pub(crate) call: CallData<'a, L, S>,
 	pub(crate) max_len: usize,
 }
 
-#[derive(Debug, Clone)]
-pub(crate) struct CallData<'a, L: Logger> {
-	pub(crate) conn_id: usize,
-	pub(crate) bounded_subscriptions: BoundedSubscriptions,
-	pub(crate) id_provider: &'a dyn IdProvider,
+#[derive(Debug)]
+pub(crate) struct CallData<'a, L: Logger, S> {
 	pub(crate) methods: &'a Methods,
+	pub(crate) rpc_service: &'a S,
 	pub(crate) max_response_body_size: u32,
 	pub(crate) max_log_length: u32,
-	pub(crate) sink: &'a MethodSink,
 	pub(crate) logger: &'a L,
 	pub(crate) request_start: L::Instant,
+	pub(crate) extensions: &'a Extensions,
+	pub(crate) access_policy: &'a AccessPolicy,
+	pub(crate) json_rpc_v1: bool,
+}
+
+// NOTE: a manual `Clone` impl is needed because `#[derive(Clone)]` would require `S: Clone`.
+impl<'a, L: Logger, S> Clone for CallData<'a, L, S> {
+	fn clone(&self) -> Self {
+		Self {
+			methods: self.methods,
+			rpc_service: self.rpc_service,
+			max_response_body_size: self.max_response_body_size,
+			max_log_length: self.max_log_length,
+			logger: self.logger,
+			request_start: self.request_start,
+			extensions: self.extensions,
+			access_policy: self.access_policy,
+			json_rpc_v1: self.json_rpc_v1,
+		}
+	}
 }
 
 // Batch responses must be sent back as a single message so we read the results from each
 // request in the batch and read the results off of a new channel, `rx_batch`, and then send the
 // complete batch response back to the client over `tx`.
 #[instrument(name = "batch", skip(b), level = "TRACE")]
-pub(crate) async fn process_batch_request<L: Logger>(b: Batch<'_, L>) -> Option<String> {
+pub(crate) async fn process_batch_request<L, S>(b: Batch<'_, L, S>) -> Option<EncodedMessage>
+where
+	L: Logger,
+	for<'a> S: RpcServiceT<'a> + Send + Sync,
+{
 	let Batch { data, call, max_len } = b;
 
 	if let Ok(batch) = serde_json::from_slice::<Vec<&JsonRawValue>>(data) {
 		if batch.len() > max_len {
-			return Some(batch_response_error(Id::Null, reject_too_big_batch_request(max_len)));
+			return Some(batch_response_error(Id::Null, reject_too_big_batch_request(max_len)).into());
 		}
 
 		let mut got_notif = false;
@@ -87,28 +241,32 @@ pub(crate) async fn process_batch_request<L: Logger>(b: Batch<'_, L>) -> Option<
 
 		let mut pending_calls: FuturesOrdered<_> = batch
 			.into_iter()
-			.filter_map(|v| {
-				if let Ok(req) = serde_json::from_str::<Request>(v.get()) {
-					Some(Either::Right(async { execute_call(req, call.clone()).await.into_response() }))
-				} else if let Ok(_notif) = serde_json::from_str::<Notif>(v.get()) {
-					// notifications should not be answered.
-					got_notif = true;
-					None
-				} else {
-					// valid JSON but could be not parsable as `InvalidRequest`
-					let id = match serde_json::from_str::<InvalidRequest>(v.get()) {
-						Ok(err) => err.id,
-						Err(_) => Id::Null,
-					};
+			.map(|v| {
+				let call = call.clone();
 
-					Some(Either::Left(async {
-						MethodResponse::error(id, ErrorObject::from(ErrorCode::InvalidRequest))
-					}))
+				match JsonCall::parse(v.get().as_bytes(), call.json_rpc_v1, call.max_log_length) {
+					JsonCall::Request(req) => Either::Right(Either::Left(async move {
+						Some(execute_call_with_tracing(req, call).await.into_response())
+					})),
+					// notifications should not be answered.
+					JsonCall::Notification(method, params) => {
+						got_notif = true;
+						Either::Right(Either::Right(async move {
+							execute_notification(&method, params, call).await;
+							None
+						}))
+					}
+					JsonCall::Invalid(id) => Either::Left(async move {
+						Some(MethodResponse::error(id, ErrorObject::from(ErrorCode::InvalidRequest)))
+					}),
 				}
 			})
 			.collect();
 
 		while let Some(response) = pending_calls.next().await {
+			let Some(response) = response else { continue };
+			let response = response.into_buffered(call.max_response_body_size as usize).await;
+
 			if let Err(too_large) = batch_response.append(&response) {
 				return Some(too_large);
 			}
@@ -120,30 +278,107 @@ pub(crate) async fn process_batch_request<L: Logger>(b: Batch<'_, L>) -> Option<
 			Some(batch_response.finish())
 		}
 	} else {
-		Some(batch_response_error(Id::Null, ErrorObject::from(ErrorCode::ParseError)))
+		Some(batch_response_error(Id::Null, ErrorObject::from(ErrorCode::ParseError)).into())
 	}
 }
 
-pub(crate) async fn process_single_request<L: Logger>(
-	data: &[u8],
-	call: CallData<'_, L>,
-) -> Option<CallOrSubscription> {
-	if let Ok(req) = serde_json::from_slice::<Request>(data) {
-		Some(execute_call_with_tracing(req, call).await)
-	} else if serde_json::from_slice::<Notif>(data).is_ok() {
+/// Same as [`process_batch_request`] for a decoded CBOR batch, which is answered in CBOR.
+#[cfg(feature = "cbor")]
+async fn process_cbor_batch_request<L, S>(
+	batch: Vec<ciborium::Value>,
+	call: CallData<'_, L, S>,
+	max_len: usize,
+) -> Option<EncodedMessage>
+where
+	L: Logger,
+	for<'a> S: RpcServiceT<'a> + Send + Sync,
+{
+	if batch.len() > max_len {
+		return Some(
+			MethodResponse::encoded_error(Id::Null, reject_too_big_batch_request(max_len), Encoding::Cbor).result,
+		);
+	}
+
+	let mut got_notif = false;
+	let mut batch_response =
+		BatchResponseBuilder::new_with_encoding(call.max_response_body_size as usize, Encoding::Cbor);
+
+	let mut pending_calls: FuturesOrdered<_> = batch
+		.into_iter()
+		.map(|v| {
+			let call = call.clone();
+
+			match cbor::Call::parse(v) {
+				cbor::Call::Request(req) => Either::Right(Either::Left(async move {
+					Some(execute_call_with_tracing(req, call).await.into_response())
+				})),
+				// notifications should not be answered.
+				cbor::Call::Notification(method, params) => {
+					got_notif = true;
+					Either::Right(Either::Right(async move {
+						execute_notification(&method, params, call).await;
+						None
+					}))
+				}
+				cbor::Call::Invalid(id) => Either::Left(async move {
+					Some(MethodResponse::encoded_error(id, ErrorCode::InvalidRequest, Encoding::Cbor))
+				}),
+			}
+		})
+		.collect();
+
+	while let Some(response) = pending_calls.next().await {
+		let Some(response) = response else { continue };
+		let response = response.into_buffered(call.max_response_body_size as usize).await;
+
+		if let Err(too_large) = batch_response.append(&response) {
+			return Some(too_large);
+		}
+	}
+
+	if got_notif && batch_response.is_empty() {
 		None
 	} else {
-		let (id, code) = prepare_error(data);
-		Some(CallOrSubscription::Call(MethodResponse::error(id, ErrorObject::from(code))))
+		Some(batch_response.finish())
 	}
 }
 
-#[instrument(name = "method_call", fields(method = req.method.as_ref()), skip(call, req), level = "TRACE")]
-pub(crate) async fn execute_call_with_tracing<'a, L: Logger>(
+pub(crate) async fn process_single_request<L, S>(data: &[u8], call: CallData<'_, L, S>) -> Option<CallOrSubscription>
+where
+	L: Logger,
+	for<'a> S: RpcServiceT<'a> + Send + Sync,
+{
+	match JsonCall::parse(data, call.json_rpc_v1, call.max_log_length) {
+		JsonCall::Request(req) => Some(execute_call_with_tracing(req, call).await),
+		JsonCall::Notification(method, params) => {
+			execute_notification(&method, params, call).await;
+			None
+		}
+		JsonCall::Invalid(_) => {
+			let (id, code) = prepare_error(data);
+			Some(CallOrSubscription::Call(MethodResponse::error(id, ErrorObject::from(code))))
+		}
+	}
+}
+
+#[instrument(
+	name = "method_call",
+	fields(method = req.method.as_ref(), trace_id = Empty, span_id = Empty, parent_id = Empty),
+	skip(call, req),
+	level = "TRACE"
+)]
+pub(crate) async fn execute_call_with_tracing<'a, L, S>(
 	req: Request<'a>,
-	call: CallData<'_, L>,
-) -> CallOrSubscription {
-	execute_call(req, call).await
+	call: CallData<'_, L, S>,
+) -> CallOrSubscription
+where
+	L: Logger,
+	for<'b> S: RpcServiceT<'b> + Send + Sync,
+{
+	match call_trace_context(&req, call.extensions) {
+		Some(ctx) => ctx.scope(execute_call(req, call)).await,
+		None => execute_call(req, call).await,
+	}
 }
 
 /// Execute a call which returns result of the call with a additional sink
@@ -151,87 +386,92 @@ pub(crate) async fn execute_call_with_tracing<'a, L: Logger>(
 ///
 /// Returns `(MethodResponse, None)` on every call that isn't a subscription
 /// Otherwise `(MethodResponse, Some(PendingSubscriptionCallTx)`.
-pub(crate) async fn execute_call<'a, L: Logger>(req: Request<'a>, call: CallData<'_, L>) -> CallOrSubscription {
-	let CallData {
-		methods,
-		max_response_body_size,
-		max_log_length,
-		conn_id,
-		id_provider,
-		sink,
-		logger,
-		request_start,
-		bounded_subscriptions,
-	} = call;
+pub(crate) async fn execute_call<'a, L, S>(mut req: Request<'a>, call: CallData<'_, L, S>) -> CallOrSubscription
+where
+	L: Logger,
+	for<'b> S: RpcServiceT<'b> + Send + Sync,
+{
+	let CallData { methods, rpc_service, max_log_length, logger, request_start, extensions, .. } = call;
+
+	// The request may carry extensions of its own, such as the encoding of a JSON-RPC 1.0 call.
+	let own_extensions = std::mem::take(&mut req.extensions);
+	req.extensions = extensions.clone();
+	req.extensions.extend(own_extensions);
+	if let Some(ctx) = TraceParent::current() {
+		req.extensions.insert(ctx);
+	}
 
 	rx_log_from_json(&req, call.max_log_length);
 
-	let params = Params::new(req.params.map(|params| params.get()));
-	let name = &req.method;
-	let id = req.id;
+	let params = match req.decoded_params {
+		Some(ref params) => params.clone(),
+		None => Params::new(req.params.as_ref().map(|params| params.get())),
+	};
+	let name = req.method.clone();
+
+	let kind = match methods.method(&name) {
+		None => logger::MethodKind::Unknown,
+		Some(MethodCallback::Sync(_) | MethodCallback::Async(_)) => logger::MethodKind::MethodCall,
+		Some(MethodCallback::Subscription(_)) => logger::MethodKind::Subscription,
+		Some(MethodCallback::Unsubscription(_)) => logger::MethodKind::Unsubscription,
+		Some(MethodCallback::Notification(_)) => logger::MethodKind::Unknown,
+	};
+	logger.on_call(&name, params, kind, TransportProtocol::WebSocket);
 
-	let response = match methods.method_with_name(name) {
-		None => {
-			logger.on_call(name, params.clone(), logger::MethodKind::Unknown, TransportProtocol::WebSocket);
-			let response = MethodResponse::error(id, ErrorObject::from(ErrorCode::MethodNotFound));
-			CallOrSubscription::Call(response)
-		}
-		Some((name, method)) => match method {
-			MethodCallback::Sync(callback) => {
-				logger.on_call(name, params.clone(), logger::MethodKind::MethodCall, TransportProtocol::WebSocket);
-				CallOrSubscription::Call((callback)(id, params, max_response_body_size as usize))
-			}
-			MethodCallback::Async(callback) => {
-				logger.on_call(name, params.clone(), logger::MethodKind::MethodCall, TransportProtocol::WebSocket);
+	let response = rpc_service.call(req).await;
 
-				let id = id.into_owned();
-				let params = params.into_owned();
+	tx_log_from_str(response.result.as_log_str(), max_log_length);
+	logger.on_result(&name, response.success_or_error, request_start, TransportProtocol::WebSocket);
 
-				let response = (callback)(id, params, conn_id, max_response_body_size as usize).await;
-				CallOrSubscription::Call(response)
-			}
-			MethodCallback::Subscription(callback) => {
-				logger.on_call(name, params.clone(), logger::MethodKind::Subscription, TransportProtocol::WebSocket);
-
-				if let Some(p) = bounded_subscriptions.acquire() {
-					let conn_state = SubscriptionState { conn_id, id_provider, subscription_permit: p };
-					match callback(id, params, sink.clone(), conn_state).await {
-						Ok(r) => CallOrSubscription::Subscription(r),
-						Err(id) => {
-							let response = MethodResponse::error(id, ErrorObject::from(ErrorCode::InternalError));
-							CallOrSubscription::Call(response)
-						}
-					}
-				} else {
-					let response =
-						MethodResponse::error(id, reject_too_many_subscriptions(bounded_subscriptions.max()));
-					CallOrSubscription::Call(response)
-				}
-			}
-			MethodCallback::Unsubscription(callback) => {
-				logger.on_call(name, params.clone(), logger::MethodKind::Unsubscription, TransportProtocol::WebSocket);
+	if response.is_subscription {
+		CallOrSubscription::Subscription(response)
+	} else {
+		CallOrSubscription::Call(response)
+	}
+}
 
-				// Don't adhere to any resource or subscription limits; always let unsubscribing happen!
-				let result = callback(id, params, conn_id, max_response_body_size as usize);
-				CallOrSubscription::Call(result)
-			}
-		},
-	};
+#[instrument(name = "notification", skip(params, call), level = "TRACE")]
+async fn execute_notification<L, S>(method: &str, params: Params<'_>, call: CallData<'_, L, S>)
+where
+	L: Logger,
+	for<'a> S: RpcServiceT<'a> + Send + Sync,
+{
+	let CallData { methods, logger, rpc_service, extensions, .. } = call;
 
-	let r = response.as_response();
+	if let Some(MethodCallback::Notification(_)) = methods.method(method) {
+		logger.on_call(method, params.clone(), logger::MethodKind::Notification, TransportProtocol::WebSocket);
+	} else {
+		tracing::debug!("No notification handler registered for method `{}`; ignoring it", method);
+	}
 
-	tx_log_from_str(&r.result, max_log_length);
-	logger.on_result(name, r.success_or_error, request_start, TransportProtocol::WebSocket);
-	response
+	// Notifications go through the RPC middleware too, the response is discarded.
+	let mut req = Request::new(method.into(), None, Id::Null);
+	req.decoded_params = Some(params);
+	req.extensions = extensions.clone();
+	req.extensions.insert(IsNotification);
+	if let Some(code) = rpc_service.call(req).await.success_or_error.as_error_code() {
+		tracing::debug!("Notification `{}` was rejected with error code {}; ignoring it", method, code);
+	}
 }
 
-pub(crate) async fn background_task<L: Logger>(sender: Sender, mut receiver: Receiver, svc: ServiceData<L>) {
+pub(crate) async fn background_task<L, R>(
+	sender: Sender,
+	mut receiver: Receiver,
+	svc: ServiceData<L, R>,
+	extensions: Extensions,
+	fragmentation: Option<Fragmentation>,
+) where
+	L: Logger,
+	R: Layer<RpcService>,
+	for<'a> <R as Layer<RpcService>>::Service: RpcServiceT<'a> + Send + Sync + 'static,
+{
 	let ServiceData {
 		methods,
 		max_request_body_size,
 		max_response_body_size,
 		max_log_length,
 		max_subscriptions_per_connection,
+		max_concurrent_calls_per_connection,
 		batch_requests_config,
 		stop_handle,
 		id_provider,
@@ -240,32 +480,67 @@ pub(crate) async fn background_task<L: Logger>(sender: Sender, mut receiver: Rec
 		logger,
 		remote_addr,
 		message_buffer_capacity,
+		default_call_timeout,
+		access_policy,
+		json_rpc_v1,
 		conn,
+		rate_limiter,
+		rpc_middleware,
 		..
 	} = svc;
 
-	let (tx, rx) = mpsc::channel::<String>(message_buffer_capacity as usize);
+	let (tx, rx) = mpsc::channel::<EncodedMessage>(message_buffer_capacity as usize);
 	let (conn_tx, conn_rx) = oneshot::channel();
-	let sink = MethodSink::new_with_limit(tx, max_response_body_size, max_log_length);
-	let bounded_subscriptions = BoundedSubscriptions::new(max_subscriptions_per_connection);
+	// Notifies the subscriptions on this connection once the server has been stopped.
+	let (shutdown_tx, shutdown_rx) = watch::channel(());
+	let sink = MethodSink::new_with_limit(tx, max_response_body_size, max_log_length)
+		.with_shutdown(shutdown_rx)
+		.with_encoding(Encoding::from_extensions(&extensions));
+	let bounded_subscriptions = {
+		let (open, close) = (logger.clone(), logger.clone());
+		BoundedSubscriptions::new(max_subscriptions_per_connection).with_hooks(
+			Arc::new(move || open.on_subscription_open(TransportProtocol::WebSocket)),
+			Arc::new(move || close.on_subscription_close(TransportProtocol::WebSocket)),
+		)
+	};
 	let pending_calls = FuturesUnordered::new();
+	let calls_limit =
+		max_concurrent_calls_per_connection.map(|(max, policy)| (Arc::new(Semaphore::new(max as usize)), policy));
 
 	// Spawn another task that sends out the responses on the Websocket.
-	let send_task_handle = tokio::spawn(send_task(rx, sender, ping_config.ping_interval(), conn_rx));
+	let stream_responses = fragmentation.is_some();
+	let send_task_handle = tokio::spawn(send_task(rx, sender, fragmentation, ping_config.ping_interval(), conn_rx));
 
 	// Buffer for incoming data.
 	let mut data = Vec::with_capacity(100);
 	let stopped = stop_handle.clone().shutdown();
 
+	let rpc_service = rpc_middleware.service(RpcService::new(
+		methods.clone(),
+		max_response_body_size as usize,
+		conn_id as usize,
+		default_call_timeout,
+		access_policy.clone(),
+		rate_limiter.clone(),
+		RpcServiceCfg::CallsAndSubscriptions {
+			bounded_subscriptions: bounded_subscriptions.clone(),
+			sink: sink.clone(),
+			id_provider,
+		},
+	));
+
 	let params = Arc::new(ExecuteCallParams {
 		batch_requests_config,
-		conn_id,
 		methods,
 		max_log_length,
 		max_response_body_size,
 		sink: sink.clone(),
-		id_provider,
 		logger: logger.clone(),
+		rpc_service,
+		extensions,
+		access_policy,
+		json_rpc_v1,
+		stream_responses,
 	});
 
 	tokio::pin!(stopped);
@@ -286,6 +561,25 @@ pub(crate) async fn background_task<L: Logger>(sender: Sender, mut receiver: Rec
 
 		stopped = stop;
 
+		// Don't read the next message until it can be executed.
+		let mut call_permit = None;
+
+		if let Some((permits, policy @ ConcurrentCallsPolicy::Backpressure)) = &calls_limit {
+			let permit = match permits.clone().try_acquire_owned() {
+				Ok(permit) => permit,
+				Err(_) => {
+					logger.on_concurrent_calls_limit(*policy, TransportProtocol::WebSocket);
+
+					let Some((permit, stop)) = wait_for_call_permit(permits.clone(), stopped).await else {
+						break Ok(Shutdown::Stopped);
+					};
+					stopped = stop;
+					permit
+				}
+			};
+			call_permit = Some(permit);
+		}
+
 		match try_recv(&mut receiver, &mut data, stopped, ping_config).await {
 			Receive::Shutdown => break Ok(Shutdown::Stopped),
 			Receive::Ok(stop) => {
@@ -319,17 +613,47 @@ pub(crate) async fn background_task<L: Logger>(sender: Sender, mut receiver: Rec
 			}
 		};
 
-		pending_calls.push(tokio::spawn(execute_unchecked_call(
-			params.clone(),
-			std::mem::take(&mut data),
-			bounded_subscriptions.clone(),
-		)));
+		if let Some(Err(retry_after)) = rate_limiter.as_ref().map(|limiter| limiter.check_request()) {
+			tracing::debug!("Rate limit exceeded by {}, retry after {:?}", remote_addr, retry_after);
+
+			if reject_message(&sink, &data, reject_rate_limited(retry_after)).await.is_err() {
+				break Ok(Shutdown::ConnectionClosed);
+			}
+
+			continue;
+		}
+
+		if let Some((permits, policy @ ConcurrentCallsPolicy::Reject)) = &calls_limit {
+			match permits.clone().try_acquire_owned() {
+				Ok(permit) => call_permit = Some(permit),
+				Err(_) => {
+					logger.on_concurrent_calls_limit(*policy, TransportProtocol::WebSocket);
+
+					if reject_message(&sink, &data, ErrorCode::ServerIsBusy.into()).await.is_err() {
+						break Ok(Shutdown::ConnectionClosed);
+					}
+
+					continue;
+				}
+			}
+		}
+
+		let call = execute_unchecked_call(params.clone(), std::mem::take(&mut data));
+		pending_calls.push(tokio::spawn(stop_handle.run_call(async move {
+			call.await;
+			drop(call_permit);
+		})));
 	};
 
+	if let Ok(Shutdown::Stopped) = result {
+		_ = shutdown_tx.send(());
+	}
+
 	// Drive all running methods to completion.
 	// **NOTE** Do not return early in this function. This `await` needs to run to guarantee
 	// proper drop behaviour.
-	graceful_shutdown(result, pending_calls, receiver, data, conn_tx, send_task_handle).await;
+	graceful_shutdown(result, pending_calls, bounded_subscriptions, receiver, conn_tx, send_task_handle, &stop_handle)
+		.await;
 
 	logger.on_disconnect(remote_addr, TransportProtocol::WebSocket);
 	drop(conn);
@@ -338,8 +662,9 @@ pub(crate) async fn background_task<L: Logger>(sender: Sender, mut receiver: Rec
 
 /// A task that waits for new messages via the `rx channel` and sends them out on the `WebSocket`.
 async fn send_task(
-	rx: mpsc::Receiver<String>,
+	rx: mpsc::Receiver<EncodedMessage>,
 	mut ws_sender: Sender,
+	fragmentation: Option<Fragmentation>,
 	ping_interval: Duration,
 	stop: oneshot::Receiver<()>,
 ) {
@@ -365,7 +690,7 @@ async fn send_task(
 			// Received message.
 			Either::Left((Some(response), not_ready)) => {
 				// If websocket message send fail then terminate the connection.
-				if let Err(err) = send_message(&mut ws_sender, response).await {
+				if let Err(err) = send_message(&mut ws_sender, response, fragmentation.as_ref()).await {
 					tracing::debug!("WS transport error: send failed: {}", err);
 					break;
 				}
@@ -424,6 +749,41 @@ where
 	}
 }
 
+/// Wait until a call may be executed on the connection, returns `None` if the server was stopped.
+async fn wait_for_call_permit<S>(permits: Arc<Semaphore>, stopped: S) -> Option<(OwnedSemaphorePermit, S)>
+where
+	S: Future<Output = ()> + Unpin,
+{
+	let acquire = permits.acquire_owned();
+	tokio::pin!(acquire);
+
+	match futures_util::future::select(acquire, stopped).await {
+		Either::Left((Ok(permit), s)) => Some((permit, s)),
+		_ => None,
+	}
+}
+
+/// Answer a message that was rejected before it was executed with `err`.
+///
+/// Batches are answered with a single error and notifications aren't answered at all.
+async fn reject_message(sink: &MethodSink, data: &[u8], err: ErrorObjectOwned) -> Result<(), DisconnectError> {
+	#[cfg(feature = "cbor")]
+	if sink.encoding() == Encoding::Cbor {
+		return match cbor::message_id(data) {
+			Some(id) => sink.send_error(id, err).await,
+			None => Ok(()),
+		};
+	}
+
+	let id = match serde_json::from_slice::<InvalidRequest>(data) {
+		Ok(req) => req.id,
+		Err(_) if serde_json::from_slice::<Notif>(data).is_ok() => return Ok(()),
+		Err(_) => Id::Null,
+	};
+
+	sink.send_error(id, err).await
+}
+
 /// Attempts to read data from WebSocket fails if the server was stopped.
 async fn try_recv<S>(receiver: &mut Receiver, data: &mut Vec<u8>, mut stopped: S, ping_config: PingConfig) -> Receive<S>
 where
@@ -483,35 +843,45 @@ where
 	}
 }
 
-struct ExecuteCallParams<L: Logger> {
+struct ExecuteCallParams<L: Logger, S> {
 	batch_requests_config: BatchRequestConfig,
-	conn_id: u32,
-	id_provider: Arc<dyn IdProvider>,
-	methods: Methods,
+	methods: SharedMethods,
 	max_response_body_size: u32,
 	max_log_length: u32,
 	sink: MethodSink,
 	logger: L,
+	rpc_service: S,
+	extensions: Extensions,
+	access_policy: AccessPolicy,
+	json_rpc_v1: bool,
+	/// Whether streamed responses are sent as fragmented messages, they are buffered otherwise.
+	stream_responses: bool,
 }
 
-async fn execute_unchecked_call<L: Logger>(
-	params: Arc<ExecuteCallParams<L>>,
-	data: Vec<u8>,
-	bounded_subscriptions: BoundedSubscriptions,
-) {
+async fn execute_unchecked_call<L, S>(params: Arc<ExecuteCallParams<L, S>>, data: Vec<u8>)
+where
+	L: Logger,
+	for<'a> S: RpcServiceT<'a> + Send + Sync,
+{
+	#[cfg(feature = "cbor")]
+	if params.sink.encoding() == Encoding::Cbor {
+		return execute_unchecked_cbor_call(params, data).await;
+	}
+
 	let request_start = params.logger.on_request(TransportProtocol::WebSocket);
 	let first_non_whitespace = data.iter().enumerate().take(128).find(|(_, byte)| !byte.is_ascii_whitespace());
+	let methods = params.methods.load();
 
 	let call_data = CallData {
-		bounded_subscriptions,
-		conn_id: params.conn_id as usize,
 		max_response_body_size: params.max_response_body_size,
 		max_log_length: params.max_log_length,
-		methods: &params.methods,
-		sink: &params.sink,
-		id_provider: &*params.id_provider,
+		methods: &methods,
+		rpc_service: &params.rpc_service,
 		logger: &params.logger,
 		request_start,
+		extensions: &params.extensions,
+		access_policy: &params.access_policy,
+		json_rpc_v1: params.json_rpc_v1,
 	};
 
 	match first_non_whitespace {
@@ -519,11 +889,14 @@ async fn execute_unchecked_call<L: Logger>(
 			if let Some(rp) = process_single_request(&data[start..], call_data).await {
 				match rp {
 					CallOrSubscription::Subscription(r) => {
-						params.logger.on_response(&r.result, request_start, TransportProtocol::WebSocket);
+						params.logger.on_response(r.result.as_log_str(), request_start, TransportProtocol::WebSocket);
 					}
 
-					CallOrSubscription::Call(r) => {
-						params.logger.on_response(&r.result, request_start, TransportProtocol::WebSocket);
+					CallOrSubscription::Call(mut r) => {
+						if !params.stream_responses {
+							r = r.into_buffered(params.max_response_body_size as usize).await;
+						}
+						params.logger.on_response(r.result.as_log_str(), request_start, TransportProtocol::WebSocket);
 						_ = params.sink.send(r.result).await;
 					}
 				}
@@ -536,7 +909,11 @@ async fn execute_unchecked_call<L: Logger>(
 						Id::Null,
 						ErrorObject::borrowed(BATCHES_NOT_SUPPORTED_CODE, BATCHES_NOT_SUPPORTED_MSG, None),
 					);
-					params.logger.on_response(&response.result, request_start, TransportProtocol::WebSocket);
+					params.logger.on_response(
+						response.result.as_log_str(),
+						request_start,
+						TransportProtocol::WebSocket,
+					);
 					_ = params.sink.send(response.result).await;
 					return;
 				}
@@ -547,8 +924,8 @@ async fn execute_unchecked_call<L: Logger>(
 			let response = process_batch_request(Batch { data: &data[start..], call: call_data, max_len: limit }).await;
 
 			if let Some(response) = response {
-				tx_log_from_str(&response, params.max_log_length);
-				params.logger.on_response(&response, request_start, TransportProtocol::WebSocket);
+				tx_log_from_str(response.as_log_str(), params.max_log_length);
+				params.logger.on_response(response.as_log_str(), request_start, TransportProtocol::WebSocket);
 				_ = params.sink.send(response).await;
 			}
 		}
@@ -558,6 +935,70 @@ async fn execute_unchecked_call<L: Logger>(
 	};
 }
 
+/// Same as [`execute_unchecked_call`] for a CBOR encoded message, which is answered in CBOR.
+#[cfg(feature = "cbor")]
+async fn execute_unchecked_cbor_call<L, S>(params: Arc<ExecuteCallParams<L, S>>, data: Vec<u8>)
+where
+	L: Logger,
+	for<'a> S: RpcServiceT<'a> + Send + Sync,
+{
+	let request_start = params.logger.on_request(TransportProtocol::WebSocket);
+	let methods = params.methods.load();
+
+	let call_data = CallData {
+		max_response_body_size: params.max_response_body_size,
+		max_log_length: params.max_log_length,
+		methods: &methods,
+		rpc_service: &params.rpc_service,
+		logger: &params.logger,
+		request_start,
+		extensions: &params.extensions,
+		access_policy: &params.access_policy,
+		json_rpc_v1: params.json_rpc_v1,
+	};
+
+	let response = match cbor::Message::decode(&data) {
+		Some(cbor::Message::Single(call)) => match cbor::Call::parse(call) {
+			cbor::Call::Request(req) => match execute_call_with_tracing(req, call_data).await {
+				CallOrSubscription::Subscription(r) => {
+					params.logger.on_response(r.result.as_log_str(), request_start, TransportProtocol::WebSocket);
+					return;
+				}
+				CallOrSubscription::Call(r) => r.result,
+			},
+			cbor::Call::Notification(method, call_params) => {
+				execute_notification(&method, call_params, call_data).await;
+				return;
+			}
+			cbor::Call::Invalid(id) => {
+				MethodResponse::encoded_error(id, ErrorCode::InvalidRequest, Encoding::Cbor).result
+			}
+		},
+		Some(cbor::Message::Batch(batch)) => {
+			let limit = match params.batch_requests_config {
+				BatchRequestConfig::Disabled => None,
+				BatchRequestConfig::Limit(limit) => Some(limit as usize),
+				BatchRequestConfig::Unlimited => Some(usize::MAX),
+			};
+
+			match limit {
+				Some(limit) => match process_cbor_batch_request(batch, call_data, limit).await {
+					Some(response) => response,
+					None => return,
+				},
+				None => {
+					let err = ErrorObject::borrowed(BATCHES_NOT_SUPPORTED_CODE, BATCHES_NOT_SUPPORTED_MSG, None);
+					MethodResponse::encoded_error(Id::Null, err, Encoding::Cbor).result
+				}
+			}
+		}
+		None => MethodResponse::encoded_error(Id::Null, ErrorCode::ParseError, Encoding::Cbor).result,
+	};
+
+	params.logger.on_response(response.as_log_str(), request_start, TransportProtocol::WebSocket);
+	_ = params.sink.send(response).await;
+}
+
 #[derive(Debug, Copy, Clone)]
 pub(crate) enum Shutdown {
 	Stopped,
@@ -570,10 +1011,11 @@ pub(crate) enum Shutdown {
 async fn graceful_shutdown<F: Future>(
 	result: Result<Shutdown, SokettoError>,
 	pending_calls: FuturesUnordered<F>,
+	subscriptions: BoundedSubscriptions,
 	receiver: Receiver,
-	data: Vec<u8>,
 	mut conn_tx: oneshot::Sender<()>,
-	send_task_handle: tokio::task::JoinHandle<()>,
+	mut send_task_handle: tokio::task::JoinHandle<()>,
+	stop_handle: &StopHandle,
 ) {
 	match result {
 		Ok(Shutdown::ConnectionClosed) | Err(SokettoError::Closed) => (),
@@ -583,29 +1025,42 @@ async fn graceful_shutdown<F: Future>(
 			// been terminated.
 			//
 			// The receiver is not cancel-safe such that it's used in a stream to enforce that.
-			let disconnect_stream = futures_util::stream::unfold((receiver, data), |(mut receiver, mut data)| async {
-				if let Err(SokettoError::Closed) = receiver.receive(&mut data).await {
-					None
-				} else {
-					Some(((), (receiver, data)))
-				}
-			});
+			let disconnect_stream =
+				futures_util::stream::unfold((receiver, Vec::new()), |(mut receiver, mut data)| async {
+					if let Err(SokettoError::Closed) = receiver.receive(&mut data).await {
+						None
+					} else {
+						Some(((), (receiver, data)))
+					}
+				});
+
+			let graceful_shutdown = async {
+				pending_calls.for_each(|_| async {}).await;
 
-			let graceful_shutdown = pending_calls.for_each(|_| async {});
+				// Let the subscriptions send out their close notifications.
+				if stop_handle.is_graceful() {
+					subscriptions.released().await;
+				}
+			};
 			let disconnect = disconnect_stream.for_each(|_| async {});
 
-			// All pending calls has been finished or the connection closed.
+			// All pending calls has been finished, the connection closed
+			// or the graceful shutdown deadline has been hit.
 			// Fine to terminate
 			tokio::select! {
 				_ = graceful_shutdown => {}
 				_ = disconnect => {}
 				_ = conn_tx.closed() => {}
+				_ = stop_handle.aborted() => {}
 			}
 		}
 	};
 
 	// Send a message to close down the "send task".
 	_ = conn_tx.send(());
-	// Ensure that send task has been closed.
-	_ = send_task_handle.await;
+	// Ensure that send task has been closed or abort it if the graceful shutdown deadline has been hit.
+	tokio::select! {
+		_ = &mut send_task_handle => {}
+		_ = stop_handle.aborted() => send_task_handle.abort(),
+	}
 }
@@ -71,6 +71,13 @@ pub fn parse_error(id: Id) -> String {
 	)
 }
 
+pub fn server_is_busy(id: Id) -> String {
+	format!(
+		r#"{{"jsonrpc":"2.0","error":{{"code":-32009,"message":"Server is busy, try again later"}},"id":{}}}"#,
+		serde_json::to_string(&id).unwrap()
+	)
+}
+
 pub fn oversized_request(max_limit: u32) -> String {
 	format!(
 		r#"{{"jsonrpc":"2.0","error":{{"code":-32007,"message":"Request is too big","data":"Exceeded max limit of {max_limit}"}},"id":null}}"#
@@ -91,12 +91,27 @@ impl From<io::Error> for WebSocketTestError {
 
 impl WebSocketTestClient {
 	pub async fn new(url: SocketAddr) -> Result<Self, WebSocketTestError> {
+		Self::connect(url, &[]).await.map(|(client, _)| client)
+	}
+
+	/// Connect with the given subprotocol, which returns the subprotocol that the server selected.
+	pub async fn new_with_protocol(
+		url: SocketAddr,
+		protocol: &str,
+	) -> Result<(Self, Option<String>), WebSocketTestError> {
+		Self::connect(url, &[protocol]).await
+	}
+
+	async fn connect(url: SocketAddr, protocols: &[&str]) -> Result<(Self, Option<String>), WebSocketTestError> {
 		let socket = TcpStream::connect(url).await?;
 		let mut client = handshake::Client::new(BufReader::new(BufWriter::new(socket.compat())), "test-client", "/");
+		for protocol in protocols {
+			client.add_protocol(protocol);
+		}
 		match client.handshake().await {
-			Ok(handshake::ServerResponse::Accepted { .. }) => {
+			Ok(handshake::ServerResponse::Accepted { protocol }) => {
 				let (tx, rx) = client.into_builder().finish();
-				Ok(Self { tx, rx })
+				Ok((Self { tx, rx }, protocol))
 			}
 			Ok(handshake::ServerResponse::Redirect { .. }) => Err(WebSocketTestError::Redirect),
 			Ok(handshake::ServerResponse::Rejected { status_code }) => {
@@ -124,6 +139,18 @@ impl WebSocketTestClient {
 		self.receive().await
 	}
 
+	pub async fn send_binary(&mut self, msg: &[u8]) -> Result<Vec<u8>, Error> {
+		self.tx.send_binary(msg).await?;
+		self.tx.flush().await?;
+		self.receive_binary().await
+	}
+
+	pub async fn receive_binary(&mut self) -> Result<Vec<u8>, Error> {
+		let mut data = Vec::new();
+		self.rx.receive_data(&mut data).await?;
+		Ok(data)
+	}
+
 	pub async fn receive(&mut self) -> Result<String, Error> {
 		let mut data = Vec::new();
 		self.rx.receive_data(&mut data).await?;
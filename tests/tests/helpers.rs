@@ -47,18 +47,23 @@ pub async fn server_with_subscription_and_handle() -> (SocketAddr, ServerHandle)
 	let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
 
 	let mut module = RpcModule::new(());
-	module.register_method("say_hello", |_, _| "hello").unwrap();
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
 
 	module
-		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, pending, _| async move {
-			let interval = interval(Duration::from_millis(50));
-			let stream = IntervalStream::new(interval).map(move |_| &"hello from subscription");
-			pipe_from_stream_and_drop(pending, stream).await.map_err(Into::into)
-		})
+		.register_subscription(
+			"subscribe_hello",
+			"subscribe_hello",
+			"unsubscribe_hello",
+			|_, pending, _, _| async move {
+				let interval = interval(Duration::from_millis(50));
+				let stream = IntervalStream::new(interval).map(move |_| &"hello from subscription");
+				pipe_from_stream_and_drop(pending, stream).await.map_err(Into::into)
+			},
+		)
 		.unwrap();
 
 	module
-		.register_subscription("subscribe_foo", "subscribe_foo", "unsubscribe_foo", |_, pending, _| async {
+		.register_subscription("subscribe_foo", "subscribe_foo", "unsubscribe_foo", |_, pending, _, _| async {
 			let interval = interval(Duration::from_millis(100));
 			let stream = IntervalStream::new(interval).map(move |_| 1337_usize);
 			pipe_from_stream_and_drop(pending, stream).await.map_err(Into::into)
@@ -70,7 +75,7 @@ pub async fn server_with_subscription_and_handle() -> (SocketAddr, ServerHandle)
 			"subscribe_add_one",
 			"subscribe_add_one",
 			"unsubscribe_add_one",
-			|params, pending, _| async move {
+			|params, pending, _, _| async move {
 				let count = match params.one::<usize>().map(|c| c.wrapping_add(1)) {
 					Ok(count) => count,
 					Err(e) => {
@@ -88,7 +93,7 @@ pub async fn server_with_subscription_and_handle() -> (SocketAddr, ServerHandle)
 		.unwrap();
 
 	module
-		.register_subscription("subscribe_noop", "subscribe_noop", "unsubscribe_noop", |_, pending, _| async {
+		.register_subscription("subscribe_noop", "subscribe_noop", "unsubscribe_noop", |_, pending, _, _| async {
 			let _sink = pending.accept().await?;
 			tokio::time::sleep(Duration::from_secs(1)).await;
 			Err(Error::Custom("Server closed the stream because it was lazy".to_string()).into())
@@ -96,7 +101,7 @@ pub async fn server_with_subscription_and_handle() -> (SocketAddr, ServerHandle)
 		.unwrap();
 
 	module
-		.register_subscription("subscribe_5_ints", "n", "unsubscribe_5_ints", |_, pending, _| async move {
+		.register_subscription("subscribe_5_ints", "n", "unsubscribe_5_ints", |_, pending, _, _| async move {
 			let interval = interval(Duration::from_millis(50));
 			let stream = IntervalStream::new(interval).zip(futures::stream::iter(1..=5)).map(|(_, c)| c);
 			pipe_from_stream_and_drop(pending, stream).await.map_err(Into::into)
@@ -104,14 +109,14 @@ pub async fn server_with_subscription_and_handle() -> (SocketAddr, ServerHandle)
 		.unwrap();
 
 	module
-		.register_subscription("subscribe_option", "n", "unsubscribe_option", |_, pending, _| async move {
+		.register_subscription("subscribe_option", "n", "unsubscribe_option", |_, pending, _, _| async move {
 			let _ = pending.accept().await;
 			SubscriptionCloseResponse::None
 		})
 		.unwrap();
 
 	module
-		.register_subscription("subscribe_unit", "n", "unubscribe_unit", |_, pending, _| async move {
+		.register_subscription("subscribe_unit", "n", "unubscribe_unit", |_, pending, _, _| async move {
 			let _sink = pending.accept().await?;
 			tokio::time::sleep(std::time::Duration::from_millis(100)).await;
 			Ok(())
@@ -137,10 +142,10 @@ pub async fn server_with_subscription() -> SocketAddr {
 pub async fn server() -> SocketAddr {
 	let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
 	let mut module = RpcModule::new(());
-	module.register_method("say_hello", |_, _| "hello").unwrap();
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
 
 	module
-		.register_async_method("slow_hello", |_, _| async {
+		.register_async_method("slow_hello", |_, _, _| async {
 			tokio::time::sleep(std::time::Duration::from_secs(1)).await;
 			"hello"
 		})
@@ -154,7 +159,7 @@ pub async fn server() -> SocketAddr {
 		}
 	}
 
-	module.register_async_method::<Result<(), CustomError>, _, _>("err", |_, _| async { Err(CustomError) }).unwrap();
+	module.register_async_method::<Result<(), CustomError>, _, _>("err", |_, _, _| async { Err(CustomError) }).unwrap();
 
 	let addr = server.local_addr().unwrap();
 
@@ -174,7 +179,7 @@ pub async fn server_with_sleeping_subscription(tx: futures::channel::mpsc::Sende
 	let mut module = RpcModule::new(tx);
 
 	module
-		.register_subscription("subscribe_sleep", "n", "unsubscribe_sleep", |_, pending, mut tx| async move {
+		.register_subscription("subscribe_sleep", "n", "unsubscribe_sleep", |_, pending, mut tx, _| async move {
 			let interval = interval(Duration::from_secs(60 * 60));
 			let stream = IntervalStream::new(interval).zip(futures::stream::iter(1..=5)).map(|(_, c)| c);
 
@@ -208,10 +213,10 @@ pub async fn server_with_cors(cors: CorsLayer) -> (SocketAddr, ServerHandle) {
 	let server = Server::builder().set_middleware(middleware).build("127.0.0.1:0").await.unwrap();
 	let mut module = RpcModule::new(());
 	let addr = server.local_addr().unwrap();
-	module.register_method("say_hello", |_, _| "hello").unwrap();
-	module.register_method("notif", |_, _| "").unwrap();
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
+	module.register_method("notif", |_, _, _| "").unwrap();
 
-	module.register_method("system_health", |_, _| serde_json::json!({ "health": true })).unwrap();
+	module.register_method("system_health", |_, _, _| serde_json::json!({ "health": true })).unwrap();
 
 	let handle = server.start(module);
 	(addr, handle)
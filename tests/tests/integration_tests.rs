@@ -48,7 +48,7 @@ use jsonrpsee::http_client::HttpClientBuilder;
 use jsonrpsee::server::middleware::HostFilterLayer;
 use jsonrpsee::server::{ServerBuilder, ServerHandle};
 use jsonrpsee::types::error::{ErrorObject, UNKNOWN_ERROR_CODE};
-use jsonrpsee::ws_client::WsClientBuilder;
+use jsonrpsee::ws_client::{WsClientBuilder, WsDeflateConfig};
 use jsonrpsee::{rpc_params, RpcModule};
 use jsonrpsee_test_utils::TimeoutFutureExt;
 use tokio::time::interval;
@@ -157,6 +157,209 @@ async fn http_method_call_works() {
 	assert_eq!(&response, "hello");
 }
 
+#[tokio::test]
+async fn http_compressed_responses_are_decoded() {
+	init_logger();
+
+	let server = ServerBuilder::default().http_compression(0).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("big", |_, _, _| "x".repeat(1024 * 1024)).unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let client = HttpClientBuilder::default().build(format!("http://{}", addr)).unwrap();
+	let response: String = client.request("big", rpc_params![]).await.unwrap();
+	assert_eq!(response.len(), 1024 * 1024);
+}
+
+#[tokio::test]
+async fn ws_permessage_deflate_works() {
+	init_logger();
+
+	let server = ServerBuilder::default()
+		.ws_permessage_deflate(WsDeflateConfig::new().server_max_window_bits(12))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("big", |params, _, _| "x".repeat(params.one().unwrap())).unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let req = hyper::Request::get(format!("http://{}", addr))
+		.header(hyper::header::CONNECTION, "upgrade")
+		.header(hyper::header::UPGRADE, "websocket")
+		.header(hyper::header::SEC_WEBSOCKET_VERSION, "13")
+		.header(hyper::header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==")
+		.header(hyper::header::SEC_WEBSOCKET_EXTENSIONS, "permessage-deflate; client_max_window_bits")
+		.body(hyper::Body::empty())
+		.unwrap();
+	let res = hyper::Client::new().request(req).await.unwrap();
+	assert_eq!(res.headers()[hyper::header::SEC_WEBSOCKET_EXTENSIONS], "permessage-deflate; server_max_window_bits=12");
+
+	let client = WsClientBuilder::default()
+		.permessage_deflate(WsDeflateConfig::new().client_no_context_takeover(true))
+		.max_response_size(64 * 1024)
+		.build(format!("ws://{}", addr))
+		.await
+		.unwrap();
+
+	for _ in 0..2 {
+		let response: String = client.request("big", rpc_params![32 * 1024]).await.unwrap();
+		assert_eq!(response.len(), 32 * 1024);
+	}
+
+	// The compressed response fits but the decompressed one exceeds the max response size.
+	let err = client.request::<String, _>("big", rpc_params![128 * 1024]).await.unwrap_err();
+	assert!(matches!(err, Error::RestartNeeded(_)));
+}
+
+fn to_cbor(value: &JsonValue) -> Vec<u8> {
+	let mut bytes = Vec::new();
+	ciborium::into_writer(value, &mut bytes).unwrap();
+	bytes
+}
+
+fn from_cbor(bytes: &[u8]) -> JsonValue {
+	ciborium::from_reader(bytes).unwrap()
+}
+
+#[tokio::test]
+async fn json_rpc_v1_clients_work() {
+	use jsonrpsee::core::client::JsonRpcVersion;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	init_logger();
+
+	let server = ServerBuilder::default().enable_json_rpc_v1().build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(AtomicUsize::new(0));
+	module.register_notification("ping", |_, pings, _| _ = pings.fetch_add(1, Ordering::SeqCst)).unwrap();
+	module.register_method("pings", |_, pings, _| pings.load(Ordering::SeqCst)).unwrap();
+	module
+		.register_method("add", |params, _, _| params.parse::<Vec<u64>>().map(|v| v.into_iter().sum::<u64>()))
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let http_client =
+		HttpClientBuilder::default().json_rpc_version(JsonRpcVersion::V1).build(format!("http://{}", addr)).unwrap();
+	let ws_client =
+		WsClientBuilder::default().json_rpc_version(JsonRpcVersion::V1).build(format!("ws://{}", addr)).await.unwrap();
+
+	async fn calls_work(client: &impl ClientT) {
+		let sum: u64 = client.request("add", rpc_params![1, 2]).await.unwrap();
+		assert_eq!(sum, 3);
+
+		let err = client.request::<u64, _>("add", rpc_params!["x"]).await.unwrap_err();
+		assert!(matches!(err, Error::Call(err) if err.code() == -32602));
+
+		let mut batch = BatchRequestBuilder::new();
+		batch.insert("add", rpc_params![1]).unwrap();
+		batch.insert("add", rpc_params![2, 3]).unwrap();
+		let res = client.batch_request::<u64>(batch).await.unwrap();
+		assert_eq!(res.into_ok().unwrap().collect::<Vec<_>>(), vec![1, 5]);
+
+		// Notifications are JSON-RPC 1.0 calls with a null id.
+		let pings: usize = client.request("pings", rpc_params![]).await.unwrap();
+		client.notification("ping", rpc_params![]).await.unwrap();
+		tokio::time::sleep(Duration::from_millis(100)).await;
+		assert_eq!(client.request::<usize, _>("pings", rpc_params![]).await.unwrap(), pings + 1);
+	}
+
+	calls_work(&http_client).await;
+	calls_work(&ws_client).await;
+}
+
+#[tokio::test]
+async fn http_cbor_works() {
+	use hyper::{Body, Client, Method, Request};
+
+	init_logger();
+
+	let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_method("add", |params, _, _| params.parse::<Vec<u64>>().map(|v| v.into_iter().sum::<u64>()))
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let call = |body: Vec<u8>| async move {
+		let req = Request::builder()
+			.method(Method::POST)
+			.uri(format!("http://{}", addr))
+			.header("content-type", "application/cbor")
+			.body(Body::from(body))
+			.unwrap();
+		let res = Client::new().request(req).await.unwrap();
+		assert_eq!(res.headers()["content-type"], "application/cbor");
+		from_cbor(&hyper::body::to_bytes(res.into_body()).await.unwrap())
+	};
+
+	let req = serde_json::json!({"jsonrpc": "2.0", "method": "add", "params": [1, 2, 3], "id": 1});
+	assert_eq!(call(to_cbor(&req)).await, serde_json::json!({"jsonrpc": "2.0", "result": 6, "id": 1}));
+
+	let batch = serde_json::json!([
+		{"jsonrpc": "2.0", "method": "add", "params": [7], "id": "a"},
+		{"jsonrpc": "2.0", "method": "add", "params": "x", "id": 2},
+		{"jsonrpc": "2.0", "method": "unknown", "id": 3},
+	]);
+	let responses = call(to_cbor(&batch)).await;
+	assert_eq!(responses[0], serde_json::json!({"jsonrpc": "2.0", "result": 7, "id": "a"}));
+	assert_eq!(responses[1]["error"]["code"], -32602);
+	assert_eq!(
+		responses[2],
+		serde_json::json!({"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": 3})
+	);
+
+	let parse_error =
+		serde_json::json!({"jsonrpc": "2.0", "error": {"code": -32700, "message": "Parse error"}, "id": null});
+	assert_eq!(call(vec![0xff, 0x00]).await, parse_error);
+}
+
+#[tokio::test]
+async fn ws_cbor_works() {
+	use jsonrpsee_test_utils::mocks::WebSocketTestClient;
+
+	init_logger();
+
+	let server_addr = server_with_subscription().await;
+
+	// The default encoding is used if the client requests no known subprotocol.
+	let (mut client, protocol) = WebSocketTestClient::new_with_protocol(server_addr, "unknown").await.unwrap();
+	assert!(protocol.is_none());
+	let response = client.send_request_text(r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#).await.unwrap();
+	assert_eq!(response, r#"{"jsonrpc":"2.0","result":"hello","id":1}"#);
+
+	let (mut client, protocol) = WebSocketTestClient::new_with_protocol(server_addr, "jsonrpc.cbor").await.unwrap();
+	assert_eq!(protocol.as_deref(), Some("jsonrpc.cbor"));
+
+	let req = serde_json::json!({"jsonrpc": "2.0", "method": "say_hello", "id": 1});
+	let response = from_cbor(&client.send_binary(&to_cbor(&req)).await.unwrap());
+	assert_eq!(response, serde_json::json!({"jsonrpc": "2.0", "result": "hello", "id": 1}));
+
+	let req = serde_json::json!({"jsonrpc": "2.0", "method": "subscribe_hello", "id": 2});
+	let response = from_cbor(&client.send_binary(&to_cbor(&req)).await.unwrap());
+	let sub_id = response["result"].clone();
+	assert!(sub_id.is_u64());
+
+	let notif = from_cbor(&client.receive_binary().await.unwrap());
+	let expected = serde_json::json!({
+		"jsonrpc": "2.0",
+		"method": "subscribe_hello",
+		"params": {"subscription": sub_id, "result": "hello from subscription"},
+	});
+	assert_eq!(notif, expected);
+
+	let req = serde_json::json!({"jsonrpc": "2.0", "method": "unsubscribe_hello", "params": [sub_id], "id": 3});
+	let mut response = from_cbor(&client.send_binary(&to_cbor(&req)).await.unwrap());
+	// Notifications that were sent before the unsubscription are skipped.
+	while response.get("id").is_none() {
+		response = from_cbor(&client.receive_binary().await.unwrap());
+	}
+	assert_eq!(response, serde_json::json!({"jsonrpc": "2.0", "result": true, "id": 3}));
+}
+
 #[tokio::test]
 async fn http_method_call_str_id_works() {
 	init_logger();
@@ -430,7 +633,7 @@ async fn ws_server_should_stop_subscription_after_client_drop() {
 			"subscribe_hello",
 			"subscribe_hello",
 			"unsubscribe_hello",
-			|_, pending, mut tx| async move {
+			|_, pending, mut tx, _| async move {
 				let sink = pending.accept().await?;
 				let msg = SubscriptionMessage::from_json(&1)?;
 				sink.send(msg).await?;
@@ -472,7 +675,7 @@ async fn ws_server_stop_subscription_when_dropped() {
 	let mut module = RpcModule::new(());
 
 	module
-		.register_subscription("subscribe_nop", "h", "unsubscribe_nop", |_params, _pending, _ctx| async { Ok(()) })
+		.register_subscription("subscribe_nop", "h", "unsubscribe_nop", |_params, _pending, _ctx, _| async { Ok(()) })
 		.unwrap();
 
 	let _handle = server.start(module);
@@ -732,7 +935,7 @@ async fn ws_server_limit_subs_per_conn_works() {
 	let mut module = RpcModule::new(());
 
 	module
-		.register_subscription("subscribe_forever", "n", "unsubscribe_forever", |_, pending, _| async move {
+		.register_subscription("subscribe_forever", "n", "unsubscribe_forever", |_, pending, _, _| async move {
 			let interval = interval(Duration::from_millis(50));
 			let stream = IntervalStream::new(interval).map(move |_| 0_usize);
 
@@ -787,7 +990,7 @@ async fn ws_server_unsub_methods_should_ignore_sub_limit() {
 	let mut module = RpcModule::new(());
 
 	module
-		.register_subscription("subscribe_forever", "n", "unsubscribe_forever", |_, pending, _| async {
+		.register_subscription("subscribe_forever", "n", "unsubscribe_forever", |_, pending, _, _| async {
 			let interval = interval(Duration::from_millis(50));
 			let stream = IntervalStream::new(interval).map(move |_| 0_usize);
 
@@ -1014,7 +1217,7 @@ async fn ws_host_filtering_wildcard_works() {
 	let server = ServerBuilder::default().set_middleware(middleware).build("127.0.0.1:0").await.unwrap();
 	let mut module = RpcModule::new(());
 	let addr = server.local_addr().unwrap();
-	module.register_method("say_hello", |_, _| "hello").unwrap();
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
 
 	let _handle = server.start(module);
 
@@ -1036,7 +1239,7 @@ async fn http_host_filtering_wildcard_works() {
 	let server = ServerBuilder::default().set_middleware(middleware).build("127.0.0.1:0").await.unwrap();
 	let mut module = RpcModule::new(());
 	let addr = server.local_addr().unwrap();
-	module.register_method("say_hello", |_, _| "hello").unwrap();
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
 
 	let _handle = server.start(module);
 
@@ -1057,7 +1260,7 @@ async fn deny_invalid_host() {
 	let server = Server::builder().set_middleware(middleware).build("127.0.0.1:0").await.unwrap();
 	let mut module = RpcModule::new(());
 	let addr = server.local_addr().unwrap();
-	module.register_method("say_hello", |_, _| "hello").unwrap();
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
 
 	let _handle = server.start(module);
 
@@ -1209,7 +1412,7 @@ async fn run_shutdown_test(transport: &str) {
 		let mut module = RpcModule::new((tx, call_answered.clone()));
 
 		module
-			.register_async_method("sleep_20s", |_, mut ctx| async move {
+			.register_async_method("sleep_20s", |_, mut ctx, _| async move {
 				let ctx = Arc::make_mut(&mut ctx);
 				let _ = ctx.0.send(());
 				tokio::time::sleep(Duration::from_secs(20)).await;
@@ -1234,3 +1437,421 @@ async fn run_shutdown_test(transport: &str) {
 		_ => unreachable!("Only `http` and `ws` supported"),
 	}
 }
+
+#[cfg(unix)]
+#[tokio::test]
+async fn unix_socket_works() {
+	use jsonrpsee::server::ConnectionDetails;
+
+	init_logger();
+
+	let path = std::env::temp_dir().join(format!("jsonrpsee-unix-socket-works-{}.sock", std::process::id()));
+	let _ = std::fs::remove_file(&path);
+
+	let server = ServerBuilder::default().build_from_unix(&path).unwrap();
+	assert!(server.local_addr().is_err());
+
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
+	module
+		.register_method("remote_addr", |_, _, ext| ext.get::<ConnectionDetails>().unwrap().remote_addr().to_string())
+		.unwrap();
+	module
+		.register_subscription(
+			"subscribe_hello",
+			"subscribe_hello",
+			"unsubscribe_hello",
+			|_, pending, _, _| async move {
+				let sink = pending.accept().await?;
+				sink.send(SubscriptionMessage::from("hello from subscription")).await?;
+				Ok(())
+			},
+		)
+		.unwrap();
+	let handle = server.start(module);
+
+	let url = format!("unix://{}", path.display());
+
+	let http_client = HttpClientBuilder::default().build(&url).unwrap();
+	let response: String =
+		http_client.request("say_hello", rpc_params![]).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(&response, "hello");
+	let response: String =
+		http_client.request("remote_addr", rpc_params![]).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(&response, "0.0.0.0:0");
+
+	let ws_client = WsClientBuilder::default().build(&url).with_default_timeout().await.unwrap().unwrap();
+	let response: String = ws_client.request("say_hello", rpc_params![]).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(&response, "hello");
+
+	let mut sub: Subscription<String> = ws_client
+		.subscribe("subscribe_hello", rpc_params![], "unsubscribe_hello")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(sub.next().with_default_timeout().await.unwrap().unwrap().unwrap(), "hello from subscription");
+
+	handle.stop().unwrap();
+	handle.stopped().await;
+	std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn unix_socket_connections_are_not_limited_per_ip() {
+	init_logger();
+
+	let path = std::env::temp_dir().join(format!("jsonrpsee-unix-socket-per-ip-{}.sock", std::process::id()));
+	let _ = std::fs::remove_file(&path);
+
+	let server = ServerBuilder::default().max_connections_per_ip(1).build_from_unix(&path).unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _, _| "hello").unwrap();
+	let handle = server.start(module);
+
+	// The connections on a Unix domain socket don't share an IP address.
+	let url = format!("unix://{}", path.display());
+	let first = WsClientBuilder::default().build(&url).with_default_timeout().await.unwrap().unwrap();
+	let second = WsClientBuilder::default().build(&url).with_default_timeout().await.unwrap().unwrap();
+	for client in [&first, &second] {
+		let response: String =
+			client.request("say_hello", rpc_params![]).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(&response, "hello");
+	}
+
+	handle.stop().unwrap();
+	handle.stopped().await;
+	std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn trace_context_propagation_works() {
+	use jsonrpsee::core::tracing::TraceParent;
+
+	init_logger();
+
+	let mut module = RpcModule::new(());
+	module.register_method("trace_id", |_, _, ext| ext.get::<TraceParent>().map(|tp| tp.trace_id())).unwrap();
+	module
+		.register_subscription(
+			"subscribe_trace",
+			"subscribe_trace",
+			"unsubscribe_trace",
+			|_, pending, _, _| async move {
+				let sink = pending.accept().await?;
+				// The subscription task runs with the trace context of the subscription call.
+				let trace_id = TraceParent::current().map(|tp| tp.trace_id());
+				sink.send(SubscriptionMessage::from_json(&trace_id)?).await?;
+				Ok(())
+			},
+		)
+		.unwrap();
+
+	let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module);
+
+	let root = TraceParent::new_root();
+	let expected = Some(root.trace_id());
+
+	// HTTP propagates the trace context in the `traceparent` header.
+	let http_client =
+		HttpClientBuilder::default().propagate_trace_context(true).build(format!("http://{}", addr)).unwrap();
+	let res: Option<String> = root.scope(http_client.request("trace_id", rpc_params![])).await.unwrap();
+	assert_eq!(res, expected);
+
+	let mut batch = BatchRequestBuilder::new();
+	batch.insert("trace_id", rpc_params![]).unwrap();
+	batch.insert("trace_id", rpc_params![]).unwrap();
+	let res = root.scope(http_client.batch_request::<Option<String>>(batch)).await.unwrap();
+	assert_eq!(res.into_ok().unwrap().collect::<Vec<_>>(), vec![expected.clone(), expected.clone()]);
+
+	// WebSocket propagates the trace context in the JSON-RPC request.
+	let ws_client =
+		WsClientBuilder::default().propagate_trace_context(true).build(format!("ws://{}", addr)).await.unwrap();
+	let res: Option<String> = root.scope(ws_client.request("trace_id", rpc_params![])).await.unwrap();
+	assert_eq!(res, expected);
+
+	let mut batch = BatchRequestBuilder::new();
+	batch.insert("trace_id", rpc_params![]).unwrap();
+	batch.insert("trace_id", rpc_params![]).unwrap();
+	let res = root.scope(ws_client.batch_request::<Option<String>>(batch)).await.unwrap();
+	assert_eq!(res.into_ok().unwrap().collect::<Vec<_>>(), vec![expected.clone(), expected.clone()]);
+
+	let mut sub: Subscription<Option<String>> =
+		root.scope(ws_client.subscribe("subscribe_trace", rpc_params![], "unsubscribe_trace")).await.unwrap();
+	assert_eq!(sub.next().with_default_timeout().await.unwrap().unwrap().unwrap(), expected);
+
+	// Without a current trace context a new trace is started.
+	let res: Option<String> = ws_client.request("trace_id", rpc_params![]).await.unwrap();
+	assert!(res.is_some());
+	assert_ne!(res, expected);
+
+	// The trace context isn't propagated unless enabled.
+	let ws_client = WsClientBuilder::default().build(format!("ws://{}", addr)).await.unwrap();
+	let res: Option<String> = root.scope(ws_client.request("trace_id", rpc_params![])).await.unwrap();
+	assert_eq!(res, None);
+
+	handle.stop().unwrap();
+	handle.stopped().await;
+}
+
+#[tokio::test]
+async fn http_subscription_filter_works() {
+	init_logger();
+
+	let server = ServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_foo", "subscribe_foo", "unsubscribe_foo", |_, pending, _, _| async {
+			let interval = interval(Duration::from_millis(50));
+			let stream = IntervalStream::new(interval).map(move |_| 1337_usize);
+			pipe_from_stream_and_drop(pending, stream).await.map_err(Into::into)
+		})
+		.unwrap();
+	module
+		.register_subscription_filter(
+			"subscribe_foo",
+			"foo_newFilter",
+			"foo_getFilterChanges",
+			"foo_uninstallFilter",
+			Duration::from_secs(60),
+		)
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let client = HttpClientBuilder::default().build(format!("http://{}", addr)).unwrap();
+	let filter: u64 = client.request("foo_newFilter", rpc_params![]).await.unwrap();
+
+	let mut changes = Vec::new();
+	while changes.len() < 3 {
+		tokio::time::sleep(Duration::from_millis(50)).await;
+		let polled: Vec<usize> = client.request("foo_getFilterChanges", rpc_params![filter]).await.unwrap();
+		changes.extend(polled);
+	}
+	assert!(changes.iter().all(|n| *n == 1337));
+
+	assert!(client.request::<bool, _>("foo_uninstallFilter", rpc_params![filter]).await.unwrap());
+}
+
+#[tokio::test]
+async fn http_subscription_filter_keeps_changes_over_max_response_size() {
+	use jsonrpsee::types::error::{CALL_EXECUTION_FAILED_CODE, FILTER_NOT_FOUND_CODE};
+
+	init_logger();
+
+	let server = ServerBuilder::default()
+		.max_response_body_size(200)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_foo", "subscribe_foo", "unsubscribe_foo", |_, pending, _, _| async {
+			let sink = pending.accept().await?;
+			for i in 0..20 {
+				sink.send(SubscriptionMessage::from_json(&format!("notification number {i}"))?).await?;
+			}
+			Err("boom".into())
+		})
+		.unwrap();
+	module
+		.register_subscription_filter(
+			"subscribe_foo",
+			"foo_newFilter",
+			"foo_getFilterChanges",
+			"foo_uninstallFilter",
+			Duration::from_secs(60),
+		)
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let client = HttpClientBuilder::default().build(format!("http://{}", addr)).unwrap();
+	let filter: u64 = client.request("foo_newFilter", rpc_params![]).await.unwrap();
+
+	// The changes which don't fit in a response are answered by the next polls, then the error of the subscription.
+	let mut changes = Vec::new();
+	let err = loop {
+		tokio::time::sleep(Duration::from_millis(10)).await;
+		match client.request::<Vec<String>, _>("foo_getFilterChanges", rpc_params![filter]).await {
+			Ok(polled) => changes.extend(polled),
+			Err(Error::Call(err)) => break err,
+			Err(e) => panic!("Unexpected error {e:?}"),
+		}
+	};
+	assert_eq!(changes, (0..20).map(|i| format!("notification number {i}")).collect::<Vec<_>>());
+	assert_eq!(err.code(), CALL_EXECUTION_FAILED_CODE);
+	assert_eq!(err.data().map(|data| data.get()), Some(r#""boom""#));
+
+	let err = client.request::<Vec<String>, _>("foo_getFilterChanges", rpc_params![filter]).await.unwrap_err();
+	assert!(matches!(err, Error::Call(err) if err.code() == FILTER_NOT_FOUND_CODE));
+}
+
+#[tokio::test]
+async fn http_subscription_filter_uses_server_id_provider() {
+	use jsonrpsee::server::RandomStringIdProvider;
+
+	init_logger();
+
+	let server = ServerBuilder::default()
+		.set_id_provider(RandomStringIdProvider::new(16))
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_foo", "subscribe_foo", "unsubscribe_foo", |_, pending, _, _| async {
+			let sink = pending.accept().await?;
+			sink.closed().await;
+			Ok(())
+		})
+		.unwrap();
+	module
+		.register_subscription_filter(
+			"subscribe_foo",
+			"foo_newFilter",
+			"foo_getFilterChanges",
+			"foo_uninstallFilter",
+			Duration::from_secs(60),
+		)
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let client = HttpClientBuilder::default().build(format!("http://{}", addr)).unwrap();
+	let filter: String = client.request("foo_newFilter", rpc_params![]).await.unwrap();
+	assert_eq!(filter.len(), 16);
+
+	assert!(client.request::<bool, _>("foo_uninstallFilter", rpc_params![filter]).await.unwrap());
+}
+
+#[tokio::test]
+async fn http_subscription_filters_are_bounded_per_client() {
+	use jsonrpsee::core::server::FilterConfig;
+	use jsonrpsee::types::error::TOO_MANY_SUBSCRIPTIONS_CODE;
+
+	init_logger();
+
+	let server = ServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_foo", "subscribe_foo", "unsubscribe_foo", |_, pending, _, _| async {
+			let sink = pending.accept().await?;
+			sink.closed().await;
+			Ok(())
+		})
+		.unwrap();
+	module
+		.register_subscription_filter(
+			"subscribe_foo",
+			"foo_newFilter",
+			"foo_getFilterChanges",
+			"foo_uninstallFilter",
+			FilterConfig::new(Duration::from_secs(60)).max_filters_per_client(2),
+		)
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let client = HttpClientBuilder::default().build(format!("http://{}", addr)).unwrap();
+	let filter: u64 = client.request("foo_newFilter", rpc_params![]).await.unwrap();
+	let _: u64 = client.request("foo_newFilter", rpc_params![]).await.unwrap();
+
+	// Another connection from the same IP address shares the limit.
+	let other_client = HttpClientBuilder::default().build(format!("http://{}", addr)).unwrap();
+	let err = other_client.request::<u64, _>("foo_newFilter", rpc_params![]).await.unwrap_err();
+	assert!(matches!(err, Error::Call(err) if err.code() == TOO_MANY_SUBSCRIPTIONS_CODE));
+
+	assert!(client.request::<bool, _>("foo_uninstallFilter", rpc_params![filter]).await.unwrap());
+	let _: u64 = other_client.request("foo_newFilter", rpc_params![]).await.unwrap();
+}
+
+#[tokio::test]
+async fn ws_subscription_resume_works() {
+	use hyper::header::HeaderMap;
+	use jsonrpsee::server::Role;
+	use jsonrpsee::types::error::SUBSCRIPTION_NOT_RESUMABLE_CODE;
+	use jsonrpsee_test_utils::mocks::WebSocketTestClient;
+
+	init_logger();
+
+	// The role of the caller is taken from the `x-role` header.
+	let middleware = tower::ServiceBuilder::new().map_request(|mut req: hyper::Request<hyper::Body>| {
+		if let Some(role) = req.headers().get("x-role").and_then(|role| role.to_str().ok()) {
+			let role = Role::new(role);
+			req.extensions_mut().insert(role);
+		}
+		req
+	});
+	let server = ServerBuilder::default()
+		.set_middleware(middleware)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_count", "count", "unsubscribe_count", |_, pending, _, _| async {
+			let interval = interval(Duration::from_millis(20));
+			let stream = IntervalStream::new(interval).enumerate().map(|(n, _)| n + 1);
+			pipe_from_stream_and_drop(pending, stream).await.map_err(Into::into)
+		})
+		.unwrap();
+	module.set_subscription_resumable("subscribe_count", "resume_count", Duration::from_secs(60)).unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module);
+
+	let notification = |msg: &str| {
+		let notif: JsonValue = serde_json::from_str(msg).unwrap();
+		let params = &notif["params"];
+		assert_eq!(params["seq"], params["result"]);
+		params["seq"].as_u64().unwrap()
+	};
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let response = client.send_request_text(r#"{"jsonrpc":"2.0","method":"subscribe_count","id":1}"#).await.unwrap();
+	let response: JsonValue = serde_json::from_str(&response).unwrap();
+	let sub_id = response["result"].clone();
+	assert_eq!(notification(&client.receive().await.unwrap()), 1);
+	assert_eq!(notification(&client.receive().await.unwrap()), 2);
+	client.close().await.unwrap();
+
+	// Callers with another role can't resume the subscription.
+	let mut headers = HeaderMap::new();
+	headers.insert("x-role", HeaderValue::from_static("other"));
+	let other = WsClientBuilder::default().set_headers(headers).build(format!("ws://{addr}")).await.unwrap();
+	let err = other.request::<JsonValue, _>("resume_count", rpc_params![&sub_id, 2]).await.unwrap_err();
+	assert!(matches!(err, Error::Call(err) if err.code() == SUBSCRIPTION_NOT_RESUMABLE_CODE));
+
+	// The notifications which were sent after the connection was closed are replayed.
+	tokio::time::sleep(Duration::from_millis(200)).await;
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let resume = serde_json::json!({"jsonrpc": "2.0", "method": "resume_count", "params": [sub_id, 2], "id": 2});
+	let response = client.send_request_text(resume.to_string()).await.unwrap();
+	let response: JsonValue = serde_json::from_str(&response).unwrap();
+	assert_eq!(response["result"], sub_id);
+	for seq in 3..15 {
+		assert_eq!(notification(&client.receive().await.unwrap()), seq);
+	}
+
+	// The subscription can't be taken over while it's attached to a subscriber.
+	let mut other_client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let resume = serde_json::json!({"jsonrpc": "2.0", "method": "resume_count", "params": [sub_id, 14], "id": 2});
+	let response = other_client.send_request_text(resume.to_string()).await.unwrap();
+	let response: JsonValue = serde_json::from_str(&response).unwrap();
+	assert_eq!(response["error"]["code"], SUBSCRIPTION_NOT_RESUMABLE_CODE);
+	assert_eq!(notification(&client.receive().await.unwrap()), 15);
+
+	let resume = serde_json::json!({"jsonrpc": "2.0", "method": "resume_count", "params": [1, 0], "id": 3});
+	let response = client.send_request_text(resume.to_string()).await.unwrap();
+	let response: JsonValue = serde_json::from_str(&response).unwrap();
+	assert_eq!(response["error"]["code"], SUBSCRIPTION_NOT_RESUMABLE_CODE);
+}
@@ -32,11 +32,13 @@ use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use helpers::init_logger;
-use jsonrpsee::core::{client::ClientT, Error};
+use jsonrpsee::core::client::{ClientT, Subscription, SubscriptionClientT};
+use jsonrpsee::core::Error;
 use jsonrpsee::http_client::HttpClientBuilder;
 use jsonrpsee::proc_macros::rpc;
 use jsonrpsee::rpc_params;
-use jsonrpsee::server::logger::{HttpRequest, Logger, MethodKind, SuccessOrError, TransportProtocol};
+use jsonrpsee::server::logger::{HttpRequest, Logger, MethodKind, PrometheusLogger, SuccessOrError, TransportProtocol};
+use jsonrpsee::server::middleware::PrometheusMetricsLayer;
 use jsonrpsee::server::{ServerBuilder, ServerHandle};
 use jsonrpsee::types::{ErrorObject, ErrorObjectOwned, Params};
 use jsonrpsee::ws_client::WsClientBuilder;
@@ -219,3 +221,85 @@ async fn http_server_logger() {
 	let inner = counter.inner.lock().unwrap();
 	assert_eq!(inner.connections, (6, 6));
 }
+
+#[tokio::test]
+async fn cached_calls_are_logged() {
+	init_logger();
+
+	let counter = Counter::default();
+	let mut module = test_module();
+	module.set_method_cache("say_hello", Duration::from_secs(60)).unwrap();
+	let (server_addr, server_handle) = websocket_server(module, counter.clone()).await.unwrap();
+
+	let client = WsClientBuilder::default().build(&format!("ws://{}", server_addr)).await.unwrap();
+
+	for _ in 0..3 {
+		let res: String = client.request("say_hello", rpc_params![]).await.unwrap();
+		assert_eq!(res, "hello");
+	}
+
+	{
+		let inner = counter.inner.lock().unwrap();
+		assert_eq!(inner.requests, (3, 3));
+		assert_eq!(inner.calls["say_hello"], (3, vec![0, 1, 2]));
+	}
+
+	server_handle.stop().unwrap();
+	server_handle.stopped().await;
+}
+
+#[tokio::test]
+async fn prometheus_logger_works() {
+	init_logger();
+
+	let registry = prometheus::Registry::new();
+	let logger = PrometheusLogger::new(&registry).unwrap();
+	let middleware = tower::ServiceBuilder::new().layer(PrometheusMetricsLayer::new("/metrics", registry).unwrap());
+
+	let mut module = test_module();
+	module
+		.register_subscription("sub", "sub_notif", "unsub", |_, pending, _, _| async move {
+			let _sink = pending.accept().await?;
+			futures::future::pending::<()>().await;
+			Ok(())
+		})
+		.unwrap();
+
+	let server =
+		ServerBuilder::default().set_logger(logger).set_middleware(middleware).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let server_handle = server.start(module);
+
+	let client = WsClientBuilder::default().build(&format!("ws://{}", addr)).await.unwrap();
+
+	let res: String = client.request("say_hello", rpc_params![]).await.unwrap();
+	assert_eq!(res, "hello");
+	let res: Result<String, Error> = client.request("err", rpc_params![]).await;
+	assert!(res.is_err());
+	let res: Result<String, Error> = client.request("unknown_method", rpc_params![]).await;
+	assert!(res.is_err());
+	let _sub: Subscription<String> = client.subscribe("sub", rpc_params![], "unsub").await.unwrap();
+
+	let uri = format!("http://{}/metrics", addr).parse().unwrap();
+	let res = hyper::Client::new().get(uri).await.unwrap();
+	assert_eq!(res.status(), hyper::StatusCode::OK);
+	assert_eq!(res.headers()["content-type"], prometheus::TEXT_FORMAT);
+	let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+	let metrics = String::from_utf8(body.to_vec()).unwrap();
+
+	for line in [
+		r#"jsonrpsee_calls_total{method="say_hello",transport="websocket"} 1"#,
+		r#"jsonrpsee_calls_total{method="err",transport="websocket"} 1"#,
+		r#"jsonrpsee_calls_total{method="unknown",transport="websocket"} 1"#,
+		r#"jsonrpsee_call_errors_total{code="1",method="err",transport="websocket"} 1"#,
+		r#"jsonrpsee_call_errors_total{code="-32601",method="unknown",transport="websocket"} 1"#,
+		r#"jsonrpsee_call_duration_seconds_count{method="say_hello",transport="websocket"} 1"#,
+		r#"jsonrpsee_open_connections{transport="websocket"} 1"#,
+		r#"jsonrpsee_active_subscriptions{transport="websocket"} 1"#,
+	] {
+		assert!(metrics.lines().any(|l| l == line), "`{line}` not found in:\n{metrics}");
+	}
+
+	server_handle.stop().unwrap();
+	server_handle.stopped().await;
+}
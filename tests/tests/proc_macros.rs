@@ -47,7 +47,9 @@ mod rpc_impl {
 	};
 	use jsonrpsee::core::{async_trait, SubscriptionResult};
 	use jsonrpsee::proc_macros::rpc;
+	use jsonrpsee::server::{ConnectionDetails, HeaderMap};
 	use jsonrpsee::types::{ErrorObject, ErrorObjectOwned};
+	use jsonrpsee::Extensions;
 
 	pub struct CustomSubscriptionRet;
 
@@ -90,6 +92,18 @@ mod rpc_impl {
 		#[subscription(name = "sync_sub", unsubscribe = "sync_unsub", item = String)]
 		fn sync_sub(&self);
 
+		#[subscription(name = "sub_conn_id", unsubscribe = "unsub_conn_id", item = usize, with_extensions)]
+		async fn sub_conn_id(&self) -> SubscriptionResult;
+
+		#[method(name = "header", with_extensions)]
+		fn header(&self, name: String) -> Result<Option<String>, ErrorObjectOwned>;
+
+		#[method(name = "conn_id", with_extensions)]
+		async fn conn_id(&self) -> Result<usize, ErrorObjectOwned>;
+
+		#[method(name = "conn_id_blocking", blocking, with_extensions)]
+		fn conn_id_blocking(&self) -> Result<usize, ErrorObjectOwned>;
+
 		#[method(name = "params")]
 		fn params(&self, a: u8, b: &str) -> Result<String, ErrorObjectOwned> {
 			Ok(format!("Called with: {}, {}", a, b))
@@ -195,6 +209,31 @@ mod rpc_impl {
 		}
 
 		async fn sub_unit_type(&self, _pending: PendingSubscriptionSink, _x: usize) {}
+
+		async fn sub_conn_id(&self, pending: PendingSubscriptionSink, ext: &Extensions) -> SubscriptionResult {
+			let conn_id = conn_id_from_ext(ext);
+			let sink = pending.accept().await?;
+			sink.send(SubscriptionMessage::from_json(&conn_id)?).await?;
+
+			Ok(())
+		}
+
+		async fn conn_id(&self, ext: &Extensions) -> Result<usize, ErrorObjectOwned> {
+			Ok(conn_id_from_ext(ext))
+		}
+
+		fn conn_id_blocking(&self, ext: &Extensions) -> Result<usize, ErrorObjectOwned> {
+			Ok(conn_id_from_ext(ext))
+		}
+
+		fn header(&self, ext: &Extensions, name: String) -> Result<Option<String>, ErrorObjectOwned> {
+			let headers = ext.get::<HeaderMap>().expect("HeaderMap is inserted by the server");
+			Ok(headers.get(&name).and_then(|val| val.to_str().ok()).map(ToOwned::to_owned))
+		}
+	}
+
+	fn conn_id_from_ext(ext: &Extensions) -> usize {
+		ext.get::<ConnectionDetails>().expect("ConnectionDetails is inserted by the server").id()
 	}
 }
 
@@ -425,3 +464,237 @@ async fn sync_sub_works() {
 
 	assert_eq!(sub.next().await.unwrap().unwrap(), "hello");
 }
+
+#[tokio::test]
+async fn extensions_with_different_ws_clients() {
+	init_logger();
+
+	let server_addr = server().await;
+	let server_url = format!("ws://{}", server_addr);
+
+	let mut headers = HeaderMap::new();
+	headers.insert("x-tenant", HeaderValue::from_static("tenant_a"));
+	let client1 = WsClientBuilder::default().set_headers(headers).build(&server_url).await.unwrap();
+	let client2 = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	let conn_id1 = client1.conn_id().await.unwrap();
+	let conn_id2 = client2.conn_id().await.unwrap();
+	assert_ne!(conn_id1, conn_id2);
+	assert_eq!(client1.conn_id_blocking().await.unwrap(), conn_id1);
+	assert_eq!(client2.conn_id_blocking().await.unwrap(), conn_id2);
+
+	let mut sub = client1.sub_conn_id().await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), conn_id1);
+
+	assert_eq!(client1.header("x-tenant".to_string()).await.unwrap(), Some("tenant_a".to_string()));
+	assert_eq!(client2.header("x-tenant".to_string()).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn extensions_with_http_client() {
+	init_logger();
+
+	let server_addr = server().await;
+	let server_url = format!("http://{}", server_addr);
+
+	let mut headers = HeaderMap::new();
+	headers.insert("x-tenant", HeaderValue::from_static("tenant_b"));
+	let client = HttpClientBuilder::default().set_headers(headers).build(&server_url).unwrap();
+
+	assert_eq!(client.header("x-tenant".to_string()).await.unwrap(), Some("tenant_b".to_string()));
+	assert!(client.conn_id().await.is_ok());
+}
+
+mod notification_impl {
+	use std::sync::Mutex;
+
+	use jsonrpsee::core::{async_trait, RpcResult};
+	use jsonrpsee::proc_macros::rpc;
+	use jsonrpsee::server::ConnectionDetails;
+	use jsonrpsee::Extensions;
+
+	#[rpc(client, server, namespace = "telemetry")]
+	pub trait Telemetry {
+		#[notification(name = "event")]
+		fn event(&self, name: String);
+
+		#[notification(name = "eventAsync", aliases = ["telemetry_event_alias"], param_kind = map, with_extensions)]
+		async fn event_async(&self, name: String, count: Option<u32>);
+
+		#[method(name = "events")]
+		fn events(&self) -> RpcResult<Vec<String>>;
+	}
+
+	#[derive(Default)]
+	pub struct TelemetryImpl(Mutex<Vec<String>>);
+
+	#[async_trait]
+	impl TelemetryServer for TelemetryImpl {
+		fn event(&self, name: String) {
+			self.0.lock().unwrap().push(name);
+		}
+
+		async fn event_async(&self, ext: &Extensions, name: String, count: Option<u32>) {
+			assert!(ext.get::<ConnectionDetails>().is_some());
+			self.0.lock().unwrap().push(format!("{name}:{}", count.unwrap_or_default()));
+		}
+
+		fn events(&self) -> RpcResult<Vec<String>> {
+			Ok(std::mem::take(&mut *self.0.lock().unwrap()))
+		}
+	}
+}
+
+#[tokio::test]
+async fn notifications_work() {
+	use jsonrpsee::core::params::ArrayParams;
+	use notification_impl::{TelemetryClient, TelemetryImpl, TelemetryServer};
+
+	init_logger();
+
+	let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(TelemetryImpl::default().into_rpc());
+
+	// The HTTP server responds once the notification has been handled.
+	let client = HttpClientBuilder::default().build(format!("http://{addr}")).unwrap();
+	client.event("a".to_string()).await.unwrap();
+	client.event_async("b".to_string(), Some(2)).await.unwrap();
+	// Invalid params are ignored.
+	client.notification("telemetry_event", ArrayParams::new()).await.unwrap();
+	client.notification("telemetry_event_alias", rpc_params!["c"]).await.unwrap();
+	assert_eq!(client.events().await.unwrap(), vec!["a".to_string(), "b:2".to_string(), "c:0".to_string()]);
+
+	// Notification handlers can't be called as methods.
+	let err = client.request::<(), _>("telemetry_event", rpc_params!["d"]).await.unwrap_err();
+	assert!(matches!(err, Error::Call(e) if e.code() == ErrorCode::MethodNotFound.code()));
+
+	// The WebSocket server processes the messages concurrently.
+	let client = WsClientBuilder::default().build(format!("ws://{addr}")).await.unwrap();
+	client.event("e".to_string()).await.unwrap();
+
+	let events = loop {
+		let events = client.events().await.unwrap();
+		if !events.is_empty() {
+			break events;
+		}
+		tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+	};
+	assert_eq!(events, vec!["e".to_string()]);
+}
+
+mod openrpc_impl {
+	use jsonrpsee::core::server::{PendingSubscriptionSink, SubscriptionMessage};
+	use jsonrpsee::core::{async_trait, RpcResult, SubscriptionResult};
+	use jsonrpsee::proc_macros::rpc;
+	use schemars::JsonSchema;
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+	pub struct Point {
+		pub x: i32,
+		pub y: i32,
+	}
+
+	#[rpc(server, namespace = "geo", openrpc)]
+	pub trait Geo {
+		/// Moves the point by the given offset.
+		///
+		/// The offset defaults to one.
+		#[method(name = "move", aliases = ["geo_shift"])]
+		async fn move_point(&self, point: Point, offset: Option<i32>) -> RpcResult<Point>;
+
+		/// Resets the world.
+		#[method(name = "reset", access = "unsafe")]
+		fn reset(&self) -> RpcResult<()>;
+
+		/// Subscribes to the positions.
+		#[subscription(name = "subscribePositions" => "position", unsubscribe = "unsubscribePositions", item = Point)]
+		async fn sub_positions(&self) -> SubscriptionResult;
+
+		/// Logs a message.
+		#[notification(name = "log")]
+		fn log(&self, message: String);
+	}
+
+	pub struct GeoImpl;
+
+	#[async_trait]
+	impl GeoServer for GeoImpl {
+		async fn move_point(&self, point: Point, offset: Option<i32>) -> RpcResult<Point> {
+			let offset = offset.unwrap_or(1);
+			Ok(Point { x: point.x + offset, y: point.y + offset })
+		}
+
+		fn reset(&self) -> RpcResult<()> {
+			Ok(())
+		}
+
+		async fn sub_positions(&self, pending: PendingSubscriptionSink) -> SubscriptionResult {
+			let sink = pending.accept().await?;
+			sink.send(SubscriptionMessage::from_json(&Point { x: 0, y: 0 })?).await?;
+			Ok(())
+		}
+
+		fn log(&self, _message: String) {}
+	}
+}
+
+#[tokio::test]
+async fn openrpc_works() {
+	use jsonrpsee::core::server::openrpc::{MethodSchema, OpenRpc, DISCOVER_METHOD};
+	use jsonrpsee::server::{AccessPolicy, MethodAccess, Permission};
+	use openrpc_impl::{GeoImpl, GeoServer};
+
+	init_logger();
+
+	let mut module = GeoImpl.into_rpc();
+	module.register_discover("Geo", "1.0.0").unwrap();
+
+	let doc = serde_json::to_value(module.openrpc("Geo", "1.0.0")).unwrap();
+	assert_eq!(doc["openrpc"], json!("1.2.6"));
+	assert_eq!(doc["info"], json!({ "title": "Geo", "version": "1.0.0" }));
+
+	let names: Vec<_> = doc["methods"].as_array().unwrap().iter().map(|m| m["name"].as_str().unwrap()).collect();
+	assert_eq!(names, vec!["geo_log", "geo_move", "geo_reset", "geo_subscribePositions"]);
+
+	let move_point = &doc["methods"][1];
+	assert_eq!(move_point["description"], json!("Moves the point by the given offset.\n\nThe offset defaults to one."));
+	assert_eq!(move_point["x-aliases"], json!(["geo_shift"]));
+	assert_eq!(move_point["params"][0]["name"], json!("point"));
+	assert_eq!(move_point["params"][0]["required"], json!(true));
+	assert_eq!(move_point["params"][0]["schema"], json!({ "$ref": "#/components/schemas/Point" }));
+	assert_eq!(move_point["params"][1]["required"], json!(false));
+	assert_eq!(move_point["result"]["schema"], json!({ "$ref": "#/components/schemas/Point" }));
+	assert_eq!(doc["components"]["schemas"]["Point"]["required"], json!(["x", "y"]));
+
+	let sub = &doc["methods"][3];
+	assert_eq!(sub["x-subscription"]["notification"], json!("geo_position"));
+	assert_eq!(sub["x-subscription"]["unsubscribe"], json!("geo_unsubscribePositions"));
+	assert!(sub.get("result").is_none());
+
+	let log = &doc["methods"][0];
+	assert_eq!(log["params"][0]["schema"], json!({ "type": "string" }));
+	assert!(log.get("result").is_none());
+
+	// `rpc.discover` leaves out the methods that are hidden from the caller.
+	let policy = AccessPolicy::new().set(MethodAccess::Unsafe, Permission::Hide);
+	let server = ServerBuilder::default().set_access_policy(policy).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module);
+
+	let client = HttpClientBuilder::default().build(format!("http://{addr}")).unwrap();
+	let doc: OpenRpc = client.request(DISCOVER_METHOD, rpc_params![]).await.unwrap();
+	let names: Vec<_> = doc.methods.iter().map(|m| m.name.as_str()).collect();
+	assert_eq!(names, vec!["geo_log", "geo_move", "geo_subscribePositions"]);
+
+	// The methods which are merged into the running server are described as well.
+	let mut plugin = jsonrpsee::RpcModule::new(());
+	plugin.register_method("geo_name", |_, _, _| "Geo").unwrap();
+	plugin.set_method_schema("geo_name", MethodSchema::new().result::<String>()).unwrap();
+	handle.merge_methods(plugin).unwrap();
+
+	let doc: OpenRpc = client.request(DISCOVER_METHOD, rpc_params![]).await.unwrap();
+	let names: Vec<_> = doc.methods.iter().map(|m| m.name.as_str()).collect();
+	assert_eq!(names, vec!["geo_log", "geo_move", "geo_name", "geo_subscribePositions"]);
+}
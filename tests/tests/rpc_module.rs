@@ -35,8 +35,9 @@ use jsonrpsee::core::error::Error;
 use jsonrpsee::core::EmptyServerParams;
 use jsonrpsee::core::{server::*, RpcResult};
 use jsonrpsee::types::error::{ErrorCode, ErrorObject, INVALID_PARAMS_MSG, PARSE_ERROR_CODE};
-use jsonrpsee::types::{ErrorObjectOwned, Params, Response, ResponsePayload};
+use jsonrpsee::types::{ErrorObjectOwned, Id, Params, Response, ResponsePayload};
 use jsonrpsee::SubscriptionMessage;
+use jsonrpsee_test_utils::TimeoutFutureExt;
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
 use tokio::time::interval;
@@ -54,9 +55,9 @@ macro_rules! assert_type {
 fn rpc_modules_with_different_contexts_can_be_merged() {
 	let cx = Vec::<u8>::new();
 	let mut mod1 = RpcModule::new(cx);
-	mod1.register_method("bla with Vec context", |_: Params, _| ()).unwrap();
+	mod1.register_method("bla with Vec context", |_: Params, _, _| ()).unwrap();
 	let mut mod2 = RpcModule::new(String::new());
-	mod2.register_method("bla with String context", |_: Params, _| ()).unwrap();
+	mod2.register_method("bla with String context", |_: Params, _, _| ()).unwrap();
 
 	mod1.merge(mod2).unwrap();
 
@@ -75,7 +76,7 @@ fn flatten_rpc_modules() {
 #[test]
 fn rpc_context_modules_can_register_subscriptions() {
 	let mut cxmodule = RpcModule::new(());
-	cxmodule.register_subscription("hi", "hi", "goodbye", |_, _, _| async { Ok(()) }).unwrap();
+	cxmodule.register_subscription("hi", "hi", "goodbye", |_, _, _, _| async { Ok(()) }).unwrap();
 
 	assert!(cxmodule.method("hi").is_some());
 	assert!(cxmodule.method("goodbye").is_some());
@@ -85,7 +86,7 @@ fn rpc_context_modules_can_register_subscriptions() {
 fn rpc_register_alias() {
 	let mut module = RpcModule::new(());
 
-	module.register_method("hello_world", |_: Params, _| RpcResult::Ok(())).unwrap();
+	module.register_method("hello_world", |_: Params, _, _| RpcResult::Ok(())).unwrap();
 	module.register_alias("hello_foobar", "hello_world").unwrap();
 
 	assert!(module.method("hello_world").is_some());
@@ -96,14 +97,14 @@ fn rpc_register_alias() {
 async fn calling_method_without_server() {
 	// Call sync method with no params
 	let mut module = RpcModule::new(());
-	module.register_method("boo", |_: Params, _| String::from("boo!")).unwrap();
+	module.register_method("boo", |_: Params, _, _| String::from("boo!")).unwrap();
 
 	let res: String = module.call("boo", EmptyServerParams::new()).await.unwrap();
 	assert_eq!(&res, "boo!");
 
 	// Call sync method with params
 	module
-		.register_method::<Result<u16, ErrorObjectOwned>, _>("foo", |params, _| {
+		.register_method::<Result<u16, ErrorObjectOwned>, _>("foo", |params, _, _| {
 			let n: u16 = params.one()?;
 			Ok(n * 2)
 		})
@@ -127,7 +128,7 @@ async fn calling_method_without_server() {
 	}
 	let mut module = RpcModule::new(MyContext);
 	module
-		.register_async_method("roo", |params, ctx| {
+		.register_async_method("roo", |params, ctx, _| {
 			let ns: Vec<u8> = params.parse().expect("valid params please");
 			async move { ctx.roo(ns) }
 		})
@@ -238,7 +239,7 @@ async fn subscribing_without_server() {
 
 	let mut module = RpcModule::new(());
 	module
-		.register_subscription("my_sub", "my_sub", "my_unsub", |_, pending, _| async move {
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, pending, _, _| async move {
 			let mut stream_data = vec!['0', '1', '2'];
 
 			let sink = pending.accept().await.unwrap();
@@ -271,7 +272,7 @@ async fn close_test_subscribing_without_server() {
 
 	let mut module = RpcModule::new(());
 	module
-		.register_subscription("my_sub", "my_sub", "my_unsub", |_, pending, _| async move {
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, pending, _, _| async move {
 			let sink = pending.accept().await?;
 			let msg = SubscriptionMessage::from_json(&"lo")?;
 
@@ -314,7 +315,7 @@ async fn close_test_subscribing_without_server() {
 async fn subscribing_without_server_bad_params() {
 	let mut module = RpcModule::new(());
 	module
-		.register_subscription("my_sub", "my_sub", "my_unsub", |params, pending, _| async move {
+		.register_subscription("my_sub", "my_sub", "my_unsub", |params, pending, _, _| async move {
 			let p = match params.one::<String>() {
 				Ok(p) => p,
 				Err(e) => {
@@ -344,7 +345,7 @@ async fn subscribing_without_server_bad_params() {
 async fn subscribing_without_server_indicates_close() {
 	let mut module = RpcModule::new(());
 	module
-		.register_subscription("my_sub", "my_sub", "my_unsub", |_, pending, _| async move {
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, pending, _, _| async move {
 			let sink = pending.accept().await?;
 
 			for m in 0..5 {
@@ -368,7 +369,7 @@ async fn subscribing_without_server_indicates_close() {
 async fn subscribe_unsubscribe_without_server() {
 	let mut module = RpcModule::new(());
 	module
-		.register_subscription("my_sub", "my_sub", "my_unsub", |_, pending, _| async move {
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, pending, _, _| async move {
 			let interval = interval(Duration::from_millis(200));
 			let stream = IntervalStream::new(interval).map(move |_| 1);
 			pipe_from_stream_and_drop(pending, stream).await.map_err(Into::into)
@@ -402,7 +403,7 @@ async fn subscribe_unsubscribe_without_server() {
 async fn rejected_subscription_without_server() {
 	let mut module = RpcModule::new(());
 	module
-		.register_subscription("my_sub", "my_sub", "my_unsub", |_, pending, _| async move {
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, pending, _, _| async move {
 			let err = ErrorObject::borrowed(PARSE_ERROR_CODE, &"rejected", None);
 			pending.reject(err.into_owned()).await;
 			Ok(())
@@ -419,7 +420,7 @@ async fn reject_works() {
 
 	let mut module = RpcModule::new(());
 	module
-		.register_subscription("my_sub", "my_sub", "my_unsub", |_, pending, _| async move {
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, pending, _, _| async move {
 			pending.reject(ErrorObject::owned(PARSE_ERROR_CODE, "rejected", None::<()>)).await;
 			tokio::time::sleep(std::time::Duration::from_millis(100)).await;
 			Err("do not send".into())
@@ -439,7 +440,7 @@ async fn bounded_subscription_works() {
 	let mut module = RpcModule::new(tx);
 
 	module
-		.register_subscription("my_sub", "my_sub", "my_unsub", |_, pending, mut ctx| async move {
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, pending, mut ctx, _| async move {
 			let mut sink = pending.accept().await?;
 
 			let mut stream = IntervalStream::new(interval(std::time::Duration::from_millis(100)))
@@ -508,7 +509,7 @@ async fn serialize_sub_error_adds_extra_string_quotes() {
 
 	let mut module = RpcModule::new(());
 	module
-		.register_subscription("my_sub", "my_sub", "my_unsub", |_, pending, _| async move {
+		.register_subscription("my_sub", "my_sub", "my_unsub", |_, pending, _, _| async move {
 			let _ = pending.accept().await?;
 			tokio::time::sleep(std::time::Duration::from_millis(100)).await;
 
@@ -518,7 +519,7 @@ async fn serialize_sub_error_adds_extra_string_quotes() {
 		.unwrap();
 
 	let (rp, mut stream) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"my_sub","id":0}"#, 1).await.unwrap();
-	let resp = serde_json::from_str::<Response<u64>>(&rp.result).unwrap();
+	let resp = serde_json::from_str::<Response<u64>>(rp.result.as_str().unwrap()).unwrap();
 	let sub_resp = stream.recv().await.unwrap();
 
 	let resp = match resp.payload {
@@ -544,7 +545,7 @@ async fn subscription_close_response_works() {
 	let mut module = RpcModule::new(());
 
 	module
-		.register_subscription("my_sub", "my_sub", "my_unsub", |params, pending, _| async move {
+		.register_subscription("my_sub", "my_sub", "my_unsub", |params, pending, _, _| async move {
 			let x = match params.one::<usize>() {
 				Ok(op) => op,
 				Err(e) => {
@@ -563,7 +564,7 @@ async fn subscription_close_response_works() {
 	{
 		let (rp, mut stream) =
 			module.raw_json_request(r#"{"jsonrpc":"2.0","method":"my_sub","params":[1],"id":0}"#, 1).await.unwrap();
-		let resp = serde_json::from_str::<Response<u64>>(&rp.result).unwrap();
+		let resp = serde_json::from_str::<Response<u64>>(rp.result.as_str().unwrap()).unwrap();
 
 		let sub_id = match resp.payload {
 			ResponsePayload::Result(val) => val,
@@ -583,3 +584,409 @@ async fn subscription_close_response_works() {
 		assert_eq!(rx, 1);
 	}
 }
+
+#[tokio::test]
+async fn method_timeout_works() {
+	use jsonrpsee::types::error::CALL_TIMEOUT_CODE;
+
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method_with_timeout("sleep", Duration::from_millis(50), |params, _, _| async move {
+			let millis: u64 = params.one().unwrap();
+			tokio::time::sleep(Duration::from_millis(millis)).await;
+			millis
+		})
+		.unwrap();
+	module
+		.register_blocking_method_with_timeout("blocking_sleep", Duration::from_millis(50), |params, _, _| {
+			let millis: u64 = params.one().unwrap();
+			std::thread::sleep(Duration::from_millis(millis));
+			millis
+		})
+		.unwrap();
+	module.register_alias("sleep_alias", "sleep").unwrap();
+
+	assert_eq!(module.method_timeout("sleep"), Some(Duration::from_millis(50)));
+	assert_eq!(module.method_timeout("sleep_alias"), Some(Duration::from_millis(50)));
+
+	for method in ["sleep", "blocking_sleep", "sleep_alias"] {
+		let res: u64 = module.call(method, [1]).await.unwrap();
+		assert_eq!(res, 1);
+
+		let err = module.call::<_, u64>(method, [1000]).await.unwrap_err();
+		assert!(matches!(err, Error::Call(e) if e.code() == CALL_TIMEOUT_CODE));
+	}
+
+	let mut merged = RpcModule::new(());
+	merged.merge(module).unwrap();
+	assert_eq!(merged.method_timeout("blocking_sleep"), Some(Duration::from_millis(50)));
+}
+
+#[tokio::test]
+async fn method_timeout_with_proc_macro_works() {
+	use jsonrpsee::proc_macros::rpc;
+	use jsonrpsee::types::error::CALL_TIMEOUT_CODE;
+
+	#[rpc(server)]
+	trait Slow {
+		#[method(name = "sleep", timeout = "50ms")]
+		async fn sleep(&self, millis: u64) -> RpcResult<u64>;
+
+		#[method(name = "blockingSleep", blocking, timeout = "1m")]
+		fn blocking_sleep(&self, millis: u64) -> RpcResult<u64>;
+	}
+
+	struct SlowImpl;
+
+	#[jsonrpsee::core::async_trait]
+	impl SlowServer for SlowImpl {
+		async fn sleep(&self, millis: u64) -> RpcResult<u64> {
+			tokio::time::sleep(Duration::from_millis(millis)).await;
+			Ok(millis)
+		}
+
+		fn blocking_sleep(&self, millis: u64) -> RpcResult<u64> {
+			std::thread::sleep(Duration::from_millis(millis));
+			Ok(millis)
+		}
+	}
+
+	let module = SlowImpl.into_rpc();
+	assert_eq!(module.method_timeout("sleep"), Some(Duration::from_millis(50)));
+	assert_eq!(module.method_timeout("blockingSleep"), Some(Duration::from_secs(60)));
+
+	let res: u64 = module.call("blockingSleep", [1]).await.unwrap();
+	assert_eq!(res, 1);
+	let err = module.call::<_, u64>("sleep", [1000]).await.unwrap_err();
+	assert!(matches!(err, Error::Call(e) if e.code() == CALL_TIMEOUT_CODE));
+}
+
+#[test]
+fn method_access_with_proc_macro_works() {
+	use jsonrpsee::proc_macros::rpc;
+
+	#[rpc(server, namespace = "admin")]
+	trait Admin {
+		#[method(name = "status")]
+		fn status(&self) -> RpcResult<bool>;
+
+		#[method(name = "reset", access = "unsafe", aliases = ["reset"])]
+		fn reset(&self) -> RpcResult<bool>;
+	}
+
+	struct AdminImpl;
+
+	impl AdminServer for AdminImpl {
+		fn status(&self) -> RpcResult<bool> {
+			Ok(true)
+		}
+
+		fn reset(&self) -> RpcResult<bool> {
+			Ok(true)
+		}
+	}
+
+	let module = AdminImpl.into_rpc();
+	assert_eq!(module.method_access("admin_status"), MethodAccess::Safe);
+	assert_eq!(module.method_access("admin_reset"), MethodAccess::Unsafe);
+	assert_eq!(module.method_access("reset"), MethodAccess::Unsafe);
+
+	let mut merged = RpcModule::new(());
+	merged.merge(module).unwrap();
+	assert_eq!(merged.method_access("admin_reset"), MethodAccess::Unsafe);
+	assert!(matches!(merged.set_method_access("admin_missing", MethodAccess::Unsafe), Err(Error::MethodNotFound(_))));
+}
+
+#[tokio::test]
+async fn method_cache_with_proc_macro_works() {
+	use jsonrpsee::proc_macros::rpc;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	#[rpc(server)]
+	trait Counter {
+		#[method(name = "count", cache = "10s", aliases = ["countAlias"], with_extensions)]
+		fn count(&self, key: String) -> RpcResult<usize>;
+	}
+
+	struct CounterImpl(AtomicUsize);
+
+	impl CounterServer for CounterImpl {
+		fn count(&self, ext: &Extensions, key: String) -> RpcResult<usize> {
+			if key == "reset" {
+				ext.get::<ResponseCache>().unwrap().clear();
+			}
+			Ok(self.0.fetch_add(1, Ordering::SeqCst) + 1)
+		}
+	}
+
+	let module = CounterImpl(AtomicUsize::new(0)).into_rpc();
+	let cache = module.method_cache("count").unwrap().clone();
+	assert_eq!(cache.config().ttl(), Duration::from_secs(10));
+
+	assert_eq!(module.call::<_, usize>("count", ["a"]).await.unwrap(), 1);
+	assert_eq!(module.call::<_, usize>("count", ["a"]).await.unwrap(), 1);
+	// Aliases share the cache of the method.
+	assert_eq!(module.call::<_, usize>("countAlias", ["a"]).await.unwrap(), 1);
+	assert_eq!(module.call::<_, usize>("count", ["b"]).await.unwrap(), 2);
+	assert_eq!(cache.len(), 2);
+
+	cache.invalidate(&Params::new(Some(r#"[ "a" ]"#)), &Extensions::new());
+	assert_eq!(module.call::<_, usize>("count", ["a"]).await.unwrap(), 3);
+
+	// The method invalidates its own entries.
+	assert_eq!(module.call::<_, usize>("count", ["reset"]).await.unwrap(), 4);
+	assert_eq!(module.call::<_, usize>("count", ["b"]).await.unwrap(), 5);
+
+	// Calls of different roles don't share the cached responses.
+	let Some(MethodCallback::Sync(count)) = module.method("count") else { panic!("sync method") };
+	let call = |role: &str| {
+		let mut extensions = Extensions::new();
+		extensions.insert(Role::new(role));
+		count(Id::Number(1), Params::new(Some(r#"["c"]"#)), usize::MAX, extensions).result
+	};
+	let (admin, user) = (call("admin"), call("user"));
+	assert_ne!(admin.as_str(), user.as_str());
+	assert_eq!(call("admin").as_str(), admin.as_str());
+
+	// Invalid params are never cached.
+	assert!(module.call::<_, usize>("count", [1]).await.is_err());
+	assert!(matches!(
+		RpcModule::new(()).set_method_cache("missing", Duration::from_secs(1)),
+		Err(Error::MethodNotFound(_))
+	));
+}
+
+#[tokio::test]
+async fn single_flight_method_works() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	let mut module = RpcModule::new(AtomicUsize::new(0));
+	module
+		.register_async_method("slow_count", |_, ctx, _| async move {
+			tokio::time::sleep(Duration::from_millis(100)).await;
+			ctx.fetch_add(1, Ordering::SeqCst) + 1
+		})
+		.unwrap();
+	module.set_method_single_flight("slow_count").unwrap();
+	module.register_alias("slowCount", "slow_count").unwrap();
+	assert!(module.is_single_flight("slowCount"));
+
+	let calls = (0..4).map(|id| {
+		let method = if id % 2 == 0 { "slow_count" } else { "slowCount" };
+		let req = format!(r#"{{"jsonrpc":"2.0","method":"{method}","params":{{"a":1,"b":2}},"id":{id}}}"#);
+		let module = &module;
+		async move { module.raw_json_request(&req, 1).await.unwrap().0 }
+	});
+	let responses = futures::future::join_all(calls).await;
+
+	// All calls share one execution and each response has the id of its request.
+	for (id, rp) in responses.iter().enumerate() {
+		assert_eq!(rp.result, format!(r#"{{"jsonrpc":"2.0","result":1,"id":{id}}}"#));
+	}
+
+	// Calls are only coalesced while in flight.
+	assert_eq!(module.call::<_, usize>("slow_count", EmptyServerParams::new()).await.unwrap(), 2);
+
+	// Calls of different roles don't share an execution.
+	let Some(MethodCallback::Async(slow_count)) = module.method("slow_count") else { panic!("async method") };
+	let calls = ["admin", "user", "user"].map(|role| {
+		let mut extensions = Extensions::new();
+		extensions.insert(Role::new(role));
+		slow_count(Id::Number(1), Params::new(Some("[]")), 0, usize::MAX, extensions)
+	});
+	let results: Vec<_> = futures::future::join_all(calls).await.into_iter().map(|rp| rp.result).collect();
+	assert_ne!(results[0].as_str(), results[1].as_str());
+	assert_eq!(results[1].as_str(), results[2].as_str());
+
+	// A panic of the method doesn't poison the later calls with the same params.
+	let mut module = RpcModule::new(AtomicUsize::new(0));
+	module
+		.register_async_method("panics_once", |_, ctx, _| async move {
+			tokio::time::sleep(Duration::from_millis(50)).await;
+			if ctx.fetch_add(1, Ordering::SeqCst) == 0 {
+				panic!("first call panics");
+			}
+			"ok"
+		})
+		.unwrap();
+	module.set_method_single_flight("panics_once").unwrap();
+	let err = module.call::<_, String>("panics_once", EmptyServerParams::new()).await.unwrap_err();
+	assert!(matches!(err, Error::Call(err) if err.code() == ErrorCode::InternalError.code()));
+	assert_eq!(module.call::<_, String>("panics_once", EmptyServerParams::new()).await.unwrap(), "ok");
+
+	let mut module = RpcModule::new(());
+	module.register_method("sync", |_, _, _| ()).unwrap();
+	assert!(matches!(module.set_method_single_flight("sync"), Err(Error::Custom(_))));
+	assert!(matches!(module.set_method_single_flight("missing"), Err(Error::MethodNotFound(_))));
+}
+
+#[tokio::test]
+async fn subscription_filter_works() {
+	use jsonrpsee::types::error::FILTER_NOT_FOUND_CODE;
+
+	init_logger();
+
+	let (closed_tx, mut closed_rx) = mpsc::unbounded_channel();
+	let mut module = RpcModule::new(closed_tx);
+	module
+		.register_subscription(
+			"subscribe_count",
+			"count",
+			"unsubscribe_count",
+			|params, pending, closed_tx, _| async move {
+				let Ok(count) = params.one::<Option<usize>>() else {
+					pending.reject(ErrorObject::from(ErrorCode::InvalidParams)).await;
+					return Ok(());
+				};
+				let sink = pending.accept().await?;
+
+				// Count to `count` or until the subscription is closed.
+				match count {
+					Some(count) => {
+						for i in 0..count {
+							sink.send(SubscriptionMessage::from_json(&i)?).await?;
+						}
+					}
+					None => {
+						sink.closed().await;
+						closed_tx.send(()).unwrap();
+					}
+				}
+				Ok(())
+			},
+		)
+		.unwrap();
+	module
+		.register_subscription_filter(
+			"subscribe_count",
+			"count_newFilter",
+			"count_getFilterChanges",
+			"count_uninstallFilter",
+			Duration::from_millis(200),
+		)
+		.unwrap();
+
+	// The changes are buffered until they are polled and the filter is dropped once the subscription has ended.
+	let filter: u64 = module.call("count_newFilter", [3]).await.unwrap();
+	let mut changes = Vec::new();
+	let err = loop {
+		match module.call::<_, Vec<usize>>("count_getFilterChanges", [filter]).await {
+			Ok(polled) => changes.extend(polled),
+			Err(Error::Call(err)) => break err,
+			Err(e) => panic!("Unexpected error {e:?}"),
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	};
+	assert_eq!(changes, vec![0, 1, 2]);
+	assert_eq!(err.code(), FILTER_NOT_FOUND_CODE);
+
+	// Errors of the subscription are answered as usual.
+	let err = module.call::<_, u64>("count_newFilter", ["x"]).await.unwrap_err();
+	assert!(matches!(err, Error::Call(err) if err.code() == ErrorCode::InvalidParams.code()));
+
+	// Uninstalling the filter unsubscribes.
+	let filter: u64 = module.call("count_newFilter", [None::<usize>]).await.unwrap();
+	assert!(module.call::<_, bool>("count_uninstallFilter", [filter]).await.unwrap());
+	assert!(!module.call::<_, bool>("count_uninstallFilter", [filter]).await.unwrap());
+	closed_rx.recv().await.unwrap();
+
+	// Filters which aren't polled are dropped after the idle timeout.
+	let filter: u64 = module.call("count_newFilter", [None::<usize>]).await.unwrap();
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	assert_eq!(module.call::<_, Vec<usize>>("count_getFilterChanges", [filter]).await.unwrap(), Vec::<usize>::new());
+	tokio::time::timeout(Duration::from_secs(5), closed_rx.recv()).await.unwrap().unwrap();
+	let err = module.call::<_, Vec<usize>>("count_getFilterChanges", [filter]).await.unwrap_err();
+	assert!(matches!(err, Error::Call(err) if err.code() == FILTER_NOT_FOUND_CODE));
+
+	let mut module = RpcModule::new(());
+	module.register_method("method", |_, _, _| ()).unwrap();
+	let filter = || FilterConfig::new(Duration::from_secs(1));
+	assert!(matches!(module.register_subscription_filter("method", "a", "b", "c", filter()), Err(Error::Custom(_))));
+	assert!(matches!(
+		module.register_subscription_filter("missing", "a", "b", "c", filter()),
+		Err(Error::MethodNotFound(_))
+	));
+}
+
+#[tokio::test]
+async fn subscription_broadcaster_lag_policies() {
+	init_logger();
+
+	let broadcaster = SubscriptionBroadcaster::new(2);
+	let mut module = RpcModule::new(broadcaster.clone());
+	for (name, unsubscribe, policy) in [
+		("drop_oldest", "unsubscribe_drop_oldest", LagPolicy::DropOldest),
+		("drop_newest", "unsubscribe_drop_newest", LagPolicy::DropNewest),
+		("close", "unsubscribe_close", LagPolicy::Close),
+	] {
+		module
+			.register_subscription(name, name, unsubscribe, move |_, pending, broadcaster, _| async move {
+				broadcaster.subscribe(pending, policy).await
+			})
+			.unwrap();
+	}
+
+	let mut drop_oldest = module.subscribe("drop_oldest", EmptyServerParams::new(), 1).await.unwrap();
+	let mut drop_newest = module.subscribe("drop_newest", EmptyServerParams::new(), 1).await.unwrap();
+	let (_, mut close) = module.raw_json_request(r#"{"jsonrpc":"2.0","method":"close","id":0}"#, 1).await.unwrap();
+	assert_eq!(broadcaster.subscriber_count(), 3);
+
+	// The subscribers don't receive anything until the items have been sent, such that they lag behind.
+	for n in 0..10 {
+		assert_eq!(broadcaster.send(&n).unwrap(), 3);
+	}
+
+	for exp in [8, 9] {
+		let (n, _) = drop_oldest.next::<usize>().await.unwrap().unwrap();
+		assert_eq!(n, exp);
+	}
+	for exp in [0, 1] {
+		let (n, _) = drop_newest.next::<usize>().await.unwrap().unwrap();
+		assert_eq!(n, exp);
+	}
+	// The subscription was closed with an error notification.
+	let notif: serde_json::Value = serde_json::from_str(close.recv().await.unwrap().as_str().unwrap()).unwrap();
+	assert_eq!(notif["method"], "close");
+	assert_eq!(notif["params"]["error"], "The subscription lagged behind the broadcaster");
+	assert!(close.recv().await.is_none());
+}
+
+#[tokio::test]
+async fn subscription_broadcaster_close_ends_subscriptions() {
+	init_logger();
+
+	let broadcaster = SubscriptionBroadcaster::new(16);
+	let mut module = RpcModule::new(broadcaster.clone());
+	module
+		.register_subscription(
+			"subscribe_blocks",
+			"block",
+			"unsubscribe_blocks",
+			|_, pending, broadcaster, _| async move { broadcaster.subscribe(pending, LagPolicy::DropOldest).await },
+		)
+		.unwrap();
+
+	let mut sub = module.subscribe("subscribe_blocks", EmptyServerParams::new(), 16).await.unwrap();
+
+	// The producer closes its broadcaster once done.
+	let producer = broadcaster.clone();
+	tokio::spawn(async move {
+		for n in 0..3 {
+			producer.send(&n).unwrap();
+		}
+		producer.close();
+	})
+	.await
+	.unwrap();
+
+	for exp in 0..3 {
+		let (n, _) = sub.next::<usize>().with_default_timeout().await.unwrap().unwrap().unwrap();
+		assert_eq!(n, exp);
+	}
+	assert!(sub.next::<usize>().with_default_timeout().await.unwrap().is_none());
+	assert_eq!(broadcaster.subscriber_count(), 0);
+
+	// Later subscriptions end right away.
+	let mut sub = module.subscribe("subscribe_blocks", EmptyServerParams::new(), 16).await.unwrap();
+	assert!(sub.next::<usize>().with_default_timeout().await.unwrap().is_none());
+}
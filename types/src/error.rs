@@ -25,6 +25,7 @@
 // DEALINGS IN THE SOFTWARE.
 
 use std::fmt;
+use std::time::Duration;
 
 use serde::de::Deserializer;
 use serde::ser::Serializer;
@@ -138,6 +139,16 @@ pub const SERVER_IS_BUSY_CODE: i32 = -32009;
 pub const TOO_BIG_BATCH_REQUEST_CODE: i32 = -32010;
 /// Batch request limit was exceed.
 pub const TOO_BIG_BATCH_RESPONSE_CODE: i32 = -32011;
+/// Call timeout was exceeded.
+pub const CALL_TIMEOUT_CODE: i32 = -32012;
+/// Access to the method was denied.
+pub const ACCESS_DENIED_CODE: i32 = -32013;
+/// Rate limit was exceeded.
+pub const RATE_LIMITED_CODE: i32 = -32014;
+/// The filter doesn't exist or has expired.
+pub const FILTER_NOT_FOUND_CODE: i32 = -32015;
+/// The subscription can't be resumed.
+pub const SUBSCRIPTION_NOT_RESUMABLE_CODE: i32 = -32016;
 
 /// Parse error message
 pub const PARSE_ERROR_MSG: &str = "Parse error";
@@ -165,6 +176,16 @@ pub const TOO_MANY_SUBSCRIPTIONS_MSG: &str = "Too many subscriptions on the conn
 pub const TOO_BIG_BATCH_REQUEST_MSG: &str = "The batch request was too large";
 /// Batch request response limit was exceed.
 pub const TOO_BIG_BATCH_RESPONSE_MSG: &str = "The batch response was too large";
+/// Call timeout was exceeded.
+pub const CALL_TIMEOUT_MSG: &str = "The call timed out";
+/// Access to the method was denied.
+pub const ACCESS_DENIED_MSG: &str = "Access to the method is denied";
+/// Rate limit was exceeded.
+pub const RATE_LIMITED_MSG: &str = "Rate limit exceeded";
+/// The filter doesn't exist or has expired.
+pub const FILTER_NOT_FOUND_MSG: &str = "Filter not found";
+/// The subscription can't be resumed.
+pub const SUBSCRIPTION_NOT_RESUMABLE_MSG: &str = "Subscription can't be resumed";
 
 /// JSONRPC error code
 #[derive(Error, Debug, PartialEq, Eq, Copy, Clone)]
@@ -296,6 +317,38 @@ pub fn reject_too_big_batch_response(limit: usize) -> ErrorObjectOwned {
 	)
 }
 
+/// Helper to get a `JSON-RPC` error object when a call didn't complete within its timeout.
+pub fn reject_call_timeout(timeout: Duration) -> ErrorObjectOwned {
+	ErrorObjectOwned::owned(
+		CALL_TIMEOUT_CODE,
+		CALL_TIMEOUT_MSG,
+		Some(format!("Exceeded timeout of {}ms", timeout.as_millis())),
+	)
+}
+
+/// Helper to get a `JSON-RPC` error object when the caller isn't permitted to call the method.
+pub fn reject_access_denied(method: &str) -> ErrorObjectOwned {
+	ErrorObjectOwned::owned(ACCESS_DENIED_CODE, ACCESS_DENIED_MSG, Some(method.to_owned()))
+}
+
+/// Helper to get a `JSON-RPC` error object when the caller exceeded a rate limit.
+///
+/// The data is an object with the number of milliseconds to wait before trying again, i.e. `{"retry_after_ms":250}`.
+pub fn reject_rate_limited(retry_after: Duration) -> ErrorObjectOwned {
+	let data = serde_json::json!({ "retry_after_ms": retry_after.as_millis() as u64 });
+	ErrorObjectOwned::owned(RATE_LIMITED_CODE, RATE_LIMITED_MSG, Some(data))
+}
+
+/// Helper to get a `JSON-RPC` error object when the polled filter doesn't exist or has expired.
+pub fn reject_filter_not_found() -> ErrorObjectOwned {
+	ErrorObjectOwned::owned(FILTER_NOT_FOUND_CODE, FILTER_NOT_FOUND_MSG, None::<()>)
+}
+
+/// Helper to get a `JSON-RPC` error object when a subscription can't be resumed, the data is the `reason`.
+pub fn reject_subscription_not_resumable(reason: &str) -> ErrorObjectOwned {
+	ErrorObjectOwned::owned(SUBSCRIPTION_NOT_RESUMABLE_CODE, SUBSCRIPTION_NOT_RESUMABLE_MSG, Some(reason.to_owned()))
+}
+
 #[cfg(test)]
 mod tests {
 	use super::{ErrorCode, ErrorObject};
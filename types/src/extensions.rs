@@ -0,0 +1,131 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Type map used to attach arbitrary data to a JSON-RPC call.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+type AnyMap = HashMap<TypeId, Arc<dyn Any + Send + Sync>>;
+
+/// A type map of extensions associated with a JSON-RPC call.
+///
+/// The server populates it with data from the underlying transport such as the HTTP headers
+/// and the remote address of the connection, and the RPC middleware may insert further values
+/// before the call reaches the method callback.
+///
+/// The values are reference counted so cloning the map is cheap and doesn't require the
+/// values to implement [`Clone`].
+#[derive(Clone, Default)]
+pub struct Extensions {
+	map: Option<Box<AnyMap>>,
+}
+
+impl Extensions {
+	/// Create an empty [`Extensions`].
+	pub fn new() -> Self {
+		Self { map: None }
+	}
+
+	/// Insert a value of type `T`.
+	///
+	/// If a value of this type already existed it's replaced and `true` is returned.
+	pub fn insert<T: Send + Sync + 'static>(&mut self, val: T) -> bool {
+		self.map.get_or_insert_with(Default::default).insert(TypeId::of::<T>(), Arc::new(val)).is_some()
+	}
+
+	/// Get a reference to the value of type `T` if it exists.
+	pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+		self.map.as_ref().and_then(|map| map.get(&TypeId::of::<T>())).and_then(|val| val.downcast_ref())
+	}
+
+	/// Remove the value of type `T` and returns whether it existed.
+	pub fn remove<T: Send + Sync + 'static>(&mut self) -> bool {
+		self.map.as_mut().and_then(|map| map.remove(&TypeId::of::<T>())).is_some()
+	}
+
+	/// Returns whether a value of type `T` exists.
+	pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+		self.map.as_ref().map_or(false, |map| map.contains_key(&TypeId::of::<T>()))
+	}
+
+	/// Extend `self` with the values of `other`.
+	///
+	/// Values of the same type in `self` are overwritten.
+	pub fn extend(&mut self, other: Self) {
+		if let Some(other) = other.map {
+			match &mut self.map {
+				Some(map) => map.extend(*other),
+				None => self.map = Some(other),
+			}
+		}
+	}
+
+	/// Returns the number of values.
+	pub fn len(&self) -> usize {
+		self.map.as_ref().map_or(0, |map| map.len())
+	}
+
+	/// Returns whether the map is empty.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
+
+impl fmt::Debug for Extensions {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Extensions").field("len", &self.len()).finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Extensions;
+
+	#[derive(Debug, PartialEq)]
+	struct MyExt(u32);
+
+	#[test]
+	fn insert_get_remove_works() {
+		let mut ext = Extensions::new();
+		assert!(ext.is_empty());
+
+		assert!(!ext.insert(MyExt(1)));
+		assert!(ext.insert(MyExt(2)));
+		assert!(!ext.insert(5_u8));
+
+		assert_eq!(ext.get::<MyExt>(), Some(&MyExt(2)));
+		assert_eq!(ext.get::<u8>(), Some(&5));
+		assert_eq!(ext.len(), 2);
+
+		let cloned = ext.clone();
+		assert!(ext.remove::<MyExt>());
+		assert!(!ext.contains::<MyExt>());
+		assert_eq!(cloned.get::<MyExt>(), Some(&MyExt(2)));
+	}
+}
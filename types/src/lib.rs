@@ -43,7 +43,11 @@ pub mod response;
 /// JSON-RPC response error object related types.
 pub mod error;
 
+/// Extensions associated with a JSON-RPC call.
+pub mod extensions;
+
 pub use error::{ErrorObject, ErrorObjectOwned};
+pub use extensions::Extensions;
 pub use params::{Id, InvalidRequestId, Params, ParamsSequence, SubscriptionId, TwoPointZero};
-pub use request::{InvalidRequest, Notification, NotificationSer, Request, RequestSer};
+pub use request::{InvalidRequest, Notification, NotificationSer, Request, RequestSer, RequestV1, RequestV1Ser};
 pub use response::{Response, ResponsePayload, SubscriptionPayload, SubscriptionResponse, Success as ResponseSuccess};
@@ -28,6 +28,8 @@
 //! Some types come with a "*Ser" variant that implements [`serde::Serialize`]; these are used in the client.
 
 use std::fmt;
+#[cfg(feature = "cbor")]
+use std::sync::Arc;
 
 use anyhow::anyhow;
 use beef::Cow;
@@ -86,20 +88,46 @@ impl Serialize for TwoPointZero {
 /// The data containing the params is a `Cow<&str>` and can either be a borrowed `&str` of JSON from an incoming
 /// [`super::request::Request`] (which in turn borrows it from the input buffer that is shared between requests);
 /// or, it can be an owned [`String`].
+///
+/// With the `cbor` feature enabled the params may also be an already decoded CBOR value, see [`Params::from_cbor`].
 #[derive(Clone, Debug)]
-pub struct Params<'a>(Option<Cow<'a, str>>);
+pub struct Params<'a>(ParamsInner<'a>);
+
+#[derive(Clone, Debug)]
+enum ParamsInner<'a> {
+	Json(Option<Cow<'a, str>>),
+	#[cfg(feature = "cbor")]
+	Cbor(Arc<ciborium::Value>),
+}
 
 impl<'a> Params<'a> {
 	/// Create params
 	pub fn new(raw: Option<&'a str>) -> Self {
-		Self(raw.map(|r| r.trim().into()))
+		Self(ParamsInner::Json(raw.map(|r| r.trim().into())))
+	}
+
+	/// Create params from a decoded CBOR value, where `None` means that no params were provided.
+	///
+	/// Such params are parsed directly from the CBOR value and are never converted to JSON. Note that
+	/// borrowed types such as `&str` can't be deserialized from CBOR params, use owned types instead.
+	#[cfg(feature = "cbor")]
+	pub fn from_cbor(value: Option<ciborium::Value>) -> Params<'static> {
+		Params(ParamsInner::Cbor(Arc::new(value.unwrap_or(ciborium::Value::Null))))
+	}
+
+	/// Returns true if the params were decoded from CBOR.
+	#[cfg(feature = "cbor")]
+	pub fn is_cbor(&self) -> bool {
+		matches!(self.0, ParamsInner::Cbor(_))
 	}
 
 	/// Returns true if the contained JSON is an object
 	pub fn is_object(&self) -> bool {
 		let json: &str = match self.0 {
-			Some(ref cow) => cow,
-			None => return false,
+			ParamsInner::Json(Some(ref cow)) => cow,
+			ParamsInner::Json(None) => return false,
+			#[cfg(feature = "cbor")]
+			ParamsInner::Cbor(ref value) => return value.is_map(),
 		};
 		json.starts_with('{')
 	}
@@ -109,13 +137,21 @@ impl<'a> Params<'a> {
 	/// This allows sequential parsing of the incoming params, using an `Iterator`-style API and is useful when the RPC
 	/// request has optional parameters at the tail that may or may not be present.
 	pub fn sequence(&self) -> ParamsSequence {
-		let json = match self.0.as_ref() {
+		let json = match self.0 {
 			// It's assumed that params is `[a,b,c]`, if empty regard as no params.
-			Some(json) if json == "[]" => "",
-			Some(json) => json,
-			None => "",
+			ParamsInner::Json(Some(ref json)) if json == "[]" => "",
+			ParamsInner::Json(Some(ref json)) => json,
+			ParamsInner::Json(None) => "",
+			#[cfg(feature = "cbor")]
+			ParamsInner::Cbor(ref value) => {
+				let values = match &**value {
+					ciborium::Value::Array(values) => values.as_slice(),
+					_ => &[],
+				};
+				return ParamsSequence(SequenceInner::Cbor(values));
+			}
 		};
-		ParamsSequence(json)
+		ParamsSequence(SequenceInner::Json(json))
 	}
 
 	/// Attempt to parse all parameters as an array or map into type `T`.
@@ -123,9 +159,15 @@ impl<'a> Params<'a> {
 	where
 		T: Deserialize<'a>,
 	{
-		// NOTE(niklasad1): Option::None is serialized as `null` so we provide that here.
-		let params = self.0.as_ref().map(AsRef::as_ref).unwrap_or("null");
-		serde_json::from_str(params).map_err(invalid_params)
+		match self.0 {
+			ParamsInner::Json(ref json) => {
+				// NOTE(niklasad1): Option::None is serialized as `null` so we provide that here.
+				let params = json.as_ref().map(AsRef::as_ref).unwrap_or("null");
+				serde_json::from_str(params).map_err(invalid_params)
+			}
+			#[cfg(feature = "cbor")]
+			ParamsInner::Cbor(ref value) => value.deserialized().map_err(invalid_params),
+		}
 	}
 
 	/// Attempt to parse parameters as an array of a single value of type `T`, and returns that value.
@@ -140,22 +182,30 @@ impl<'a> Params<'a> {
 	///
 	/// This will cause an allocation if the params internally are using a borrowed JSON slice.
 	pub fn into_owned(self) -> Params<'static> {
-		Params(self.0.map(|s| Cow::owned(s.into_owned())))
+		match self.0 {
+			ParamsInner::Json(json) => Params(ParamsInner::Json(json.map(|s| Cow::owned(s.into_owned())))),
+			#[cfg(feature = "cbor")]
+			ParamsInner::Cbor(value) => Params(ParamsInner::Cbor(value)),
+		}
 	}
 
 	/// Return the length of underlying JSON string in number of bytes.
+	///
+	/// This is always `0` for CBOR params.
 	pub fn len_bytes(&self) -> usize {
 		match self.0 {
-			Some(ref cow) => cow.len(),
-			None => 0,
+			ParamsInner::Json(Some(ref cow)) => cow.len(),
+			_ => 0,
 		}
 	}
 
 	/// Return the underlying JSON string as a `&str`.
+	///
+	/// This is always `None` for CBOR params.
 	pub fn as_str(&self) -> Option<&str> {
 		match self.0 {
-			Some(ref cow) => Some(cow.as_ref()),
-			None => None,
+			ParamsInner::Json(Some(ref cow)) => Some(cow.as_ref()),
+			_ => None,
 		}
 	}
 }
@@ -168,18 +218,37 @@ impl<'a> Params<'a> {
 ///
 /// Regards empty array `[]` as no parameters provided.
 #[derive(Debug, Copy, Clone)]
-pub struct ParamsSequence<'a>(&'a str);
+pub struct ParamsSequence<'a>(SequenceInner<'a>);
+
+#[derive(Debug, Copy, Clone)]
+enum SequenceInner<'a> {
+	Json(&'a str),
+	#[cfg(feature = "cbor")]
+	Cbor(&'a [ciborium::Value]),
+}
 
 impl<'a> ParamsSequence<'a> {
+	#[cfg_attr(not(feature = "cbor"), allow(clippy::infallible_destructuring_match))]
 	fn next_inner<T>(&mut self) -> Option<Result<T, ErrorObjectOwned>>
 	where
 		T: Deserialize<'a>,
 	{
-		let mut json = self.0;
+		let mut json = match self.0 {
+			SequenceInner::Json(json) => json,
+			#[cfg(feature = "cbor")]
+			SequenceInner::Cbor(values) => {
+				let (value, rest) = values.split_first()?;
+				self.0 = SequenceInner::Cbor(rest);
+				return Some(value.deserialized().map_err(|e| {
+					self.0 = SequenceInner::Cbor(&[]);
+					invalid_params(e)
+				}));
+			}
+		};
 		tracing::trace!("[next_inner] Params JSON: {:?}", json);
 		match json.as_bytes().first()? {
 			b']' => {
-				self.0 = "";
+				self.0 = SequenceInner::Json("");
 
 				tracing::trace!("[next_inner] Reached end of sequence.");
 				return None;
@@ -196,7 +265,7 @@ impl<'a> ParamsSequence<'a> {
 
 		match iter.next()? {
 			Ok(value) => {
-				self.0 = json[iter.byte_offset()..].trim_start();
+				self.0 = SequenceInner::Json(json[iter.byte_offset()..].trim_start());
 
 				Some(Ok(value))
 			}
@@ -207,7 +276,7 @@ impl<'a> ParamsSequence<'a> {
 					e,
 					json
 				);
-				self.0 = "";
+				self.0 = SequenceInner::Json("");
 
 				Some(Err(invalid_params(e)))
 			}
@@ -597,4 +666,37 @@ mod test {
 		assert_eq!(seq.optional_next::<Vec<Vec<u32>>>().unwrap(), Some(vec![vec![5], vec![6, 7], vec![]]));
 		assert_eq!(seq.optional_next::<serde_json::Value>().unwrap(), Some(serde_json::json!({"named":7})));
 	}
+
+	#[cfg(feature = "cbor")]
+	#[test]
+	fn cbor_params_parse() {
+		use ciborium::Value;
+
+		let none = Params::from_cbor(None);
+		assert!(none.is_cbor());
+		assert!(none.parse::<Option<u64>>().unwrap().is_none());
+		assert!(none.sequence().next::<u64>().is_err());
+
+		let array = Value::Array(vec![Value::Integer(1.into()), Value::Text("a".into()), Value::Array(vec![])]);
+		let params = Params::from_cbor(Some(array));
+		assert!(!params.is_object());
+		assert_eq!(params.len_bytes(), 0);
+		assert!(params.as_str().is_none());
+		assert_eq!(params.parse::<(u64, String, Vec<u8>)>().unwrap(), (1, "a".to_owned(), vec![]));
+
+		let mut seq = params.sequence();
+		assert_eq!(seq.next::<u64>().unwrap(), 1);
+		assert_eq!(seq.next::<String>().unwrap(), "a");
+		assert_eq!(seq.optional_next::<Vec<u8>>().unwrap(), Some(vec![]));
+		assert!(seq.optional_next::<u64>().unwrap().is_none());
+
+		let object = Value::Map(vec![(Value::Text("beef".into()), Value::Integer(99.into()))]);
+		let params = Params::from_cbor(Some(object)).into_owned();
+		assert!(params.is_object());
+		assert_eq!(params.parse::<JsonValue>().unwrap(), serde_json::json!({"beef": 99}));
+		assert!(params.sequence().next::<u64>().is_err());
+
+		let params = Params::from_cbor(Some(Value::Array(vec![Value::Text("abc".into())])));
+		assert_eq!(params.one::<SubscriptionId>().unwrap(), SubscriptionId::Str("abc".into()));
+	}
 }
@@ -29,9 +29,10 @@
 
 use std::borrow::Cow as StdCow;
 
-use crate::params::{Id, TwoPointZero};
+use crate::extensions::Extensions;
+use crate::params::{Id, Params, TwoPointZero};
 use beef::Cow;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use serde_json::value::RawValue;
 
 /// JSON-RPC request object as defined in the [spec](https://www.jsonrpc.org/specification#request-object).
@@ -48,15 +49,66 @@ pub struct Request<'a> {
 	/// Parameter values of the request.
 	#[serde(borrow)]
 	pub params: Option<&'a RawValue>,
+	/// W3C `traceparent` of the caller.
+	///
+	/// Not part of the JSON-RPC specification, used to propagate the trace context of the caller.
+	#[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+	pub traceparent: Option<Cow<'a, str>>,
+	/// Parameter values of a request that wasn't encoded as JSON, already decoded by the server.
+	///
+	/// When this is set `params` is `None` and the method callback receives these params instead.
+	#[serde(skip)]
+	pub decoded_params: Option<Params<'a>>,
+	/// The request's extensions.
+	///
+	/// These are not part of the JSON-RPC request but may be populated by the server
+	/// or by middleware to pass data along to the method callback.
+	#[serde(skip)]
+	pub extensions: Extensions,
 }
 
 impl<'a> Request<'a> {
 	/// Create a new [`Request`].
 	pub fn new(method: Cow<'a, str>, params: Option<&'a RawValue>, id: Id<'a>) -> Self {
-		Self { jsonrpc: TwoPointZero, id, method, params }
+		Self {
+			jsonrpc: TwoPointZero,
+			id,
+			method,
+			params,
+			traceparent: None,
+			decoded_params: None,
+			extensions: Extensions::new(),
+		}
+	}
+
+	/// Get the extensions of the request.
+	pub fn extensions(&self) -> &Extensions {
+		&self.extensions
+	}
+
+	/// Get a mutable reference to the extensions of the request.
+	pub fn extensions_mut(&mut self) -> &mut Extensions {
+		&mut self.extensions
 	}
 }
 
+/// JSON-RPC 1.0 request object as defined in the
+/// [spec](https://www.jsonrpc.org/specification_v1#a1.1Requestmethodinvocation).
+///
+/// It has no `jsonrpc` member and a request whose `id` is null is a notification.
+#[derive(Deserialize, Debug)]
+pub struct RequestV1<'a> {
+	/// Request ID, which is null for notifications.
+	#[serde(borrow)]
+	pub id: Id<'a>,
+	/// Name of the method to be invoked.
+	#[serde(borrow)]
+	pub method: Cow<'a, str>,
+	/// Parameter values of the request.
+	#[serde(borrow)]
+	pub params: Option<&'a RawValue>,
+}
+
 /// JSON-RPC Invalid request as defined in the [spec](https://www.jsonrpc.org/specification#request-object).
 #[derive(Deserialize, Debug, PartialEq, Eq)]
 pub struct InvalidRequest<'a> {
@@ -98,6 +150,9 @@ pub struct RequestSer<'a> {
 	/// Parameter values of the request.
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub params: Option<StdCow<'a, RawValue>>,
+	/// W3C `traceparent` of the caller.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub traceparent: Option<Cow<'a, str>>,
 }
 
 impl<'a> RequestSer<'a> {
@@ -108,12 +163,25 @@ impl<'a> RequestSer<'a> {
 			id: id.clone(),
 			method: method.as_ref().into(),
 			params: params.map(StdCow::Borrowed),
+			traceparent: None,
 		}
 	}
 
 	/// Create a owned serializable JSON-RPC method call.
 	pub fn owned(id: Id<'a>, method: impl Into<String>, params: Option<Box<RawValue>>) -> Self {
-		Self { jsonrpc: TwoPointZero, id, method: method.into().into(), params: params.map(StdCow::Owned) }
+		Self {
+			jsonrpc: TwoPointZero,
+			id,
+			method: method.into().into(),
+			params: params.map(StdCow::Owned),
+			traceparent: None,
+		}
+	}
+
+	/// Attach the W3C `traceparent` of the caller to the method call.
+	pub fn with_traceparent(mut self, traceparent: Option<String>) -> Self {
+		self.traceparent = traceparent.map(Into::into);
+		self
 	}
 }
 
@@ -142,9 +210,44 @@ impl<'a> NotificationSer<'a> {
 	}
 }
 
+/// Serializable [JSON-RPC 1.0 request object](https://www.jsonrpc.org/specification_v1#a1.1Requestmethodinvocation),
+/// which is a notification if its `id` is null.
+#[derive(Serialize, Debug)]
+pub struct RequestV1Ser<'a> {
+	/// Name of the method to be invoked.
+	pub method: Cow<'a, str>,
+	/// Parameter values of the request, serialized as an empty array if there are none.
+	#[serde(serialize_with = "serialize_params_v1")]
+	pub params: Option<StdCow<'a, RawValue>>,
+	/// Request ID.
+	pub id: Id<'a>,
+}
+
+impl<'a> From<RequestSer<'a>> for RequestV1Ser<'a> {
+	fn from(request: RequestSer<'a>) -> Self {
+		Self { method: request.method, params: request.params, id: request.id }
+	}
+}
+
+impl<'a> From<NotificationSer<'a>> for RequestV1Ser<'a> {
+	fn from(notif: NotificationSer<'a>) -> Self {
+		Self { method: notif.method, params: notif.params, id: Id::Null }
+	}
+}
+
+fn serialize_params_v1<S: Serializer>(params: &Option<StdCow<RawValue>>, serializer: S) -> Result<S::Ok, S::Error> {
+	match params {
+		Some(params) => params.serialize(serializer),
+		None => serializer.collect_seq(std::iter::empty::<()>()),
+	}
+}
+
 #[cfg(test)]
 mod test {
-	use super::{Id, InvalidRequest, Notification, NotificationSer, Request, RequestSer, StdCow, TwoPointZero};
+	use super::{
+		Id, InvalidRequest, Notification, NotificationSer, Request, RequestSer, RequestV1, RequestV1Ser, StdCow,
+		TwoPointZero,
+	};
 	use serde_json::value::RawValue;
 
 	fn assert_request<'a>(request: Request<'a>, id: Id<'a>, method: &str, params: Option<&str>) {
@@ -187,6 +290,18 @@ mod test {
 		assert_request(req, Id::Number(1), "\"m\"", None);
 	}
 
+	#[test]
+	fn deserialize_call_with_traceparent() {
+		let tp = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+		let ser = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"m","traceparent":"{tp}"}}"#);
+		let req: Request = serde_json::from_str(&ser).unwrap();
+		assert_eq!(req.traceparent.as_deref(), Some(tp));
+
+		let ser = serde_json::to_string(&RequestSer::owned(Id::Number(1), "m", None).with_traceparent(Some(tp.into())))
+			.unwrap();
+		assert_eq!(ser, format!(r#"{{"jsonrpc":"2.0","id":1,"method":"m","traceparent":"{tp}"}}"#));
+	}
+
 	#[test]
 	fn deserialize_valid_notif_works() {
 		let ser = r#"{"jsonrpc":"2.0","method":"say_hello","params":[]}"#;
@@ -247,6 +362,7 @@ mod test {
 				method: method.into(),
 				id: id.unwrap_or(Id::Null),
 				params: params.map(StdCow::Owned),
+				traceparent: None,
 			})
 			.unwrap();
 
@@ -254,6 +370,25 @@ mod test {
 		}
 	}
 
+	#[test]
+	fn v1_request_works() {
+		let req: RequestV1 = serde_json::from_str(r#"{"method":"echo","params":["Hello"],"id":1}"#).unwrap();
+		assert_eq!(
+			(req.id, req.method.as_ref(), req.params.map(RawValue::get)),
+			(Id::Number(1), "echo", Some(r#"["Hello"]"#))
+		);
+
+		let req: RequestV1 = serde_json::from_str(r#"{"jsonrpc":"1.0","method":"ping","id":null}"#).unwrap();
+		assert_eq!((req.id, req.method.as_ref(), req.params.is_none()), (Id::Null, "ping", true));
+
+		let params = RawValue::from_string(r#"["Hello"]"#.into()).unwrap();
+		let ser = serde_json::to_string(&RequestV1Ser::from(RequestSer::owned(Id::Number(1), "echo", Some(params))));
+		assert_eq!(ser.unwrap(), r#"{"method":"echo","params":["Hello"],"id":1}"#);
+
+		let ser = serde_json::to_string(&RequestV1Ser::from(NotificationSer::owned("ping", None))).unwrap();
+		assert_eq!(ser, r#"{"method":"ping","params":[],"id":null}"#);
+	}
+
 	#[test]
 	fn serialize_notif() {
 		let exp = r#"{"jsonrpc":"2.0","method":"say_hello","params":["hello"]}"#;
@@ -267,7 +267,7 @@ where
 							if error.is_some() {
 								return Err(serde::de::Error::duplicate_field("error"));
 							}
-							error = Some(map.next_value()?);
+							error = Some(map.next_value::<Option<ErrorObject>>()?);
 						}
 						Field::Id => {
 							if id.is_some() {
@@ -286,6 +286,19 @@ where
 
 				let id = id.ok_or_else(|| serde::de::Error::missing_field("id"))?;
 
+				// JSON-RPC 1.0 responses have no `jsonrpc` member and carry both `result` and `error`,
+				// where the one that doesn't apply is null.
+				let is_v1 = !matches!(jsonrpc, Some(Some(TwoPointZero)));
+				let error = match error {
+					Some(Some(err)) => Some(err),
+					Some(None) if is_v1 => None,
+					Some(None) => {
+						return Err(serde::de::Error::invalid_type(serde::de::Unexpected::Unit, &"an error object"))
+					}
+					None => None,
+				};
+				let result = if is_v1 && error.is_some() { None } else { result };
+
 				let response = match (jsonrpc, result, error) {
 					(_, Some(_), Some(_)) => {
 						return Err(serde::de::Error::duplicate_field("result and error are mutually exclusive"))
@@ -405,4 +418,20 @@ mod tests {
 		assert_eq!(dsr.payload, exp.payload);
 		assert_eq!(dsr.id, exp.id);
 	}
+
+	#[test]
+	fn deserialize_v1_response() {
+		let dsr: Response<u64> = serde_json::from_str(r#"{"result":99,"error":null,"id":11}"#).unwrap();
+		assert_eq!(dsr.jsonrpc, None);
+		assert_eq!(dsr.payload, ResponsePayload::result(99));
+		assert_eq!(dsr.id, Id::Number(11));
+
+		let dsr: Response<Option<u64>> =
+			serde_json::from_str(r#"{"result":null,"error":{"code":1,"message":"lo"},"id":11}"#).unwrap();
+		assert_eq!(dsr.payload, ResponsePayload::Error(ErrorObjectOwned::owned(1, "lo", None::<()>)));
+
+		// Only JSON-RPC 1.0 responses carry both `result` and `error`.
+		let ser = r#"{"jsonrpc":"2.0","result":99,"error":null,"id":11}"#;
+		assert!(serde_json::from_str::<Response<u64>>(ser).is_err());
+	}
 }